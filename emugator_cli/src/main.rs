@@ -42,6 +42,20 @@ fn main() {
         tester::Command::Test(test_args) => {
             tests_with_ratatui(test_args);
         }
+        tester::Command::Disasm(disasm_args) => match tester::disassemble(disasm_args) {
+            Ok(listing) => print!("{}", listing),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        },
+        tester::Command::Trace(trace_args) => match tester::trace(trace_args) {
+            Ok(trace) => print!("{}", trace),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        },
     }
 }
 
@@ -142,7 +156,8 @@ impl Widget for &App {
         render_header(header_area, buf);
         render_footer(footer_area, buf);
         if self.ending_msg.is_some() {
-            self.render_ending_text(middle_area, buf);
+            // The gauges are gone, so the message can list UART mismatches below the summary
+            self.render_ending_text(gauge_area.union(middle_area), buf);
         } else {
             self.render_gauges(gauge_area, buf);
         }
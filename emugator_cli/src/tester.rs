@@ -1,8 +1,9 @@
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 use emugator_core::{
-    assembler::{AssembledProgram, assemble},
-    emulator::{EmulatorState, cve2::CVE2Pipeline},
+    assembler::{AssembleOptions, AssembledProgram, MemoryMap, SectionBases, assemble},
+    emulator::{AnyEmulatorState, EmulatorState, Pipeline, cve2::CVE2Pipeline},
+    utils,
 };
 use serde::{Deserialize, Serialize};
 use std::{
@@ -16,14 +17,106 @@ use std::{
 struct Test {
     name: String,
     input: String,
+    /// Raw bytes fed to the UART after `input`
+    input_bytes: Vec<u8>,
     expected_state: OutputState,
+    /// Overrides the `--timeout` for this test
+    max_cycles: Option<usize>,
+}
+
+/// Version of the manifest schema this grader understands
+const MANIFEST_VERSION: u32 = 1;
+
+/// Describes the programs to grade and the tests to run on each of them.
+/// Fields added in later versions must be optional so older manifests keep
+/// working, while manifests newer than `MANIFEST_VERSION` are rejected.
+#[derive(Debug, Deserialize, Serialize)]
+struct Manifest {
+    version: u32,
+    /// File names in the programs folder to grade, or every file if empty
+    #[serde(default)]
+    programs: Vec<String>,
+    tests: Vec<ManifestTest>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ManifestTest {
+    name: String,
+    /// Text fed to the UART receiver
+    #[serde(default)]
+    input: String,
+    /// Bytes fed to the UART receiver after `input`, for input that isn't text
+    #[serde(default)]
+    input_bytes: Vec<u8>,
+    /// Clock cycles the program may run for before it is stopped
+    #[serde(default)]
+    max_cycles: Option<usize>,
+    /// Registers, data memory bytes and UART output checked after the run
+    expected: OutputState,
+}
+
+impl Manifest {
+    fn parse(json: &str) -> Result<Manifest, String> {
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+
+        // Check the version first so newer manifests get a clear error
+        let Version { version } = serde_json::from_str(json)
+            .map_err(|e| format!("Manifest is missing its version: {}", e))?;
+        if version > MANIFEST_VERSION {
+            return Err(format!(
+                "Manifest version {} is newer than the supported version {}",
+                version, MANIFEST_VERSION
+            ));
+        }
+        serde_json::from_str(json).map_err(|e| format!("Manifest is improperly formatted: {}", e))
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(default)]
 struct OutputState {
     registers: HashMap<u8, HexValue>,
     data_memory: HashMap<HexValue, HexValue>,
     output_buffer: String,
+    /// Why the program failed to terminate, only written to the results
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stop_reason: Option<String>,
+    /// Where the UART output first differs from the expected output, only
+    /// written to the results
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_mismatch: Option<String>,
+}
+
+/// Bytes of UART output shown on each side of the first mismatch
+const MISMATCH_CONTEXT: usize = 8;
+
+/// Describes the first byte where `actual` differs from `expected`, with the
+/// bytes around it from both, or `None` if they match
+fn output_mismatch(expected: &[u8], actual: &[u8]) -> Option<String> {
+    let position = zip(expected, actual).take_while(|(a, b)| a == b).count();
+    if position == expected.len() && position == actual.len() {
+        return None;
+    }
+
+    let start = position.saturating_sub(MISMATCH_CONTEXT);
+    let context = |bytes: &[u8]| {
+        let end = bytes.len().min(position + MISMATCH_CONTEXT);
+        let ellipsis = if start > 0 { "..." } else { "" };
+        format!(
+            "\"{}{}\"",
+            ellipsis,
+            bytes[start.min(end)..end].escape_ascii()
+        )
+    };
+    Some(format!(
+        "UART output differs at byte {}: expected {}, got {}",
+        position,
+        context(expected),
+        context(actual)
+    ))
 }
 
 impl OutputState {
@@ -61,13 +154,14 @@ impl OutputState {
         // Check UART output
         let expected_output = self.output_buffer.as_bytes();
         let actual_output = state.data_memory.get_serial_output();
-        if actual_output != expected_output {
+        if let Some(mismatch) = output_mismatch(expected_output, actual_output) {
             pass = false;
             let count = zip(expected_output, actual_output)
                 .take_while(|(a, b)| a == b)
                 .count();
 
             diff.output_buffer = String::from_utf8_lossy(&actual_output[count..]).to_string();
+            diff.output_mismatch = Some(mismatch);
         }
 
         if pass { None } else { Some(diff) }
@@ -93,6 +187,8 @@ pub struct Arguments {
 pub enum Command {
     New(NewArgs),
     Test(TestArgs),
+    Disasm(DisasmArgs),
+    Trace(TraceArgs),
 }
 
 #[derive(Args, Debug)]
@@ -113,30 +209,181 @@ pub struct TestArgs {
     #[arg(long, default_value_t = String::from("programs"))]
     programs: String,
 
-    /// Folder containing the tests to be run
+    /// Folder containing the tests to be run, used when there is no manifest
     #[arg(long, default_value_t = String::from("tests"))]
     tests: String,
 
+    /// Manifest describing the programs and tests, used instead of the tests folder if it exists
+    #[arg(long, default_value_t = String::from("manifest.json"))]
+    manifest: String,
+
     /// Maximum number of clock cycles to simulate a program
     #[arg(short, long, default_value_t = 1_000_000)]
     timeout: usize,
+
+    /// Address the text section starts at when a program doesn't give one
+    #[arg(long, default_value_t = 0, value_parser = parse_address)]
+    text_base: u32,
+
+    /// Address the data section starts at when a program doesn't give one
+    #[arg(long, default_value_t = 0, value_parser = parse_address)]
+    data_base: u32,
+
+    /// Write each program's section starts and symbol addresses to test_output/<program>.map
+    #[arg(long)]
+    symbol_map: bool,
+
+    /// Record a test as crashed and keep grading when the emulator panics on a program
+    #[arg(long)]
+    continue_on_error: bool,
+
+    /// Skip directives the assembler doesn't know with a warning instead of failing,
+    /// for programs written for other assemblers
+    #[arg(long)]
+    lenient_directives: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(about)]
+/// Print the address, encoding, disassembly and source line of every instruction in a program
+pub struct DisasmArgs {
+    /// Assembly source file, or machine code when `--format` says so
+    file: String,
+
+    /// How the file is read
+    #[arg(long, value_enum, default_value_t = InputFormat::Asm)]
+    format: InputFormat,
+
+    /// Address the text section starts at when a program doesn't give one,
+    /// and where machine code is placed
+    #[arg(long, default_value_t = 0, value_parser = parse_address)]
+    text_base: u32,
+
+    /// Address the data section starts at when a program doesn't give one
+    #[arg(long, default_value_t = 0, value_parser = parse_address)]
+    data_base: u32,
+
+    /// Skip directives the assembler doesn't know with a warning instead of failing,
+    /// for programs written for other assemblers
+    #[arg(long)]
+    lenient_directives: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(about)]
+/// Clock a program one cycle at a time, printing what every pipeline stage holds each cycle
+pub struct TraceArgs {
+    /// Assembly source file
+    file: String,
+
+    /// Pipeline to run the program on
+    #[arg(long, value_enum, default_value_t = TracePipeline::Cve2)]
+    pipeline: TracePipeline,
+
+    /// Most clock cycles to trace, stopping sooner if the program halts
+    #[arg(short, long, default_value_t = 100)]
+    cycles: usize,
+
+    /// How each cycle is printed
+    #[arg(long, value_enum, default_value_t = TraceFormat::Table)]
+    format: TraceFormat,
+
+    /// Address the text section starts at when a program doesn't give one
+    #[arg(long, default_value_t = 0, value_parser = parse_address)]
+    text_base: u32,
+
+    /// Address the data section starts at when a program doesn't give one
+    #[arg(long, default_value_t = 0, value_parser = parse_address)]
+    data_base: u32,
+
+    /// Skip directives the assembler doesn't know with a warning instead of failing,
+    /// for programs written for other assemblers
+    #[arg(long)]
+    lenient_directives: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TracePipeline {
+    /// The two-stage CVE2 pipeline
+    Cve2,
+    /// The classic five-stage pipeline
+    FiveStage,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TraceFormat {
+    /// A block of aligned rows per cycle
+    Table,
+    /// One JSON object per cycle, per line
+    Ndjson,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum InputFormat {
+    /// Assembly source
+    Asm,
+    /// Whitespace separated 32-bit words in hex, optionally prefixed with 0x
+    Hex,
+    /// Raw little-endian machine code
+    Binary,
 }
 
-const EXAMPLE_JSON: &str = r##"
-{
-    "registers": {
-        "1": "00000076",
-        "2": "00000000",
-        "9": "00000042"
-    },
-    "data_memory": {
-        "00000064": "00000000",
-        "00000065": "00000001"
-    },
-    "output_buffer": "Alas!\nPoor\tYorick"
+/// Parses an address given in hex (0x...) or decimal
+fn parse_address(input: &str) -> Result<u32, String> {
+    utils::parse_address(input).ok_or_else(|| format!("'{}' is not a valid 32-bit address", input))
+}
+
+const EXAMPLE_MANIFEST: &str = r##"{
+    "version": 1,
+    "programs": ["example_program.s"],
+    "tests": [
+        {
+            "name": "example_test",
+            "input": "input data",
+            "max_cycles": 100000,
+            "expected": {
+                "registers": {
+                    "1": "00000076",
+                    "2": "00000000",
+                    "9": "00000042"
+                },
+                "data_memory": {
+                    "00000064": "00000000",
+                    "00000065": "00000001"
+                },
+                "output_buffer": "Alas!\nPoor\tYorick"
+            }
+        }
+    ]
 }
 "##;
 
+const MANIFEST_README: &str = r##"# Test Manifest
+
+`manifest.json` lists the programs to grade and the tests each one must pass.
+Run `emugator-cli test` from this folder to grade every program against every test.
+
+- `version`: Version of the manifest format. Currently `1`.
+- `programs`: File names in the `programs` folder to grade. Leave it empty to grade every file.
+- `tests`: The tests to run on each program, each with:
+  - `name`: Name of the test, used in the results.
+  - `input`: Text the program can read from the UART. Optional.
+  - `input_bytes`: Bytes the program can read from the UART after `input`, as numbers from 0 to 255. Use this for input that isn't text. Optional.
+  - `max_cycles`: Clock cycles the program may run for. Optional, defaults to `--timeout`.
+  - `expected`: The state checked once the program stops. Every part is optional.
+    - `registers`: Register number to its expected value, as 8 hex digits.
+    - `data_memory`: Address to its expected byte, both as 8 hex digits.
+    - `output_buffer`: Text the program must have written to the UART.
+
+Results are written to `test_output/testresults.csv`. For failed tests, the
+values that did not match are written to `test_output/<test>/<program>_finalstate.json`,
+along with a `stop_reason` if the program did not reach an `ebreak` or `wfi` and an
+`output_mismatch` showing where the UART output first differed from `output_buffer`.
+Pass `--symbol-map` to also write each program's section start addresses and
+symbols to `test_output/<program>.map`. Pass `--continue-on-error` to keep grading
+when the emulator crashes on a program, recording that test as failed.
+"##;
+
 pub fn new_project(args: NewArgs) {
     // create the new project folder relative to the current directory
     let project_path = std::path::Path::new(&args.name);
@@ -150,25 +397,272 @@ pub fn new_project(args: NewArgs) {
     let programs_path = project_path.join("programs");
     std::fs::create_dir(&programs_path).expect("Failed to create programs directory");
 
-    // create the tests folder
-    let tests_path = project_path.join("tests");
-    std::fs::create_dir(&tests_path).expect("Failed to create tests directory");
-
-    // create example test in the tests folder
-    let test_name = "example_test";
-    let test_path = tests_path.join(test_name);
-    std::fs::create_dir(&test_path).expect("Failed to create example test directory");
-
-    // populate example test
+    // populate example program and the manifest describing its tests
     let example_program = programs_path.join("example_program.s");
-    let input_path = test_path.join("input.txt");
-    let final_state = test_path.join("final_state.json");
     std::fs::write(&example_program, include_str!("example_program.s"))
         .expect("Failed to create example program");
-    std::fs::write(&input_path, "input data").expect("Failed to create input file");
-    std::fs::write(&final_state, EXAMPLE_JSON).expect("Failed to create final state file");
+    std::fs::write(project_path.join("manifest.json"), EXAMPLE_MANIFEST)
+        .expect("Failed to create manifest");
+    std::fs::write(project_path.join("README.md"), MANIFEST_README)
+        .expect("Failed to create manifest documentation");
 }
 
+/// Assembles a source file, resolving includes and binaries relative to it and
+/// printing any warnings. Errors are listed one per line as `file:line:column`.
+fn assemble_file(
+    file: &str,
+    memory_map: MemoryMap,
+    strict: bool,
+) -> Result<(AssembledProgram, String), String> {
+    let path = std::path::Path::new(file);
+    let source =
+        std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", file, e))?;
+    let folder = path.parent().unwrap_or(std::path::Path::new(""));
+    let options = AssembleOptions {
+        memory_map,
+        resolve_include: Box::new(|include| std::fs::read_to_string(folder.join(include)).ok()),
+        resolve_binary: Box::new(|include| std::fs::read(folder.join(include)).ok()),
+        strict,
+    };
+
+    let program = assemble(&source, options).map_err(|errors| {
+        errors
+            .iter()
+            .map(|error| {
+                let file = error.file.as_deref().unwrap_or(file);
+                format!(
+                    "{}:{}:{}: {}",
+                    file, error.line_number, error.column, error.error_message
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    })?;
+    for warning in &program.warnings {
+        eprintln!(
+            "{}:{}:{}: warning: {}",
+            file, warning.line_number, warning.column, warning.error_message
+        );
+    }
+    Ok((program, source))
+}
+
+/// Assembles the file, or loads its machine code, and lists every instruction
+pub fn disassemble(args: DisasmArgs) -> Result<String, String> {
+    let path = std::path::Path::new(&args.file);
+    let read_error = |e: std::io::Error| format!("Failed to read {}: {}", args.file, e);
+
+    let (program, source) = match args.format {
+        InputFormat::Asm => {
+            let memory_map = MemoryMap::with_sections(SectionBases {
+                text: args.text_base,
+                data: args.data_base,
+            });
+            let (program, source) =
+                assemble_file(&args.file, memory_map, !args.lenient_directives)?;
+            (program, Some(source))
+        }
+        InputFormat::Hex => {
+            let text = std::fs::read_to_string(path).map_err(read_error)?;
+            let bytes = text
+                .split_whitespace()
+                .map(|word| {
+                    let digits = word
+                        .strip_prefix("0x")
+                        .or(word.strip_prefix("0X"))
+                        .unwrap_or(word);
+                    u32::from_str_radix(digits, 16)
+                        .map(u32::to_le_bytes)
+                        .map_err(|_| format!("'{}' is not a 32-bit hex word", word))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .concat();
+            (
+                AssembledProgram::from_machine_code(args.text_base, &bytes),
+                None,
+            )
+        }
+        InputFormat::Binary => {
+            let bytes = std::fs::read(path).map_err(read_error)?;
+            (
+                AssembledProgram::from_machine_code(args.text_base, &bytes),
+                None,
+            )
+        }
+    };
+
+    Ok(program.listing(source.as_deref()))
+}
+
+/// Assembles the file and clocks it until it stops or runs for `cycles`,
+/// listing each stage's instruction and lines along with the registers written
+/// after every cycle
+pub fn trace(args: TraceArgs) -> Result<String, String> {
+    let memory_map = MemoryMap::with_sections(SectionBases {
+        text: args.text_base,
+        data: args.data_base,
+    });
+    let (mut program, _) = assemble_file(&args.file, memory_map, !args.lenient_directives)?;
+    let mut state = match args.pipeline {
+        TracePipeline::Cve2 => AnyEmulatorState::new_cve2(&program),
+        TracePipeline::FiveStage => AnyEmulatorState::new_five_stage(&program),
+    };
+
+    let mut output = String::new();
+    for cycle in 1..=args.cycles {
+        let next = state.clock(&mut program);
+        let writes: Vec<(usize, u32)> = zip(state.registers().x, next.registers().x)
+            .enumerate()
+            .filter(|(_, (before, after))| before != after)
+            .map(|(register, (_, after))| (register, after))
+            .collect();
+        let stages = next.pipeline_snapshot();
+
+        match args.format {
+            TraceFormat::Table => {
+                output.push_str(&format!("cycle {}\n", cycle));
+                for stage in &stages {
+                    let pc = stage.pc.map_or("-".into(), |pc| format!("{:#010x}", pc));
+                    let instruction = match stage.pc {
+                        _ if stage.bubble => "bubble".to_string(),
+                        Some(pc) => program.disassemble(pc).unwrap_or_default(),
+                        None => String::new(),
+                    };
+                    let signals: Vec<String> = stage
+                        .signals
+                        .iter()
+                        .map(|(name, value)| match value {
+                            Some(value) => format!("{}={:#x}", name, value),
+                            None => format!("{}=-", name),
+                        })
+                        .collect();
+                    output.push_str(
+                        format!(
+                            "  {:<4} {:<10}  {:<24} {}",
+                            stage.name,
+                            pc,
+                            instruction,
+                            signals.join(" ")
+                        )
+                        .trim_end(),
+                    );
+                    output.push('\n');
+                }
+                if !writes.is_empty() {
+                    let writes: Vec<String> = writes
+                        .iter()
+                        .map(|(register, value)| format!("x{}={:#010x}", register, value))
+                        .collect();
+                    output.push_str(&format!("  writes {}\n", writes.join(" ")));
+                }
+            }
+            TraceFormat::Ndjson => {
+                let stages: Vec<serde_json::Value> = stages
+                    .iter()
+                    .map(|stage| {
+                        let signals: serde_json::Map<String, serde_json::Value> = stage
+                            .signals
+                            .iter()
+                            .map(|(name, value)| (name.to_string(), (*value).into()))
+                            .collect();
+                        serde_json::json!({
+                            "name": stage.name,
+                            "pc": stage.pc,
+                            "instruction": stage.instruction,
+                            "bubble": stage.bubble,
+                            "signals": signals,
+                        })
+                    })
+                    .collect();
+                let writes: serde_json::Map<String, serde_json::Value> = writes
+                    .iter()
+                    .map(|(register, value)| (format!("x{}", register), (*value).into()))
+                    .collect();
+                let line = serde_json::json!({
+                    "cycle": cycle,
+                    "stages": stages,
+                    "register_writes": writes,
+                });
+                output.push_str(&line.to_string());
+                output.push('\n');
+            }
+        }
+
+        state = next;
+        if state.has_terminated() || state.stop_reason().is_some() {
+            break;
+        }
+    }
+
+    Ok(output)
+}
+
+/// Reads tests laid out as one folder per test, holding the UART input and the
+/// expected final state
+fn read_test_folder(tests: &str) -> Vec<Test> {
+    std::fs::read_dir(tests)
+        .expect("Failed to read tests dir")
+        .filter_map(|entry| {
+            let entry = entry.expect("Failed to read entry");
+            let path = entry.path();
+            if path.is_dir() {
+                let test_name = path.file_stem()?.to_str()?.to_string();
+                let mut input = None;
+                let mut expected_state: Option<OutputState> = None;
+
+                // read files in test directory
+                for entry in std::fs::read_dir(path.as_path()).expect("Failed to read") {
+                    let entry = entry.expect("Failed to read entry");
+                    if entry.path().is_file() {
+                        let file_path = entry.path();
+                        let name = file_path.file_stem()?.to_str()?.to_string();
+
+                        if name.contains("input") {
+                            input = Some(std::fs::read_to_string(file_path).ok()?);
+                        } else if name.contains("state") || name.contains("registers") {
+                            let file = std::fs::File::open(file_path)
+                                .expect("Failed to open expected state file.");
+                            expected_state = Some(
+                                serde_json::from_reader(file)
+                                    .expect("Failed to read JSON, improperly formatted."),
+                            );
+                        }
+                    }
+                }
+                Some(Test {
+                    name: test_name,
+                    input: input.unwrap_or_default(),
+                    input_bytes: Vec::new(),
+                    expected_state: expected_state.unwrap_or_default(),
+                    max_cycles: None,
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// How a single test went for a single program
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum TestOutcome {
+    Passed,
+    /// Terminated, but with the wrong final state
+    #[default]
+    WrongAnswer,
+    /// Still running after its cycle limit
+    TimedOut,
+    /// Stopped on an instruction it could not execute
+    Faulted,
+    /// The program did not assemble so the test was never run
+    NotAssembled,
+    /// The emulator panicked, only recorded with `--continue-on-error`
+    Crashed,
+}
+
+/// UART mismatches listed by `finish_up`, the rest are only in the results
+const MAX_SHOWN_MISMATCHES: usize = 5;
+
 #[derive(Debug, Default)]
 pub struct TestInfo {
     programs: Vec<(String, Option<AssembledProgram>)>,
@@ -176,15 +670,42 @@ pub struct TestInfo {
     pub curr_prog: usize,
     pub curr_test: usize,
     output_path: std::path::PathBuf,
-    test_results: Vec<Vec<bool>>,
+    test_results: Vec<Vec<TestOutcome>>,
+    /// "program/test: mismatch" for every test whose UART output was wrong
+    output_mismatches: Vec<String>,
     timeout: usize,
+    continue_on_error: bool,
 }
 
 impl TestInfo {
     pub fn prepare_to_test(&mut self, args: TestArgs) {
         self.timeout = args.timeout;
+        self.output_mismatches.clear();
+        self.continue_on_error = args.continue_on_error;
         self.curr_prog = 0;
         self.curr_test = 0;
+        let memory_map = MemoryMap::with_sections(SectionBases {
+            text: args.text_base,
+            data: args.data_base,
+        });
+
+        // the manifest takes the place of the tests folder when there is one
+        let manifest_path = std::path::Path::new(&args.manifest);
+        let manifest = manifest_path.is_file().then(|| {
+            let json = std::fs::read_to_string(manifest_path).expect("Failed to read manifest");
+            Manifest::parse(&json).unwrap_or_else(|error| panic!("{}", error))
+        });
+        let is_graded = |file_name: &str| {
+            manifest.as_ref().is_none_or(|manifest| {
+                manifest.programs.is_empty() || manifest.programs.iter().any(|p| p == file_name)
+            })
+        };
+        let output_root = match manifest {
+            Some(_) => manifest_path.parent(),
+            None => std::path::Path::new(&args.tests).parent(),
+        }
+        .expect("Cannot get parent of test dir")
+        .to_path_buf();
 
         // get (name, source) pairs from the programs folder
         self.programs = std::fs::read_dir(&args.programs)
@@ -192,11 +713,31 @@ impl TestInfo {
             .filter_map(|entry| {
                 let entry = entry.expect("Failed to read entry");
                 let path = entry.path();
-                if path.is_file() {
+                if path.is_file() && is_graded(path.file_name()?.to_str()?) {
                     let name = path.file_stem()?.to_str()?.to_string();
                     let source = std::fs::read_to_string(path).ok()?;
-                    match assemble(&source) {
-                        Ok(program) => Some((name, Some(program))),
+                    // Includes and binaries are resolved relative to the programs folder
+                    let folder = std::path::Path::new(&args.programs);
+                    let options = AssembleOptions {
+                        memory_map,
+                        resolve_include: Box::new(|include| {
+                            std::fs::read_to_string(folder.join(include)).ok()
+                        }),
+                        resolve_binary: Box::new(|include| {
+                            std::fs::read(folder.join(include)).ok()
+                        }),
+                        strict: !args.lenient_directives,
+                    };
+                    match assemble(&source, options) {
+                        Ok(program) => {
+                            for warning in &program.warnings {
+                                println!(
+                                    "Warning in {} at line {}: {}",
+                                    name, warning.line_number, warning.error_message
+                                );
+                            }
+                            Some((name, Some(program)))
+                        }
                         Err(err) => {
                             println!("Failed to assemble {}: {:?}", name, err);
                             Some((name, None))
@@ -208,58 +749,41 @@ impl TestInfo {
             })
             .collect::<Vec<_>>();
 
-        self.tests = std::fs::read_dir(&args.tests)
-            .expect("Failed to read tests dir")
-            .filter_map(|entry| {
-                let entry = entry.expect("Failed to read entry");
-                let path = entry.path();
-                if path.is_dir() {
-                    let test_name = path.file_stem()?.to_str()?.to_string();
-                    let mut input = None;
-                    let mut expected_state: Option<OutputState> = None;
-
-                    // read files in test directory
-                    for entry in std::fs::read_dir(path.as_path()).expect("Failed to read") {
-                        let entry = entry.expect("Failed to read entry");
-                        if entry.path().is_file() {
-                            let file_path = entry.path();
-                            let name = file_path.file_stem()?.to_str()?.to_string();
-
-                            if name.contains("input") {
-                                input = Some(std::fs::read_to_string(file_path).ok()?);
-                            } else if name.contains("state") || name.contains("registers") {
-                                let file = std::fs::File::open(file_path)
-                                    .expect("Failed to open expected state file.");
-                                expected_state = Some(
-                                    serde_json::from_reader(file)
-                                        .expect("Failed to read JSON, improperly formatted."),
-                                );
-                            }
-                        }
-                    }
-                    Some(Test {
-                        name: test_name,
-                        input: input.unwrap_or_default(),
-                        expected_state: expected_state.unwrap_or_default(),
-                    })
-                } else {
-                    None
-                }
-            })
-            .collect::<Vec<_>>();
+        self.tests = match manifest {
+            Some(manifest) => manifest
+                .tests
+                .into_iter()
+                .map(|test| Test {
+                    name: test.name,
+                    input: test.input,
+                    input_bytes: test.input_bytes,
+                    expected_state: test.expected,
+                    max_cycles: test.max_cycles,
+                })
+                .collect(),
+            None => read_test_folder(&args.tests),
+        };
 
         // fill test results
-        self.test_results = vec![vec![false; self.tests.len()]; self.programs.len()];
+        self.test_results =
+            vec![vec![TestOutcome::default(); self.tests.len()]; self.programs.len()];
 
         // check that output dir exists (or create it) and is valid
-        self.output_path = std::path::Path::new(&args.tests)
-            .parent()
-            .expect("Cannot get parent of test dir")
-            .join("test_output");
+        self.output_path = output_root.join("test_output");
         if !std::fs::exists(&self.output_path).expect("Can't check if output directory exists") {
             std::fs::create_dir(&self.output_path).expect("Failed to create test output directory");
         }
 
+        if args.symbol_map {
+            for (name, program) in &self.programs {
+                if let Some(program) = program {
+                    let map_path = self.output_path.join(format!("{}.map", name));
+                    std::fs::write(map_path, program.symbol_map())
+                        .expect("Failed to write symbol map");
+                }
+            }
+        }
+
         let mut tests_str: String = String::new();
         for test in &self.tests {
             tests_str.push_str(&format!(",{}", test.name));
@@ -282,20 +806,38 @@ impl TestInfo {
         let (name, program) = &self.programs[self.curr_prog];
         if let Some(program) = program {
             let test = &self.tests[self.curr_test];
+            let max_cycles = test.max_cycles.unwrap_or(self.timeout);
 
-            let mut starting_state = EmulatorState::<CVE2Pipeline>::new(&program);
-            starting_state
-                .data_memory
-                .set_serial_input(test.input.replace("\r\n", "\n").as_bytes());
-            let starting_state = starting_state;
-
-            let ending_state =
-                starting_state.clock_until_break(&program, &BTreeSet::new(), self.timeout);
-
-            let state_diff = test.expected_state.validate(&ending_state);
-            let pass = state_diff.is_none();
+            let (outcome, state_diff) = if self.continue_on_error {
+                // A panic only fails this test instead of ending the batch
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    run_test(program, test, max_cycles)
+                }))
+                .unwrap_or_else(|payload| {
+                    let message = payload
+                        .downcast_ref::<&str>()
+                        .map(|message| message.to_string())
+                        .or_else(|| payload.downcast_ref::<String>().cloned())
+                        .unwrap_or_default();
+                    let diff = OutputState {
+                        stop_reason: Some(format!("Emulator crashed: {}", message)),
+                        ..Default::default()
+                    };
+                    (TestOutcome::Crashed, Some(diff))
+                })
+            } else {
+                run_test(program, test, max_cycles)
+            };
+            let pass = outcome == TestOutcome::Passed;
 
-            self.test_results[self.curr_prog][self.curr_test] = pass;
+            self.test_results[self.curr_prog][self.curr_test] = outcome;
+            if let Some(mismatch) = state_diff
+                .as_ref()
+                .and_then(|diff| diff.output_mismatch.as_ref())
+            {
+                self.output_mismatches
+                    .push(format!("{}/{}: {}", name, test.name, mismatch));
+            }
 
             if !pass {
                 let test_dir = self.output_path.join(&test.name);
@@ -326,6 +868,8 @@ impl TestInfo {
                 let test_result_path = test_dir.join(file_name);
                 let _ = std::fs::remove_file(&test_result_path);
             }
+        } else {
+            self.test_results[self.curr_prog][self.curr_test] = TestOutcome::NotAssembled;
         }
 
         // move to the next test
@@ -354,11 +898,23 @@ impl TestInfo {
 
         for (prog, test_results) in self.programs.iter().zip(self.test_results.iter()) {
             let test_count = test_results.len();
-            let passed_count = test_results.iter().filter(|&&val| val).count();
+            let passed_count = test_results
+                .iter()
+                .filter(|&&outcome| outcome == TestOutcome::Passed)
+                .count();
 
             let str = test_results
                 .iter()
-                .map(|val| format!(",{}", if *val { "PASSED" } else { "FAILED" }))
+                .map(|&outcome| {
+                    format!(
+                        ",{}",
+                        if outcome == TestOutcome::Passed {
+                            "PASSED"
+                        } else {
+                            "FAILED"
+                        }
+                    )
+                })
                 .collect::<Vec<String>>()
                 .join("");
 
@@ -368,10 +924,51 @@ impl TestInfo {
     }
 
     pub fn finish_up(&self) -> String {
-        format!(
-            "Done! The difference between ending states for failed tests can be found in: {:?}",
+        let mut message = format!(
+            "Done! {}\nThe difference between ending states for failed tests can be found in: {:?}",
+            self.summary(),
             self.output_path.to_str()
-        )
+        );
+        for mismatch in self.output_mismatches.iter().take(MAX_SHOWN_MISMATCHES) {
+            message.push_str(&format!("\n{}", mismatch));
+        }
+        if self.output_mismatches.len() > MAX_SHOWN_MISMATCHES {
+            message.push_str(&format!(
+                "\n...and {} more UART mismatches",
+                self.output_mismatches.len() - MAX_SHOWN_MISMATCHES
+            ));
+        }
+        message
+    }
+
+    /// Counts how each test went, with programs that did not assemble counted once
+    fn summary(&self) -> String {
+        let count = |outcome: TestOutcome| {
+            self.test_results
+                .iter()
+                .flatten()
+                .filter(|&&result| result == outcome)
+                .count()
+        };
+        let not_assembled = self
+            .programs
+            .iter()
+            .filter(|(_, program)| program.is_none())
+            .count();
+
+        let mut summary = format!(
+            "{}/{} tests passed, {} wrong answers, {} timeouts, {} faults",
+            count(TestOutcome::Passed),
+            self.test_results.iter().flatten().count(),
+            count(TestOutcome::WrongAnswer),
+            count(TestOutcome::TimedOut),
+            count(TestOutcome::Faulted),
+        );
+        if self.continue_on_error {
+            summary.push_str(&format!(", {} crashes", count(TestOutcome::Crashed)));
+        }
+        summary.push_str(&format!(", {} programs failed to assemble", not_assembled));
+        summary
     }
 
     pub fn num_programs(&self) -> usize {
@@ -394,3 +991,514 @@ impl TestInfo {
             .map_or("", |p| p.name.as_str())
     }
 }
+
+/// Runs one test on a program, returning how it went and, unless it passed,
+/// the values that did not match
+fn run_test(
+    program: &AssembledProgram,
+    test: &Test,
+    max_cycles: usize,
+) -> (TestOutcome, Option<OutputState>) {
+    let mut starting_state = EmulatorState::<CVE2Pipeline>::new(program);
+    starting_state
+        .data_memory
+        .set_serial_input(test.input.replace("\r\n", "\n").as_bytes());
+    starting_state
+        .data_memory
+        .push_serial_input(&test.input_bytes);
+    let starting_state = starting_state;
+
+    let ending_state = starting_state.clock_until_break(program, &BTreeSet::new(), max_cycles);
+
+    let mut state_diff = test.expected_state.validate(&ending_state);
+    // Programs that never finish fail even if their state happens to match
+    let outcome = if !ending_state.has_terminated() {
+        let (outcome, reason) = match ending_state.pipeline.stop_reason() {
+            Some(reason) => (TestOutcome::Faulted, reason.to_string()),
+            None => (
+                TestOutcome::TimedOut,
+                format!("Did not terminate within {} cycles", max_cycles),
+            ),
+        };
+        state_diff.get_or_insert_default().stop_reason = Some(reason);
+        outcome
+    } else if state_diff.is_some() {
+        TestOutcome::WrongAnswer
+    } else {
+        TestOutcome::Passed
+    };
+    (outcome, state_diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    /// Creates an empty project, lets `setup` add its files, then grades it
+    /// with the given command line
+    fn grade(name: &str, setup: impl FnOnce(&Path), command: &[&str]) -> String {
+        grade_and_inspect(name, setup, command, |project, _| {
+            std::fs::read_to_string(project.join("test_output/testresults.csv")).unwrap()
+        })
+    }
+
+    /// Like `grade`, but returns what `inspect` reads from the graded project
+    /// before it is removed
+    fn grade_and_inspect<T>(
+        name: &str,
+        setup: impl FnOnce(&Path),
+        command: &[&str],
+        inspect: impl FnOnce(&Path, &TestInfo) -> T,
+    ) -> T {
+        let project = std::env::temp_dir().join(format!("emugator-cli-{}", name));
+        let _ = std::fs::remove_dir_all(&project);
+        std::fs::create_dir_all(project.join("programs")).unwrap();
+        std::fs::create_dir_all(project.join("tests")).unwrap();
+        setup(&project);
+
+        let (programs, tests, manifest) = (
+            project.join("programs"),
+            project.join("tests"),
+            project.join("manifest.json"),
+        );
+        let mut args = vec![
+            "emugator-cli",
+            "test",
+            "--programs",
+            programs.to_str().unwrap(),
+            "--tests",
+            tests.to_str().unwrap(),
+            "--manifest",
+            manifest.to_str().unwrap(),
+        ];
+        args.extend(command);
+        let Command::Test(test_args) = Arguments::try_parse_from(args).unwrap().command else {
+            panic!("Expected the test command");
+        };
+
+        let mut tester = TestInfo::default();
+        tester.prepare_to_test(test_args);
+        while tester.run_curr_test() {}
+
+        let inspected = inspect(&project, &tester);
+        let _ = std::fs::remove_dir_all(&project);
+        inspected
+    }
+
+    #[test]
+    fn section_bases() {
+        let setup = |project: &Path| {
+            std::fs::write(
+                project.join("programs/student.s"),
+                "auipc x1, 0\nebreak\n.data\nvalue: .byte 7",
+            )
+            .unwrap();
+            std::fs::create_dir(project.join("tests/check")).unwrap();
+            std::fs::write(
+                project.join("tests/check/final_state.json"),
+                r#"{
+                    "registers": { "1": "00400000" },
+                    "data_memory": { "10010000": "00000007" },
+                    "output_buffer": ""
+                }"#,
+            )
+            .unwrap();
+        };
+
+        let results = grade(
+            "bases",
+            setup,
+            &["--text-base", "0x00400000", "--data-base", "0x10010000"],
+        );
+        assert!(results.contains("student,PASSED (1/1)"), "{}", results);
+
+        // Without the flags the program is placed at 0 and fails the test
+        let results = grade("no-bases", setup, &[]);
+        assert!(results.contains("student,FAILED (0/1)"), "{}", results);
+    }
+
+    #[test]
+    fn symbol_map() {
+        let setup = |project: &Path| {
+            std::fs::write(
+                project.join("programs/student.s"),
+                "main: ebreak\n.data\nvalue: .byte 7",
+            )
+            .unwrap();
+            std::fs::write(project.join("programs/broken.s"), "bogus x1").unwrap();
+            std::fs::create_dir(project.join("tests/check")).unwrap();
+            std::fs::write(
+                project.join("tests/check/final_state.json"),
+                r#"{ "registers": {}, "data_memory": {}, "output_buffer": "" }"#,
+            )
+            .unwrap();
+        };
+        let read_maps = |project: &Path, _: &TestInfo| {
+            let output = project.join("test_output");
+            (
+                std::fs::read_to_string(output.join("student.map")).ok(),
+                output.join("broken.map").exists(),
+            )
+        };
+
+        let (map, broken_map) = grade_and_inspect(
+            "symbol-map",
+            setup,
+            &["--symbol-map", "--data-base", "0x100"],
+            read_maps,
+        );
+        let map = map.expect("Symbol map should be written");
+        assert!(map.contains(".data    0x00000100"), "{}", map);
+        assert!(map.contains("0x00000000  main    .text"), "{}", map);
+        assert!(map.contains("0x00000100  value   .data"), "{}", map);
+        assert!(!broken_map);
+
+        // Maps are only written when asked for
+        let (map, _) = grade_and_inspect("no-symbol-map", setup, &[], read_maps);
+        assert!(map.is_none());
+    }
+
+    #[test]
+    fn disasm() {
+        let folder = std::env::temp_dir().join("emugator-cli-disasm");
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let write = |name: &str, contents: &[u8]| {
+            let path = folder.join(name);
+            std::fs::write(&path, contents).unwrap();
+            path.to_str().unwrap().to_string()
+        };
+        let disasm = |args: &[&str]| {
+            let args = ["emugator-cli", "disasm"].iter().chain(args);
+            let Command::Disasm(disasm_args) = Arguments::try_parse_from(args).unwrap().command
+            else {
+                panic!("Expected the disasm command");
+            };
+            disassemble(disasm_args)
+        };
+
+        let source = write("program.s", b"main: addi x1, x0, 5\njal x0, main");
+        let listing = disasm(&[&source, "--text-base", "0x100"]).unwrap();
+        assert_eq!(
+            listing.lines().collect::<Vec<_>>(),
+            vec![
+                "0x00000100  00500093  addi x1, x0, 5                   1: main: addi x1, x0, 5",
+                "0x00000104  FFDFF06F  jal x0, main                     2: jal x0, main",
+            ]
+        );
+
+        // Machine code lists the same instructions without the source
+        let hex = write("program.hex", b"00500093\n0xFFDFF06F\n");
+        let binary = write("program.bin", &[0x93, 0x00, 0x50, 0x00]);
+        let listing = disasm(&["--format", "hex", &hex]).unwrap();
+        assert_eq!(
+            listing,
+            "0x00000000  00500093  addi x1, x0, 5\n0x00000004  FFDFF06F  jal x0, 0x0\n"
+        );
+        let listing = disasm(&["--format", "binary", &binary]).unwrap();
+        assert_eq!(listing, "0x00000000  00500093  addi x1, x0, 5\n");
+
+        let broken = write("broken.s", b"bogus x1");
+        let error = disasm(&[&broken]).unwrap_err();
+        assert!(error.starts_with(&format!("{}:1:", broken)), "{}", error);
+
+        // Directives from other assemblers only fail in strict mode
+        let foreign = write("foreign.s", b".globl main\nmain: addi x1, x0, 5");
+        assert!(disasm(&[&foreign]).is_err());
+        let listing = disasm(&[&foreign, "--lenient-directives"]).unwrap();
+        assert_eq!(listing.lines().count(), 1, "{}", listing);
+        let bad_hex = write("bad.hex", b"00500093 xyz");
+        assert_eq!(
+            disasm(&["--format", "hex", &bad_hex]).unwrap_err(),
+            "'xyz' is not a 32-bit hex word"
+        );
+
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn trace_cycles() {
+        let folder = std::env::temp_dir().join("emugator-cli-trace");
+        let _ = std::fs::remove_dir_all(&folder);
+        std::fs::create_dir_all(&folder).unwrap();
+        let source = folder.join("program.s");
+        std::fs::write(&source, "addi x1, x0, 5\nadd x2, x1, x1\nebreak").unwrap();
+        let run_trace = |args: &[&str]| {
+            let command = ["emugator-cli", "trace", source.to_str().unwrap()];
+            let args = command.iter().chain(args);
+            let Command::Trace(trace_args) = Arguments::try_parse_from(args).unwrap().command
+            else {
+                panic!("Expected the trace command");
+            };
+            trace(trace_args).unwrap()
+        };
+
+        // One line per cycle until the ebreak reaches decode
+        for pipeline in ["cve2", "five-stage"] {
+            let trace = run_trace(&["--pipeline", pipeline, "--format", "ndjson"]);
+            let cycles: Vec<serde_json::Value> = trace
+                .lines()
+                .map(|line| serde_json::from_str(line).unwrap())
+                .collect();
+            let last = cycles.last().unwrap();
+            let decode = &last["stages"][1];
+            assert_eq!(decode["name"], "ID", "{}", pipeline);
+            assert_eq!(decode["pc"], 8, "{}", pipeline);
+            assert!(cycles.len() < 100, "{}", pipeline);
+        }
+
+        let trace = run_trace(&["--cycles", "2"]);
+        assert_eq!(trace.matches("cycle ").count(), 2, "{}", trace);
+        assert!(
+            trace.contains("  ID   0x00000004  add x2, x1, x1"),
+            "{}",
+            trace
+        );
+        assert!(trace.contains("  writes x1=0x00000005"), "{}", trace);
+
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn invalid_base() {
+        let result = Arguments::try_parse_from(["emugator-cli", "test", "--text-base", "0xZZ"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn manifest() {
+        let results = grade(
+            "manifest",
+            |project| {
+                let count = "addi x1, x0, 0\nloop: addi x1, x1, 1\naddi x2, x0, 50\nblt x1, x2, loop\nebreak";
+                std::fs::write(project.join("programs/count.s"), count).unwrap();
+                std::fs::write(project.join("programs/ignored.s"), "ebreak").unwrap();
+                std::fs::write(
+                    project.join("manifest.json"),
+                    r#"{
+                        "version": 1,
+                        "programs": ["count.s"],
+                        "tests": [
+                            { "name": "finishes", "expected": { "registers": { "1": "00000032" } } },
+                            { "name": "too_slow", "max_cycles": 20, "expected": { "registers": { "1": "00000032" } } }
+                        ]
+                    }"#,
+                )
+                .unwrap();
+            },
+            &[],
+        );
+
+        assert!(
+            results.starts_with("program name,finishes,too_slow\n"),
+            "{}",
+            results
+        );
+        assert!(results.contains("count,PASSED,FAILED (1/2)"), "{}", results);
+        assert!(!results.contains("ignored"), "{}", results);
+    }
+
+    #[test]
+    fn non_terminating() {
+        let diff = grade_and_inspect(
+            "non_terminating",
+            |project| {
+                let spin = "addi x1, x0, 5\nloop: jal x0, loop";
+                std::fs::write(project.join("programs/spin.s"), spin).unwrap();
+                std::fs::create_dir(project.join("tests/check")).unwrap();
+                std::fs::write(
+                    project.join("tests/check/final_state.json"),
+                    r#"{ "registers": { "1": "00000005" } }"#,
+                )
+                .unwrap();
+            },
+            &["--timeout", "100"],
+            |project, _| {
+                std::fs::read_to_string(project.join("test_output/check/spin_finalstate.json"))
+                    .unwrap()
+            },
+        );
+
+        let diff: OutputState = serde_json::from_str(&diff).unwrap();
+        assert!(diff.registers.is_empty());
+        assert_eq!(
+            diff.stop_reason.as_deref(),
+            Some("Did not terminate within 100 cycles")
+        );
+    }
+
+    #[test]
+    fn continue_on_error() {
+        let setup = |project: &Path| {
+            let programs = project.join("programs");
+            std::fs::write(programs.join("correct.s"), "addi x1, x0, 5\nebreak").unwrap();
+            std::fs::write(programs.join("wrong.s"), "addi x1, x0, 4\nebreak").unwrap();
+            std::fs::write(programs.join("spin.s"), "loop: jal x0, loop").unwrap();
+            std::fs::write(programs.join("broken.s"), "bogus x1").unwrap();
+            // Jumping to a misaligned address panics the emulator
+            std::fs::write(
+                programs.join("crash.s"),
+                "addi x1, x0, 2\njalr x0, x1, 0\nebreak",
+            )
+            .unwrap();
+            std::fs::create_dir(project.join("tests/check")).unwrap();
+            std::fs::write(
+                project.join("tests/check/final_state.json"),
+                r#"{ "registers": { "1": "00000005" } }"#,
+            )
+            .unwrap();
+        };
+
+        let (results, summary, crash) = grade_and_inspect(
+            "continue-on-error",
+            setup,
+            &["--timeout", "100", "--continue-on-error"],
+            |project, tester| {
+                (
+                    std::fs::read_to_string(project.join("test_output/testresults.csv")).unwrap(),
+                    tester.summary(),
+                    std::fs::read_to_string(
+                        project.join("test_output/check/crash_finalstate.json"),
+                    )
+                    .unwrap(),
+                )
+            },
+        );
+
+        // Every program is graded even though one crashed
+        for program in [
+            "correct,PASSED",
+            "wrong,FAILED",
+            "spin,FAILED",
+            "crash,FAILED",
+        ] {
+            assert!(results.contains(program), "{}", results);
+        }
+        assert_eq!(
+            summary,
+            "1/5 tests passed, 1 wrong answers, 1 timeouts, 0 faults, 1 crashes, \
+             1 programs failed to assemble"
+        );
+        let crash: OutputState = serde_json::from_str(&crash).unwrap();
+        assert_eq!(
+            crash.stop_reason.as_deref(),
+            Some("Emulator crashed: PC must be on a 4-byte boundary")
+        );
+    }
+
+    #[test]
+    fn uart_mismatch() {
+        assert_eq!(output_mismatch(b"Hello", b"Hello"), None);
+        assert_eq!(
+            output_mismatch(b"The quick brown fox\n", b"The quick brown Fox\n").as_deref(),
+            Some(
+                "UART output differs at byte 16: expected \"...k brown fox\\n\", \
+                 got \"...k brown Fox\\n\""
+            )
+        );
+        // Output that stops early differs where it ends
+        assert_eq!(
+            output_mismatch(b"abc", b"ab").as_deref(),
+            Some("UART output differs at byte 2: expected \"abc\", got \"ab\"")
+        );
+
+        let finish = grade_and_inspect(
+            "uart-mismatch",
+            |project| {
+                let program = "addi x1, x0, 0x41\nsb x1, 0(x0)\nebreak";
+                std::fs::write(project.join("programs/echo.s"), program).unwrap();
+                std::fs::write(
+                    project.join("manifest.json"),
+                    r#"{
+                        "version": 1,
+                        "tests": [{ "name": "greets", "expected": { "output_buffer": "B" } }]
+                    }"#,
+                )
+                .unwrap();
+            },
+            &[],
+            |_, tester| tester.finish_up(),
+        );
+        assert!(
+            finish.contains("echo/greets: UART output differs at byte 0"),
+            "{}",
+            finish
+        );
+    }
+
+    #[test]
+    fn uart_input() {
+        let results = grade(
+            "uart-input",
+            |project| {
+                // Echoes bytes back until a zero byte arrives
+                let echo = "loop: lbu x1, 0xF4(x0)\nandi x1, x1, 1\nbeq x1, x0, loop\n\
+                            lbu x2, 0xF0(x0)\nbeq x2, x0, done\nsb x2, 0xF0(x0)\njal x0, loop\n\
+                            done: ebreak";
+                std::fs::write(project.join("programs/echo.s"), echo).unwrap();
+                std::fs::write(
+                    project.join("manifest.json"),
+                    r#"{
+                        "version": 1,
+                        "tests": [
+                            { "name": "text", "input": "hi", "input_bytes": [0], "expected": { "output_buffer": "hi" } },
+                            { "name": "bytes", "input_bytes": [79, 75, 0], "expected": { "output_buffer": "OK" } },
+                            { "name": "wrong", "input_bytes": [79, 0], "expected": { "output_buffer": "OK" } }
+                        ]
+                    }"#,
+                )
+                .unwrap();
+            },
+            &[],
+        );
+        assert!(
+            results.contains("echo,PASSED,PASSED,FAILED (2/3)"),
+            "{}",
+            results
+        );
+    }
+
+    #[test]
+    fn incbin_data() {
+        let results = grade(
+            "incbin-data",
+            |project| {
+                // Prints the bytes of the included file up to a zero byte
+                let print = "la x3, message\nloop: lbu x2, 0(x3)\nbeq x2, x0, done\n\
+                             wait: lbu x4, 0xF4(x0)\nandi x4, x4, 4\nbeq x4, x0, wait\n\
+                             sb x2, 0xF0(x0)\naddi x3, x3, 1\njal x0, loop\n\
+                             done: lbu x4, 0xF4(x0)\nandi x4, x4, 8\nbeq x4, x0, done\n\
+                             ebreak\n.data\nmessage: .incbin \"message.bin\"";
+                std::fs::write(project.join("programs/print.s"), print).unwrap();
+                std::fs::write(project.join("programs/message.bin"), b"OK\0").unwrap();
+                std::fs::write(
+                    project.join("manifest.json"),
+                    r#"{
+                        "version": 1,
+                        "programs": ["print.s"],
+                        "tests": [
+                            { "name": "message", "expected": { "output_buffer": "OK" } }
+                        ]
+                    }"#,
+                )
+                .unwrap();
+            },
+            &[],
+        );
+        assert!(results.contains("print,PASSED"), "{}", results);
+    }
+
+    #[test]
+    fn manifest_versions() {
+        assert!(Manifest::parse(r#"{ "version": 1, "tests": [] }"#).is_ok());
+        assert!(Manifest::parse(r#"{ "tests": [] }"#).is_err());
+        assert!(Manifest::parse(r#"{ "version": 2, "tests": [] }"#).is_err());
+
+        // The scaffolded example is a valid manifest
+        let manifest = Manifest::parse(EXAMPLE_MANIFEST).unwrap();
+        assert_eq!(manifest.tests.len(), 1);
+        assert_eq!(manifest.tests[0].max_cycles, Some(100000));
+    }
+}
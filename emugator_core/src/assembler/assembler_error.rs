@@ -1,11 +1,16 @@
 use super::{Expression, lexer::Token, rpn::RPN};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AssemblerError {
     pub error_message: String,
     pub line_number: usize,
     pub column: usize,
     pub width: usize,
+    /// Name of the file the error is in, when assembling several files
+    pub file: Option<String>,
+    /// Line and column just past the end of the error, when it ends on a
+    /// later line than it starts. `width` then only covers its first token.
+    pub end: Option<(usize, usize)>,
 }
 
 impl AssemblerError {
@@ -15,6 +20,8 @@ impl AssemblerError {
             line_number,
             column,
             width,
+            file: None,
+            end: None,
         }
     }
 
@@ -24,6 +31,40 @@ impl AssemblerError {
             line_number: token.line,
             column: token.column,
             width: token.width,
+            file: None,
+            end: None,
+        }
+    }
+
+    /// Error covering everything from the start of `first` to the end of `last`.
+    /// Tokens substituted into a macro keep their own columns, so `last` can
+    /// end before `first` starts, in which case only `first` is covered.
+    pub fn from_token_span(error_message: String, first: &Token, last: &Token) -> Self {
+        if last.line == first.line {
+            Self {
+                width: (last.column + last.width)
+                    .saturating_sub(first.column)
+                    .max(first.width),
+                ..Self::from_token(error_message, first)
+            }
+        } else {
+            Self {
+                end: Some((last.line, last.column + last.width)),
+                ..Self::from_token(error_message, first)
+            }
+        }
+    }
+
+    /// Moves the error to start on `line_number`, keeping its end the same
+    /// number of lines after its start
+    pub fn with_line_number(self, line_number: usize) -> Self {
+        let end = self
+            .end
+            .map(|(line, column)| (line + line_number - self.line_number, column));
+        Self {
+            line_number,
+            end,
+            ..self
         }
     }
 
@@ -31,18 +72,15 @@ impl AssemblerError {
         if let Some(RPN { token: first, .. }) = expression.first() {
             // Safe to unwrap because we know the expression is not empty
             let last = &expression.last().unwrap().token;
-            Self {
-                error_message,
-                line_number: first.line,
-                column: first.column,
-                width: last.column + last.width - first.column,
-            }
+            Self::from_token_span(error_message, first, last)
         } else {
             Self {
                 error_message: error_message + " (empty expression somewhere)",
                 line_number: 0,
                 column: 0,
                 width: 0,
+                file: None,
+                end: None,
             }
         }
     }
@@ -1,4 +1,5 @@
-use std::iter::Enumerate;
+use std::iter::{Chain, Once, Peekable, once};
+use std::str::CharIndices;
 
 use ibig::IBig;
 
@@ -45,40 +46,67 @@ pub struct Token<'a> {
     pub width: usize,
 }
 
+/// Splits source into tokens. Columns count UTF-16 code units like Monaco does,
+/// so a tab is one column and markers line up with the editor. A `\r\n` ends a
+/// line like `\n` does, and any other whitespace, including a lone `\r` or a
+/// non-breaking space, separates tokens like a space.
 #[derive(Clone, Debug)]
 pub struct Lexer<'a> {
     source: &'a str,
-    char_iter: std::iter::Peekable<
-        Enumerate<std::iter::Chain<std::str::Chars<'a>, std::str::Chars<'static>>>,
-    >,
+    /// Each character with its byte offset, ending with an extra newline
+    char_iter: Peekable<Chain<CharIndices<'a>, Once<(usize, char)>>>,
     line: usize,
+    /// Column of the last character taken
     column: usize,
+    /// Column of the next character
+    next_column: usize,
     terminated: bool,
+    /// Set after a '.' directly followed by a digit, so names like `8byte` lex as symbols
+    directive_name: bool,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(source: &'a str) -> Self {
         Lexer {
             source,
-            char_iter: source.chars().chain("\n".chars()).enumerate().peekable(),
+            char_iter: source
+                .char_indices()
+                .chain(once((source.len(), '\n')))
+                .peekable(),
             line: 1,
             column: 0,
+            next_column: 1,
             terminated: false,
+            directive_name: false,
         }
     }
 
     fn next_char(&mut self) -> Option<(usize, char)> {
-        self.column += 1;
-        self.char_iter.next()
+        let next = self.char_iter.next();
+        self.column = self.next_column;
+        if let Some((_, c)) = next {
+            self.next_column += c.len_utf16();
+        }
+        next
+    }
+
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.column = 0;
+        self.next_column = 1;
+    }
+
+    /// Width in columns of the source between two byte offsets
+    fn width(&self, start: usize, end: usize) -> usize {
+        self.source[start..end].encode_utf16().count()
     }
 
     fn skip_whitespace(&mut self) {
-        loop {
-            let next_char = self.char_iter.peek();
-            match next_char {
-                Some((_, ' ' | '\t')) => self.next_char(),
-                _ => break,
-            };
+        while let Some(&(i, c)) = self.char_iter.peek() {
+            if c == '\n' || !c.is_whitespace() || self.source[i..].starts_with("\r\n") {
+                break;
+            }
+            self.next_char();
         }
     }
 
@@ -112,7 +140,7 @@ impl<'a> Lexer<'a> {
                                 "Unexpected EOF while parsing escape sequence.".to_string(),
                                 self.line,
                                 token_col,
-                                end - i,
+                                self.width(i, end),
                             ));
                         }
                     }
@@ -126,7 +154,7 @@ impl<'a> Lexer<'a> {
                     "Unexpected EOF while parsing string".to_string(),
                     self.line,
                     token_col,
-                    end - i,
+                    self.width(i, end),
                 ));
             }
         }
@@ -165,14 +193,18 @@ impl<'a> Iterator for Lexer<'a> {
                                 break;
                             }
                         }
-                        self.line += 1;
-                        self.column = 0;
+                        // The '\r' of a "\r\n" is part of the line ending
+                        let width = self.source[i..end]
+                            .trim_end_matches('\r')
+                            .encode_utf16()
+                            .count();
+                        self.new_line();
 
                         Token {
                             kind: TokenKind::Newline,
                             line,
                             column: token_col,
-                            width: end - i,
+                            width,
                         }
                     }
                     '(' => Token {
@@ -273,12 +305,15 @@ impl<'a> Iterator for Lexer<'a> {
                         column: self.column,
                         width: 1,
                     },
-                    '.' => Token {
-                        kind: TokenKind::Dot,
-                        line: self.line,
-                        column: self.column,
-                        width: 1,
-                    },
+                    '.' => {
+                        self.directive_name = matches!(self.char_iter.peek(), Some((_, '0'..='9')));
+                        Token {
+                            kind: TokenKind::Dot,
+                            line: self.line,
+                            column: self.column,
+                            width: 1,
+                        }
+                    }
                     ',' => Token {
                         kind: TokenKind::Comma,
                         line: self.line,
@@ -291,7 +326,7 @@ impl<'a> Iterator for Lexer<'a> {
                         column: self.column,
                         width: 1,
                     },
-                    '0'..='9' => {
+                    '0'..='9' if !self.directive_name => {
                         // Int token
                         let mut end = i;
                         let token_col = self.column;
@@ -358,7 +393,12 @@ impl<'a> Iterator for Lexer<'a> {
                         let value =
                             IBig::from_str_radix(&self.source[start..end].replace("_", ""), base)
                                 .map_err(|e| {
-                                AssemblerError::new(e.to_string(), self.line, token_col, end - i)
+                                AssemblerError::new(
+                                    e.to_string(),
+                                    self.line,
+                                    token_col,
+                                    self.width(i, end),
+                                )
                             })?;
                         Token {
                             kind: TokenKind::IntLiteral(literal, base, value),
@@ -389,7 +429,7 @@ impl<'a> Iterator for Lexer<'a> {
                                     ),
                                     line: self.line,
                                     column: token_col,
-                                    width: literal.len(),
+                                    width: self.width(i, i + literal.len()),
                                 }
                             }
                         } else {
@@ -397,12 +437,13 @@ impl<'a> Iterator for Lexer<'a> {
                                 kind: TokenKind::StrLiteral(literal, parsed),
                                 line: self.line,
                                 column: token_col,
-                                width: literal.len(),
+                                width: self.width(i, i + literal.len()),
                             }
                         }
                     }
                     _ => {
                         // Symbol token
+                        self.directive_name = false;
                         let mut end = i;
                         let token_col = self.column;
 
@@ -422,7 +463,7 @@ impl<'a> Iterator for Lexer<'a> {
                             kind: TokenKind::Symbol(&self.source[i..end]),
                             line: self.line,
                             column: token_col,
-                            width: end - i,
+                            width: self.width(i, end),
                         }
                     }
                 })
@@ -433,8 +474,7 @@ impl<'a> Iterator for Lexer<'a> {
             // Consume the rest of the line if there was an error
             while let Some((_, c)) = self.next_char() {
                 if c == '\n' {
-                    self.line += 1;
-                    self.column = 0;
+                    self.new_line();
                     break;
                 }
             }
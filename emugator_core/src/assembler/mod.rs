@@ -6,24 +6,28 @@ use std::{
     str::FromStr,
 };
 
-use bimap::BiBTreeMap;
 use ibig::IBig;
 use lexer::{Lexer, Token, TokenKind};
 use peeking_take_while::PeekableExt;
+use preprocessor::{
+    Inclusion, expand_includes, expand_macros, expand_repetitions, fold_label_case, include_names,
+    load_binaries, load_includes,
+};
 use rpn::{Expression, RPN, RPNKind};
 
 use crate::{
     bitmask, bits,
     isa::{
         ISA, Instruction, InstructionBuildErrorType, InstructionDefinition, InstructionFormat,
-        Operands,
+        Operands, csr::csr_address,
     },
     utils::IBigLittleEndianIterator,
 };
 
 pub use address::Address;
 pub use assembler_error::AssemblerError;
-pub use program::{AssembledProgram, Section};
+pub use program::{AssembledProgram, MemoryMap, Section, SectionBases, SourceSpan};
+pub use validation::{find_unused_labels, lint_operands, validate_branch_targets};
 
 #[cfg(test)]
 mod tests;
@@ -31,16 +35,18 @@ mod tests;
 mod address;
 mod assembler_error;
 mod lexer;
+mod preprocessor;
 mod program;
 mod rpn;
+mod validation;
 
 fn aligned(value: u32, alignment: u32) -> u32 {
     (value + bitmask!(alignment)) & !bitmask!(alignment)
 }
 
-fn consume_line<'a>(
+fn consume_line<'a, I: Iterator<Item = Result<Token<'a>, AssemblerError>>>(
     token: &mut Token<'a>,
-    lexer: &mut Peekable<Lexer<'a>>,
+    lexer: &mut Peekable<I>,
 ) -> Result<Vec<Token<'a>>, AssemblerError> {
     let parts = lexer
         .peeking_take_while(|token_result| {
@@ -62,9 +68,9 @@ fn consume_line<'a>(
     Ok(parts)
 }
 
-fn parse_label<'a>(
+fn parse_label<'a, I: Iterator<Item = Result<Token<'a>, AssemblerError>>>(
     token: &mut Token<'a>,
-    lexer: &mut Peekable<Lexer<'a>>,
+    lexer: &mut Peekable<I>,
 ) -> Result<Option<(&'a str, Token<'a>)>, AssemblerError> {
     Ok(if is_kind(lexer.peek(), TokenKind::Colon) {
         // Parse label
@@ -91,7 +97,9 @@ fn parse_label<'a>(
     })
 }
 
-fn parse_expression<'a>(lexer: &mut Peekable<Lexer<'a>>) -> Result<Expression<'a>, AssemblerError> {
+fn parse_expression<'a, I: Iterator<Item = Result<Token<'a>, AssemblerError>>>(
+    lexer: &mut Peekable<I>,
+) -> Result<Expression<'a>, AssemblerError> {
     Expression::shunting_yard(
         &mut lexer
             .peeking_take_while(|token_result| {
@@ -109,12 +117,69 @@ enum Directive<'a> {
     Alignment(u32),
     Symbol(String, (Expression<'a>, Token<'a>)),
     Section(Section, (Expression<'a>, Token<'a>)),
+    StringTable(&'a str, Token<'a>, Vec<Vec<u8>>), // Name, Name token, Null-terminated strings
+    Skipped(AssemblerError),                       // Warning for an unknown directive
+}
+
+/// Name of the label a `.stringtable` gives its `index`th string
+fn string_table_label(name: &str, index: usize) -> String {
+    format!("{}_{}", name, index)
 }
 
-fn parse_directive<'a>(
+/// Name of the constant holding how many strings a `.stringtable` has
+fn string_table_count(name: &str) -> String {
+    format!("{}_count", name)
+}
+
+/// Offset of each string of a string table from the start of its pointer
+/// array, which the strings follow
+fn string_table_offsets(strings: &[Vec<u8>]) -> Vec<u32> {
+    let mut offset = 4 * strings.len() as u32;
+    strings
+        .iter()
+        .map(|string| {
+            let start = offset;
+            offset += string.len() as u32;
+            start
+        })
+        .collect()
+}
+
+/// `org + offset`, where a label `offset` bytes into the section started at
+/// `org` is
+fn org_offset<'a>(org: &str, offset: u32, token: &Token<'a>) -> Expression<'a> {
+    vec![
+        RPN {
+            kind: RPNKind::Variable(org.into()),
+            token: token.clone(),
+        },
+        RPN {
+            kind: RPNKind::Integer(offset.into()),
+            token: token.clone(),
+        },
+        RPN {
+            kind: RPNKind::Add,
+            token: token.clone(),
+        },
+    ]
+    .into()
+}
+
+/// Directives other assemblers emit only for debuggers and linkers, which are
+/// skipped without a warning even when unknown directives are errors
+const IGNORED_DIRECTIVES: &[&str] = &["size", "type", "ident"];
+
+fn is_ignored_directive(directive: &str) -> bool {
+    IGNORED_DIRECTIVES.contains(&directive) || directive.starts_with("cfi_")
+}
+
+fn parse_directive<'a, I: Iterator<Item = Result<Token<'a>, AssemblerError>>>(
     token: &mut Token<'a>,
-    lexer: &mut Peekable<Lexer<'a>>,
+    lexer: &mut Peekable<I>,
     symbol_table: Option<&HashMap<String, Address>>,
+    binaries: &HashMap<String, Option<Vec<u8>>>,
+    bases: SectionBases,
+    strict: bool,
 ) -> Result<Option<Directive<'a>>, AssemblerError> {
     if token.kind == TokenKind::Dot {
         *token = lexer.next().ok_or(AssemblerError::from_token(
@@ -188,7 +253,7 @@ fn parse_directive<'a>(
                         expression
                     } else {
                         vec![RPN {
-                            kind: RPNKind::Integer(0.into()),
+                            kind: RPNKind::Integer(bases.base(&section_str.into()).into()),
                             token: token.clone(),
                         }]
                         .into()
@@ -294,7 +359,10 @@ fn parse_directive<'a>(
                             let mut bytes: Vec<_> =
                                 IBigLittleEndianIterator::from(&value.1).collect();
 
-                            if bytes.len() > width {
+                            // Negative values must also fit the signed range, since the
+                            // byte iterator drops a sign byte of 0xFF after a byte < 0x80
+                            let min = -(IBig::from(1) << (width * 8 - 1));
+                            if bytes.len() > width || value.1 < min {
                                 return Err(AssemblerError {
                                     error_message: format!(
                                         "Value {} is too large for {} bytes.",
@@ -353,6 +421,93 @@ fn parse_directive<'a>(
                     // Return string length
                     Directive::Data(data, 0)
                 }
+                "stringtable" => {
+                    let Some(Ok(
+                        symbol @ Token {
+                            kind: TokenKind::Symbol(name),
+                            ..
+                        },
+                    )) = lexer.next_if(|next| {
+                        matches!(
+                            next,
+                            Ok(Token {
+                                kind: TokenKind::Symbol(_),
+                                ..
+                            })
+                        )
+                    })
+                    else {
+                        return Err(AssemblerError::from_token(
+                            "Expected table name after '.stringtable' directive.".into(),
+                            token,
+                        ));
+                    };
+
+                    let mut strings = vec![];
+                    while is_kind(lexer.peek(), TokenKind::Comma) {
+                        lexer.next(); // Skip comma
+
+                        let string = lexer.next().ok_or(AssemblerError::from_token(
+                            "Unexpected EOF after '.stringtable' directive.".into(),
+                            token,
+                        ))??;
+
+                        if let TokenKind::StrLiteral(_, c) = string.kind {
+                            let mut data = c.into_bytes();
+                            data.push(0u8);
+                            strings.push(data);
+                        } else {
+                            return Err(AssemblerError::from_token(
+                                "Expected string [, string ...] literal after table name in .stringtable directive.".into(),
+                                &string,
+                            ));
+                        }
+                    }
+
+                    if strings.is_empty() {
+                        return Err(AssemblerError::from_token(
+                            "Expected at least one string after table name in .stringtable directive.".into(),
+                            &symbol,
+                        ));
+                    }
+
+                    Directive::StringTable(name, symbol, strings)
+                }
+                "incbin" => {
+                    let file = lexer.next().ok_or(AssemblerError::from_token(
+                        "Unexpected EOF after '.incbin' directive.".into(),
+                        token,
+                    ))??;
+
+                    let TokenKind::StrLiteral(_, name) = &file.kind else {
+                        return Err(AssemblerError::from_token(
+                            "Expected file name string after '.incbin' directive.".into(),
+                            &file,
+                        ));
+                    };
+
+                    match binaries.get(name) {
+                        Some(Some(data)) => Directive::Data(data.clone(), 0),
+                        _ => {
+                            return Err(AssemblerError::from_token(
+                                format!("Could not find file '{}' to include.", name),
+                                &file,
+                            ));
+                        }
+                    }
+                }
+                _ if is_ignored_directive(directive_str) => {
+                    consume_line(token, lexer)?;
+                    return Ok(None);
+                }
+                _ if !strict => {
+                    let warning = AssemblerError::from_token(
+                        format!("Unknown directive '{}' skipped", directive_str),
+                        token,
+                    );
+                    consume_line(token, lexer)?;
+                    return Ok(Some(Directive::Skipped(warning)));
+                }
                 _ => {
                     return Err(AssemblerError::from_token(
                         format!("Unknown directive '{}'", directive_str),
@@ -378,16 +533,27 @@ fn parse_directive<'a>(
     }
 }
 
-fn parse_instruction<'a>(
+fn parse_instruction<'a, I: Iterator<Item = Result<Token<'a>, AssemblerError>>>(
     token: &mut Token<'a>,
-    lexer: &mut Peekable<Lexer<'a>>,
+    lexer: &mut Peekable<I>,
     symbol_table: &HashMap<String, Address>,
     current_address: u32,
-) -> Result<Option<(Instruction, Token<'a>)>, AssemblerError> {
+) -> Result<Option<(Vec<Instruction>, Token<'a>)>, AssemblerError> {
     if let TokenKind::Symbol(instr) = token.kind {
         let instruction_token = token.clone();
         let parts = consume_line(token, lexer)?;
 
+        if pseudo_instruction_size(instr).is_some() {
+            let instructions = expand_pseudo_instruction(
+                instr,
+                &parts,
+                &instruction_token,
+                symbol_table,
+                current_address,
+            )?;
+            return Ok(Some((instructions, instruction_token)));
+        }
+
         // Parse instruction
         let def = ISA::from_str(&instr.to_uppercase())
             .map_err(|_| {
@@ -397,13 +563,14 @@ fn parse_instruction<'a>(
 
         let (operands, (rd_token, rs1_token, rs2_token, imm_expression)) =
             match (def.format, &parts.as_slice()) {
-                // Special syntax for ECALL and EBREAK
+                // Special syntax for ECALL, EBREAK and WFI
                 // ECALL
                 (InstructionFormat::I, &[]) if def.opcode == ISA::ECALL.definition().opcode => (
                     Operands {
                         imm: match instr.to_uppercase().as_str() {
                             "ECALL" => 0,
                             "EBREAK" => 1,
+                            "WFI" => 0x105,
                             _ => unreachable!(),
                         },
                         ..Default::default()
@@ -572,6 +739,70 @@ fn parse_instruction<'a>(
                         (None, Some(rs1_token), Some(rs2_token), Some(imm_expression)),
                     )
                 }
+                // CSR instructions, naming the CSR or giving its address
+                // CSRRS rd, csr, rs1
+                // CSRRSI rd, csr, uimm
+                (
+                    InstructionFormat::I,
+                    &[
+                        rd_token @ Token {
+                            kind: TokenKind::Symbol(rd),
+                            ..
+                        },
+                        Token {
+                            kind: TokenKind::Comma,
+                            ..
+                        },
+                        csr_expression @ ..,
+                        Token {
+                            kind: TokenKind::Comma,
+                            ..
+                        },
+                        src_token,
+                    ],
+                ) if def.opcode == ISA::CSRRW.definition().opcode && def.funct3 != Some(0x0) => {
+                    let rd =
+                        parse_register(rd).map_err(|e| AssemblerError::from_token(e, rd_token))?;
+                    let csr = parse_csr(csr_expression, symbol_table)?;
+
+                    // The immediate forms put a 5-bit immediate in the rs1 field
+                    let rs1 = if def.funct3.is_some_and(|f| f & 0x4 != 0) {
+                        let (uimm, uimm_err) =
+                            evaluate_immediate(std::slice::from_ref(src_token), symbol_table)?;
+                        if !(0..=31).contains(&uimm) {
+                            return Err(AssemblerError {
+                                error_message: format!(
+                                    "Immediate value {} is out of range (0 to 31)",
+                                    uimm
+                                ),
+                                ..uimm_err
+                            });
+                        }
+                        uimm as u32
+                    } else {
+                        match src_token.kind {
+                            TokenKind::Symbol(rs1) => parse_register(rs1)
+                                .map_err(|e| AssemblerError::from_token(e, src_token))?,
+                            _ => {
+                                return Err(AssemblerError::from_token(
+                                    "Expected a register".into(),
+                                    src_token,
+                                ));
+                            }
+                        }
+                    };
+
+                    (
+                        Operands {
+                            rd,
+                            rs1,
+                            // The 12-bit CSR address is stored as a sign-extended immediate
+                            imm: ((csr << 20) as i32) >> 20,
+                            ..Default::default()
+                        },
+                        (Some(rd_token), Some(src_token), None, Some(csr_expression)),
+                    )
+                }
                 // Register-immediate arithmetic instructions
                 // ADDI rd, rs1, imm
                 (
@@ -698,43 +929,409 @@ fn parse_instruction<'a>(
             };
 
         Ok(Some((
-            Instruction::try_from_def_operands(def, operands).map_err(|e| match e.error_type {
-                InstructionBuildErrorType::InvalidOpcode
-                | InstructionBuildErrorType::InvalidFunct3
-                | InstructionBuildErrorType::InvalidFunct7 => {
-                    AssemblerError::from_token(e.error_message, &instruction_token)
-                }
-                InstructionBuildErrorType::InvalidRd => AssemblerError::from_token(
-                    e.error_message,
-                    rd_token.unwrap_or(&instruction_token),
-                ),
-                InstructionBuildErrorType::InvalidRs1 => AssemblerError::from_token(
-                    e.error_message,
-                    rs1_token.unwrap_or(&instruction_token),
-                ),
-                InstructionBuildErrorType::InvalidRs2 => AssemblerError::from_token(
-                    e.error_message,
-                    rs2_token.unwrap_or(&instruction_token),
+            vec![
+                Instruction::try_from_def_operands(def, operands).map_err(|e| {
+                    match e.error_type {
+                        InstructionBuildErrorType::InvalidOpcode
+                        | InstructionBuildErrorType::InvalidFunct3
+                        | InstructionBuildErrorType::InvalidFunct7 => {
+                            AssemblerError::from_token(e.error_message, &instruction_token)
+                        }
+                        InstructionBuildErrorType::InvalidRd => AssemblerError::from_token(
+                            e.error_message,
+                            rd_token.unwrap_or(&instruction_token),
+                        ),
+                        InstructionBuildErrorType::InvalidRs1 => AssemblerError::from_token(
+                            e.error_message,
+                            rs1_token.unwrap_or(&instruction_token),
+                        ),
+                        InstructionBuildErrorType::InvalidRs2 => AssemblerError::from_token(
+                            e.error_message,
+                            rs2_token.unwrap_or(&instruction_token),
+                        ),
+                        InstructionBuildErrorType::InvalidImm => match imm_expression {
+                            Some([first, rest @ ..]) => AssemblerError {
+                                error_message: e.error_message,
+                                line_number: first.line,
+                                column: first.column,
+                                width: rest
+                                    .iter()
+                                    .fold(first.width, |acc, token| acc + token.width),
+                                file: None,
+                                end: None,
+                            },
+                            _ => AssemblerError::from_token(e.error_message, &instruction_token),
+                        },
+                    }
+                })?,
+            ],
+            instruction_token,
+        )))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Size in bytes of the instructions a pseudo-instruction expands to, or
+/// `None` if the name is not a pseudo-instruction
+fn pseudo_instruction_size(name: &str) -> Option<u32> {
+    match name.to_uppercase().as_str() {
+        "LA" | "CALL" => Some(8),
+        "BEQZ" | "BNEZ" | "BLEZ" | "BGEZ" | "BLTZ" | "BGTZ" => Some(4),
+        "NOT" | "NEG" | "SEQZ" | "SNEZ" => Some(4),
+        _ => None,
+    }
+}
+
+fn expand_pseudo_instruction(
+    name: &str,
+    parts: &[Token],
+    instruction_token: &Token,
+    symbol_table: &HashMap<String, Address>,
+    current_address: u32,
+) -> Result<Vec<Instruction>, AssemblerError> {
+    match (name.to_uppercase().as_str(), parts) {
+        // Load address relative to the PC
+        // LA rd, address
+        (
+            "LA",
+            [
+                rd_token @ Token {
+                    kind: TokenKind::Symbol(rd),
+                    ..
+                },
+                Token {
+                    kind: TokenKind::Comma,
+                    ..
+                },
+                imm_expression @ ..,
+            ],
+        ) => {
+            let rd = parse_register(rd).map_err(|e| AssemblerError::from_token(e, rd_token))?;
+            let (target, _) = evaluate_immediate(imm_expression, symbol_table)?;
+            let (upper, lower) = pc_relative_split(target, current_address);
+
+            Ok(vec![
+                Instruction::from_def_operands(
+                    ISA::AUIPC.definition(),
+                    Operands {
+                        rd,
+                        imm: upper,
+                        ..Default::default()
+                    },
                 ),
-                InstructionBuildErrorType::InvalidImm => match imm_expression {
-                    Some([first, rest @ ..]) => AssemblerError {
-                        error_message: e.error_message,
-                        line_number: first.line,
-                        column: first.column,
-                        width: rest
-                            .iter()
-                            .fold(first.width, |acc, token| acc + token.width),
+                Instruction::from_def_operands(
+                    ISA::ADDI.definition(),
+                    Operands {
+                        rd,
+                        rs1: rd,
+                        imm: lower,
+                        ..Default::default()
                     },
-                    _ => AssemblerError::from_token(e.error_message, &instruction_token),
+                ),
+            ])
+        }
+        // Call a function anywhere in the address space relative to the PC,
+        // linking through ra unless another register is given
+        // CALL [rd,] address
+        (
+            "CALL",
+            [
+                rd_token @ Token {
+                    kind: TokenKind::Symbol(rd),
+                    ..
+                },
+                Token {
+                    kind: TokenKind::Comma,
+                    ..
+                },
+                imm_expression @ ..,
+            ],
+        ) => {
+            let rd = parse_register(rd).map_err(|e| AssemblerError::from_token(e, rd_token))?;
+            // AUIPC also puts the upper bits of the target in rd, which x0 would drop
+            if rd == 0 {
+                return Err(AssemblerError::from_token(
+                    "Call cannot link through x0, since it holds the upper bits of the target."
+                        .into(),
+                    rd_token,
+                ));
+            }
+            expand_call(rd, imm_expression, symbol_table, current_address)
+        }
+        ("CALL", imm_expression @ [_, ..]) => {
+            expand_call(1, imm_expression, symbol_table, current_address)
+        }
+        // Branch comparing a register against zero. The greater than and less
+        // than or equal forms swap the operands of BLT and BGE.
+        // BEQZ rs, address
+        (
+            branch @ ("BEQZ" | "BNEZ" | "BLEZ" | "BGEZ" | "BLTZ" | "BGTZ"),
+            [
+                rs_token @ Token {
+                    kind: TokenKind::Symbol(rs),
+                    ..
+                },
+                Token {
+                    kind: TokenKind::Comma,
+                    ..
+                },
+                imm_expression @ ..,
+            ],
+        ) => {
+            let rs = parse_register(rs).map_err(|e| AssemblerError::from_token(e, rs_token))?;
+            let (isa, rs1, rs2) = match branch {
+                "BEQZ" => (ISA::BEQ, rs, 0),
+                "BNEZ" => (ISA::BNE, rs, 0),
+                "BLEZ" => (ISA::BGE, 0, rs),
+                "BGEZ" => (ISA::BGE, rs, 0),
+                "BLTZ" => (ISA::BLT, rs, 0),
+                "BGTZ" => (ISA::BLT, 0, rs),
+                _ => unreachable!(),
+            };
+            let def = isa.definition();
+            let imm = parse_immediate(imm_expression, &def, symbol_table, current_address)?;
+
+            Ok(vec![Instruction::from_def_operands(
+                def,
+                Operands {
+                    rs1,
+                    rs2,
+                    imm,
+                    ..Default::default()
                 },
-            })?,
+            )])
+        }
+        // Unary operation on a register, as a register-immediate or
+        // register-register instruction with x0
+        // NOT rd, rs
+        (
+            unary @ ("NOT" | "NEG" | "SEQZ" | "SNEZ"),
+            [
+                rd_token @ Token {
+                    kind: TokenKind::Symbol(rd),
+                    ..
+                },
+                Token {
+                    kind: TokenKind::Comma,
+                    ..
+                },
+                rs_token @ Token {
+                    kind: TokenKind::Symbol(rs),
+                    ..
+                },
+            ],
+        ) => {
+            let rd = parse_register(rd).map_err(|e| AssemblerError::from_token(e, rd_token))?;
+            let rs = parse_register(rs).map_err(|e| AssemblerError::from_token(e, rs_token))?;
+            let (isa, rs1, rs2, imm) = match unary {
+                "NOT" => (ISA::XORI, rs, 0, -1),
+                "NEG" => (ISA::SUB, 0, rs, 0),
+                "SEQZ" => (ISA::SLTIU, rs, 0, 1),
+                "SNEZ" => (ISA::SLTU, 0, rs, 0),
+                _ => unreachable!(),
+            };
+
+            Ok(vec![Instruction::from_def_operands(
+                isa.definition(),
+                Operands { rd, rs1, rs2, imm },
+            )])
+        }
+        _ => Err(AssemblerError::from_token(
+            format!("Invalid operands for instruction {}", name),
             instruction_token,
-        )))
+        )),
+    }
+}
+
+/// Splits the offset from `current_address` to `target` into the upper bits
+/// for AUIPC and the lower 12 bits for the instruction after it. AUIPC adds the
+/// upper bits to its own address, so they are rounded to account for the lower
+/// bits being sign extended.
+fn pc_relative_split(target: i32, current_address: u32) -> (i32, i32) {
+    let offset = target.wrapping_sub(current_address as i32);
+    let upper = offset.wrapping_add(0x800) & !bitmask!(12);
+    (upper, offset.wrapping_sub(upper))
+}
+
+/// Relocation functions, which make up a whole immediate operand such as
+/// `%pcrel_hi(symbol)` and give part of an address
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Relocation {
+    /// Upper bits of an absolute address, for LUI
+    Hi,
+    /// Lower 12 bits of an absolute address, after `%hi` in the instruction before
+    Lo,
+    /// Upper bits of the offset from this instruction to an address, for AUIPC
+    PcrelHi,
+    /// Lower 12 bits of the offset found by a `%pcrel_hi`. Its argument is the
+    /// address of the AUIPC using that `%pcrel_hi`, usually a label on it, rather
+    /// than the address being loaded, since the offset is from the AUIPC.
+    PcrelLo,
+}
+
+/// Splits an immediate written as `%function(argument)` into the relocation
+/// and its argument, or returns None if it is a plain expression
+fn parse_relocation<'b, 'a>(
+    imm: &'b [Token<'a>],
+) -> Option<Result<(Relocation, &'b [Token<'a>]), AssemblerError>> {
+    let percent = imm
+        .first()
+        .filter(|token| token.kind == TokenKind::Percent)?;
+    Some(match imm {
+        [
+            _,
+            function_token @ Token {
+                kind: TokenKind::Symbol(function),
+                ..
+            },
+            Token {
+                kind: TokenKind::LParenthesis,
+                ..
+            },
+            argument @ ..,
+            Token {
+                kind: TokenKind::RParenthesis,
+                ..
+            },
+        ] if !argument.is_empty() => match function.to_lowercase().as_str() {
+            "hi" => Ok((Relocation::Hi, argument)),
+            "lo" => Ok((Relocation::Lo, argument)),
+            "pcrel_hi" => Ok((Relocation::PcrelHi, argument)),
+            "pcrel_lo" => Ok((Relocation::PcrelLo, argument)),
+            _ => Err(AssemblerError::from_token(
+                format!("Unknown relocation function %{}", function),
+                function_token,
+            )),
+        },
+        _ => Err(AssemblerError::from_token_span(
+            "Expected a relocation function like %pcrel_hi(symbol)".into(),
+            percent,
+            imm.last().unwrap_or(percent),
+        )),
+    })
+}
+
+/// The argument of a `%pcrel_hi` among an instruction's operands, if it has one
+fn pcrel_hi_argument<'b, 'a>(parts: &'b [Token<'a>]) -> Option<&'b [Token<'a>]> {
+    let start = parts
+        .iter()
+        .position(|token| token.kind == TokenKind::Percent)?;
+    match parse_relocation(&parts[start..])? {
+        Ok((Relocation::PcrelHi, argument)) => Some(argument),
+        _ => None,
+    }
+}
+
+/// Evaluates a relocation function for an instruction at `current_address`.
+/// The upper parts are rounded like [`pc_relative_split`] so adding the sign
+/// extended lower part gives back the full value.
+fn relocate(
+    relocation: Relocation,
+    argument: &[Token],
+    def: &InstructionDefinition,
+    symbol_table: &HashMap<String, Address>,
+    current_address: u32,
+) -> Result<i32, AssemblerError> {
+    let (value, expression_err) = evaluate_immediate(argument, symbol_table)?;
+    let (imm, formats) = match relocation {
+        Relocation::Hi => (pc_relative_split(value, 0).0, "U"),
+        Relocation::Lo => (pc_relative_split(value, 0).1, "I or S"),
+        Relocation::PcrelHi => (pc_relative_split(value, current_address).0, "U"),
+        Relocation::PcrelLo => {
+            let auipc_address = value as u32;
+            let target = symbol_table
+                .get(&pcrel_hi_symbol(auipc_address))
+                .and_then(|target| i32::try_from(&target.1).ok())
+                .ok_or(AssemblerError {
+                    error_message: format!(
+                        "%pcrel_lo must name the address of an instruction using %pcrel_hi, but {:#x} has none",
+                        auipc_address
+                    ),
+                    ..expression_err.clone()
+                })?;
+            (pc_relative_split(target, auipc_address).1, "I or S")
+        }
+    };
+
+    let allowed = match relocation {
+        Relocation::Hi | Relocation::PcrelHi => def.format == InstructionFormat::U,
+        Relocation::Lo | Relocation::PcrelLo => {
+            matches!(def.format, InstructionFormat::I | InstructionFormat::S)
+        }
+    };
+    if allowed {
+        Ok(imm)
     } else {
-        Ok(None)
+        Err(AssemblerError {
+            error_message: format!(
+                "This relocation can only be used by {} type instructions",
+                formats
+            ),
+            ..expression_err
+        })
     }
 }
 
+/// AUIPC rd, upper followed by JALR rd, rd, lower, jumping to the target and
+/// linking the return address in rd
+fn expand_call(
+    rd: u32,
+    imm_expression: &[Token],
+    symbol_table: &HashMap<String, Address>,
+    current_address: u32,
+) -> Result<Vec<Instruction>, AssemblerError> {
+    let (target, _) = evaluate_immediate(imm_expression, symbol_table)?;
+    let (upper, lower) = pc_relative_split(target, current_address);
+
+    Ok(vec![
+        Instruction::from_def_operands(
+            ISA::AUIPC.definition(),
+            Operands {
+                rd,
+                imm: upper,
+                ..Default::default()
+            },
+        ),
+        Instruction::from_def_operands(
+            ISA::JALR.definition(),
+            Operands {
+                rd,
+                rs1: rd,
+                imm: lower,
+                ..Default::default()
+            },
+        ),
+    ])
+}
+
+/// Address of the CSR named by an operand, either by its name or as an
+/// expression giving its 12-bit address
+fn parse_csr(
+    expression: &[Token],
+    symbol_table: &HashMap<String, Address>,
+) -> Result<u32, AssemblerError> {
+    if let [
+        Token {
+            kind: TokenKind::Symbol(name),
+            ..
+        },
+    ] = expression
+        && let Some(address) = csr_address(name)
+    {
+        return Ok(address);
+    }
+
+    let (address, expression_err) = evaluate_immediate(expression, symbol_table)?;
+    if !(0..=0xFFF).contains(&address) {
+        return Err(AssemblerError {
+            error_message: format!("CSR address {} is out of range (0 to 0xFFF)", address),
+            ..expression_err
+        });
+    }
+    Ok(address as u32)
+}
+
 fn parse_register(reg: &str) -> Result<u32, String> {
     let reg = reg.to_lowercase();
     if !reg.starts_with('x') {
@@ -747,12 +1344,12 @@ fn parse_register(reg: &str) -> Result<u32, String> {
     }
 }
 
-fn parse_immediate(
+/// Evaluates an immediate expression, returning its value along with an error
+/// spanning the expression for reporting problems with the value
+fn evaluate_immediate(
     imm: &[Token],
-    def: &InstructionDefinition,
     symbol_table: &HashMap<String, Address>,
-    current_address: u32,
-) -> Result<i32, AssemblerError> {
+) -> Result<(i32, AssemblerError), AssemblerError> {
     let expression = Expression::shunting_yard(&mut imm.iter().cloned())?;
     let expression_err = AssemblerError::from_expression("".into(), &expression);
     let imm = expression
@@ -769,9 +1366,25 @@ fn parse_immediate(
         .try_into()
         .map_err(|_| AssemblerError {
             error_message: "Invalid immediate value.".to_string(),
-            ..expression_err
+            ..expression_err.clone()
         })?;
 
+    Ok((imm, expression_err))
+}
+
+fn parse_immediate(
+    imm: &[Token],
+    def: &InstructionDefinition,
+    symbol_table: &HashMap<String, Address>,
+    current_address: u32,
+) -> Result<i32, AssemblerError> {
+    if let Some(relocation) = parse_relocation(imm) {
+        let (relocation, argument) = relocation?;
+        return relocate(relocation, argument, def, symbol_table, current_address);
+    }
+
+    let (imm, expression_err) = evaluate_immediate(imm, symbol_table)?;
+
     match def.format {
         InstructionFormat::I | InstructionFormat::S => {
             if !(-2048..=2047).contains(&imm) {
@@ -846,9 +1459,10 @@ fn parse_immediate(
 
 fn run_pass<
     'a,
-    T: FnMut(&mut Token<'a>, &mut Peekable<Lexer<'a>>) -> Result<(), AssemblerError>,
+    I: Iterator<Item = Result<Token<'a>, AssemblerError>>,
+    T: FnMut(&mut Token<'a>, &mut Peekable<I>) -> Result<(), AssemblerError>,
 >(
-    lexer: &mut Peekable<Lexer<'a>>,
+    lexer: &mut Peekable<I>,
     mut pass: T,
 ) -> Vec<AssemblerError> {
     let mut errors = Vec::new();
@@ -892,6 +1506,29 @@ fn is_kind(token: Option<&Result<Token, AssemblerError>>, token_kind: TokenKind)
     }
 }
 
+/// Prefix of the symbols the assembler defines to mark where unlabelled sections
+/// start. Source symbols are made of letters, digits and underscores, so they can
+/// never start with it or the other internal prefixes and collide with them.
+const SECTION_SYMBOL_PREFIX: &str = ".section";
+
+/// Prefix of the symbols recording the target of the `%pcrel_hi` at an address
+const PCREL_HI_SYMBOL_PREFIX: &str = ".pcrel_hi";
+
+/// The internal symbol marking a section directive without a label
+fn section_symbol(line: usize, column: usize) -> String {
+    format!("{}({},{})", SECTION_SYMBOL_PREFIX, line, column)
+}
+
+/// The internal symbol holding the target of the `%pcrel_hi` at an address
+fn pcrel_hi_symbol(address: u32) -> String {
+    format!("{}({:#x})", PCREL_HI_SYMBOL_PREFIX, address)
+}
+
+/// Whether a symbol was defined by the assembler rather than the source
+pub fn is_internal_symbol(symbol: &str) -> bool {
+    symbol.starts_with(SECTION_SYMBOL_PREFIX) || symbol.starts_with(PCREL_HI_SYMBOL_PREFIX)
+}
+
 fn insert<'a>(
     symbol_table: &mut HashMap<String, (Option<Section>, Expression<'a>, Token<'a>)>,
     symbol: String,
@@ -899,6 +1536,13 @@ fn insert<'a>(
 ) -> Result<(), AssemblerError> {
     let line = entry.2.line;
     match symbol_table.insert(symbol.clone(), entry) {
+        Some((_, _, token)) if is_internal_symbol(&symbol) => Err(AssemblerError::from_token(
+            format!(
+                "Symbol {} at line {} clashes with an internal section symbol.",
+                symbol, line
+            ),
+            &token,
+        )),
         Some((_, _, token)) => Err(AssemblerError::from_token(
             format!("Symbol {} redefined at line {}.", symbol, line),
             &token,
@@ -907,19 +1551,141 @@ fn insert<'a>(
     }
 }
 
-pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerError>> {
-    let mut errors = Vec::new();
+/// Returns the source of the named file if it exists
+pub type IncludeResolver<'a> = Box<dyn FnMut(&str) -> Option<String> + 'a>;
+
+/// Returns the bytes of the named file if it exists
+pub type BinaryResolver<'a> = Box<dyn FnMut(&str) -> Option<Vec<u8>> + 'a>;
+
+/// How to assemble a program besides its source. The default places sections
+/// by the default memory map, finds no included files and treats unknown
+/// directives as errors.
+pub struct AssembleOptions<'a> {
+    /// Places the sections, and is kept in the program for the emulator
+    pub memory_map: MemoryMap,
+    /// Finds files named by `.include "name"` directives
+    pub resolve_include: IncludeResolver<'a>,
+    /// Finds files named by `.incbin "name"` directives
+    pub resolve_binary: BinaryResolver<'a>,
+    /// Whether unknown directives are errors rather than skipped with a warning
+    pub strict: bool,
+}
+
+impl Default for AssembleOptions<'_> {
+    fn default() -> Self {
+        Self {
+            memory_map: MemoryMap::default(),
+            resolve_include: Box::new(|_| None),
+            resolve_binary: Box::new(|_| None),
+            strict: true,
+        }
+    }
+}
+
+impl AssembleOptions<'_> {
+    /// The default options with the given memory map
+    pub fn with_memory_map(memory_map: MemoryMap) -> Self {
+        Self {
+            memory_map,
+            ..Self::default()
+        }
+    }
+}
+
+/// Assembles a program, placing sections by the bases in the memory map of
+/// `options` unless a section directive gives an address, and keeping the map
+/// in the program for the emulator.
+///
+/// `.include "name"` directives splice in the source of the named file. Errors
+/// inside included files name the file they occur in, and instructions from
+/// included files map to the line of the `.include` in the source map.
+/// `.incbin "name"` directives place the bytes of the named file as data where
+/// the directive appears.
+///
+/// Unknown directives are errors when `strict`, and otherwise skipped with a
+/// warning in [`AssembledProgram::warnings`] so programs written for other
+/// assemblers still assemble. Directives that only matter to debuggers and
+/// linkers, like `.size`, `.type`, `.ident` and `.cfi_*`, are always skipped.
+pub fn assemble(
+    source: &str,
+    options: AssembleOptions,
+) -> Result<AssembledProgram, Vec<AssemblerError>> {
+    let AssembleOptions {
+        memory_map,
+        resolve_include,
+        resolve_binary,
+        strict,
+    } = options;
+    let sources = load_includes(source, resolve_include);
+    let line_count = source.matches('\n').count() + 1;
+    let (tokens, inclusions, mut errors) =
+        expand_includes(Lexer::new(source), &sources, line_count);
+
+    // Fold label case, then expand macros, conditional blocks and repetition
+    // blocks once so both passes see the same tokens
+    let (tokens, mut expansion_errors) = fold_label_case(tokens.into_iter());
+    errors.append(&mut expansion_errors);
+    let (tokens, mut expansion_errors) = expand_macros(tokens.into_iter());
+    errors.append(&mut expansion_errors);
+    let (tokens, mut expansion_errors) = expand_repetitions(tokens.into_iter());
+    errors.append(&mut expansion_errors);
+
+    let binaries = load_binaries(&tokens, resolve_binary);
+
+    let find_inclusion = |line: usize| {
+        inclusions
+            .iter()
+            .find(|inclusion| inclusion.offset < line && line <= inclusion.offset + inclusion.lines)
+    };
+
+    match assemble_tokens(tokens, errors, &binaries, memory_map, strict) {
+        Ok(mut program) => {
+            for line in program.source_map.values_mut() {
+                if let Some(Inclusion { root_line, .. }) = find_inclusion(*line) {
+                    *line = *root_line;
+                }
+            }
+            for warning in program.warnings.iter_mut() {
+                if let Some(Inclusion { root_line, .. }) = find_inclusion(warning.line_number) {
+                    *warning = warning.clone().with_line_number(*root_line);
+                }
+            }
+            Ok(program)
+        }
+        Err(errors) => Err(errors
+            .into_iter()
+            .map(|error| match find_inclusion(error.line_number) {
+                Some(inclusion) => {
+                    let line_number = error.line_number - inclusion.offset;
+                    AssemblerError {
+                        file: Some(inclusion.file.clone()),
+                        ..error.with_line_number(line_number)
+                    }
+                }
+                None => error,
+            })
+            .collect()),
+    }
+}
 
+fn assemble_tokens<'a>(
+    tokens: Vec<Result<Token<'a>, AssemblerError>>,
+    mut errors: Vec<AssemblerError>,
+    binaries: &HashMap<String, Option<Vec<u8>>>,
+    memory_map: MemoryMap,
+    strict: bool,
+) -> Result<AssembledProgram, Vec<AssemblerError>> {
+    let bases = memory_map.sections;
     let mut symbol_table: HashMap<String, (Option<Section>, Expression<'a>, Token<'a>)> =
         std::collections::HashMap::new();
-    let first_org: &str = ".section(0,0)";
+    let first_org = section_symbol(0, 0);
     insert(
         &mut symbol_table,
-        first_org.into(),
+        first_org.clone(),
         (
             Some(Section::Text),
             vec![RPN {
-                kind: RPNKind::Integer(0.into()),
+                kind: RPNKind::Integer(bases.text.into()),
                 token: Token {
                     kind: TokenKind::IntLiteral("0", 10, 0.into()),
                     line: 1,
@@ -929,7 +1695,7 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
             }]
             .into(),
             Token {
-                kind: TokenKind::Symbol(first_org),
+                kind: TokenKind::Symbol(SECTION_SYMBOL_PREFIX),
                 line: 1,
                 column: 1,
                 width: 0,
@@ -938,11 +1704,19 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
     )
     .expect("There should be no errors inserting the initial section.");
 
+    // Where each `%pcrel_hi` is used, as its section symbol and offset, along
+    // with its argument
+    let mut pcrel_his: Vec<(String, u32, Vec<Token<'a>>)> = Vec::new();
+
+    // Labels generated for the strings of each `.stringtable`, which its pointer
+    // array refers to
+    let mut string_table_labels: Vec<String> = Vec::new();
+
     // First Pass
     {
-        let mut lexer = Lexer::new(source).peekable();
+        let mut lexer = tokens.clone().into_iter().peekable();
         let mut current_section = Section::Text;
-        let mut current_org: String = first_org.into();
+        let mut current_org = first_org;
         let mut offset: u32 = 0;
 
         errors.append(&mut run_pass(&mut lexer, |token, lexer| {
@@ -950,7 +1724,7 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
             let label = parse_label(token, lexer)?;
 
             // Check for other directives
-            let directive = parse_directive(token, lexer, None)?;
+            let directive = parse_directive(token, lexer, None, binaries, bases, strict)?;
 
             // Handle section directive and label (must be handled together)
             if let Some(Directive::Section(section, (expression, token))) = directive {
@@ -961,7 +1735,7 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
                     (label.into(), (Some(section), expression, token))
                 } else {
                     (
-                        format!(".section({},{})", token.line, token.column),
+                        section_symbol(token.line, token.column),
                         (Some(section), expression, token),
                     )
                 };
@@ -969,8 +1743,11 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
                 insert(&mut symbol_table, current_org.clone(), entry)?;
             } else {
                 // Perform alignment before assigning label
-                if let Some(Directive::Data(_, alignment)) = &directive {
-                    offset = aligned(offset, *alignment);
+                match &directive {
+                    Some(Directive::Data(_, alignment)) => offset = aligned(offset, *alignment),
+                    // Pointer arrays are word aligned
+                    Some(Directive::StringTable(..)) => offset = aligned(offset, 2),
+                    _ => {}
                 }
 
                 if let Some((label, token)) = label {
@@ -979,21 +1756,7 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
                         label.into(),
                         (
                             Some(current_section.clone()),
-                            vec![
-                                RPN {
-                                    kind: RPNKind::Variable(current_org.clone()),
-                                    token: token.clone(),
-                                },
-                                RPN {
-                                    kind: RPNKind::Integer(offset.into()),
-                                    token: token.clone(),
-                                },
-                                RPN {
-                                    kind: RPNKind::Add,
-                                    token: token.clone(),
-                                },
-                            ]
-                            .into(),
+                            org_offset(&current_org, offset, &token),
                             token,
                         ),
                     )?;
@@ -1012,6 +1775,50 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
                         Directive::Symbol(symbol, entry) => {
                             insert(&mut symbol_table, symbol, (None, entry.0, entry.1))?;
                         }
+                        Directive::StringTable(name, token, strings) => {
+                            insert(
+                                &mut symbol_table,
+                                name.into(),
+                                (
+                                    Some(current_section.clone()),
+                                    org_offset(&current_org, offset, &token),
+                                    token.clone(),
+                                ),
+                            )?;
+                            for (i, string_offset) in
+                                string_table_offsets(&strings).into_iter().enumerate()
+                            {
+                                insert(
+                                    &mut symbol_table,
+                                    string_table_label(name, i),
+                                    (
+                                        Some(current_section.clone()),
+                                        org_offset(&current_org, offset + string_offset, &token),
+                                        token.clone(),
+                                    ),
+                                )?;
+                                string_table_labels.push(string_table_label(name, i));
+                            }
+                            insert(
+                                &mut symbol_table,
+                                string_table_count(name),
+                                (
+                                    None,
+                                    vec![RPN {
+                                        kind: RPNKind::Integer(strings.len().into()),
+                                        token: token.clone(),
+                                    }]
+                                    .into(),
+                                    token,
+                                ),
+                            )?;
+                            offset += 4 * strings.len() as u32
+                                + strings
+                                    .iter()
+                                    .map(|string| string.len() as u32)
+                                    .sum::<u32>();
+                        }
+                        Directive::Skipped(_) => {} // Warned about in the second pass
                         Directive::Section(_, _) => unreachable!(), // Section directives are handled above
                     }
                 }
@@ -1020,21 +1827,48 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
             // Check for instruction
             if let TokenKind::Symbol(instr) = token.kind {
                 // Parse instruction
-                ISA::from_str(&instr.to_uppercase()).map_err(|_| {
-                    AssemblerError::from_token(format!("Invalid instruction {}", instr), token)
-                })?;
+                let size = match pseudo_instruction_size(instr) {
+                    Some(size) => size,
+                    None => {
+                        ISA::from_str(&instr.to_uppercase()).map_err(|_| {
+                            AssemblerError::from_token(
+                                format!("Invalid instruction {}", instr),
+                                token,
+                            )
+                        })?;
+                        4
+                    }
+                };
 
-                consume_line(token, lexer)?;
+                let parts = consume_line(token, lexer)?;
 
-                // Instructions are 4 bytes and must be aligned
+                // Instructions are 4 bytes each and must be aligned
                 offset = aligned(offset, 2);
-                offset += 4;
+                if let Some(target) = pcrel_hi_argument(&parts) {
+                    pcrel_his.push((current_org.clone(), offset, target.to_vec()));
+                }
+                offset += size;
             }
 
             Ok(())
         }));
     };
 
+    // Where each label is defined, before resolving consumes the symbol table.
+    // Section markers and constants are not labels.
+    let labels: BTreeMap<String, SourceSpan> = symbol_table
+        .iter()
+        .filter(|(name, (section, _, _))| section.is_some() && !is_internal_symbol(name))
+        .map(|(name, (_, _, token))| {
+            let span = SourceSpan {
+                line: token.line,
+                column: token.column,
+                width: token.width,
+            };
+            (name.clone(), span)
+        })
+        .collect();
+
     // Resolve text labels
     let mut resolved_symbols = HashMap::new();
 
@@ -1071,17 +1905,57 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
         }
     };
 
-    let symbol_table = resolved_symbols;
+    let mut symbol_table = resolved_symbols;
+
+    // Record the target of each `%pcrel_hi` under its address, so a `%pcrel_lo`
+    // naming that address can find it. Targets that fail to evaluate are
+    // reported when their instruction is assembled.
+    for (org, offset, target) in pcrel_his {
+        let address = symbol_table
+            .get(&org)
+            .and_then(|org| u32::try_from(&org.1).ok())
+            .map(|org| org.wrapping_add(offset));
+        if let (Some(address), Ok((target, _))) =
+            (address, evaluate_immediate(&target, &symbol_table))
+        {
+            symbol_table.insert(
+                pcrel_hi_symbol(address),
+                Address(Section::Absolute, target.into()),
+            );
+        }
+    }
 
     let mut instruction_memory = BTreeMap::new();
     let mut initial_data_memory = BTreeMap::new();
-    let mut source_map = BiBTreeMap::new();
+    let mut source_map = BTreeMap::new();
+    let mut warnings = Vec::new();
+
+    // Any symbol other than a label's own definition is a reference to it
+    let tokens_ok: Vec<&Token> = tokens
+        .iter()
+        .filter_map(|token| token.as_ref().ok())
+        .collect();
+    let referenced_symbols: HashSet<String> = tokens_ok
+        .iter()
+        .enumerate()
+        .filter_map(|(i, token)| match token.kind {
+            TokenKind::Symbol(name)
+                if !tokens_ok
+                    .get(i + 1)
+                    .is_some_and(|next| next.kind == TokenKind::Colon) =>
+            {
+                Some(name.to_string())
+            }
+            _ => None,
+        })
+        .chain(string_table_labels)
+        .collect();
 
     // Second Pass
     {
-        let mut lexer = Lexer::new(source).peekable();
+        let mut lexer = tokens.into_iter().peekable();
         let mut current_section = Section::Text;
-        let mut address: u32 = 0;
+        let mut address: u32 = bases.text;
 
         errors.append(&mut run_pass(&mut lexer, |token, lexer| {
             let mut memory = match current_section {
@@ -1094,14 +1968,14 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
             let label = parse_label(token, lexer)?;
 
             // Check for section directive
-            let directive = parse_directive(token, lexer, Some(&symbol_table))?;
+            let directive = parse_directive(token, lexer, Some(&symbol_table), binaries, bases, strict)?;
 
             // Handle section directive and label (must be handled together)
             if let Some(Directive::Section(section, (_, token))) = directive {
                 let org = if let Some((label, _)) = label {
                     label.into()
                 } else {
-                    format!(".section({},{})", token.line, token.column)
+                    section_symbol(token.line, token.column)
                 };
 
                 current_section = section;
@@ -1119,8 +1993,10 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
                         })?;
             } else {
                 // Perform alignment before assigning label
-                if let Some(Directive::Data(_, alignment)) = &directive {
-                    address = aligned(address, *alignment);
+                match &directive {
+                    Some(Directive::Data(_, alignment)) => address = aligned(address, *alignment),
+                    Some(Directive::StringTable(..)) => address = aligned(address, 2),
+                    _ => {}
                 }
 
                 if let Some((label, label_token)) = label {
@@ -1148,31 +2024,47 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
 
                             address += data.len() as u32;
                         }
+                        Directive::StringTable(_, token, strings) => {
+                            let offsets = string_table_offsets(&strings);
+                            let pointers = offsets.iter().flat_map(|offset| (address + offset).to_le_bytes());
+                            let data: Vec<u8> = pointers.chain(strings.into_iter().flatten()).collect();
+
+                            for (i, data) in data.iter().enumerate() {
+                                match memory.insert(address + u32::try_from(i).map_err(|_| AssemblerError::from_token("Data too large to fit in memory.".into(), &token))?, *data) {
+                                    Some(_) => Err(AssemblerError::from_token("Memory collision.".into(), &token)),
+                                    None => Ok(())
+                                }?
+                            }
+
+                            address += data.len() as u32;
+                        }
                         Directive::Symbol(_, _) => {} // Symbols are already resolved
+                        Directive::Skipped(warning) => warnings.push(warning),
                         Directive::Section(_, _) => unreachable!(), // Section directives are handled above
                     }
                 }
             }
 
             // Check for instruction
-            let instruction = parse_instruction(token, lexer, &symbol_table, address)?;
-
-            if let Some((instruction, instruction_token)) = instruction {
-
-                let data = instruction.raw().to_le_bytes();
+            let instructions = parse_instruction(token, lexer, &symbol_table, address)?;
+
+            if let Some((instructions, instruction_token)) = instructions {
+                for instruction in instructions {
+                    let data = instruction.raw().to_le_bytes();
+
+                    for (i, data) in data.iter().enumerate() {
+                        // Safe to unwrap because we know i < 4 
+                        match memory.insert(address + u32::try_from(i).unwrap(), *data) {
+                            Some(_) => Err(AssemblerError::from_token("Memory collision.".into(), &instruction_token)),
+                            None => Ok(())
+                        }?
+                    }
+                    source_map.insert(address, instruction_token.line);
 
-                for (i, data) in data.iter().enumerate() {
-                    // Safe to unwrap because we know i < 4 
-                    match memory.insert(address + u32::try_from(i).unwrap(), *data) {
-                        Some(_) => Err(AssemblerError::from_token("Memory collision.".into(), &instruction_token)),
-                        None => Ok(())
-                    }?
+                    // Instructions are 4 bytes
+                    address = (address + 4 - 1) & !(4 - 1);
+                    address += 4;
                 }
-                source_map.insert(address, instruction_token.line);
-
-                // Instructions are 4 bytes
-                address = (address + 4 - 1) & !(4 - 1);
-                address += 4;
             }
 
             Ok(())
@@ -1187,10 +2079,137 @@ pub fn assemble<'a>(source: &'a str) -> Result<AssembledProgram, Vec<AssemblerEr
             initial_data_memory,
             source_map,
             symbol_table,
+            memory_map,
+            labels,
+            referenced_symbols,
+            warnings,
         })
     }
 }
 
+/// Returns the number of lines before each file once the files are joined by
+/// [`assemble_files`].
+pub fn file_line_offsets<'a>(sources: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    let mut offset = 0;
+    sources
+        .into_iter()
+        .map(|source| {
+            let start = offset;
+            offset += source.matches('\n').count() + 1;
+            start
+        })
+        .collect()
+}
+
+/// Evaluates a single expression written like a directive or immediate operand,
+/// looking up the value of every symbol it names with `resolve`
+pub fn evaluate_expression(
+    source: &str,
+    mut resolve: impl FnMut(&str) -> Option<IBig>,
+) -> Result<IBig, AssemblerError> {
+    let mut lexer = Lexer::new(source).peekable();
+    let expression = parse_expression(&mut lexer)?;
+    if expression.is_empty() {
+        return Err(AssemblerError::new(
+            "Expected an expression.".into(),
+            1,
+            1,
+            0,
+        ));
+    }
+
+    // Only the newline the lexer adds at the end may follow the expression
+    if let Some(token) = lexer.next() {
+        let token = token?;
+        if token.kind != TokenKind::Newline {
+            return Err(AssemblerError::from_token(
+                "Unexpected token after expression.".into(),
+                &token,
+            ));
+        }
+    }
+
+    let expression_err = AssemblerError::from_expression("".into(), &expression);
+    let value = expression.evaluate(|name| {
+        resolve(name)
+            .map(|value| Address(Section::Absolute, value))
+            .ok_or(AssemblerError {
+                error_message: format!("Symbol {} not defined.", name),
+                ..expression_err.clone()
+            })
+    })?;
+    Ok(value.1)
+}
+
+/// Maps a line of the joined source back to the index of the file it came
+/// from and the line within that file.
+pub fn locate_line(offsets: &[usize], line: usize) -> Option<(usize, usize)> {
+    if line == 0 {
+        return None;
+    }
+
+    let index = offsets
+        .partition_point(|&offset| offset < line)
+        .checked_sub(1)?;
+    Some((index, line - offsets[index]))
+}
+
+/// Assembles several named source files as one program by joining them in
+/// order, with everything else handled as in [`assemble`]. `.include`
+/// directives resolve against the other files by name before falling back to
+/// the resolver in `options`, and files included by another file are only
+/// assembled where they are included. Errors point at the file and line they
+/// occur on, while the source map of the program refers to lines of the joined
+/// source.
+pub fn assemble_files(
+    files: &[(&str, &str)],
+    options: AssembleOptions,
+) -> Result<AssembledProgram, Vec<AssemblerError>> {
+    let included: HashSet<String> = files
+        .iter()
+        .flat_map(|(_, source)| include_names(source))
+        .collect();
+
+    // Blank out included files so every file keeps its place in the joined source
+    let source = files
+        .iter()
+        .map(|(name, source)| {
+            if included.contains(*name) {
+                "\n".repeat(source.matches('\n').count())
+            } else {
+                source.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let offsets = file_line_offsets(files.iter().map(|(_, source)| *source));
+
+    let mut resolve_include = options.resolve_include;
+    let options = AssembleOptions {
+        resolve_include: Box::new(move |name: &str| {
+            files
+                .iter()
+                .find(|(file, _)| *file == name)
+                .map(|(_, source)| source.to_string())
+                .or_else(|| resolve_include(name))
+        }),
+        ..options
+    };
+
+    assemble(&source, options).map_err(|errors| {
+        errors
+            .into_iter()
+            .map(|error| match locate_line(&offsets, error.line_number) {
+                Some((index, line_number)) if error.file.is_none() => AssemblerError {
+                    file: Some(files[index].0.to_string()),
+                    ..error.with_line_number(line_number)
+                },
+                _ => error,
+            })
+            .collect()
+    })
+}
+
 fn resolve_label(
     label: &String,
     labels: &mut HashMap<String, (Option<Section>, Expression, Token)>,
@@ -1206,6 +2225,8 @@ fn resolve_label(
                 line_number: 0,
                 column: 0,
                 width: 0,
+                file: None,
+                end: None,
             });
         }
 
@@ -1216,6 +2237,8 @@ fn resolve_label(
             line_number: 0,
             column: 0,
             width: 0,
+            file: None,
+            end: None,
         })?;
 
         let value =
@@ -0,0 +1,1054 @@
+use std::{collections::HashMap, str::FromStr};
+
+use super::{
+    Address, AssemblerError,
+    lexer::{Lexer, Token, TokenKind},
+    parse_register, pseudo_instruction_size,
+    rpn::Expression,
+};
+use crate::isa::{ISA, csr::csr_address};
+
+type TokenResult<'a> = Result<Token<'a>, AssemblerError>;
+
+enum Line<'a> {
+    Rept(Vec<TokenResult<'a>>, Token<'a>, Result<u32, AssemblerError>),
+    Endr(Token<'a>),
+    Other(Vec<TokenResult<'a>>),
+}
+
+/// Splits a token stream into lines. Lexer errors terminate a line since the
+/// lexer skips the remainder of the line after an error.
+fn split_lines<'a>(tokens: impl Iterator<Item = TokenResult<'a>>) -> Vec<Vec<TokenResult<'a>>> {
+    let mut lines = Vec::new();
+    let mut line = Vec::new();
+
+    for token in tokens {
+        let end_of_line = match &token {
+            Ok(token) => token.kind == TokenKind::Newline,
+            Err(_) => true,
+        };
+
+        line.push(token);
+
+        if end_of_line {
+            lines.push(std::mem::take(&mut line));
+        }
+    }
+
+    if !line.is_empty() {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Returns the name and token of the directive at the start of the line (after
+/// an optional label) along with the index of the first token after it.
+fn leading_directive<'a, 'b>(
+    line: &'b [TokenResult<'a>],
+) -> Option<(&'a str, &'b Token<'a>, usize)> {
+    let start = match line {
+        [
+            Ok(Token {
+                kind: TokenKind::Symbol(_),
+                ..
+            }),
+            Ok(Token {
+                kind: TokenKind::Colon,
+                ..
+            }),
+            ..,
+        ] => 2,
+        _ => 0,
+    };
+
+    match &line[start..] {
+        [
+            Ok(Token {
+                kind: TokenKind::Dot,
+                ..
+            }),
+            Ok(
+                directive @ Token {
+                    kind: TokenKind::Symbol(name),
+                    ..
+                },
+            ),
+            ..,
+        ] => Some((*name, directive, start + 2)),
+        _ => None,
+    }
+}
+
+fn parse_count<'a>(
+    arguments: Vec<TokenResult<'a>>,
+    directive: &Token<'a>,
+) -> Result<u32, AssemblerError> {
+    let mut tokens = Vec::new();
+    for token in arguments {
+        let token = token?;
+        if token.kind != TokenKind::Newline {
+            tokens.push(token);
+        }
+    }
+
+    let expression = Expression::shunting_yard(&mut tokens.into_iter())?;
+    if expression.is_empty() {
+        return Err(AssemblerError::from_token(
+            "Expected repeat count after '.rept' directive.".into(),
+            directive,
+        ));
+    }
+
+    let expression_err = AssemblerError::from_expression("".into(), &expression);
+    expression
+        .evaluate(|_| {
+            Err(AssemblerError {
+                error_message: "Cannot use symbols in '.rept' directive.".into(),
+                ..expression_err.clone()
+            })
+        })?
+        .1
+        .try_into()
+        .map_err(|_| AssemblerError {
+            error_message: "Invalid repeat count.".into(),
+            ..expression_err
+        })
+}
+
+fn classify_line<'a>(mut line: Vec<TokenResult<'a>>) -> Result<Line<'a>, AssemblerError> {
+    let Some((name, directive, rest)) = leading_directive(&line) else {
+        return Ok(Line::Other(line));
+    };
+
+    match name {
+        "rept" => {
+            let directive = directive.clone();
+            let arguments = line.split_off(rest);
+
+            // Keep any label on its own line so it marks the start of the block
+            line.truncate(rest - 2);
+            if let (false, Some(Ok(newline))) = (line.is_empty(), arguments.last()) {
+                line.push(Ok(newline.clone()));
+            }
+
+            let count = parse_count(arguments, &directive);
+            Ok(Line::Rept(line, directive, count))
+        }
+        "endr" => {
+            let directive = directive.clone();
+            match &line[rest..] {
+                []
+                | [
+                    Ok(Token {
+                        kind: TokenKind::Newline,
+                        ..
+                    }),
+                ] => Ok(Line::Endr(directive)),
+                [Err(e), ..] => Err(e.clone()),
+                [Ok(token), ..] => Err(AssemblerError::from_token(
+                    "Expected newline after '.endr' directive.".into(),
+                    token,
+                )),
+            }
+        }
+        _ => Ok(Line::Other(line)),
+    }
+}
+
+/// Expands lines until the end of the stream or a matching `.endr`.
+/// Returns the `.endr` token if one was found.
+fn expand_block<'a>(
+    lines: &mut impl Iterator<Item = Vec<TokenResult<'a>>>,
+    output: &mut Vec<TokenResult<'a>>,
+    errors: &mut Vec<AssemblerError>,
+) -> Option<Token<'a>> {
+    while let Some(line) = lines.next() {
+        match classify_line(line) {
+            Ok(Line::Other(mut tokens)) => output.append(&mut tokens),
+            Ok(Line::Endr(token)) => return Some(token),
+            Ok(Line::Rept(mut label, token, count)) => {
+                output.append(&mut label);
+
+                let count = count.unwrap_or_else(|e| {
+                    errors.push(e);
+                    0
+                });
+
+                let mut body = Vec::new();
+                if expand_block(lines, &mut body, errors).is_none() {
+                    errors.push(AssemblerError::from_token(
+                        "Missing '.endr' for '.rept' directive.".into(),
+                        &token,
+                    ));
+                }
+
+                for _ in 0..count {
+                    output.extend(body.iter().cloned());
+                }
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+
+    None
+}
+
+/// Expands `.rept N` ... `.endr` blocks by repeating the enclosed tokens N
+/// times. Tokens keep their original positions, so every repetition maps back
+/// to the lines inside the block.
+pub fn expand_repetitions<'a>(
+    tokens: impl Iterator<Item = TokenResult<'a>>,
+) -> (Vec<TokenResult<'a>>, Vec<AssemblerError>) {
+    let mut output = Vec::new();
+    let mut errors = Vec::new();
+    let mut lines = split_lines(tokens).into_iter();
+
+    while let Some(token) = expand_block(&mut lines, &mut output, &mut errors) {
+        errors.push(AssemblerError::from_token(
+            "'.endr' without matching '.rept' directive.".into(),
+            &token,
+        ));
+    }
+
+    (output, errors)
+}
+
+/// An open `.if` block
+struct Conditional<'a> {
+    directive: Token<'a>,
+    /// Whether the lines around the block are assembled
+    enclosing: bool,
+    /// Whether the `.if` condition was true
+    taken: bool,
+    in_else: bool,
+}
+
+impl Conditional<'_> {
+    fn active(&self) -> bool {
+        self.enclosing && self.taken != self.in_else
+    }
+}
+
+/// Drops the directive from a line, keeping any label before it on its own
+/// line so it still marks the position of the directive.
+fn keep_label<'a>(mut line: Vec<TokenResult<'a>>, rest: usize, output: &mut Vec<TokenResult<'a>>) {
+    let newline = line.last().cloned();
+    line.truncate(rest - 2);
+    if let (false, Some(Ok(newline))) = (line.is_empty(), newline) {
+        line.push(Ok(newline));
+        output.append(&mut line);
+    }
+}
+
+fn expect_line_end(arguments: &[TokenResult], name: &str) -> Result<(), AssemblerError> {
+    match arguments {
+        []
+        | [
+            Ok(Token {
+                kind: TokenKind::Newline,
+                ..
+            }),
+        ] => Ok(()),
+        [Err(e), ..] => Err(e.clone()),
+        [Ok(token), ..] => Err(AssemblerError::from_token(
+            format!("Expected newline after '.{}' directive.", name),
+            token,
+        )),
+    }
+}
+
+/// Value of an `.equ` constant defined before the current line
+fn resolve_constant<'a>(
+    name: &str,
+    constants: &HashMap<&'a str, Vec<Token<'a>>>,
+    resolving: &mut Vec<String>,
+) -> Result<Address, AssemblerError> {
+    let tokens = constants.get(name).ok_or(AssemblerError::new(
+        format!(
+            "Symbol {} must be an '.equ' constant defined before the '.if' directive.",
+            name
+        ),
+        0,
+        0,
+        0,
+    ))?;
+    if resolving.iter().any(|symbol| symbol == name) {
+        return Err(AssemblerError::new(
+            format!("Recursive loop found while resolving {}", name),
+            0,
+            0,
+            0,
+        ));
+    }
+
+    resolving.push(name.to_string());
+    let value = Expression::shunting_yard(&mut tokens.iter().cloned())?
+        .evaluate(|name| resolve_constant(name, constants, resolving));
+    resolving.pop();
+    value
+}
+
+fn parse_condition<'a>(
+    arguments: Vec<TokenResult<'a>>,
+    directive: &Token<'a>,
+    constants: &HashMap<&'a str, Vec<Token<'a>>>,
+) -> Result<bool, AssemblerError> {
+    let mut tokens = Vec::new();
+    for token in arguments {
+        let token = token?;
+        if token.kind != TokenKind::Newline {
+            tokens.push(token);
+        }
+    }
+
+    let expression = Expression::shunting_yard(&mut tokens.into_iter())?;
+    if expression.is_empty() {
+        return Err(AssemblerError::from_token(
+            "Expected condition after '.if' directive.".into(),
+            directive,
+        ));
+    }
+
+    let expression_err = AssemblerError::from_expression("".into(), &expression);
+    let value = expression
+        .evaluate(|name| resolve_constant(name, constants, &mut Vec::new()))
+        .map_err(|e| {
+            if e.line_number == 0 {
+                AssemblerError {
+                    error_message: e.error_message,
+                    ..expression_err
+                }
+            } else {
+                e
+            }
+        })?;
+    Ok(value.1 != 0.into())
+}
+
+/// Evaluates one line against the `.if` blocks open in `stack`, so only the
+/// taken branch of each `.if expr` ... `.else` ... `.endif` block is kept.
+/// Returns the line if it should be assembled, or `None` if it is skipped or
+/// is itself part of a block. Conditions may use `.equ` constants from earlier
+/// kept lines, which are recorded in `constants`.
+fn filter_conditional<'a>(
+    mut line: Vec<TokenResult<'a>>,
+    stack: &mut Vec<Conditional<'a>>,
+    constants: &mut HashMap<&'a str, Vec<Token<'a>>>,
+    output: &mut Vec<TokenResult<'a>>,
+    errors: &mut Vec<AssemblerError>,
+) -> Option<Vec<TokenResult<'a>>> {
+    let active = stack.last().is_none_or(Conditional::active);
+    let Some((name, directive, rest)) = leading_directive(&line) else {
+        return active.then_some(line);
+    };
+    let directive = directive.clone();
+
+    match name {
+        "if" => {
+            let arguments = line.split_off(rest);
+            let taken = active
+                && parse_condition(arguments, &directive, constants).unwrap_or_else(|e| {
+                    errors.push(e);
+                    false
+                });
+            if active {
+                keep_label(line, rest, output);
+            }
+            stack.push(Conditional {
+                directive,
+                enclosing: active,
+                taken,
+                in_else: false,
+            });
+            None
+        }
+        "else" | "endif" => {
+            if let Err(e) = expect_line_end(&line[rest..], name) {
+                errors.push(e);
+            }
+            if active {
+                keep_label(line, rest, output);
+            }
+            match (name, stack.last_mut()) {
+                ("else", Some(conditional)) if !conditional.in_else => conditional.in_else = true,
+                ("else", Some(_)) => errors.push(AssemblerError::from_token(
+                    "Duplicate '.else' in '.if' block.".into(),
+                    &directive,
+                )),
+                ("endif", Some(_)) => {
+                    stack.pop();
+                }
+                (_, None) => errors.push(AssemblerError::from_token(
+                    format!("'.{}' without matching '.if' directive.", name),
+                    &directive,
+                )),
+                _ => unreachable!(),
+            }
+            None
+        }
+        _ if active => {
+            // Remember constants for later conditions
+            if let (
+                "equ",
+                [
+                    Ok(Token {
+                        kind: TokenKind::Symbol(symbol),
+                        ..
+                    }),
+                    Ok(Token {
+                        kind: TokenKind::Comma,
+                        ..
+                    }),
+                    value @ ..,
+                ],
+            ) = (name, &line[rest..])
+            {
+                let value = value
+                    .iter()
+                    .filter_map(|token| token.as_ref().ok())
+                    .filter(|token| token.kind != TokenKind::Newline)
+                    .cloned()
+                    .collect();
+                constants.insert(symbol, value);
+            }
+            Some(line)
+        }
+        _ => None,
+    }
+}
+
+fn close_conditionals(stack: Vec<Conditional>, errors: &mut Vec<AssemblerError>) {
+    for conditional in stack {
+        errors.push(AssemblerError::from_token(
+            "Missing '.endif' for '.if' directive.".into(),
+            &conditional.directive,
+        ));
+    }
+}
+
+/// Whether a symbol names an instruction, register or CSR, which ignore case
+/// whether or not `.option nocase` is used
+fn is_keyword(name: &str) -> bool {
+    ISA::from_str(&name.to_uppercase()).is_ok()
+        || pseudo_instruction_size(name).is_some()
+        || parse_register(name).is_ok()
+        || csr_address(name).is_some()
+}
+
+/// Handles `.option nocase`, which makes labels, constants and macro names
+/// case-insensitive for the whole program. Labels are case-sensitive
+/// otherwise, like in other assemblers. Every spelling of a name is replaced by
+/// the first one used, so `Loop`, `loop` and `LOOP` all name the same symbol.
+/// Runs before macros and `.if` blocks are expanded so they see the folded
+/// names. Directive names, mnemonics, registers and CSRs are left alone.
+pub fn fold_label_case<'a>(
+    tokens: impl Iterator<Item = TokenResult<'a>>,
+) -> (Vec<TokenResult<'a>>, Vec<AssemblerError>) {
+    let mut output = Vec::new();
+    let mut errors = Vec::new();
+    let mut fold = false;
+
+    for line in split_lines(tokens) {
+        match leading_directive(&line) {
+            Some(("option", directive, rest)) => {
+                match &line[rest..] {
+                    [
+                        Ok(Token {
+                            kind: TokenKind::Symbol("nocase"),
+                            ..
+                        }),
+                        end @ ..,
+                    ] => match expect_line_end(end, "option") {
+                        Ok(()) => fold = true,
+                        Err(e) => errors.push(e),
+                    },
+                    [
+                        Ok(
+                            option @ Token {
+                                kind: TokenKind::Symbol(name),
+                                ..
+                            },
+                        ),
+                        ..,
+                    ] => errors.push(AssemblerError::from_token(
+                        format!("Unknown option '{}', expected 'nocase'.", name),
+                        option,
+                    )),
+                    _ => errors.push(AssemblerError::from_token(
+                        "Expected an option after '.option' directive.".into(),
+                        directive,
+                    )),
+                }
+                keep_label(line, rest, &mut output);
+            }
+            _ => output.extend(line),
+        }
+    }
+
+    if fold {
+        let mut spellings: HashMap<String, &'a str> = HashMap::new();
+        let mut after_dot = false;
+        for token in output.iter_mut().flatten() {
+            if let TokenKind::Symbol(name) = &mut token.kind
+                && !after_dot
+                && !is_keyword(name)
+            {
+                *name = spellings.entry(name.to_lowercase()).or_insert(*name);
+            }
+            after_dot = token.kind == TokenKind::Dot;
+        }
+    }
+
+    (output, errors)
+}
+
+/// Limit on macros invoked from within other macros, which stops recursive
+/// macros without a reachable base case from expanding forever
+const MAX_MACRO_DEPTH: usize = 32;
+
+#[derive(Clone)]
+struct Macro<'a> {
+    parameters: Vec<&'a str>,
+    body: Vec<Vec<TokenResult<'a>>>,
+}
+
+fn parse_macro_header<'a>(
+    line: &[TokenResult<'a>],
+    directive: &Token<'a>,
+) -> Result<(&'a str, Vec<&'a str>), AssemblerError> {
+    let mut tokens = Vec::new();
+    for token in line {
+        let token = token.clone()?;
+        if token.kind != TokenKind::Newline {
+            tokens.push(token);
+        }
+    }
+
+    let mut tokens = tokens.into_iter();
+    let name = match tokens.next() {
+        Some(Token {
+            kind: TokenKind::Symbol(name),
+            ..
+        }) => name,
+        _ => {
+            return Err(AssemblerError::from_token(
+                "Expected macro name after '.macro' directive.".into(),
+                directive,
+            ));
+        }
+    };
+
+    let mut parameters: Vec<&str> = Vec::new();
+    for token in tokens {
+        match token.kind {
+            TokenKind::Comma => {}
+            TokenKind::Symbol(parameter) if !parameters.contains(&parameter) => {
+                parameters.push(parameter)
+            }
+            TokenKind::Symbol(parameter) => {
+                return Err(AssemblerError::from_token(
+                    format!("Duplicate macro parameter '{}'.", parameter),
+                    &token,
+                ));
+            }
+            _ => {
+                return Err(AssemblerError::from_token(
+                    "Expected parameter name in '.macro' directive.".into(),
+                    &token,
+                ));
+            }
+        }
+    }
+
+    Ok((name, parameters))
+}
+
+/// Collects the lines up to the `.endm` matching an already consumed `.macro`.
+/// Returns `None` if the stream ended first.
+fn capture_macro_body<'a>(
+    lines: &mut impl Iterator<Item = Vec<TokenResult<'a>>>,
+) -> Option<Vec<Vec<TokenResult<'a>>>> {
+    let mut body = Vec::new();
+    let mut depth = 0;
+
+    for line in lines {
+        match leading_directive(&line) {
+            Some(("macro", _, _)) => depth += 1,
+            Some(("endm", _, _)) if depth == 0 => return Some(body),
+            Some(("endm", _, _)) => depth -= 1,
+            _ => {}
+        }
+        body.push(line);
+    }
+
+    None
+}
+
+/// Returns the index and token of the macro name if the line (after an
+/// optional label) invokes one of `macros`.
+fn macro_invocation<'a>(
+    line: &[TokenResult<'a>],
+    macros: &HashMap<&'a str, Macro<'a>>,
+) -> Option<(usize, Token<'a>)> {
+    let start = match line {
+        [
+            Ok(Token {
+                kind: TokenKind::Symbol(_),
+                ..
+            }),
+            Ok(Token {
+                kind: TokenKind::Colon,
+                ..
+            }),
+            ..,
+        ] => 2,
+        _ => 0,
+    };
+
+    match line.get(start) {
+        Some(Ok(
+            token @ Token {
+                kind: TokenKind::Symbol(name),
+                ..
+            },
+        )) if macros.contains_key(name) => Some((start, token.clone())),
+        _ => None,
+    }
+}
+
+/// Splits the arguments of a macro invocation on commas
+fn parse_macro_arguments<'a>(
+    arguments: &[TokenResult<'a>],
+) -> Result<Vec<Vec<Token<'a>>>, AssemblerError> {
+    let mut tokens = Vec::new();
+    for token in arguments {
+        let token = token.clone()?;
+        if token.kind != TokenKind::Newline {
+            tokens.push(token);
+        }
+    }
+
+    if tokens.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(tokens
+        .split(|token| token.kind == TokenKind::Comma)
+        .map(|argument| argument.to_vec())
+        .collect())
+}
+
+/// Copies the body of a macro with each `\parameter` replaced by its argument.
+/// Every other token is moved onto the invocation so the expansion maps back
+/// to the line that invoked the macro.
+fn substitute_macro<'a>(
+    definition: &Macro<'a>,
+    arguments: &[Vec<Token<'a>>],
+    invocation: &Token<'a>,
+) -> Result<Vec<Vec<TokenResult<'a>>>, AssemblerError> {
+    let mut lines = Vec::new();
+
+    for line in &definition.body {
+        let mut expanded = Vec::new();
+        for token in line {
+            match token {
+                Ok(Token {
+                    kind: TokenKind::Symbol(name),
+                    ..
+                }) if name.starts_with('\\') => {
+                    let parameter = definition
+                        .parameters
+                        .iter()
+                        .position(|parameter| *parameter == &name[1..])
+                        .ok_or(AssemblerError::from_token(
+                            format!("Unknown macro parameter '{}'.", name),
+                            invocation,
+                        ))?;
+                    expanded.extend(arguments[parameter].iter().cloned().map(Ok));
+                }
+                Ok(token) => expanded.push(Ok(Token {
+                    line: invocation.line,
+                    column: invocation.column,
+                    width: invocation.width,
+                    ..token.clone()
+                })),
+                Err(e) => expanded.push(Err(AssemblerError::from_token(
+                    e.error_message.clone(),
+                    invocation,
+                ))),
+            }
+        }
+        lines.push(expanded);
+    }
+
+    Ok(lines)
+}
+
+fn expand_macro_lines<'a>(
+    lines: impl IntoIterator<Item = Vec<TokenResult<'a>>>,
+    depth: usize,
+    macros: &mut HashMap<&'a str, Macro<'a>>,
+    constants: &mut HashMap<&'a str, Vec<Token<'a>>>,
+    output: &mut Vec<TokenResult<'a>>,
+    errors: &mut Vec<AssemblerError>,
+) {
+    let mut lines = lines.into_iter();
+    // Each expansion has its own `.if` blocks, so a block can't span the end
+    // of a macro body
+    let mut stack = Vec::new();
+
+    while let Some(line) = lines.next() {
+        let Some(mut line) = filter_conditional(line, &mut stack, constants, output, errors) else {
+            continue;
+        };
+
+        match leading_directive(&line) {
+            Some(("macro", directive, rest)) => {
+                let directive = directive.clone();
+                let header = parse_macro_header(&line[rest..], &directive);
+
+                // Keep any label on its own line
+                if rest > 2 {
+                    let newline = line.pop();
+                    line.truncate(rest - 2);
+                    output.append(&mut line);
+                    output.extend(newline);
+                }
+
+                let Some(body) = capture_macro_body(&mut lines) else {
+                    errors.push(AssemblerError::from_token(
+                        "Missing '.endm' for '.macro' directive.".into(),
+                        &directive,
+                    ));
+                    continue;
+                };
+
+                match header {
+                    Ok((name, parameters)) => {
+                        macros.insert(name, Macro { parameters, body });
+                    }
+                    Err(e) => errors.push(e),
+                }
+                continue;
+            }
+            Some(("endm", directive, _)) => {
+                errors.push(AssemblerError::from_token(
+                    "'.endm' without matching '.macro' directive.".into(),
+                    directive,
+                ));
+                continue;
+            }
+            _ => {}
+        }
+
+        let Some((start, invocation)) = macro_invocation(&line, macros) else {
+            output.append(&mut line);
+            continue;
+        };
+
+        let TokenKind::Symbol(name) = invocation.kind else {
+            unreachable!("Macro invocations start with a symbol");
+        };
+
+        // Keep any label on its own line so it marks the start of the expansion
+        let arguments = line.split_off(start + 1);
+        line.truncate(start);
+        if let (false, Some(Ok(newline))) = (line.is_empty(), arguments.last()) {
+            line.push(Ok(newline.clone()));
+        }
+        output.append(&mut line);
+
+        if depth >= MAX_MACRO_DEPTH {
+            errors.push(AssemblerError::from_token(
+                format!(
+                    "Macro '{}' nested more than {} levels deep.",
+                    name, MAX_MACRO_DEPTH
+                ),
+                &invocation,
+            ));
+            continue;
+        }
+
+        let definition = macros[name].clone();
+        let arguments = match parse_macro_arguments(&arguments) {
+            Ok(arguments) if arguments.len() == definition.parameters.len() => arguments,
+            Ok(arguments) => {
+                errors.push(AssemblerError::from_token(
+                    format!(
+                        "Macro '{}' expects {} arguments but got {}.",
+                        name,
+                        definition.parameters.len(),
+                        arguments.len()
+                    ),
+                    &invocation,
+                ));
+                continue;
+            }
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+
+        match substitute_macro(&definition, &arguments, &invocation) {
+            Ok(body) => expand_macro_lines(body, depth + 1, macros, constants, output, errors),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    close_conditionals(stack, errors);
+}
+
+/// Expands `.macro NAME params` ... `.endm` definitions, replacing each later
+/// line that starts with `NAME` by the body of the macro with every `\param`
+/// replaced by the matching comma separated argument. Expanded tokens take the
+/// position of the invocation, so the source map and errors point at the line
+/// that used the macro.
+///
+/// `.if expr` ... `.else` ... `.endif` blocks are evaluated in the same pass,
+/// keeping only the taken branch. Conditions are evaluated in order as macros
+/// expand and may use `.equ` constants from earlier lines, so a macro can
+/// invoke itself inside an `.if` block that eventually stops the recursion.
+pub fn expand_macros<'a>(
+    tokens: impl Iterator<Item = TokenResult<'a>>,
+) -> (Vec<TokenResult<'a>>, Vec<AssemblerError>) {
+    let mut output = Vec::new();
+    let mut errors = Vec::new();
+    let mut macros = HashMap::new();
+    let mut constants = HashMap::new();
+
+    expand_macro_lines(
+        split_lines(tokens),
+        0,
+        &mut macros,
+        &mut constants,
+        &mut output,
+        &mut errors,
+    );
+
+    (output, errors)
+}
+
+/// A file spliced in by an `.include` directive. Its lines are renumbered to
+/// come after every line before it, so each line of the expanded program is
+/// unique and can be traced back to the file it came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Inclusion {
+    pub file: String,
+    /// Amount added to the line numbers of the included file
+    pub offset: usize,
+    pub lines: usize,
+    /// Line of the outermost `.include` directive in the including source
+    pub root_line: usize,
+}
+
+/// Returns the index of the `.include` directive in the line along with the
+/// name of the file to include.
+fn parse_include<'a>(
+    line: &[TokenResult<'a>],
+) -> Option<(usize, Token<'a>, Result<String, AssemblerError>)> {
+    let (name, directive, rest) = leading_directive(line)?;
+    if name != "include" {
+        return None;
+    }
+
+    let file = match &line[rest..] {
+        [
+            Ok(Token {
+                kind: TokenKind::StrLiteral(_, file),
+                ..
+            }),
+        ]
+        | [
+            Ok(Token {
+                kind: TokenKind::StrLiteral(_, file),
+                ..
+            }),
+            Ok(Token {
+                kind: TokenKind::Newline,
+                ..
+            }),
+        ] => Ok(file.clone()),
+        [.., Err(e)] => Err(e.clone()),
+        _ => Err(AssemblerError::from_token(
+            "Expected file name string after '.include' directive.".into(),
+            directive,
+        )),
+    };
+
+    Some((rest - 2, directive.clone(), file))
+}
+
+/// Names of the files included directly by the source
+pub fn include_names(source: &str) -> Vec<String> {
+    split_lines(Lexer::new(source))
+        .iter()
+        .filter_map(|line| parse_include(line)?.2.ok())
+        .collect()
+}
+
+/// Loads every file reachable through `.include` directives in the source.
+/// Files that could not be resolved are kept as `None`.
+pub fn load_includes(
+    source: &str,
+    mut resolve: impl FnMut(&str) -> Option<String>,
+) -> HashMap<String, Option<String>> {
+    let mut sources = HashMap::new();
+    let mut pending = include_names(source);
+
+    while let Some(name) = pending.pop() {
+        if sources.contains_key(&name) {
+            continue;
+        }
+
+        let included = resolve(&name);
+        if let Some(included) = &included {
+            pending.extend(include_names(included));
+        }
+        sources.insert(name, included);
+    }
+
+    sources
+}
+
+/// Loads the files named by `.incbin "name"` directives in the expanded
+/// tokens. Files that could not be resolved are kept as `None`.
+pub fn load_binaries(
+    tokens: &[TokenResult],
+    mut resolve: impl FnMut(&str) -> Option<Vec<u8>>,
+) -> HashMap<String, Option<Vec<u8>>> {
+    let mut binaries = HashMap::new();
+
+    for window in tokens.windows(3) {
+        if let [
+            Ok(Token {
+                kind: TokenKind::Dot,
+                ..
+            }),
+            Ok(Token {
+                kind: TokenKind::Symbol("incbin"),
+                ..
+            }),
+            Ok(Token {
+                kind: TokenKind::StrLiteral(_, name),
+                ..
+            }),
+        ] = window
+            && !binaries.contains_key(name)
+        {
+            binaries.insert(name.clone(), resolve(name));
+        }
+    }
+
+    binaries
+}
+
+struct IncludeState<'a> {
+    sources: &'a HashMap<String, Option<String>>,
+    next_offset: usize,
+    stack: Vec<&'a str>,
+    inclusions: Vec<Inclusion>,
+    errors: Vec<AssemblerError>,
+}
+
+fn splice_includes<'a>(
+    lines: Vec<Vec<TokenResult<'a>>>,
+    root_line: Option<usize>,
+    state: &mut IncludeState<'a>,
+    output: &mut Vec<TokenResult<'a>>,
+) {
+    for mut line in lines {
+        let Some((start, directive, file)) = parse_include(&line) else {
+            output.append(&mut line);
+            continue;
+        };
+
+        // Keep any label on its own line so it marks the start of the file
+        let newline = line.pop();
+        line.truncate(start);
+        output.append(&mut line);
+        if let Some(Ok(
+            newline @ Token {
+                kind: TokenKind::Newline,
+                ..
+            },
+        )) = newline
+        {
+            output.push(Ok(newline));
+        }
+
+        let file = match file {
+            Ok(file) => file,
+            Err(e) => {
+                state.errors.push(e);
+                continue;
+            }
+        };
+
+        let Some((file, Some(source))) = state.sources.get_key_value(&file) else {
+            state.errors.push(AssemblerError::from_token(
+                format!("Could not find file '{}' to include.", file),
+                &directive,
+            ));
+            continue;
+        };
+
+        if state.stack.contains(&file.as_str()) {
+            state.errors.push(AssemblerError::from_token(
+                format!("File '{}' includes itself.", file),
+                &directive,
+            ));
+            continue;
+        }
+
+        let offset = state.next_offset;
+        let lines = source.matches('\n').count() + 1;
+        state.next_offset += lines;
+        let root_line = root_line.unwrap_or(directive.line);
+        state.inclusions.push(Inclusion {
+            file: file.clone(),
+            offset,
+            lines,
+            root_line,
+        });
+
+        let tokens = Lexer::new(source).map(|token| match token {
+            Ok(token) => Ok(Token {
+                line: token.line + offset,
+                ..token
+            }),
+            Err(e) => Err(AssemblerError {
+                line_number: e.line_number + offset,
+                ..e
+            }),
+        });
+
+        state.stack.push(file);
+        splice_includes(split_lines(tokens), Some(root_line), state, output);
+        state.stack.pop();
+    }
+}
+
+/// Replaces `.include "name"` directives with the tokens of the named file
+/// from `sources`. Included lines are numbered after the `line_count` lines of
+/// the including source, with each inclusion recorded so they can be mapped
+/// back to their file.
+pub fn expand_includes<'a>(
+    tokens: impl Iterator<Item = TokenResult<'a>>,
+    sources: &'a HashMap<String, Option<String>>,
+    line_count: usize,
+) -> (Vec<TokenResult<'a>>, Vec<Inclusion>, Vec<AssemblerError>) {
+    let mut state = IncludeState {
+        sources,
+        next_offset: line_count,
+        stack: Vec::new(),
+        inclusions: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut output = Vec::new();
+
+    splice_includes(split_lines(tokens), None, &mut state, &mut output);
+
+    (output, state.inclusions, state.errors)
+}
@@ -1,7 +1,8 @@
-use crate::assembler::Address;
+use crate::assembler::{Address, AssemblerError, is_internal_symbol};
+use crate::emulator::timer::TIMER_REGISTER_SIZE;
+use crate::isa::Instruction;
 
-use bimap::BiBTreeMap;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::OnceLock;
 
 #[derive(Clone, Debug)]
@@ -12,29 +13,200 @@ pub struct AssembledProgram {
     /// Map of initial data memory addresses to data bytes
     pub initial_data_memory: BTreeMap<u32, u8>,
 
-    /// Map of instruction addresses (left) to line numbers (right)
-    pub source_map: BiBTreeMap<u32, usize>,
+    /// Map of instruction addresses to line numbers
+    pub source_map: BTreeMap<u32, usize>,
 
     /// Map of instruction labels to addresses
     pub symbol_table: HashMap<String, Address>,
+
+    /// Where sections start when not given an address, and where the
+    /// emulator maps its devices
+    pub memory_map: MemoryMap,
+
+    /// Where each label is defined in the source
+    pub labels: BTreeMap<String, SourceSpan>,
+
+    /// Symbols named by at least one instruction or directive
+    pub referenced_symbols: HashSet<String>,
+
+    /// Problems that did not stop the program assembling, such as skipped directives
+    pub warnings: Vec<AssemblerError>,
 }
 
 impl AssembledProgram {
+    /// Where a section starts, which for the text section is where execution
+    /// begins. When the source has several `.text` or `.data` sections, the
+    /// start is the lowest address in any of them, whatever order they appear
+    /// in. The text section starts at its lowest instruction, so data placed
+    /// in it is never run as the first instruction. A section with nothing in
+    /// it starts at its base from `memory_map`.
     pub fn get_section_start(&self, section: Section) -> u32 {
         match section {
-            Section::Text => self.source_map.left_values().next().copied().unwrap_or(0),
-            Section::Data => self.initial_data_memory.keys().next().copied().unwrap_or(0),
+            Section::Text => self
+                .source_map
+                .first_key_value()
+                .map_or(self.memory_map.sections.text, |(&address, _)| address),
+            Section::Data => self
+                .initial_data_memory
+                .first_key_value()
+                .map_or(self.memory_map.sections.data, |(&address, _)| address),
             _ => todo!(), // TODO: Add support for other sections and user-defined sections
         }
     }
 
+    /// Lists the section start addresses followed by every symbol's resolved
+    /// address, name and section, sorted by address like a linker map
+    pub fn symbol_map(&self) -> String {
+        let mut symbols: Vec<_> = self
+            .symbol_table
+            .iter()
+            .filter(|(name, _)| !is_internal_symbol(name))
+            .collect();
+        symbols.sort_by(|(a_name, a), (b_name, b)| a.1.cmp(&b.1).then(a_name.cmp(b_name)));
+        let name_width = symbols
+            .iter()
+            .map(|(name, _)| name.len())
+            .chain(["Symbol".len()])
+            .max()
+            .unwrap_or_default();
+
+        let mut map = String::from("Section  Start\n");
+        for section in [Section::Text, Section::Data] {
+            let start = self.get_section_start(section.clone());
+            map.push_str(&format!("{:<8} 0x{:08X}\n", format!(".{}", section), start));
+        }
+
+        map.push_str(&format!(
+            "\nAddress     {:<name_width$}  Section\n",
+            "Symbol"
+        ));
+        for (name, symbol) in symbols {
+            let address = u32::try_from(&symbol.1)
+                .map(|address| format!("0x{:08X}", address))
+                .unwrap_or_else(|_| symbol.1.to_string());
+            let section = match &symbol.0 {
+                Section::Absolute => "*ABS*".to_string(),
+                section => format!(".{}", section),
+            };
+            map.push_str(&format!(
+                "{:<10}  {:<name_width$}  {}\n",
+                address, name, section
+            ));
+        }
+        map
+    }
+
+    /// Names an address after the closest symbol at or before it in the same
+    /// section, as `label` or `label+N`. Constants and section markers are skipped.
+    pub fn symbol_for(&self, address: u32, section: Section) -> Option<String> {
+        self.symbol_table
+            .iter()
+            .filter(|(name, symbol)| !is_internal_symbol(name) && symbol.0 == section)
+            .filter_map(|(name, symbol)| {
+                let symbol_address = u32::try_from(&symbol.1).ok()?;
+                Some((address.checked_sub(symbol_address)?, name))
+            })
+            // Prefer the nearest symbol, then the first name alphabetically
+            .min()
+            .map(|(offset, name)| match offset {
+                0 => name.clone(),
+                offset => format!("{}+{}", name, offset),
+            })
+    }
+
+    /// The instruction stored at an address, if all four of its bytes were assembled
+    pub fn instruction_at(&self, address: u32) -> Option<Instruction> {
+        let mut bytes = [0u8; 4];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let byte_address = address.checked_add(i as u32)?;
+            *byte = *self.instruction_memory.get(&byte_address)?;
+        }
+        Some(Instruction::from_raw(u32::from_le_bytes(bytes)))
+    }
+
+    /// The global source line the instruction at an address was assembled from
+    pub fn source_line_at(&self, address: u32) -> Option<usize> {
+        self.source_map.get(&address).copied()
+    }
+
+    /// Disassembles the instruction at an address, naming branch and jump
+    /// targets after the labels they land on
+    pub fn disassemble(&self, address: u32) -> Option<String> {
+        self.instruction_at(address)?
+            .to_asm(address, |target| self.symbol_for(target, Section::Text))
+    }
+
+    /// Lists instruction memory one instruction per line, as its address,
+    /// encoding and disassembly, followed by the line of `source` it was
+    /// assembled from. Words that aren't instructions are listed as `.word`
+    /// and bytes that don't make up a whole word as `.byte`.
+    pub fn listing(&self, source: Option<&str>) -> String {
+        let source_lines: Vec<&str> = source.map(|s| s.lines().collect()).unwrap_or_default();
+        let mut listing = String::new();
+        let mut next_address = None;
+        for &address in self.instruction_memory.keys() {
+            // Skip the bytes of the word just listed
+            if next_address.is_some_and(|next| address < next) {
+                continue;
+            }
+
+            let (encoding, asm, size) = match self.instruction_at(address) {
+                Some(instruction) => (
+                    format!("{:08X}", instruction.raw()),
+                    self.disassemble(address)
+                        .unwrap_or_else(|| format!(".word 0x{:08x}", instruction.raw())),
+                    4,
+                ),
+                None => {
+                    let byte = self.instruction_memory[&address];
+                    (format!("{:02X}", byte), format!(".byte 0x{:02x}", byte), 1)
+                }
+            };
+            next_address = address.checked_add(size);
+
+            let mut row = format!("0x{:08X}  {:<8}  {}", address, encoding, asm);
+            let line = self.source_line_at(address).and_then(|line| {
+                let text = source_lines.get(line.checked_sub(1)?)?;
+                Some((line, text.trim()))
+            });
+            if let Some((line, text)) = line {
+                row = format!("{:<50}  {:>4}: {}", row, line, text);
+            }
+            listing.push_str(row.trim_end());
+            listing.push('\n');
+        }
+        listing
+    }
+
+    /// A program holding only the given machine code, starting at `base`,
+    /// with no source or symbols
+    pub fn from_machine_code(base: u32, bytes: &[u8]) -> Self {
+        AssembledProgram {
+            instruction_memory: (base..).zip(bytes.iter().copied()).collect(),
+            initial_data_memory: BTreeMap::new(),
+            source_map: BTreeMap::new(),
+            symbol_table: HashMap::new(),
+            memory_map: MemoryMap::with_sections(SectionBases {
+                text: base,
+                ..SectionBases::default()
+            }),
+            labels: BTreeMap::new(),
+            referenced_symbols: HashSet::new(),
+            warnings: Vec::new(),
+        }
+    }
+
     pub fn empty() -> &'static Self {
         static EMPTY: OnceLock<AssembledProgram> = OnceLock::new();
         EMPTY.get_or_init(|| AssembledProgram {
             instruction_memory: BTreeMap::new(),
             initial_data_memory: BTreeMap::new(),
-            source_map: BiBTreeMap::new(),
+            source_map: BTreeMap::new(),
             symbol_table: HashMap::new(),
+            memory_map: MemoryMap::default(),
+            labels: BTreeMap::new(),
+            referenced_symbols: HashSet::new(),
+            warnings: Vec::new(),
         })
     }
 
@@ -43,7 +215,7 @@ impl AssembledProgram {
         &self,
     ) -> (
         &BTreeMap<u32, u8>,
-        &BiBTreeMap<u32, usize>,
+        &BTreeMap<u32, usize>,
         &BTreeMap<u32, u8>,
     ) {
         (
@@ -54,6 +226,87 @@ impl AssembledProgram {
     }
 }
 
+/// A span of source text, such as a label's name where it is defined
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SourceSpan {
+    pub line: usize,
+    pub column: usize,
+    pub width: usize,
+}
+
+/// Default start addresses of the `.text` and `.data` sections, used for
+/// section directives without an address and for code before any directive.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct SectionBases {
+    pub text: u32,
+    pub data: u32,
+}
+
+impl SectionBases {
+    /// The classic RISC-V/MIPS layout, with text at `0x00400000` and data at `0x10010000`
+    pub const CLASSIC: Self = Self {
+        text: 0x0040_0000,
+        data: 0x1001_0000,
+    };
+
+    /// The address a section starts at when none is given
+    pub fn base(&self, section: &Section) -> u32 {
+        match section {
+            Section::Text => self.text,
+            Section::Data => self.data,
+            _ => 0,
+        }
+    }
+}
+
+/// Where everything a program can address lives. The assembler places the
+/// sections and the emulator maps the UART and timer from it, with the UART's
+/// line status register 4 bytes after its data register.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MemoryMap {
+    /// Where `.text` and `.data` start when a section directive gives no address
+    pub sections: SectionBases,
+    /// Address of the UART's data register
+    pub uart: u32,
+    /// Address of the timer's `mtime` register, with `mtimecmp` 8 bytes after it
+    pub timer: u32,
+    /// Bytes from the start of the program's data zeroed by `MemoryInit::Zeroed`
+    pub zeroed_size: u32,
+}
+
+impl Default for MemoryMap {
+    fn default() -> Self {
+        Self {
+            sections: SectionBases::default(),
+            uart: 0xF0,
+            // The top 16 bytes of memory, out of the way of any data and still
+            // reachable as a negative offset from x0
+            timer: 0xFFFF_FFF0,
+            zeroed_size: 0x400,
+        }
+    }
+}
+
+impl MemoryMap {
+    /// The default map with the given section bases
+    pub fn with_sections(sections: SectionBases) -> Self {
+        Self {
+            sections,
+            ..Self::default()
+        }
+    }
+
+    /// Address of the UART's line status register
+    pub fn uart_status(&self) -> u32 {
+        self.uart + 4
+    }
+
+    /// Address of the timer's `mtimecmp` register
+    pub fn timer_compare(&self) -> u32 {
+        self.timer + TIMER_REGISTER_SIZE
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Section {
     Absolute,
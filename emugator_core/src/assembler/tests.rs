@@ -2,12 +2,16 @@
 
 use std::collections::BTreeMap;
 
-use bimap::BiBTreeMap;
 use ibig::IBig;
 
 use crate::assembler::lexer::Lexer;
+use crate::isa::{ISA, Instruction};
 
-use super::{assemble, parse_expression};
+use super::{
+    AssembleOptions, MemoryMap, Section, SectionBases, assemble, assemble_files, file_line_offsets,
+    find_unused_labels, is_internal_symbol, lint_operands, locate_line, parse_expression,
+    validate_branch_targets,
+};
 use crate::include_test_file;
 
 #[ignore]
@@ -27,15 +31,16 @@ fn print_lexer() {
 #[test]
 fn print_some_output() {
     let program = include_test_file!("simple-loop.s");
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, source_map, data_mem) = assembled_program.emulator_maps();
 
     println!("Instruction Memory (Address -> Byte):");
@@ -54,7 +59,7 @@ fn print_some_output() {
     }
 
     println!("\nReconstructed 32-bit Instructions:");
-    for &addr in source_map.left_values() {
+    for &addr in source_map.keys() {
         let instruction = u32::from_le_bytes([
             inst_mem[&addr],
             inst_mem[&(addr + 1)],
@@ -85,7 +90,7 @@ fn test_randomized_instructions() {
             writeln!(program, "{}", instr).unwrap();
         }
 
-        match assemble(&program) {
+        match assemble(&program, AssembleOptions::default()) {
             Ok(_) => {}
             Err(errors) => {
                 writeln!(errors_panic, "Test {}: Program failed to assemble:", i).unwrap();
@@ -149,7 +154,7 @@ fn test_randomized_data_section() {
         writeln!(program, "LW x2, 4(x0)").unwrap();
         writeln!(program, "ADD x3, x1, x2").unwrap();
 
-        let result = assemble(&program);
+        let result = assemble(&program, AssembleOptions::default());
         match result {
             Ok(_) => {}
             Err(errors) => {
@@ -235,7 +240,7 @@ fn test_randomized_branches() {
             writeln!(program, "{}:", labels[0]).unwrap();
         }
 
-        let result = assemble(&program);
+        let result = assemble(&program, AssembleOptions::default());
         match result {
             Ok(_) => {}
             Err(errors) => {
@@ -339,15 +344,16 @@ fn gen_random_instruction<R: rand::Rng>(rng: &mut R) -> String {
 #[test]
 fn test_large_word() {
     let program = ".data\nwords: .word 12345678";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (_, _, data_mem) = assembled_program.emulator_maps();
 
     // these bytes are just 12345678 in little endian so essentially what .word should store
@@ -363,18 +369,79 @@ fn test_large_word() {
     }
 }
 
+fn assemble_data(source: &str) -> Vec<u8> {
+    let program = assemble(source, AssembleOptions::default()).unwrap_or_else(|errors| {
+        panic!("Assembly failed: {}", errors[0].error_message);
+    });
+    let (_, _, data_mem) = program.emulator_maps();
+    data_mem.values().copied().collect()
+}
+
 #[test]
-fn test_directive_equ() {
-    let program = ".equ value, 42 << 1";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
+fn test_dword_values() {
+    for directive in [".dword", ".8byte"] {
+        assert_eq!(
+            assemble_data(&format!(".data\n{} -1", directive)),
+            [0xFF; 8],
+            "{} -1",
+            directive
+        );
+        assert_eq!(
+            assemble_data(&format!(".data\n{} 0xFFFFFFFFFFFFFFFF", directive)),
+            [0xFF; 8]
+        );
+        assert_eq!(
+            assemble_data(&format!(".data\n{} 0x7FFFFFFFFFFFFFFF", directive)),
+            [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]
+        );
+        assert_eq!(
+            assemble_data(&format!(".data\n{} -0x8000000000000000", directive)),
+            [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80]
+        );
+        assert_eq!(
+            assemble_data(&format!(".data\n{} 0x12345678, -2", directive)),
+            [
+                0x78, 0x56, 0x34, 0x12, 0x00, 0x00, 0x00, 0x00, 0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+                0xFF, 0xFF
+            ]
+        );
+
+        for too_large in ["0x10000000000000000", "-0x8000000000000001"] {
+            let result = assemble(
+                &format!(".data\n{} {}", directive, too_large),
+                AssembleOptions::default(),
+            );
+            let errors = result.expect_err(too_large);
+            assert!(
+                errors[0].error_message.contains("too large for 8 bytes"),
+                "{}",
+                errors[0].error_message
             );
         }
-        panic!("Assembly failed");
-    });
+    }
+}
+
+#[test]
+fn test_data_signed_range() {
+    assert_eq!(assemble_data(".data\n.byte -128, 255"), [0x80, 0xFF]);
+    assert!(assemble(".data\n.byte -129", AssembleOptions::default()).is_err());
+    assert!(assemble(".data\n.half -0x8001", AssembleOptions::default()).is_err());
+    assert!(assemble(".data\n.word -0x80000001", AssembleOptions::default()).is_err());
+}
+
+#[test]
+fn test_directive_equ() {
+    let program = ".equ value, 42 << 1";
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
 
     assert_eq!(
         assembled_program.symbol_table.get("value").unwrap().1,
@@ -382,46 +449,193 @@ fn test_directive_equ() {
     );
 }
 
+#[test]
+fn symbols_like_section_markers() {
+    let program = assemble(
+        ".equ section, 4\n.text\nsection_0_0: addi x1, x0, section\n.data\nsection_4_1: .word section",
+        AssembleOptions::default(),
+    )
+    .expect("Symbols named like section markers should not clash with them.");
+
+    assert_eq!(program.symbol_table["section"].1, 4.into());
+    assert_eq!(program.symbol_table["section_0_0"].1, 0.into());
+    assert_eq!(program.symbol_table["section_4_1"].1, 0.into());
+    assert!(
+        program
+            .symbol_table
+            .keys()
+            .any(|name| is_internal_symbol(name))
+    );
+    assert!(
+        ["section", "section_0_0", "section_4_1"]
+            .iter()
+            .all(|name| !is_internal_symbol(name))
+    );
+
+    let errors = assemble(
+        ".equ section, 1\n.equ section, 2",
+        AssembleOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].error_message,
+        "Symbol section redefined at line 2."
+    );
+}
+
+#[test]
+fn label_case() {
+    // Labels are case-sensitive by default, so these are two different labels
+    let source = "Loop: addi x1, x1, 1\nloop: addi x2, x2, 1\nbne x1, x0, Loop\nbne x2, x0, loop";
+    let program = assemble(source, AssembleOptions::default())
+        .expect("Labels differing in case should both assemble.");
+    assert_eq!(program.symbol_table["Loop"].1, 0.into());
+    assert_eq!(program.symbol_table["loop"].1, 4.into());
+    assert_eq!(program.instruction_at(8).unwrap().immediate(), Some(-8));
+    assert_eq!(program.instruction_at(12).unwrap().immediate(), Some(-8));
+
+    let errors = assemble(
+        "Loop: addi x1, x1, 1\nbne x1, x0, loop",
+        AssembleOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(errors[0].error_message, "Symbol loop not defined.");
+
+    // With the option, every spelling names the first one used
+    let program = assemble(
+        ".option nocase\n.equ Step, 1\nLoop: addi x1, x1, STEP\nbne x1, x0, loop\nbeq x1, x0, LOOP",
+        AssembleOptions::default(),
+    )
+    .expect("Labels should match in any case with '.option nocase'.");
+    assert_eq!(program.symbol_table["Loop"].1, 0.into());
+    assert!(!program.symbol_table.contains_key("loop"));
+    assert_eq!(program.instruction_at(0).unwrap().immediate(), Some(1));
+    assert_eq!(program.instruction_at(4).unwrap().immediate(), Some(-4));
+    assert_eq!(program.instruction_at(8).unwrap().immediate(), Some(-8));
+
+    // Constants in conditions and macro names fold too
+    let program = assemble(
+        ".option nocase\n.equ Foo, 1\n.macro Bump\naddi x1, x1, FOO\n.endm\n.if FOO\nBUMP\n.else\naddi x1, x1, 2\n.endif",
+        AssembleOptions::default(),
+    )
+    .expect("Conditions and macros should match in any case with '.option nocase'.");
+    assert_eq!(program.instruction_at(0).unwrap().immediate(), Some(1));
+    assert!(program.instruction_at(4).is_none());
+
+    // The option applies to the whole program, wherever it is
+    let errors = assemble(
+        "Loop: addi x1, x1, 1\nloop: addi x2, x2, 1\n.option nocase",
+        AssembleOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(errors[0].error_message, "Symbol Loop redefined at line 2.");
+
+    // Directive names keep their meaning even when a label is spelled like one
+    let program = assemble(
+        ".option nocase\nData: addi x1, x0, 1\n.data\nvalue: .word DATA",
+        AssembleOptions::default(),
+    )
+    .expect("Directives should be unaffected by '.option nocase'.");
+    assert_eq!(
+        program
+            .initial_data_memory
+            .values()
+            .copied()
+            .collect::<Vec<_>>(),
+        [0, 0, 0, 0]
+    );
+
+    let errors = assemble(".option rvc", AssembleOptions::default()).unwrap_err();
+    assert_eq!(
+        errors[0].error_message,
+        "Unknown option 'rvc', expected 'nocase'."
+    );
+}
+
+#[test]
+fn equ_branch_targets() {
+    let program = assemble(
+        "loop: addi x1, x1, 1\n.equ LOOP, loop\n.equ SKIP, end + 4\nbeq x1, x2, LOOP\nbne x1, x2, SKIP\nend: jal x0, LOOP",
+        AssembleOptions::default(),
+    )
+    .expect("Branches to .equ constants should assemble.");
+    let expected = assemble(
+        "loop: addi x1, x1, 1\nbeq x1, x2, loop\nbne x1, x2, end + 4\nend: jal x0, loop",
+        AssembleOptions::default(),
+    )
+    .expect("Branches to labels should assemble.");
+    assert_eq!(program.instruction_memory, expected.instruction_memory);
+
+    let read = |address: u32| {
+        Instruction::from_raw(u32::from_le_bytes(
+            [0, 1, 2, 3].map(|i| program.instruction_memory[&(address + i)]),
+        ))
+    };
+    assert_eq!(read(0x4).immediate(), Some(-4));
+    assert_eq!(read(0x8).immediate(), Some(8));
+    assert_eq!(read(0xC).immediate(), Some(-12));
+}
+
+#[test]
+fn equ_displacements() {
+    let program = assemble(
+        ".equ OFFSET, 8\n.equ NEGATIVE, -OFFSET / 2\nlw x1, OFFSET(x2)\nsw x1, NEGATIVE(x2)\naddi x3, x0, NEGATIVE",
+        AssembleOptions::default(),
+    )
+    .expect("Displacements from .equ constants should assemble.");
+
+    let read = |address: u32| {
+        Instruction::from_raw(u32::from_le_bytes(
+            [0, 1, 2, 3].map(|i| program.instruction_memory[&(address + i)]),
+        ))
+    };
+    assert_eq!(read(0x0).immediate(), Some(8));
+    assert_eq!(read(0x4).immediate(), Some(-4));
+    assert_eq!(read(0x8).immediate(), Some(-4));
+}
+
 #[test]
 fn test_ECALL_extra_operands() {
     let program = ".text\nECALL x1";
-    let assembled_program = assemble(program);
+    let assembled_program = assemble(program, AssembleOptions::default());
     println!("{:?}", assembled_program.unwrap_err());
 }
 
 #[test]
 fn test_I_missing_operands() {
     let program = ".text\nSLLI";
-    let assembled_program = assemble(program);
+    let assembled_program = assemble(program, AssembleOptions::default());
     println!("{:?}", assembled_program.unwrap_err());
 }
 
 #[test]
 fn test_I_missing_rs1() {
     let program = ".text\nSLLI x1";
-    let assembled_program = assemble(program);
+    let assembled_program = assemble(program, AssembleOptions::default());
     println!("{:?}", assembled_program.unwrap_err());
 }
 
 #[test]
 fn test_I_missing_imm() {
     let program = ".text\nSLLI x1, x2";
-    let assembled_program = assemble(program);
+    let assembled_program = assemble(program, AssembleOptions::default());
     println!("{:?}", assembled_program.unwrap_err());
 }
 
 #[test]
 fn test_ADD() {
     let program = ".text\nADD X1, X2, X3";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xB3, 0x00, 0x31, 0x00];
@@ -439,15 +653,16 @@ fn test_ADD() {
 #[test]
 fn test_SUB() {
     let program = ".text\nSUB X1, X2, X3";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xB3, 0x00, 0x31, 0x40];
@@ -465,15 +680,16 @@ fn test_SUB() {
 #[test]
 fn test_SLT() {
     let program = ".text\nSLT X1, X2, X3";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xB3, 0x20, 0x31, 0x00];
@@ -491,15 +707,16 @@ fn test_SLT() {
 #[test]
 fn test_SLTU() {
     let program = ".text\nSLTU X1, X2, X3";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xB3, 0x30, 0x31, 0x00];
@@ -517,15 +734,16 @@ fn test_SLTU() {
 #[test]
 fn test_AND() {
     let program = ".text\nAND X1, X2, X3";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xB3, 0x70, 0x31, 0x00];
@@ -543,15 +761,16 @@ fn test_AND() {
 #[test]
 fn test_OR() {
     let program = ".text\nOR X1, X2, X3";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xB3, 0x60, 0x31, 0x00];
@@ -569,15 +788,16 @@ fn test_OR() {
 #[test]
 fn test_XOR() {
     let program = ".text\nXOR X1, X2, X3";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xB3, 0x40, 0x31, 0x00];
@@ -595,15 +815,16 @@ fn test_XOR() {
 #[test]
 fn test_SLL() {
     let program = ".text\nSLL X1, X2, X3";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xB3, 0x10, 0x31, 0x00];
@@ -621,15 +842,16 @@ fn test_SLL() {
 #[test]
 fn test_SRL() {
     let program = ".text\nSRL X1, X2, X3";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xB3, 0x50, 0x31, 0x00];
@@ -647,15 +869,16 @@ fn test_SRL() {
 #[test]
 fn test_SRA() {
     let program = ".text\nSRA X1, X2, X3";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xB3, 0x50, 0x31, 0x40];
@@ -673,15 +896,16 @@ fn test_SRA() {
 #[test]
 fn test_ADDI() {
     let program = ".text\nADDI X1, X2, 10";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x93, 0x00, 0xA1, 0x00];
@@ -698,15 +922,16 @@ fn test_ADDI() {
 
 #[test]
 fn test_ADDI_neg() {
-    let assembled_program = assemble(".text\nADDI x1, x2, -5").unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program = assemble(".text\nADDI x1, x2, -5", AssembleOptions::default())
+        .unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x93, 0x00, 0xB1, 0xFF];
@@ -724,15 +949,16 @@ fn test_ADDI_neg() {
 #[test]
 fn test_SLTI() {
     let program = ".text\nSLTI X1, X2, 10";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x93, 0x20, 0xA1, 0x00];
@@ -750,15 +976,16 @@ fn test_SLTI() {
 #[test]
 fn test_SLTIU() {
     let program = ".text\nSLTIU X1, X2, 10";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x93, 0x30, 0xA1, 0x00];
@@ -776,15 +1003,16 @@ fn test_SLTIU() {
 #[test]
 fn test_ANDI() {
     let program = ".text\nANDI X1, X2, 0xFF";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x93, 0x70, 0xF1, 0x0F];
@@ -802,15 +1030,16 @@ fn test_ANDI() {
 #[test]
 fn test_ORI() {
     let program = ".text\nORI X1, X2, 0xFF";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x93, 0x60, 0xF1, 0x0F];
@@ -828,15 +1057,16 @@ fn test_ORI() {
 #[test]
 fn test_XORI() {
     let program = ".text\nXORI X1, X2, 0xFF";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x93, 0x40, 0xF1, 0x0F];
@@ -854,15 +1084,16 @@ fn test_XORI() {
 #[test]
 fn test_SLLI() {
     let program = ".text\nSLLI X1, X2, 2";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x93, 0x10, 0x21, 0x00];
@@ -880,15 +1111,16 @@ fn test_SLLI() {
 #[test]
 fn test_SRLI() {
     let program = ".text\nSRLI X1, X2, 2";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x93, 0x50, 0x21, 0x00];
@@ -906,15 +1138,16 @@ fn test_SRLI() {
 #[test]
 fn test_SRAI() {
     let program = ".text\nSRAI X1, X2, 2";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x93, 0x50, 0x21, 0x40];
@@ -932,15 +1165,16 @@ fn test_SRAI() {
 #[test]
 fn test_JALR() {
     let program = ".text\nJALR X1, X2, 0x100";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xE7, 0x00, 0x01, 0x10];
@@ -958,15 +1192,16 @@ fn test_JALR() {
 #[test]
 fn test_LW() {
     let program = ".text\nLW X1, 0(X2)";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x83, 0x20, 0x01, 0x00];
@@ -984,15 +1219,16 @@ fn test_LW() {
 #[test]
 fn test_LH() {
     let program = ".text\nLH X1, 0(X2)";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x83, 0x10, 0x01, 0x00];
@@ -1010,15 +1246,16 @@ fn test_LH() {
 #[test]
 fn test_LHU() {
     let program = ".text\nLHU X1, 0(X2)";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x83, 0x50, 0x01, 0x00];
@@ -1036,15 +1273,16 @@ fn test_LHU() {
 #[test]
 fn test_LB() {
     let program = ".text\nLB X1, 0(X2)";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x83, 0x00, 0x01, 0x00];
@@ -1062,15 +1300,16 @@ fn test_LB() {
 #[test]
 fn test_LBU() {
     let program = ".text\nLBU X1, 0(X2)";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x83, 0x40, 0x01, 0x00];
@@ -1088,15 +1327,16 @@ fn test_LBU() {
 #[test]
 fn test_FENCE() {
     let program = ".text\nFENCE";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x0F, 0x00, 0x00, 0x00];
@@ -1114,15 +1354,16 @@ fn test_FENCE() {
 #[test]
 fn test_ECALL() {
     let program = ".text\nECALL";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x73, 0x00, 0x00, 0x00];
@@ -1140,15 +1381,16 @@ fn test_ECALL() {
 #[test]
 fn test_EBREAK() {
     let program = ".text\nEBREAK";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x73, 0x00, 0x10, 0x00];
@@ -1166,15 +1408,16 @@ fn test_EBREAK() {
 #[test]
 fn test_BEQ() {
     let program = ".text\nlabel:\nBEQ X1, X2, label";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x63, 0x80, 0x20, 0x00];
@@ -1192,15 +1435,16 @@ fn test_BEQ() {
 #[test]
 fn test_BNE() {
     let program = ".text\nlabel:\nBNE X1, X2, label";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x63, 0x90, 0x20, 0x00];
@@ -1218,15 +1462,16 @@ fn test_BNE() {
 #[test]
 fn test_BLT() {
     let program = ".text\nlabel:\nBLT X1, X2, label";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x63, 0xC0, 0x20, 0x00];
@@ -1244,15 +1489,16 @@ fn test_BLT() {
 #[test]
 fn test_BLTU() {
     let program = ".text\nlabel:\nBLTU X1, X2, label";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x63, 0xE0, 0x20, 0x00];
@@ -1270,15 +1516,16 @@ fn test_BLTU() {
 #[test]
 fn test_BGE() {
     let program = ".text\nlabel:\nBGE X1, X2, label";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x63, 0xD0, 0x20, 0x00];
@@ -1296,15 +1543,16 @@ fn test_BGE() {
 #[test]
 fn test_BGEU() {
     let program = ".text\nlabel:\nBGEU X1, X2, label";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x63, 0xF0, 0x20, 0x00];
@@ -1322,15 +1570,16 @@ fn test_BGEU() {
 #[test]
 fn test_LUI() {
     let program = ".text\nLUI X1, 0xFFF";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0xB7, 0xF0, 0xFF, 0x00];
@@ -1348,15 +1597,16 @@ fn test_LUI() {
 #[test]
 fn test_AUIPC() {
     let program = ".text\nAUIPC X1, 0xFFF";
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, _, _) = assembled_program.emulator_maps();
 
     let expected_bytes = [0x97, 0xF0, 0xFF, 0x00];
@@ -1374,15 +1624,16 @@ fn test_AUIPC() {
 #[test]
 fn assembler_different_locations() {
     let program = include_test_file!("different-locations.s");
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, source_map, data_mem) = assembled_program.emulator_maps();
 
     // actual instruction memory
@@ -1420,7 +1671,7 @@ fn assembler_different_locations() {
 
     for (addr, expected_line) in expected_source_lines {
         assert_eq!(
-            source_map.get_by_left(&addr),
+            source_map.get(&addr),
             Some(&expected_line),
             "Mismatch in source map at address 0x{:08X}",
             addr
@@ -1478,15 +1729,16 @@ fn assembler_different_locations() {
 #[test]
 fn assembler_simple_loop() {
     let program = include_test_file!("simple-loop.s");
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, source_map, data_mem) = assembled_program.emulator_maps();
 
     // Verify instruction memory
@@ -1541,7 +1793,7 @@ fn assembler_simple_loop() {
     .collect();
 
     // Verify source map
-    let expected_source_map: BiBTreeMap<u32, usize> = [
+    let expected_source_map: BTreeMap<u32, usize> = [
         (0x00000000, 10),
         (0x00000004, 11),
         (0x00000008, 12),
@@ -1594,7 +1846,7 @@ fn assembler_simple_loop() {
 
         // Print full 32-bit instructions for debugging
         println!("\nReconstructed 32-bit Instructions:");
-        for &addr in source_map.left_values() {
+        for &addr in source_map.keys() {
             let actual = u32::from_le_bytes([
                 inst_mem[&addr],
                 inst_mem[&(addr + 1)],
@@ -1619,13 +1871,13 @@ fn assembler_simple_loop() {
     if *source_map != expected_source_map {
         println!("Source Map Differences:");
         for (&addr, &line) in source_map {
-            let expected = expected_source_map.get_by_left(&addr);
+            let expected = expected_source_map.get(&addr);
             if expected != Some(&line) {
                 println!("0x{:08X}: Got line {}, Expected {:?}", addr, line, expected);
             }
         }
         for (&addr, &line) in &expected_source_map {
-            if !source_map.contains_left(&addr) {
+            if !source_map.contains_key(&addr) {
                 println!("0x{:08X}: Missing, Expected line {}", addr, line);
             }
         }
@@ -1653,15 +1905,16 @@ fn assembler_simple_loop() {
 #[test]
 fn assembler_all_instructions() {
     let program = include_test_file!("syntax-check.s");
-    let assembled_program = assemble(program).unwrap_or_else(|errors| {
-        for error in errors {
-            panic!(
-                "Assembly Error on line {}, column {}: {}",
-                error.line_number, error.column, error.error_message
-            );
-        }
-        panic!("Assembly failed");
-    });
+    let assembled_program =
+        assemble(program, AssembleOptions::default()).unwrap_or_else(|errors| {
+            for error in errors {
+                panic!(
+                    "Assembly Error on line {}, column {}: {}",
+                    error.line_number, error.column, error.error_message
+                );
+            }
+            panic!("Assembly failed");
+        });
     let (inst_mem, source_map, data_mem) = assembled_program.emulator_maps();
 
     // actual instruction memory
@@ -1864,7 +2117,7 @@ fn assembler_all_instructions() {
     .collect();
 
     // Verify instruction source map
-    let expected_source_map: BiBTreeMap<u32, usize> = [
+    let expected_source_map: BTreeMap<u32, usize> = [
         (0x00000000, 11),
         (0x00000004, 12),
         (0x00000008, 13),
@@ -1977,12 +2230,12 @@ fn assembler_all_instructions() {
     if *source_map != expected_source_map {
         println!("Source Map Differences:");
         for (addr, &line) in &expected_source_map {
-            if !source_map.contains_left(addr) || source_map.get_by_left(addr) != Some(&line) {
+            if !source_map.contains_key(addr) || source_map.get(addr) != Some(&line) {
                 println!(
                     "At 0x{:08X}: Expected line {}, got {:?}",
                     addr,
                     line,
-                    source_map.get_by_left(addr)
+                    source_map.get(addr)
                 );
             }
         }
@@ -2131,10 +2384,1262 @@ fn too_many_arguments() {
 
 #[test]
 fn multiple_definition() {
-    let result = assemble(".equ value, 3\n.equ value, 5");
+    let result = assemble(".equ value, 3\n.equ value, 5", AssembleOptions::default());
 
     assert!(
         result.is_err(),
         "Multiple definition should return an error."
     );
 }
+
+#[test]
+fn rept_block() {
+    let program = assemble(
+        "addi x1, x0, 1\n.rept 3\naddi x1, x1, 1\n.endr\nadd x2, x1, x1",
+        AssembleOptions::default(),
+    )
+    .expect("Repetition block should assemble.");
+
+    let expected_source_map: BTreeMap<u32, usize> =
+        [(0x0, 1), (0x4, 3), (0x8, 3), (0xC, 3), (0x10, 5)]
+            .into_iter()
+            .collect();
+    assert_eq!(program.source_map, expected_source_map);
+
+    let repeated = &program.instruction_memory;
+    for addr in [0x4, 0x8, 0xC] {
+        assert_eq!(repeated[&addr], repeated[&0x4], "Repetitions should match");
+    }
+}
+
+#[test]
+fn nested_rept_blocks() {
+    let program = assemble(
+        ".rept 2\n  addi x1, x1, 1\n  .rept 1 + 2\n    addi x2, x2, 1\n  .endr\n.endr\nloop: beq x0, x0, loop",
+        AssembleOptions::default(),
+    )
+    .expect("Nested repetition blocks should assemble.");
+
+    let lines: Vec<usize> = program.source_map.values().copied().collect();
+    assert_eq!(lines, vec![2, 4, 4, 4, 2, 4, 4, 4, 7]);
+    assert_eq!(program.symbol_table["loop"].1, IBig::from(0x20));
+}
+
+#[test]
+fn rept_data() {
+    let program = assemble(
+        ".data\nvalues: .rept 4\n.byte 7\n.endr\nend: .byte 9",
+        AssembleOptions::default(),
+    )
+    .expect("Repeated data should assemble.");
+
+    let expected_data_mem: BTreeMap<u32, u8> = [(0, 7), (1, 7), (2, 7), (3, 7), (4, 9)]
+        .into_iter()
+        .collect();
+    assert_eq!(program.initial_data_memory, expected_data_mem);
+    assert_eq!(program.symbol_table["values"].1, IBig::from(0));
+    assert_eq!(program.symbol_table["end"].1, IBig::from(4));
+}
+
+#[test]
+fn unmatched_rept_blocks() {
+    assert!(
+        assemble(".rept 2\naddi x1, x1, 1", AssembleOptions::default()).is_err(),
+        "Missing '.endr' should return an error."
+    );
+    assert!(
+        assemble("addi x1, x1, 1\n.endr", AssembleOptions::default()).is_err(),
+        "Unmatched '.endr' should return an error."
+    );
+    assert!(
+        assemble(
+            ".equ count, 2\n.rept count\n.endr",
+            AssembleOptions::default()
+        )
+        .is_err(),
+        "Symbols in repeat counts should return an error."
+    );
+}
+
+#[test]
+fn conditional_blocks() {
+    let program = assemble(
+        ".equ DEBUG, 1\n.equ LEVEL, DEBUG + 1\n.if DEBUG\naddi x1, x0, 1\n.else\naddi x1, x0, 2\n.endif\n.if LEVEL - 2\naddi x2, x0, 2\n.endif\nend: addi x3, x0, 3",
+        AssembleOptions::default(),
+    )
+    .expect("Conditional blocks should assemble.");
+
+    let lines: Vec<usize> = program.source_map.values().copied().collect();
+    assert_eq!(lines, vec![4, 11]);
+    assert_eq!(program.symbol_table["end"].1, IBig::from(4));
+}
+
+#[test]
+fn nested_conditional_blocks() {
+    let program = assemble(
+        ".if 0\n  .if 1\n    addi x1, x0, 1\n  .endif\n  .equ SKIPPED, 1\n.else\n  .if 2 - 2\n    addi x2, x0, 2\n  .else\n    addi x3, x0, 3\n  .endif\n.endif",
+        AssembleOptions::default(),
+    )
+    .expect("Nested conditional blocks should assemble.");
+
+    let lines: Vec<usize> = program.source_map.values().copied().collect();
+    assert_eq!(lines, vec![10]);
+    assert!(!program.symbol_table.contains_key("SKIPPED"));
+}
+
+#[test]
+fn invalid_conditional_blocks() {
+    assert!(
+        assemble(".if 1\naddi x1, x0, 1", AssembleOptions::default()).is_err(),
+        "Missing '.endif' should return an error."
+    );
+    assert!(
+        assemble(".else\n.endif", AssembleOptions::default()).is_err(),
+        "Unmatched '.else' and '.endif' should return an error."
+    );
+    assert!(
+        assemble(".if 1\n.else\n.else\n.endif", AssembleOptions::default()).is_err(),
+        "Duplicate '.else' should return an error."
+    );
+    assert!(
+        assemble(".if FLAG\n.endif\n.equ FLAG, 1", AssembleOptions::default()).is_err(),
+        "Constants defined after the condition should return an error."
+    );
+    assert!(
+        assemble("start: .if start\n.endif", AssembleOptions::default()).is_err(),
+        "Labels in conditions should return an error."
+    );
+}
+
+#[test]
+fn macro_expansion() {
+    let program = assemble(
+        ".macro push reg\naddi x2, x2, -4\nsw \\reg, 0(x2)\n.endm\naddi x1, x0, 1\npush x1\nstart: push x3",
+        AssembleOptions::default(),
+    )
+    .expect("Macro should assemble.");
+
+    let lines: Vec<usize> = program.source_map.values().copied().collect();
+    assert_eq!(lines, vec![5, 6, 6, 7, 7]);
+    assert_eq!(program.symbol_table["start"].1, IBig::from(0xC));
+
+    let expected = assemble(
+        "addi x1, x0, 1\naddi x2, x2, -4\nsw x1, 0(x2)\naddi x2, x2, -4\nsw x3, 0(x2)",
+        AssembleOptions::default(),
+    )
+    .expect("Expanded program should assemble.");
+    assert_eq!(program.instruction_memory, expected.instruction_memory);
+}
+
+#[test]
+fn nested_macros() {
+    let program = assemble(
+        ".macro inc reg, amount\naddi \\reg, \\reg, \\amount\n.endm\n.macro inc_both a, b\ninc \\a, 1 + 1\ninc \\b, 2\n.endm\ninc_both x1, x2",
+        AssembleOptions::default(),
+    )
+    .expect("Nested macros should assemble.");
+
+    let expected = assemble("addi x1, x1, 2\naddi x2, x2, 2", AssembleOptions::default())
+        .expect("Program should assemble.");
+    assert_eq!(program.instruction_memory, expected.instruction_memory);
+    assert!(program.source_map.values().all(|line| *line == 8));
+}
+
+#[test]
+fn recursive_macros() {
+    let program = assemble(
+        ".macro count n\n.if \\n\naddi x1, x1, 1\ncount \\n - 1\n.endif\n.endm\ncount 3",
+        AssembleOptions::default(),
+    )
+    .expect("Recursive macro guarded by '.if' should assemble.");
+
+    let expected = assemble(
+        "addi x1, x1, 1\naddi x1, x1, 1\naddi x1, x1, 1",
+        AssembleOptions::default(),
+    )
+    .expect("Program should assemble.");
+    assert_eq!(program.instruction_memory, expected.instruction_memory);
+    assert!(program.source_map.values().all(|line| *line == 7));
+}
+
+#[test]
+fn invalid_macros() {
+    let errors = assemble(
+        ".macro forever\nforever\n.endm\naddi x1, x0, 1\nforever",
+        AssembleOptions::default(),
+    )
+    .expect_err("Recursive macro should return an error.");
+    assert!(errors.iter().all(|error| error.line_number == 5));
+
+    assert!(
+        assemble(
+            ".macro twice a, b\nadd \\a, \\b, \\b\n.endm\ntwice x1",
+            AssembleOptions::default()
+        )
+        .is_err(),
+        "Wrong argument count should return an error."
+    );
+    assert!(
+        assemble(
+            ".macro bad a\naddi \\b, x0, 1\n.endm\nbad x1",
+            AssembleOptions::default()
+        )
+        .is_err(),
+        "Unknown parameter should return an error."
+    );
+    assert!(
+        assemble(".macro open\naddi x1, x0, 1", AssembleOptions::default()).is_err(),
+        "Missing '.endm' should return an error."
+    );
+    assert!(
+        assemble("addi x1, x0, 1\n.endm", AssembleOptions::default()).is_err(),
+        "Unmatched '.endm' should return an error."
+    );
+}
+
+#[test]
+fn valid_branch_targets() {
+    let program = assemble(
+        "loop: addi x1, x1, 1\nbne x1, x0, loop\njal x0, end\nend: addi x2, x2, 1",
+        AssembleOptions::default(),
+    )
+    .expect("Program should assemble.");
+
+    assert!(validate_branch_targets(&program).is_empty());
+}
+
+#[test]
+fn misaligned_branch_targets() {
+    let program = assemble(
+        "loop: addi x1, x1, 1\nbeq x0, x0, loop + 2\njal x0, data\nbne x1, x0, loop\ndata: .word 0",
+        AssembleOptions::default(),
+    )
+    .expect("Program should assemble.");
+
+    let lines: Vec<usize> = validate_branch_targets(&program)
+        .iter()
+        .map(|warning| warning.line_number)
+        .collect();
+    assert_eq!(lines, vec![2, 3]);
+}
+
+#[test]
+fn unused_labels() {
+    let program = assemble(
+        "main: la x5, value\nloop: addi x1, x1, 1\nbne x1, x0, loop\nlop: addi x2, x0, 1\n\
+         done: jal x0, done\nlast: ebreak\n.equ END, last\n\
+         .data\nvalue: .word 1\nunused: .word 2",
+        AssembleOptions::default(),
+    )
+    .expect("Program should assemble.");
+
+    let warnings: Vec<(String, usize, usize, usize)> = find_unused_labels(&program)
+        .into_iter()
+        .map(|warning| {
+            (
+                warning.error_message,
+                warning.line_number,
+                warning.column,
+                warning.width,
+            )
+        })
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            ("Label 'lop' is never used.".to_string(), 4, 1, 3),
+            ("Label 'unused' is never used.".to_string(), 10, 1, 6),
+        ]
+    );
+}
+
+#[test]
+fn operand_lint() {
+    let program = assemble(
+        "addi x0, x0, 0\naddi x0, x1, 4\nadd x0, x1, x2\nlui x0, 5\nlw x0, 0(x1)\n\
+         not x0, x1\njal x0, next\nnext: jalr x0, x1, 0\naddi x1, x0, 1\nebreak",
+        AssembleOptions::default(),
+    )
+    .expect("Program should assemble.");
+
+    let warnings: Vec<(usize, String)> = lint_operands(&program)
+        .into_iter()
+        .map(|warning| (warning.line_number, warning.error_message))
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            (
+                2,
+                "ADDI writes its result to x0, which discards it.".to_string()
+            ),
+            (
+                3,
+                "ADD writes its result to x0, which discards it.".to_string()
+            ),
+            (
+                4,
+                "LUI writes its result to x0, which discards it.".to_string()
+            ),
+            (
+                5,
+                "LW writes its result to x0, which discards it.".to_string()
+            ),
+            (
+                6,
+                "XORI writes its result to x0, which discards it.".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn multiple_files() {
+    let program = assemble_files(
+        &[
+            ("main.s", "jal x0, helper\naddi x1, x0, 1"),
+            ("helper.s", "helper:\n  addi x2, x0, 2"),
+        ],
+        AssembleOptions::default(),
+    )
+    .expect("Files should assemble as one program.");
+
+    let lines: Vec<usize> = program.source_map.values().copied().collect();
+    assert_eq!(lines, vec![1, 2, 4]);
+    assert_eq!(program.symbol_table["helper"].1, IBig::from(8));
+}
+
+#[test]
+fn multiple_files_error_location() {
+    let errors = assemble_files(
+        &[
+            ("main.s", "addi x1, x0, 1\naddi x1, x1, 1"),
+            ("broken.s", "addi x0, x0, 0\nfoo x1, x2"),
+        ],
+        AssembleOptions::default(),
+    )
+    .expect_err("Invalid instruction should return an error.");
+
+    for error in errors {
+        assert_eq!(error.file.as_deref(), Some("broken.s"));
+        assert_eq!(error.line_number, 2);
+    }
+}
+
+#[test]
+fn error_spans() {
+    let tokens: Vec<_> = Lexer::new("addi x1, x0,\n  (1 + 2)\n")
+        .map(|token| token.unwrap())
+        .collect();
+    let first = tokens.first().unwrap();
+    let on_line = |line| tokens.iter().filter(move |token| token.line == line);
+
+    let error =
+        super::AssemblerError::from_token_span("".into(), first, on_line(1).next_back().unwrap());
+    assert_eq!((error.line_number, error.column, error.width), (1, 1, 12));
+    assert_eq!(error.end, None);
+
+    let last = on_line(2).next_back().unwrap();
+    let error = super::AssemblerError::from_token_span("".into(), first, last);
+    assert_eq!((error.line_number, error.column, error.width), (1, 1, 4));
+    assert_eq!(error.end, Some((2, 10)));
+
+    let error = error.with_line_number(5);
+    assert_eq!(error.line_number, 5);
+    assert_eq!(error.end, Some((6, 10)));
+}
+
+#[test]
+fn file_line_lookup() {
+    let offsets = file_line_offsets(["a\nb", "", "c\nd\ne"]);
+    assert_eq!(offsets, vec![0, 2, 3]);
+
+    assert_eq!(locate_line(&offsets, 0), None);
+    assert_eq!(locate_line(&offsets, 2), Some((0, 2)));
+    assert_eq!(locate_line(&offsets, 3), Some((1, 1)));
+    assert_eq!(locate_line(&offsets, 6), Some((2, 3)));
+}
+
+fn resolve_test_files(name: &str) -> Option<String> {
+    match name {
+        "macros.s" => Some("addi x2, x0, 2\n.include \"inner.s\"".into()),
+        "inner.s" => Some("addi x3, x0, 3".into()),
+        "broken.s" => Some("addi x2, x0, 2\nfoo x1".into()),
+        "loop.s" => Some(".include \"loop.s\"".into()),
+        _ => None,
+    }
+}
+
+/// Options resolving includes with [`resolve_test_files`]
+fn with_test_files() -> AssembleOptions<'static> {
+    AssembleOptions {
+        resolve_include: Box::new(resolve_test_files),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn include_directive() {
+    let program = assemble(
+        "addi x1, x0, 1\nstart: .include \"macros.s\"\nadd x4, x1, x2",
+        with_test_files(),
+    )
+    .expect("Included files should assemble.");
+
+    let lines: Vec<usize> = program.source_map.values().copied().collect();
+    assert_eq!(lines, vec![1, 2, 2, 3]);
+    assert_eq!(program.symbol_table["start"].1, IBig::from(4));
+}
+
+#[test]
+fn include_error_location() {
+    let errors = assemble(
+        "addi x1, x0, 1\n.include \"broken.s\"\nfoo x2",
+        with_test_files(),
+    )
+    .expect_err("Invalid instructions should return errors.");
+
+    let locations: Vec<(Option<&str>, usize)> = errors
+        .iter()
+        .map(|error| (error.file.as_deref(), error.line_number))
+        .collect();
+    assert!(locations.contains(&(Some("broken.s"), 2)));
+    assert!(locations.contains(&(None, 3)));
+}
+
+#[test]
+fn invalid_includes() {
+    assert!(
+        assemble(".include \"missing.s\"", with_test_files()).is_err(),
+        "Missing files should return an error."
+    );
+    assert!(
+        assemble(".include \"loop.s\"", with_test_files()).is_err(),
+        "Recursive includes should return an error."
+    );
+    assert!(
+        assemble(".include macros", with_test_files()).is_err(),
+        "Include without a file name string should return an error."
+    );
+}
+
+#[test]
+fn include_between_files() {
+    let program = assemble_files(
+        &[
+            ("main.s", ".include \"consts.s\"\naddi x1, x0, VALUE"),
+            ("consts.s", ".equ VALUE, 5"),
+        ],
+        AssembleOptions::default(),
+    )
+    .expect("Files should be able to include each other.");
+
+    assert_eq!(
+        program.source_map.values().copied().collect::<Vec<_>>(),
+        vec![2]
+    );
+}
+
+#[test]
+fn incbin_directive() {
+    let resolve_binary = |name: &str| (name == "input.bin").then(|| vec![1, 2, 3]);
+    let program = assemble(
+        ".data\nstart: .incbin \"input.bin\"\nafter: .byte 4",
+        AssembleOptions {
+            resolve_binary: Box::new(resolve_binary),
+            ..Default::default()
+        },
+    )
+    .expect("Included binaries should assemble.");
+
+    let data: Vec<u8> = program.initial_data_memory.values().copied().collect();
+    assert_eq!(data, vec![1, 2, 3, 4]);
+    assert_eq!(program.symbol_table["start"].1, IBig::from(0));
+    assert_eq!(program.symbol_table["after"].1, IBig::from(3));
+}
+
+#[test]
+fn invalid_incbins() {
+    let incbin = |source: &str| {
+        assemble(
+            source,
+            AssembleOptions {
+                resolve_binary: Box::new(|name| (name == "input.bin").then(Vec::new)),
+                ..Default::default()
+            },
+        )
+    };
+    assert!(
+        incbin(".data\n.incbin \"missing.bin\"").is_err(),
+        "Missing files should return an error."
+    );
+    assert!(
+        incbin(".data\n.incbin input").is_err(),
+        "Incbin without a file name string should return an error."
+    );
+    assert!(
+        incbin(".data\n.incbin \"input.bin\"").is_ok(),
+        "Empty files should assemble."
+    );
+}
+
+#[test]
+fn incbin_between_files() {
+    let program = assemble_files(
+        &[
+            ("main.s", "lw x1, 0(x0)\n.include \"data.s\""),
+            ("data.s", ".data\n.incbin \"word.bin\""),
+        ],
+        AssembleOptions {
+            resolve_binary: Box::new(|name| {
+                (name == "word.bin").then(|| vec![0xEF, 0xBE, 0xAD, 0xDE])
+            }),
+            ..Default::default()
+        },
+    )
+    .expect("Included files should be able to include binaries.");
+
+    let data: Vec<u8> = program.initial_data_memory.values().copied().collect();
+    assert_eq!(data, vec![0xEF, 0xBE, 0xAD, 0xDE]);
+}
+
+#[test]
+fn ignored_directives() {
+    let program = assemble(
+        ".type main, @function\nmain: addi x1, x0, 1\n.cfi_startproc\n.cfi_def_cfa_offset 16\n\
+         .size main, .-main\n.ident \"GCC: (GNU) 13.2.0\"",
+        AssembleOptions::default(),
+    )
+    .expect("Debugger and linker directives should be skipped.");
+
+    assert_eq!(program.source_map.len(), 1);
+    assert!(program.warnings.is_empty());
+
+    let errors = assemble(
+        ".cfi_def_cfa_offset 1z\naddi x1, x0, 1",
+        AssembleOptions::default(),
+    )
+    .expect_err("Invalid tokens in a skipped directive should return an error.");
+    assert!(errors.iter().all(|error| error.line_number == 1));
+}
+
+#[test]
+fn unknown_directives() {
+    let source = ".file \"main.c\"\n.globl main\nmain: addi x1, x0, 1\n.p2align 2";
+    let lenient = |source: &str| {
+        assemble(
+            source,
+            AssembleOptions {
+                strict: false,
+                ..Default::default()
+            },
+        )
+    };
+
+    assert!(
+        assemble(source, AssembleOptions::default()).is_err(),
+        "Unknown directives should be errors in strict mode."
+    );
+
+    let program = lenient(source).expect("Unknown directives should be skipped in lenient mode.");
+    let warnings: Vec<(usize, &str)> = program
+        .warnings
+        .iter()
+        .map(|warning| (warning.line_number, warning.error_message.as_str()))
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            (1, "Unknown directive 'file' skipped"),
+            (2, "Unknown directive 'globl' skipped"),
+            (4, "Unknown directive 'p2align' skipped"),
+        ]
+    );
+    assert_eq!(program.source_map.len(), 1);
+    assert_eq!(program.symbol_table["main"].1, IBig::from(0));
+
+    assert!(
+        lenient(".foo\naddi x1, x0").is_err(),
+        "Other errors should still fail in lenient mode."
+    );
+    assert!(
+        lenient(".foo 1z\naddi x1, x0, 1").is_err(),
+        "Invalid tokens in a skipped directive should return an error."
+    );
+}
+
+#[test]
+fn load_address() {
+    let program = assemble(
+        ".text 0x10\nla x5, target\n.text 0x1900\ntarget: addi x0, x0, 0",
+        AssembleOptions::default(),
+    )
+    .expect("Load address should assemble.");
+
+    let lines: Vec<usize> = program.source_map.values().copied().collect();
+    assert_eq!(lines, vec![2, 2, 4]);
+
+    let read = |address: u32| {
+        Instruction::from_raw(u32::from_le_bytes(
+            [0, 1, 2, 3].map(|i| program.instruction_memory[&(address + i)]),
+        ))
+    };
+    let (auipc, addi) = (read(0x10), read(0x14));
+    assert_eq!(auipc.opcode(), 0b0010111);
+    assert_eq!((addi.rd(), addi.rs1()), (5, 5));
+
+    // The lower bits are sign extended, so the upper bits are rounded up
+    let upper = auipc.immediate().unwrap();
+    let lower = addi.immediate().unwrap();
+    assert_eq!(lower, -0x710);
+    assert_eq!(0x10 + upper + lower, 0x1900);
+}
+
+#[test]
+fn call_expansion() {
+    let program = assemble(
+        ".text 0x10\ncall target\ncall x5, target\n.text 0x1900\ntarget: jalr x0, x1, 0",
+        AssembleOptions::default(),
+    )
+    .expect("Call should assemble.");
+
+    let lines: Vec<usize> = program.source_map.values().copied().collect();
+    assert_eq!(lines, vec![2, 2, 3, 3, 5]);
+
+    for (address, rd) in [(0x10, 1), (0x18, 5)] {
+        let auipc = program.instruction_at(address).unwrap();
+        let jalr = program.instruction_at(address + 4).unwrap();
+        assert!(matches!(ISA::instr_to_isa(auipc), Some(ISA::AUIPC)));
+        assert!(matches!(ISA::instr_to_isa(jalr), Some(ISA::JALR)));
+        assert_eq!(auipc.rd(), rd);
+        assert_eq!((jalr.rd(), jalr.rs1()), (rd, rd));
+
+        // JALR sign extends its offset just like ADDI
+        let target = address as i32 + auipc.immediate().unwrap() + jalr.immediate().unwrap();
+        assert_eq!(target, 0x1900);
+    }
+
+    assert!(assemble("call", AssembleOptions::default()).is_err());
+    assert!(
+        assemble(
+            "call x0, target\ntarget: ebreak",
+            AssembleOptions::default()
+        )
+        .is_err(),
+        "Call through x0 should return an error."
+    );
+}
+
+#[test]
+fn zero_branch_expansion() {
+    let program = assemble(
+        "start: beqz x5, start\nbnez x5, start\nblez x5, start\n\
+         bgez x5, start\nbltz x5, start\nbgtz x5, start",
+        AssembleOptions::default(),
+    )
+    .expect("Branches against zero should assemble.");
+    let expected = assemble(
+        "start: beq x5, x0, start\nbne x5, x0, start\nbge x0, x5, start\n\
+         bge x5, x0, start\nblt x5, x0, start\nblt x0, x5, start",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(program.instruction_memory, expected.instruction_memory);
+    assert_eq!(program.source_map, expected.source_map);
+
+    assert!(assemble("beqz x5", AssembleOptions::default()).is_err());
+    assert!(assemble("bgtz x5, x6, 0", AssembleOptions::default()).is_err());
+    assert!(assemble("bnez x5, 0x2000", AssembleOptions::default()).is_err());
+}
+
+#[test]
+fn unary_expansion() {
+    let program = assemble(
+        "not x5, x6
+neg x5, x6
+seqz x5, x6
+snez x5, x6",
+        AssembleOptions::default(),
+    )
+    .expect("Unary pseudo-instructions should assemble.");
+    let expected = assemble(
+        "xori x5, x6, -1
+sub x5, x0, x6
+sltiu x5, x6, 1
+sltu x5, x0, x6",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(program.instruction_memory, expected.instruction_memory);
+    assert_eq!(program.source_map, expected.source_map);
+
+    assert!(assemble("not x5", AssembleOptions::default()).is_err());
+    assert!(assemble("neg x5, x6, x7", AssembleOptions::default()).is_err());
+    assert!(assemble("seqz x5, 1", AssembleOptions::default()).is_err());
+    assert!(assemble("snez x5, x32", AssembleOptions::default()).is_err());
+}
+
+#[test]
+fn pcrel_relocations() {
+    // %pcrel_lo names the AUIPC, not the target, since the offset is from the AUIPC
+    let program = assemble(
+        ".text 0x10\nfirst: auipc x5, %pcrel_hi(target)\naddi x5, x5, %pcrel_lo(first)\n\
+         second: auipc x6, %pcrel_hi(target + 8)\nlw x7, %pcrel_lo(second)(x6)\n\
+         sw x7, %pcrel_lo(second)(x6)\nthird: auipc x1, %PCREL_HI(target)\njalr x1, x1, %pcrel_lo(third)\n\
+         .text 0x1900\ntarget: addi x0, x0, 0",
+         AssembleOptions::default(),
+    )
+    .expect("PC-relative relocations should assemble.");
+    let expected = assemble(
+        ".text 0x10\nla x5, target\nla x6, target + 8\naddi x0, x0, 0\ncall target\n\
+         .text 0x1900\ntarget: addi x0, x0, 0",
+        AssembleOptions::default(),
+    )
+    .expect("Load address and call should assemble.");
+
+    // The same as the pseudo-instructions, apart from the extra load and store
+    for address in [0x10, 0x14, 0x18, 0x24, 0x28, 0x1900] {
+        assert_eq!(
+            program.instruction_at(address).unwrap().raw(),
+            expected.instruction_at(address).unwrap().raw(),
+            "at {:#x}",
+            address
+        );
+    }
+    let auipc = program.instruction_at(0x18).unwrap();
+    for address in [0x1C, 0x20] {
+        let offset = program
+            .instruction_at(address)
+            .unwrap()
+            .immediate()
+            .unwrap();
+        assert_eq!(0x18 + auipc.immediate().unwrap() + offset, 0x1908);
+    }
+
+    // Internal symbols pairing them up are not listed as labels
+    assert!(!program.symbol_map().contains("pcrel"));
+}
+
+#[test]
+fn absolute_relocations() {
+    let program = assemble(
+        "lui x5, %hi(0x12345FFF)\naddi x5, x5, %lo(0x12345FFF)",
+        AssembleOptions::default(),
+    )
+    .expect("Absolute relocations should assemble.");
+
+    let (lui, addi) = (
+        program.instruction_at(0).unwrap(),
+        program.instruction_at(4).unwrap(),
+    );
+    assert_eq!(lui.immediate(), Some(0x12346000));
+    assert_eq!(addi.immediate(), Some(-1));
+}
+
+#[test]
+fn relocation_errors() {
+    let error = |source: &str| {
+        assemble(source, AssembleOptions::default()).unwrap_err()[0]
+            .error_message
+            .clone()
+    };
+
+    // %pcrel_lo must name an AUIPC using %pcrel_hi, not the target itself
+    assert!(
+        error("here: auipc x5, %pcrel_hi(target)\naddi x5, x5, %pcrel_lo(target)\ntarget: ebreak")
+            .contains("%pcrel_lo must name the address of an instruction using %pcrel_hi")
+    );
+    assert!(error("addi x5, x5, %pcrel_hi(target)\ntarget: ebreak").contains("U type"));
+    assert!(error("lui x5, %lo(4)").contains("I or S type"));
+    assert!(error("addi x5, x5, %bogus(4)").contains("Unknown relocation function %bogus"));
+    assert!(error("addi x5, x5, %lo 4").contains("Expected a relocation function"));
+}
+
+#[test]
+fn csr_operands() {
+    let program = assemble(
+        "csrrs x5, cycle, x0\ncsrrc x6, INSTRETH, x2\ncsrrwi x7, 0xC01, 31\ncsrrs x8, 0x123, x0",
+        AssembleOptions::default(),
+    )
+    .expect("CSR instructions should assemble.");
+
+    let csr = program.instruction_at(4).unwrap();
+    assert_eq!((csr.rd(), csr.rs1(), csr.csr()), (6, 2, 0xC82));
+
+    let listing: Vec<String> = (0..4)
+        .map(|i| program.disassemble(i * 4).unwrap())
+        .collect();
+    assert_eq!(
+        listing,
+        vec![
+            "csrrs x5, cycle, x0",
+            "csrrc x6, instreth, x2",
+            "csrrwi x7, time, 31",
+            "csrrs x8, 0x123, x0",
+        ]
+    );
+
+    let error = assemble("csrrs x1, 0x1000, x0", AssembleOptions::default()).unwrap_err();
+    assert_eq!(
+        error[0].error_message,
+        "CSR address 4096 is out of range (0 to 0xFFF)"
+    );
+    let error = assemble("csrrsi x1, cycle, 32", AssembleOptions::default()).unwrap_err();
+    assert_eq!(
+        error[0].error_message,
+        "Immediate value 32 is out of range (0 to 31)"
+    );
+    assert!(assemble("csrrs x1, cycle, 4", AssembleOptions::default()).is_err());
+}
+
+#[test]
+fn section_bases() {
+    let source = "start: addi x1, x0, 1\n.data\nvalue: .byte 7\n.text 0x20\nlater: addi x2, x0, 2";
+    let program = assemble(
+        source,
+        AssembleOptions::with_memory_map(MemoryMap::with_sections(SectionBases::CLASSIC)),
+    )
+    .expect("Program should assemble with section bases.");
+
+    assert_eq!(program.symbol_table["start"].1, IBig::from(0x0040_0000));
+    assert_eq!(program.symbol_table["value"].1, IBig::from(0x1001_0000));
+    assert_eq!(program.symbol_table["later"].1, IBig::from(0x20));
+    assert_eq!(program.initial_data_memory[&0x1001_0000], 7);
+    assert_eq!(program.get_section_start(Section::Text), 0x20);
+    assert_eq!(program.get_section_start(Section::Data), 0x1001_0000);
+
+    // Empty programs still start at the configured base
+    let bases = SectionBases {
+        text: 0x100,
+        data: 0x200,
+    };
+    let program = assemble(
+        "",
+        AssembleOptions::with_memory_map(MemoryMap::with_sections(bases)),
+    )
+    .expect("Empty program should assemble.");
+    assert_eq!(program.get_section_start(Section::Text), 0x100);
+    assert_eq!(program.get_section_start(Section::Data), 0x200);
+}
+
+#[test]
+fn section_start_with_several_sections() {
+    // The lowest address wins rather than the first section in the source
+    let source = ".text 0x40\nlater: addi x1, x0, 1\n.data 0x300\n.word 1\n\
+                  .text 0x10\nfirst: addi x2, x0, 2\n.data 0x200\n.byte 2";
+    let program =
+        assemble(source, AssembleOptions::default()).expect("Several sections should assemble.");
+    assert_eq!(program.get_section_start(Section::Text), 0x10);
+    assert_eq!(program.get_section_start(Section::Data), 0x200);
+
+    // The text section starts at its first instruction, skipping data and
+    // sections with nothing in them
+    let source = ".text 0x8\n.text 0x10\n.word 5\n.text 0x40\nentry: addi x1, x0, 1";
+    let program =
+        assemble(source, AssembleOptions::default()).expect("Data in text should assemble.");
+    assert_eq!(program.get_section_start(Section::Text), 0x40);
+}
+
+#[test]
+fn instruction_and_source_line_at() {
+    let bases = SectionBases {
+        text: 0x100,
+        data: 0x200,
+    };
+    let source = "addi x1, x0, 3\n\nsw x1, 0(x0)\nebreak\n.data\nvalue: .word 7";
+    let program = assemble(
+        source,
+        AssembleOptions::with_memory_map(MemoryMap::with_sections(bases)),
+    )
+    .expect("Program should assemble.");
+
+    let first = program
+        .instruction_at(0x100)
+        .expect("Text start should hold an instruction.");
+    assert!(matches!(ISA::instr_to_isa(first), Some(ISA::ADDI)));
+    assert_eq!(program.source_line_at(0x100), Some(1));
+
+    // The last instruction sits right before the end of the text section
+    let last = program
+        .instruction_at(0x108)
+        .expect("Last address should hold an instruction.");
+    assert!(matches!(ISA::instr_to_isa(last), Some(ISA::EBREAK)));
+    assert_eq!(program.source_line_at(0x104), Some(3));
+    assert_eq!(program.source_line_at(0x108), Some(4));
+
+    // Past the end, before the start, and straddling the end are all unmapped
+    assert!(program.instruction_at(0x10C).is_none());
+    assert!(program.instruction_at(0xFC).is_none());
+    assert!(program.instruction_at(0x10A).is_none());
+    assert_eq!(program.source_line_at(0x10C), None);
+    assert_eq!(program.source_line_at(0xFC), None);
+
+    // Misaligned reads inside the section decode whatever bytes are there
+    assert!(program.instruction_at(0x102).is_some());
+    assert_eq!(program.source_line_at(0x102), None);
+
+    // Data is not instruction memory, and the end of the address space doesn't wrap
+    assert!(program.instruction_at(0x200).is_none());
+    assert_eq!(program.source_line_at(0x200), None);
+    assert!(program.instruction_at(u32::MAX).is_none());
+    assert!(program.instruction_at(u32::MAX - 3).is_none());
+}
+
+#[test]
+fn symbol_map_listing() {
+    let bases = SectionBases {
+        text: 0x100,
+        data: 0x200,
+    };
+    let source = ".equ SIZE, 4\nmain: addi x1, x0, SIZE\nloop: jal x0, loop\n.data\nbuffer: .word 0\nend: .byte 0";
+    let program = assemble(
+        source,
+        AssembleOptions::with_memory_map(MemoryMap::with_sections(bases)),
+    )
+    .expect("Program should assemble.");
+
+    assert_eq!(
+        program.symbol_map(),
+        "Section  Start\n\
+         .text    0x00000100\n\
+         .data    0x00000200\n\
+         \n\
+         Address     Symbol  Section\n\
+         0x00000004  SIZE    *ABS*\n\
+         0x00000100  main    .text\n\
+         0x00000104  loop    .text\n\
+         0x00000200  buffer  .data\n\
+         0x00000204  end     .data\n"
+    );
+}
+
+#[test]
+fn disassemble_with_symbols() {
+    let source = "start: addi x1, x0, 3\nloop: lw x2, -4(x1)\nsw x2, 8(x3)\nsrai x2, x2, 3\nbne x1, x0, loop\njal x0, done\nlui x5, 0xFFF\nbeq x0, x0, 0x100\ndone: ebreak";
+    let program = assemble(source, AssembleOptions::default()).expect("Program should assemble.");
+
+    let listing: Vec<String> = (0..9)
+        .map(|i| {
+            program
+                .disassemble(i * 4)
+                .expect("Address should hold an instruction.")
+        })
+        .collect();
+    assert_eq!(
+        listing,
+        vec![
+            "addi x1, x0, 3",
+            "lw x2, -4(x1)",
+            "sw x2, 8(x3)",
+            "srai x2, x2, 3",
+            "bne x1, x0, loop",
+            "jal x0, done",
+            "lui x5, 0xfff",
+            "beq x0, x0, done+224",
+            "ebreak",
+        ]
+    );
+    assert_eq!(program.disassemble(36), None);
+
+    assert_eq!(
+        program.symbol_for(4, Section::Text).as_deref(),
+        Some("loop")
+    );
+    assert_eq!(
+        program.symbol_for(12, Section::Text).as_deref(),
+        Some("loop+8")
+    );
+
+    // Without names the listing assembles back to the same program
+    let plain: Vec<String> = (0..9)
+        .map(|i| {
+            let word =
+                u32::from_le_bytes([0, 1, 2, 3].map(|j| program.instruction_memory[&(i * 4 + j)]));
+            Instruction::from_raw(word).to_asm(i * 4, |_| None).unwrap()
+        })
+        .collect();
+    assert_eq!(plain[4], "bne x1, x0, 0x4");
+    let reassembled =
+        assemble(&plain.join("\n"), AssembleOptions::default()).expect("Listing should assemble.");
+    assert_eq!(reassembled.instruction_memory, program.instruction_memory);
+}
+
+#[test]
+fn immediate_representation() {
+    let program = assemble(
+        "addi x1, x0, -4\nsw x2, -8(x1)\nbeq x0, x0, 0\nlui x5, 0xFFFFF\nauipc x6, 0x10\njal x0, 0\nadd x1, x2, x3",
+        AssembleOptions::default(),
+    )
+    .expect("Program should assemble.");
+    let read = |address| program.instruction_at(address).unwrap().immediate_asm();
+
+    assert_eq!(read(0x0).as_deref(), Some("-4"));
+    assert_eq!(read(0x4).as_deref(), Some("-8"));
+    assert_eq!(read(0x8).as_deref(), Some("-8"));
+    assert_eq!(read(0xC).as_deref(), Some("0xfffff"));
+    assert_eq!(read(0x10).as_deref(), Some("0x10"));
+    assert_eq!(read(0x14).as_deref(), Some("-20"));
+    assert_eq!(read(0x18), None);
+    assert_eq!(program.disassemble(0xC).as_deref(), Some("lui x5, 0xfffff"));
+}
+
+#[test]
+fn definition_operands_assemble() {
+    for isa in ISA::all() {
+        let def = isa.definition();
+        assert!(
+            !def.description.is_empty(),
+            "{} has no description",
+            def._name
+        );
+
+        let operands = def
+            .operands()
+            .replace("csr", "cycle")
+            .replace("uimm", "4")
+            .replace("rd", "x1")
+            .replace("rs1", "x2")
+            .replace("rs2", "x3")
+            .replace("imm", "4")
+            .replace("address", "0");
+        let source = format!("{} {}", def._name, operands);
+        assert!(
+            assemble(&source, AssembleOptions::default()).is_ok(),
+            "'{}' should assemble",
+            source
+        );
+    }
+}
+
+#[test]
+fn program_listing() {
+    let source = "main: addi x1, x0, 5\n\nloop: beq x1, x0, main\n.byte 0x12";
+    let program = assemble(source, AssembleOptions::default()).expect("Program should assemble.");
+    assert_eq!(
+        program.listing(Some(source)),
+        "0x00000000  00500093  addi x1, x0, 5                   1: main: addi x1, x0, 5\n\
+         0x00000004  FE008EE3  beq x1, x0, main                 3: loop: beq x1, x0, main\n\
+         0x00000008  12        .byte 0x12\n"
+    );
+
+    // Machine code has no source, and words that aren't instructions are data
+    let program =
+        super::AssembledProgram::from_machine_code(0x100, &[0x93, 0x00, 0x50, 0x00, 0, 0, 0, 0]);
+    assert_eq!(
+        program.listing(None),
+        "0x00000100  00500093  addi x1, x0, 5\n\
+         0x00000104  00000000  .word 0x00000000\n"
+    );
+    assert_eq!(program.get_section_start(Section::Text), 0x100);
+}
+
+#[test]
+fn two_pass_addresses_agree() {
+    // Every directive that moves the address, with the address it should end at
+    // when it starts from an odd address. Only `.align` aligns.
+    let directives = [
+        (".byte 1, 2", 3),
+        (".half 2", 3),
+        (".2byte 2", 3),
+        (".word 3", 5),
+        (".4byte 3", 5),
+        (".dword 4", 9),
+        (".align 3", 8),
+        (".zero 5", 6),
+        (".ascii \"ab\"", 3),
+        (".asciz \"ab\"", 4),
+        ("", 1),
+    ];
+    for (first, _) in directives {
+        for (second, end) in directives {
+            let source = format!(".data\n.byte 0\nfirst: {first}\nsecond: {second}\nlast: .byte 0");
+            let program = assemble(&source, AssembleOptions::default()).unwrap_or_else(|errors| {
+                panic!("{:?} should assemble: {}", source, errors[0].error_message)
+            });
+            let address = |label: &str| u32::try_from(&program.symbol_table[label].1).unwrap();
+            assert_eq!(address("first"), 1, "{:?}", source);
+            assert!(address("second") <= address("last"), "{:?}", source);
+            if first.is_empty() {
+                assert_eq!(address("last"), end, "{:?}", source);
+            }
+        }
+    }
+
+    // Pseudo-instructions take as many bytes in the first pass as they expand to
+    let program = assemble(
+        "la x5, end\nmiddle: call end\naddi x0, x0, 0\nend: ebreak",
+        AssembleOptions::default(),
+    )
+    .expect("Pseudo-instructions should assemble.");
+    assert_eq!(program.symbol_table["middle"].1, 8.into());
+    assert_eq!(program.symbol_table["end"].1, 20.into());
+}
+
+#[test]
+fn two_pass_mismatch_reported() {
+    // The byte is counted in the first pass but fails to assemble in the second,
+    // so the label after it no longer lands where the first pass put it
+    let errors = assemble(
+        ".data\n.byte 300\nlabel: .byte 1",
+        AssembleOptions::default(),
+    )
+    .unwrap_err();
+    let messages: Vec<(usize, &str)> = errors
+        .iter()
+        .map(|error| (error.line_number, error.error_message.as_str()))
+        .collect();
+    assert_eq!(
+        messages,
+        vec![
+            (2, "Value 0x12c (absolute) is too large for 1 bytes."),
+            (
+                3,
+                "Invalid address. Expected 0x1 (data), got 0. This is probably due to a directive or error causing misalignment."
+            ),
+        ]
+    );
+    assert_eq!(errors[1].column, 1);
+}
+
+#[test]
+fn empty_programs() {
+    for source in ["", "  \n\t\n", "# comment\n    # another comment\n"] {
+        let program = assemble(source, AssembleOptions::default()).unwrap();
+        assert!(program.instruction_memory.is_empty(), "{:?}", source);
+        assert!(program.initial_data_memory.is_empty(), "{:?}", source);
+        assert!(program.source_map.is_empty(), "{:?}", source);
+        assert!(program.labels.is_empty(), "{:?}", source);
+    }
+
+    // Directives alone give data but still no instructions
+    let program = assemble(".data\nvalue: .word 5\n.text\n", AssembleOptions::default()).unwrap();
+    assert!(program.instruction_memory.is_empty());
+    assert!(program.source_map.is_empty());
+    assert_eq!(
+        program.initial_data_memory,
+        BTreeMap::from([(0, 5), (1, 0), (2, 0), (3, 0)])
+    );
+    assert_eq!(program.symbol_table["value"].1, 0.into());
+}
+
+#[test]
+fn control_flow_targets() {
+    let program = assemble(
+        "loop: beq x1, x0, data\njal x0, loop\nbne x1, x0, end\njal x0, done\n\
+         done: ebreak\nend:\n.data 0x100\ndata: .word 0",
+        AssembleOptions::default(),
+    )
+    .expect("Branches into data should only warn.");
+    assert!(program.warnings.is_empty());
+
+    let warnings: Vec<(usize, String)> = validate_branch_targets(&program)
+        .into_iter()
+        .map(|warning| (warning.line_number, warning.error_message))
+        .collect();
+    assert_eq!(
+        warnings,
+        vec![
+            (
+                1,
+                "Target 0x00000100 of BEQ is not the start of an instruction.".to_string()
+            ),
+            (
+                3,
+                "Target 0x00000014 of BNE is not the start of an instruction.".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn string_table() {
+    let program = assemble(
+        "la x5, messages\naddi x6, x0, messages_count\nebreak\n\
+         .data\n.byte 7\ntable: .stringtable messages, \"Hi\", \"\", \"Bye\"\nafter: .byte 1",
+        AssembleOptions::default(),
+    )
+    .expect("Program should assemble.");
+
+    // The pointer array is word aligned, and the strings follow it
+    let address = |name: &str| program.symbol_table[name].1.clone();
+    assert_eq!(address("table"), 4.into());
+    assert_eq!(address("messages"), 4.into());
+    assert_eq!(address("messages_0"), 16.into());
+    assert_eq!(address("messages_1"), 19.into());
+    assert_eq!(address("messages_2"), 20.into());
+    assert_eq!(address("messages_count"), 3.into());
+    assert_eq!(address("after"), 24.into());
+
+    let data: Vec<u8> = (4..25).map(|a| program.initial_data_memory[&a]).collect();
+    assert_eq!(
+        data,
+        [
+            16, 0, 0, 0, 19, 0, 0, 0, 20, 0, 0, 0, b'H', b'i', 0, 0, b'B', b'y', b'e', 0, 1
+        ]
+    );
+
+    // Generated labels are referenced by the pointer array
+    let unused: Vec<String> = find_unused_labels(&program)
+        .into_iter()
+        .map(|warning| warning.error_message)
+        .collect();
+    assert_eq!(
+        unused,
+        vec![
+            "Label 'after' is never used.".to_string(),
+            "Label 'table' is never used.".to_string(),
+        ]
+    );
+
+    for source in [
+        ".data\n.stringtable messages",
+        ".data\n.stringtable \"Hi\"",
+        ".data\n.stringtable messages, 5",
+    ] {
+        assert!(
+            assemble(source, AssembleOptions::default()).is_err(),
+            "{:?}",
+            source
+        );
+    }
+}
+
+#[test]
+fn whitespace_columns() {
+    let error = |source: &str| {
+        let errors =
+            assemble(source, AssembleOptions::default()).expect_err("Source should not assemble.");
+        (errors[0].line_number, errors[0].column, errors[0].width)
+    };
+
+    // Tabs count as one column, like Monaco counts them
+    assert_eq!(error("\taddi x1, x0, 1\n\t\tfoo x1, x0, 1"), (2, 3, 3));
+    assert_eq!(error("main:\taddi\tx1,\tx0, 1\n\tbar\tx2"), (2, 2, 3));
+
+    // "\r\n" ends a line once, and the '\r' is not part of the next token
+    assert_eq!(error("addi x1, x0, 1\r\n\r\n  foo x1\r\n"), (3, 3, 3));
+    assert_eq!(error("addi x1, x0, 1 # comment\r\nfoo\r\n"), (2, 1, 3));
+
+    // Other whitespace separates tokens like a space
+    assert_eq!(error("addi\u{a0}x1, x0, 1\nfoo\u{a0}x1"), (2, 1, 3));
+    assert_eq!(error("addi x1, x0, 1\rfoo"), (1, 14, 5));
+
+    // Characters before an error are counted in UTF-16 code units
+    assert_eq!(error("# café\n.string \"é🐊\", foo"), (2, 16, 3));
+
+    let program = assemble(
+        "\taddi x1, x0, 1\t# café\r\n\r\n.data\r\nmessage:\u{a0}.string \"¡hola!\"\r\n",
+        AssembleOptions::default(),
+    )
+    .expect("Program should assemble.");
+    assert_eq!(program.source_map[&0], 1);
+    assert_eq!(program.labels["message"].line, 4);
+    assert_eq!(
+        program
+            .initial_data_memory
+            .values()
+            .copied()
+            .collect::<Vec<u8>>(),
+        b"\xc2\xa1hola!\0"
+    );
+}
@@ -0,0 +1,107 @@
+use crate::isa::{Instruction, InstructionDefinition, InstructionFormat};
+
+use super::{AssembledProgram, AssemblerError};
+
+/// Post-assembly check that every branch and jump lands on the start of an
+/// assembled instruction rather than in data or the middle of an instruction.
+/// Returns one warning per offending instruction.
+pub fn validate_branch_targets(program: &AssembledProgram) -> Vec<AssemblerError> {
+    let mut warnings = Vec::new();
+
+    for (&address, &line) in &program.source_map {
+        let bytes: Option<Vec<u8>> = (address..address + 4)
+            .map(|addr| program.instruction_memory.get(&addr).copied())
+            .collect();
+        let Some(Ok(raw)) = bytes.map(<[u8; 4]>::try_from) else {
+            continue;
+        };
+
+        let instruction = Instruction::from_raw(u32::from_le_bytes(raw));
+        let Some(definition) = InstructionDefinition::from_instr(instruction) else {
+            continue;
+        };
+        if !matches!(
+            definition.format,
+            InstructionFormat::B | InstructionFormat::J
+        ) {
+            continue;
+        }
+
+        // B/J immediates are always present and relative to the instruction
+        let offset = instruction.immediate().unwrap_or(0);
+        let target = address.wrapping_add_signed(offset);
+
+        if target % 4 != 0 || !program.source_map.contains_key(&target) {
+            warnings.push(AssemblerError::new(
+                format!(
+                    "Target 0x{:08X} of {} is not the start of an instruction.",
+                    target, definition._name
+                ),
+                line,
+                1,
+                0,
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// Post-assembly lint for instructions that assemble cleanly but are unlikely to
+/// do what was meant, such as computing a value only to write it to `x0`. The
+/// canonical `addi x0, x0, 0` no-op and jumps that do not link are exempt.
+/// Shift amounts of 32 or more need no check here, since they fail assembly.
+/// Returns one warning per suspicious instruction.
+pub fn lint_operands(program: &AssembledProgram) -> Vec<AssemblerError> {
+    const NOP: u32 = 0x0000_0013;
+
+    program
+        .source_map
+        .iter()
+        .filter_map(|(&address, &line)| {
+            let instruction = program.instruction_at(address)?;
+            let definition = InstructionDefinition::from_instr(instruction)?;
+            // LUI, AUIPC, register-immediate and register-register
+            // arithmetic, and loads
+            let computes = matches!(
+                instruction.opcode(),
+                0b0110111 | 0b0010111 | 0b0010011 | 0b0110011 | 0b0000011
+            );
+            (computes && instruction.rd() == 0 && instruction.raw() != NOP).then(|| {
+                AssemblerError::new(
+                    format!(
+                        "{} writes its result to x0, which discards it.",
+                        definition._name
+                    ),
+                    line,
+                    1,
+                    0,
+                )
+            })
+        })
+        .collect()
+}
+
+/// Labels that mark where execution starts and so are used without a reference
+const ENTRY_LABELS: [&str; 2] = ["main", "_start"];
+
+/// Post-assembly check for labels that no instruction or directive refers to,
+/// which often means a reference misspelled the label. Entry points such as
+/// `main` are exempt. Returns one warning per unused label, at its definition.
+pub fn find_unused_labels(program: &AssembledProgram) -> Vec<AssemblerError> {
+    program
+        .labels
+        .iter()
+        .filter(|(name, _)| {
+            !ENTRY_LABELS.contains(&name.as_str()) && !program.referenced_symbols.contains(*name)
+        })
+        .map(|(name, span)| {
+            AssemblerError::new(
+                format!("Label '{}' is never used.", name),
+                span.line,
+                span.column,
+                span.width,
+            )
+        })
+        .collect()
+}
@@ -0,0 +1,43 @@
+use crate::isa::{ISA, Instruction};
+
+/// A call that has not returned yet
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallFrame {
+    /// Address of the `jal` or `jalr` that made the call
+    pub call_pc: u32,
+    /// Where the callee returns to, just after the call
+    pub return_address: u32,
+}
+
+/// Calls the program is nested in, found by watching instructions retire
+/// rather than from any debug information. A `jal` or `jalr` writing `ra` is a
+/// call and a `jalr` through `ra` that discards the link is a return, so calls
+/// linking through another register or returning some other way are missed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CallStack {
+    frames: Vec<CallFrame>,
+}
+
+impl CallStack {
+    /// Frames from the outermost call to the innermost
+    pub fn frames(&self) -> &[CallFrame] {
+        &self.frames
+    }
+
+    /// Updates the stack for the instruction at `pc` retiring. Returns with
+    /// nothing to return from, such as from the program's entry, are ignored.
+    pub fn retire(&mut self, pc: u32, instruction: Instruction) {
+        match ISA::instr_to_isa(instruction) {
+            Some(ISA::JALR) if instruction.rd() == 0 && instruction.rs1() == 1 => {
+                self.frames.pop();
+            }
+            Some(ISA::JAL | ISA::JALR) if instruction.rd() == 1 => {
+                self.frames.push(CallFrame {
+                    call_pc: pc,
+                    return_address: pc.wrapping_add(4),
+                });
+            }
+            _ => {}
+        }
+    }
+}
@@ -38,6 +38,57 @@ impl ALUOp {
             ALUOp::SELB => b,
         }
     }
+
+    /// What the operation does, in words, for showing alongside its name
+    pub fn description(self) -> &'static str {
+        match self {
+            ALUOp::ADD => "add",
+            ALUOp::SUB => "subtract",
+            ALUOp::XOR => "bitwise exclusive or",
+            ALUOp::OR => "bitwise or",
+            ALUOp::AND => "bitwise and",
+            ALUOp::SLL => "logical left shift",
+            ALUOp::SRL => "logical right shift",
+            ALUOp::SRA => "arithmetic right shift",
+            ALUOp::EQ => "compare equal",
+            ALUOp::NEQ => "compare not equal",
+            ALUOp::LT => "compare less than (signed)",
+            ALUOp::GE => "compare greater or equal (signed)",
+            ALUOp::LTU => "compare less than (unsigned)",
+            ALUOp::GEU => "compare greater or equal (unsigned)",
+            ALUOp::SELB => "pass operand B through",
+        }
+    }
+
+    /// Whether operand B is a shift amount, of which only the low 5 bits are used
+    pub fn is_shift(self) -> bool {
+        matches!(self, ALUOp::SLL | ALUOp::SRL | ALUOp::SRA)
+    }
+
+    /// Flags a conventional ALU would raise for this operation. RISC-V has no
+    /// flags register, so these are purely observational.
+    pub fn flags(self, a: u32, b: u32) -> Option<ALUFlags> {
+        match self {
+            ALUOp::ADD => Some(ALUFlags {
+                carry: a.overflowing_add(b).1,
+                overflow: (a as i32).overflowing_add(b as i32).1,
+            }),
+            ALUOp::SUB => Some(ALUFlags {
+                carry: a.overflowing_sub(b).1,
+                overflow: (a as i32).overflowing_sub(b as i32).1,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Carry and overflow of the last add/sub performed by the ALU.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct ALUFlags {
+    /// Unsigned carry out of bit 31 (a borrow for subtraction)
+    pub carry: bool,
+    /// Signed overflow
+    pub overflow: bool,
 }
 
 #[repr(u32)]
@@ -60,6 +111,7 @@ pub enum OpBSel {
 pub enum DataDestSel {
     ALU,
     LSU,
+    CSR,
 }
 
 #[repr(u32)]
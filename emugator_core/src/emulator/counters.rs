@@ -0,0 +1,36 @@
+use crate::isa::csr::{CYCLE, CYCLEH, INSTRET, INSTRETH, TIME, TIMEH};
+
+/// Performance counters read through the `cycle`, `time` and `instret` CSRs.
+/// They are read-only and start from zero whenever the emulator is reset.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Counters {
+    /// Clock cycles since reset
+    pub cycle: u64,
+    /// The timer's `mtime`, which the `time` CSR shadows
+    pub time: u64,
+    /// Instructions retired since reset
+    pub instret: u64,
+}
+
+impl Counters {
+    /// Zeroes the cycle and retired instruction counts. `time` follows the
+    /// timer, so it keeps counting from where it was.
+    pub fn reset(&mut self) {
+        self.cycle = 0;
+        self.instret = 0;
+    }
+
+    /// Value of a counter CSR, `None` for CSRs that are not implemented
+    pub fn read_csr(&self, csr: u32) -> Option<u32> {
+        let counter = match csr {
+            CYCLE | CYCLEH => self.cycle,
+            TIME | TIMEH => self.time,
+            INSTRET | INSTRETH => self.instret,
+            _ => return None,
+        };
+        Some(match csr {
+            CYCLEH | TIMEH | INSTRETH => (counter >> 32) as u32,
+            _ => counter as u32,
+        })
+    }
+}
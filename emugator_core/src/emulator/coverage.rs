@@ -0,0 +1,134 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Write;
+
+use crate::{
+    assembler::{AssembleOptions, AssembledProgram, assemble},
+    emulator::{EmulatorState, Pipeline, cve2::CVE2Pipeline, five_stage::FiveStagePipeline},
+    isa::ISA,
+};
+
+/// Records which instructions retire across one or more runs, to report how
+/// much of the instruction set the tests exercise
+#[derive(Debug, Default)]
+pub struct OpcodeCoverage {
+    retired: HashSet<&'static str>,
+}
+
+impl OpcodeCoverage {
+    /// Records the instruction in the pipeline's last stage, where it retires.
+    /// Bubbles and words that don't decode are skipped.
+    pub fn record<P: Pipeline + Clone + Default + PartialEq>(
+        &mut self,
+        state: &EmulatorState<P>,
+        program: &AssembledProgram,
+    ) {
+        if let Some((_, isa)) = state.retiring(program) {
+            self.retired.insert(isa.definition()._name);
+        }
+    }
+
+    /// Same as `clock_until_break`, recording every instruction that retires
+    pub fn clock_until_break<P: Pipeline + Clone + Default + PartialEq>(
+        &mut self,
+        state: &EmulatorState<P>,
+        program: &AssembledProgram,
+        breakpoints: &BTreeSet<usize>,
+        max_clocks: usize,
+    ) -> EmulatorState<P> {
+        state.clock_until(program, max_clocks, |state| {
+            self.record(state, program);
+            state.at_breakpoint(program, breakpoints)
+        })
+    }
+
+    pub fn executed(&self) -> usize {
+        self.retired.len()
+    }
+
+    pub fn total() -> usize {
+        ISA::all().count()
+    }
+
+    /// Table of every instruction and whether it retired, followed by the
+    /// executed vs total count
+    pub fn report(&self) -> String {
+        let mut report = String::from("Instruction  Format  Retired\n");
+        for isa in ISA::all() {
+            let def = isa.definition();
+            let retired = if self.retired.contains(def._name) {
+                "yes"
+            } else {
+                "-"
+            };
+            writeln!(
+                report,
+                "{:<11}  {:<6}  {}",
+                def._name,
+                format!("{:?}", def.format),
+                retired
+            )
+            .unwrap();
+        }
+        write!(
+            report,
+            "Coverage: {}/{} instructions ({:.1}%)",
+            self.executed(),
+            Self::total(),
+            100.0 * self.executed() as f64 / Self::total() as f64
+        )
+        .unwrap();
+        report
+    }
+}
+
+#[test]
+fn test_opcode_coverage() {
+    let program = assemble(
+        "addi x1, x0, 5\nslli x2, x1, 2\nsw x2, 0(x0)\nlw x3, 0(x0)\nbeq x3, x2, done\nadd x4, x0, x0\ndone: ebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+
+    let mut cve2 = OpcodeCoverage::default();
+    let state = cve2.clock_until_break(
+        &EmulatorState::<CVE2Pipeline>::new(&program),
+        &program,
+        &breakpoints,
+        1000,
+    );
+    assert_eq!(state.x[3], 20);
+
+    let mut five_stage = OpcodeCoverage::default();
+    let state = five_stage.clock_until_break(
+        &EmulatorState::<FiveStagePipeline>::new(&program),
+        &program,
+        &breakpoints,
+        1000,
+    );
+    assert_eq!(state.x[3], 20);
+
+    // The skipped ADD never retires
+    for coverage in [&cve2, &five_stage] {
+        let report = coverage.report();
+        for name in ["ADDI", "SLLI", "SW", "LW", "BEQ"] {
+            assert!(coverage.retired.contains(name), "{}:\n{}", name, report);
+        }
+        assert!(
+            report.contains("\nADDI         I       yes\n"),
+            "{}",
+            report
+        );
+        assert!(!coverage.retired.contains("ADD"), "{}", report);
+        assert!(report.contains("\nADD          R       -\n"), "{}", report);
+    }
+    // CVE2 retires in decode, where EBREAK stops it, while the five-stage run
+    // stops before EBREAK reaches writeback
+    assert!(cve2.retired.contains("EBREAK"));
+    assert!(!five_stage.retired.contains("EBREAK"));
+    assert_eq!(cve2.executed(), 6);
+    assert!(five_stage.report().contains(&format!(
+        "Coverage: 5/{} instructions",
+        OpcodeCoverage::total()
+    )));
+}
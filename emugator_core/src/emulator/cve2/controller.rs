@@ -193,6 +193,18 @@ impl CVE2Control {
     }
 }
 
+/// Number of cycles `instr` spends in ID before the next instruction is let in.
+/// Branches take an extra cycle when taken, so `branch_cmp` must hold the
+/// registered comparison result.
+pub fn instruction_cycles(instr: Instruction, branch_cmp: bool) -> u32 {
+    match instr.opcode() {
+        0b1101111 | 0b1100111 => 2,         // JAL, JALR: jump, then link
+        0b1100011 => 2 + branch_cmp as u32, // Branch: compare, jump, then NOP if taken
+        0b0000011 | 0b0100011 => 2,         // Load, store: request, then completion
+        _ => 1,
+    }
+}
+
 #[allow(clippy::unusual_byte_groupings)]
 pub fn get_control_signals(
     instr: Instruction,
@@ -324,7 +336,15 @@ pub fn get_control_signals(
                 debug_req: true,
                 ..Default::default()
             }), // EBREAK
-            _ => Some(CVE2Control::default()), // CSR (no-op),
+            0b0001_0000_0101_00000_000_00000_1110011 => Some(CVE2Control::default()), // WFI (halts in decode)
+            // CSRs are read-only, so every CSR instruction just reads the CSR
+            // into rd, leaving it unchanged if the CSR is not implemented
+            _ if instr.funct3() != 0b000 => Some(CVE2Control {
+                data_dest_sel: Some(DataDestSel::CSR),
+                reg_write: true,
+                ..Default::default()
+            }),
+            _ => None,
         },
         _ => None,
     }
@@ -1,3 +1,5 @@
+use crate::emulator::controller_common::ALUFlags;
+
 /// Lines in the datapath
 ///
 /// Initially based on the `cve2_top` module.
@@ -53,6 +55,9 @@ pub struct CVE2Datapath {
     pub data_s1: u32,
     pub data_s2: u32,
 
+    // csr read, `None` for CSRs that are not implemented
+    pub csr_data: Option<u32>,
+
     /// alu and lsu
     pub alu_op_a: Option<u32>, // Operand A input.
     pub alu_op_b: Option<u32>, // Operand B input.
@@ -60,6 +65,9 @@ pub struct CVE2Datapath {
     pub lsu_out: Option<u32>,  // Load/Store Unit output.
     pub reg_write_data: Option<u32>,
 
+    // carry/overflow of the last add/sub (observational only)
+    pub alu_flags: Option<ALUFlags>,
+
     // program counter
     pub cmp_result: bool,     // Result of the branch comparison operation.
     pub next_pc: Option<u32>, // Next program counter value.
@@ -85,6 +93,7 @@ impl PartialEq for CVE2Datapath {
             && self.alu_op_a == other.alu_op_a
             && self.alu_op_b == other.alu_op_b
             && self.alu_out == other.alu_out
+            && self.alu_flags == other.alu_flags
             && self.lsu_out == other.lsu_out
             && self.reg_write_data == other.reg_write_data
             && self.cmp_result == other.cmp_result
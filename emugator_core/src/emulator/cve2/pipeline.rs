@@ -1,15 +1,16 @@
 use super::{
-    controller::{CVE2Control, get_control_signals},
+    controller::{CVE2Control, get_control_signals, instruction_cycles},
     datapath::CVE2Datapath,
 };
 use crate::{
     assembler::AssembledProgram,
     bitmask, bits,
     emulator::{
-        PcPos, Pipeline, RegisterFile,
+        PcPos, PendingStore, Pipeline, RegisterFile, StageSnapshot, StopReason,
         controller_common::{DataDestSel, OpASel, OpBSel, PCSel},
+        counters::Counters,
+        decode_fault,
         memory_module::MemoryModule,
-        read_instruction,
     },
     isa::Instruction,
 };
@@ -24,6 +25,7 @@ pub struct CVE2Pipeline {
     pub instr_cycle: u32,     // The number of cycles that this instruction has been in ID.
     pub datapath: CVE2Datapath,
     pub control: CVE2Control,
+    pub stop_reason: Option<StopReason>,
 }
 
 impl Pipeline for CVE2Pipeline {
@@ -32,7 +34,19 @@ impl Pipeline for CVE2Pipeline {
         program: &AssembledProgram,
         registers: &mut RegisterFile,
         data_memory: &mut MemoryModule,
+        counters: &mut Counters,
     ) {
+        // An empty program halts before fetching anything
+        if program.instruction_memory.is_empty() {
+            self.stop_reason = Some(StopReason::NoInstructions);
+            return;
+        }
+
+        // The instruction in decode retires once the next one is let in
+        if self.control.if_id_set && self.ID_inst.is_some() {
+            counters.instret += 1;
+        }
+
         // Run the registers that had stuff to write in the last cycle
         // (this is done first to represent it taking a clock edge to write)
         self.run_pipeline_buffer_registers();
@@ -43,6 +57,12 @@ impl Pipeline for CVE2Pipeline {
         // Run the instruction fetch stage
         self.run_instruction_fetch(program);
 
+        // Halt on instructions that can't be executed
+        if let Some(fault) = self.ID_pc.and_then(|pc| decode_fault(pc, self.ID_inst)) {
+            self.stop_reason = Some(fault);
+            return;
+        }
+
         // Set control signals
         let Some(id_inst) = self.ID_inst else {
             // no id stage yet
@@ -57,6 +77,7 @@ impl Pipeline for CVE2Pipeline {
 
         // Read from register file
         self.run_read_registers(registers);
+        self.run_read_csr(instr, counters);
 
         // Operand muxes
         self.run_operand_muxes();
@@ -101,12 +122,63 @@ impl Pipeline for CVE2Pipeline {
         }
         pcs
     }
+
+    fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    fn pending_store(&self) -> Option<PendingStore> {
+        let datapath = &self.datapath;
+        if !(datapath.data_req_o && datapath.data_we_o) {
+            return None;
+        }
+        // The instruction in decode drives the LSU
+        Some(PendingStore {
+            pc: self.ID_pc?,
+            address: datapath.data_addr_o,
+            byte_enable: datapath.data_be_o,
+        })
+    }
+
+    fn pipeline_snapshot(&self) -> Vec<StageSnapshot> {
+        let datapath = &self.datapath;
+        let data_address = datapath.data_req_o.then_some(datapath.data_addr_o);
+        vec![
+            StageSnapshot::new("IF", Some(self.IF_pc), self.IF_inst)
+                .signal("next_pc", datapath.next_pc),
+            StageSnapshot::new("ID", self.ID_pc, self.ID_inst)
+                .signal("instr_cycle", self.ID_inst.map(|_| self.instr_cycle))
+                .signal("instr_cycles", self.instruction_cycles())
+                .signal("rs1", Some(datapath.reg_s1.into()))
+                .signal("rs2", Some(datapath.reg_s2.into()))
+                .signal("rd", Some(datapath.reg_d.into()))
+                .signal("imm", datapath.imm)
+                .signal("rs1_v", Some(datapath.data_s1))
+                .signal("rs2_v", Some(datapath.data_s2))
+                .signal("alu_out", datapath.alu_out)
+                .signal("mem_addr", data_address)
+                .signal("lsu_out", datapath.lsu_out)
+                .signal("wb_data", datapath.reg_write_data),
+        ]
+    }
 }
 
 impl CVE2Pipeline {
+    /// Total number of cycles the instruction in ID takes, `None` if ID is empty.
+    /// Compare against `instr_cycle` to find which phase it is in.
+    pub fn instruction_cycles(&self) -> Option<u32> {
+        self.ID_inst
+            .map(|inst| instruction_cycles(Instruction::from_raw(inst), self.datapath.cmp_result))
+    }
+
+    /// Whether the instruction in ID spans more than one cycle
+    pub fn in_multi_cycle(&self) -> bool {
+        self.instruction_cycles().is_some_and(|cycles| cycles > 1)
+    }
+
     fn run_instruction_fetch(&mut self, program: &AssembledProgram) {
         // Read the next instruction into the instruction fetch register
-        self.IF_inst = read_instruction(&program.instruction_memory, self.IF_pc)
+        self.IF_inst = program.instruction_at(self.IF_pc).map(|instr| instr.raw())
     }
 
     fn run_decode(&mut self, instr: Instruction) {
@@ -121,6 +193,10 @@ impl CVE2Pipeline {
         self.datapath.data_s2 = register_file[self.datapath.reg_s2 as usize];
     }
 
+    fn run_read_csr(&mut self, instr: Instruction, counters: &Counters) {
+        self.datapath.csr_data = counters.read_csr(instr.csr());
+    }
+
     fn run_operand_muxes(&mut self) {
         self.datapath.alu_op_a = match self.control.alu_op_a_sel {
             Some(OpASel::PC) => self.ID_pc,
@@ -144,6 +220,7 @@ impl CVE2Pipeline {
         };
 
         self.datapath.alu_out = self.control.alu_op.map(|op| op.apply(a, b));
+        self.datapath.alu_flags = self.control.alu_op.and_then(|op| op.flags(a, b));
     }
 
     fn run_lsu(&mut self) {
@@ -182,10 +259,14 @@ impl CVE2Pipeline {
         self.datapath.reg_write_data = match self.control.data_dest_sel {
             Some(DataDestSel::ALU) => self.datapath.alu_out,
             Some(DataDestSel::LSU) => self.datapath.lsu_out,
+            Some(DataDestSel::CSR) => self.datapath.csr_data,
             None => None,
         };
     }
 
+    /// Answers the request the LSU made last cycle, since data memory grants
+    /// and returns data on the clock edge after a request. Loads and stores
+    /// therefore take a second cycle in ID to complete.
     fn run_data_memory(&mut self, data_memory: &mut MemoryModule) {
         // Perform any requested memory read/write
         if self.datapath.data_req_o {
@@ -1,9 +1,11 @@
 #![allow(non_snake_case)]
 
-use bimap::BiBTreeMap;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use super::*;
+use crate::assembler::{AssembleOptions, MemoryMap, SectionBases};
+use crate::emulator::controller_common::ALUFlags;
+use crate::emulator::test_utils::run_source;
 use crate::isa::{ISA, Instruction, Operands};
 
 impl ISA {
@@ -37,8 +39,12 @@ fn populate_with_offset(instructions: &[Instruction], offset: u32) -> AssembledP
     AssembledProgram {
         instruction_memory,
         initial_data_memory: BTreeMap::new(),
-        source_map: BiBTreeMap::new(),
+        source_map: BTreeMap::new(),
         symbol_table: HashMap::new(),
+        memory_map: MemoryMap::default(),
+        labels: BTreeMap::new(),
+        referenced_symbols: HashSet::new(),
+        warnings: Vec::new(),
     }
 }
 
@@ -1951,3 +1957,902 @@ fn test_AND() {
 
     assert_eq!(state.x[3], 0b1000); // x3 = 8 (0b1100 & 0b1010)
 }
+
+#[test]
+fn test_ALU_flags() {
+    let program = populate(&[
+        ISA::LUI.build(Operands {
+            rd: 1,
+            imm: 0x80000000u32 as i32,
+            ..Default::default()
+        }),
+        ISA::ADDI.build(Operands {
+            rd: 2,
+            rs1: 0,
+            imm: -1,
+            ..Default::default()
+        }),
+        ISA::ADD.build(Operands {
+            rd: 3,
+            rs1: 1,
+            rs2: 2,
+            ..Default::default()
+        }),
+        ISA::XOR.build(Operands {
+            rd: 4,
+            rs1: 1,
+            rs2: 2,
+            ..Default::default()
+        }),
+    ]);
+
+    let mut state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    // LUI
+    state = state.clock(&program);
+
+    // 0 + -1 neither carries nor overflows
+    state = state.clock(&program);
+    assert_eq!(
+        state.pipeline.datapath.alu_flags,
+        Some(ALUFlags {
+            carry: false,
+            overflow: false
+        })
+    );
+
+    // 0x80000000 + 0xFFFFFFFF both carries and overflows
+    state = state.clock(&program);
+    assert_eq!(
+        state.pipeline.datapath.alu_flags,
+        Some(ALUFlags {
+            carry: true,
+            overflow: true
+        })
+    );
+
+    // Flags are only reported for add/sub and don't affect the result
+    state = state.clock(&program);
+    assert_eq!(state.pipeline.datapath.alu_flags, None);
+    assert_eq!(state.x[3], 0x7FFFFFFF);
+}
+
+#[test]
+fn test_reverse_until_break() {
+    let mut program = crate::assembler::assemble(
+        "addi x1, x0, 1\naddi x1, x1, 1\naddi x1, x1, 1\naddi x1, x1, 1",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::from([2]);
+
+    let mut states = vec![AnyEmulatorState::new_cve2(&program)];
+    for _ in 0..5 {
+        let next = states.last().unwrap().clock(&mut program);
+        states.push(next);
+    }
+
+    // The breakpoint line is in decode after the second clock
+    let hit = reverse_until_break(&states, states.len() - 1, &program, &breakpoints);
+    assert_eq!(hit, 2);
+    assert!(states[hit].at_breakpoint(&program, &breakpoints));
+
+    // Nothing before the breakpoint, so it stops at the first state
+    assert_eq!(reverse_until_break(&states, hit, &program, &breakpoints), 0);
+}
+
+#[test]
+fn test_stuck_loop_detection() {
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 1\nstuck: jal x0, stuck\naddi x2, x0, 2",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    let (_, stuck_pc) = state.clock_until_break_or_stuck(&program, &BTreeSet::new(), 1000);
+    assert_eq!(stuck_pc, Some(4));
+
+    // Counting memory accesses should not make a loop that loads look like progress
+    let program = crate::assembler::assemble(
+        "loop: lw x1, 0(x0)\njal x0, loop",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    let (_, stuck_pc) = state.clock_until_break_or_stuck(&program, &BTreeSet::new(), 1000);
+    assert_eq!(stuck_pc, Some(4));
+
+    // Loops that keep changing registers are still making progress
+    let program = crate::assembler::assemble(
+        "loop: addi x1, x1, 1\njal x0, loop",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    let (_, stuck_pc) = state.clock_until_break_or_stuck(&program, &BTreeSet::new(), 1000);
+    assert_eq!(stuck_pc, None);
+}
+
+#[test]
+fn test_la_load() {
+    let program = crate::assembler::assemble(
+        ".text 0x100\nla x1, value\nlw x2, 0(x1)\n.data 0x40\n.word 0\nvalue: .word 0x12345678",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let mut state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    for _ in 0..10 {
+        state = state.clock(&program);
+    }
+
+    assert_eq!(state.x[1], 0x44);
+    assert_eq!(state.x[2], 0x12345678);
+}
+
+#[test]
+fn test_call_return() {
+    let program = crate::assembler::assemble(
+        "main: addi x10, x0, 3\ncall double\naddi x11, x10, 1\nebreak\n.text 0x2000\ndouble: add x10, x10, x10\njalr x0, x1, 0",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+    let state = state.clock_until_break(&program, &BTreeSet::new(), 1000);
+
+    // The return lands on the instruction after the two-instruction call
+    assert_eq!(state.x[1], 12);
+    assert_eq!(state.x[10], 6);
+    assert_eq!(state.x[11], 7);
+}
+
+#[test]
+fn test_performance_counters() {
+    let program = crate::assembler::assemble(
+        "addi x14, x0, 7\nlw x2, 0(x0)\ncsrrs x10, cycle, x0\ncsrrs x11, instret, x0\ncsrrs x12, time, x0\ncsrrs x13, cycleh, x0\ncsrrs x14, 0x123, x0\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+    let state = state.clock_until_break(&program, &BTreeSet::new(), 1000);
+
+    // Each CSR is read in decode, after the load took two cycles there
+    assert_eq!(state.x[10], 3);
+    assert_eq!(state.x[11], 3);
+    assert_eq!(state.x[12], 5);
+    assert_eq!(state.x[13], 0);
+    // Reading a CSR that isn't implemented leaves rd unchanged
+    assert_eq!(state.x[14], 7);
+    assert_eq!(state.counters.cycle, 9);
+    assert_eq!(state.counters.time, 9);
+    assert_eq!(state.counters.instret, 7);
+}
+
+#[test]
+fn test_reset_counters() {
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 1\naddi x2, x0, 2\ncsrrs x3, cycle, x0\ncsrrs x4, instret, x0\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let mut state = AnyEmulatorState::new_cve2(&program);
+    for _ in 0..3 {
+        state = state.clock(&mut program.clone());
+    }
+    let (registers, time) = (*state.registers(), state.counters().time);
+    assert!(state.counters().cycle > 0);
+
+    state.reset_counters();
+    assert_eq!(state.counters().cycle, 0);
+    assert_eq!(state.counters().instret, 0);
+    assert_eq!(state.counters().time, time);
+    assert_eq!(*state.registers(), registers);
+
+    // The CSRs count up from the reset rather than from the start
+    let mut program = program;
+    let state = state.clock_until_break(&mut program, &BTreeSet::new(), 100);
+    assert_eq!(state.registers()[3], 2);
+    assert_eq!(state.registers()[4], 1);
+}
+
+#[test]
+fn test_pipeline_snapshot() {
+    let program =
+        crate::assembler::assemble("addi x1, x0, 5\naddi x2, x1, 7", AssembleOptions::default())
+            .unwrap();
+    let mut state = EmulatorState::<CVE2Pipeline>::new(&program);
+    state = state.clock(&program);
+
+    let snapshot = state.pipeline.pipeline_snapshot();
+    let names: Vec<_> = snapshot.iter().map(|stage| stage.name).collect();
+    assert_eq!(names, vec!["IF", "ID"]);
+
+    assert_eq!(snapshot[0].pc, Some(4));
+    assert_eq!(snapshot[1].pc, Some(0));
+    assert_eq!(
+        snapshot[1].instruction,
+        program.instruction_at(0).map(|instr| instr.raw())
+    );
+    assert_eq!(snapshot[1].get("rd"), Some(1));
+    assert_eq!(snapshot[1].get("alu_out"), Some(5));
+    assert_eq!(snapshot[1].get("mem_addr"), None);
+    assert!(snapshot[1].to_string().starts_with("ID   pc=0x00000000"));
+}
+
+#[test]
+fn test_multi_cycle_phases() {
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 0\nlw x2, 0(x1)\nbeq x0, x0, done\naddi x3, x0, 1\ndone: ebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let mut state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    // Record (pc, instr_cycle, instruction_cycles) for each cycle
+    let mut phases = Vec::new();
+    for _ in 0..7 {
+        state = state.clock(&program);
+        let id = &state.pipeline.pipeline_snapshot()[1];
+        phases.push((id.pc, id.get("instr_cycle"), id.get("instr_cycles")));
+    }
+
+    assert_eq!(
+        phases,
+        vec![
+            (Some(0), Some(0), Some(1)),
+            (Some(4), Some(0), Some(2)),
+            (Some(4), Some(1), Some(2)),
+            (Some(8), Some(0), Some(3)),
+            (Some(8), Some(1), Some(3)),
+            (Some(8), Some(2), Some(3)),
+            (Some(16), Some(0), Some(1)),
+        ]
+    );
+    assert!(!state.pipeline.in_multi_cycle());
+}
+
+#[test]
+fn test_memory_access_cycles() {
+    // Clocks until the instruction after the first reaches ID
+    let cycles_to_next = |first: &str| {
+        let program = crate::assembler::assemble(
+            &format!("{first}\naddi x3, x2, 1\nebreak\n.data 0x200\nvalue: .word 42"),
+            AssembleOptions::default(),
+        )
+        .unwrap();
+        let mut state = EmulatorState::<CVE2Pipeline>::new(&program);
+        while state.pipeline.ID_pc != Some(4) {
+            state = state.clock(&program);
+        }
+        (state.counters.cycle, state.counters.instret)
+    };
+
+    // Data memory answers a request on the next clock edge, so loads and
+    // stores spend one cycle more in ID than an ALU op
+    let (alu_cycles, alu_instret) = cycles_to_next("addi x2, x0, 42");
+    assert_eq!((alu_cycles, alu_instret), (2, 1));
+    assert_eq!(cycles_to_next("lw x2, 0x200(x0)"), (alu_cycles + 1, 1));
+    assert_eq!(cycles_to_next("sw x2, 0x200(x0)"), (alu_cycles + 1, 1));
+
+    // Record (pc, instr_cycle, mem_addr, lsu_out, x2, wb_data) each cycle of a load
+    // followed by an instruction using its result
+    let program = crate::assembler::assemble(
+        "lw x2, 0x200(x0)\naddi x3, x2, 1\nebreak\n.data 0x200\nvalue: .word 42",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let mut state = EmulatorState::<CVE2Pipeline>::new(&program);
+    let mut phases = Vec::new();
+    for _ in 0..3 {
+        state = state.clock(&program);
+        let id = &state.pipeline.pipeline_snapshot()[1];
+        phases.push((
+            id.pc,
+            id.get("instr_cycle"),
+            id.get("mem_addr"),
+            id.get("lsu_out"),
+            state.x[2],
+            id.get("wb_data"),
+        ));
+    }
+
+    assert_eq!(
+        phases,
+        vec![
+            // The request puts the address on the bus
+            (Some(0), Some(0), Some(0x200), None, 0, None),
+            // The data arrives and is written back at the end of the cycle
+            (Some(0), Some(1), None, Some(42), 0, Some(42)),
+            // The next instruction reads the loaded value without stalling
+            (Some(4), Some(0), None, None, 42, Some(43)),
+        ]
+    );
+}
+
+#[test]
+fn test_no_instructions() {
+    for source in ["", "# nothing here", ".data\n.word 1"] {
+        let program = crate::assembler::assemble(source, AssembleOptions::default()).unwrap();
+        let state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+        let stopped = state.clock(&program);
+        assert_eq!(
+            stopped.pipeline.stop_reason(),
+            Some(StopReason::NoInstructions)
+        );
+        assert_eq!(stopped.counters.instret, 0);
+        assert_eq!(stopped.clock(&program), stopped);
+
+        let stopped = state.into_five_stage().clock(&program);
+        assert_eq!(
+            stopped.pipeline.stop_reason(),
+            Some(StopReason::NoInstructions)
+        );
+    }
+}
+
+#[test]
+fn test_entry_with_several_text_sections() {
+    let program = crate::assembler::assemble(
+        ".text 0x40\naddi x1, x0, 1\nebreak\n.text 0x10\n.word 0\n.text 0x20\naddi x2, x0, 2\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+    assert_eq!(state.pipeline.IF_pc, 0x20);
+
+    // Only the section holding the lowest instruction runs
+    let state = state.clock_until_break(&program, &BTreeSet::new(), 100);
+    assert_eq!((state.x[1], state.x[2]), (0, 2));
+}
+
+#[test]
+fn test_instruction_access_fault() {
+    let program =
+        crate::assembler::assemble("addi x1, x0, 1\njal x0, 0x100", AssembleOptions::default())
+            .unwrap();
+    let mut state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    for _ in 0..20 {
+        state = state.clock(&program);
+    }
+
+    assert_eq!(
+        state.pipeline.stop_reason(),
+        Some(StopReason::InstructionAccessFault { pc: 0x100 })
+    );
+    assert_eq!(state.x[1], 1);
+    assert_eq!(
+        state.clock(&program),
+        state,
+        "Halted state should not change"
+    );
+
+    // Fetching past a loop at the end of the program is not a fault
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 1\nloop: addi x1, x1, 1\njal x0, loop",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let mut state = EmulatorState::<CVE2Pipeline>::new(&program);
+    for _ in 0..50 {
+        state = state.clock(&program);
+    }
+    assert_eq!(state.pipeline.stop_reason(), None);
+}
+
+#[test]
+fn test_illegal_instruction() {
+    let mut program = crate::assembler::assemble(
+        "addi x1, x0, 1\naddi x2, x0, 2\naddi x3, x0, 3",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    write(
+        &mut program.instruction_memory,
+        4,
+        &0xFFFF_FFFFu32.to_le_bytes(),
+    );
+    let mut state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    for _ in 0..20 {
+        state = state.clock(&program);
+    }
+
+    let reason = state.pipeline.stop_reason();
+    assert_eq!(
+        reason,
+        Some(StopReason::IllegalInstruction {
+            pc: 4,
+            word: 0xFFFF_FFFF
+        })
+    );
+    assert_eq!(
+        reason.unwrap().to_string(),
+        "Illegal instruction 0xFFFFFFFF at 0x00000004"
+    );
+    assert_eq!(state.x[1], 1);
+    assert_eq!(
+        state.x[3], 0,
+        "Instructions after the illegal one should not run"
+    );
+}
+
+#[test]
+fn test_stack_overflow() {
+    let program = crate::assembler::assemble(
+        "addi x2, x0, 0x40\nloop: addi x2, x2, -4\nsw x1, 0(x2)\njal x0, loop",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+    let conditions = BreakConditions {
+        stack_overflow: true,
+        ..Default::default()
+    };
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    // The stack grows down until it overwrites the instructions at 0x0-0xF
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(
+        reason,
+        Some(BreakReason::StackOverflow {
+            pc: 8,
+            address: 0xC
+        })
+    );
+    assert_eq!(state.data_memory.preview(0xC), 0);
+
+    // Stores through other registers, or to the rest of memory, are not checked
+    let program = crate::assembler::assemble(
+        "addi x3, x0, 4\nsw x1, 0(x3)\nsb x1, 0x40(x2)\naddi x3, x0, 0\naddi x3, x0, 0\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(reason, None);
+    assert!(state.data_memory.ram().contains_key(&0x40));
+}
+
+#[test]
+fn test_text_writes() {
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 0x13\nsw x1, 8(x0)\naddi x2, x0, 1\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+    let conditions = BreakConditions {
+        text_writes: true,
+        ..Default::default()
+    };
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(reason, Some(BreakReason::TextWrite { pc: 4, address: 8 }));
+
+    // The store lands in data memory and the program still runs to its end
+    let state = state.clock_until_break(&program, &breakpoints, 1000);
+    assert_eq!(state.data_memory.preview(8), 0x13);
+    assert!(state.has_terminated());
+
+    // Without the break condition, the store is still reported
+    let state = EmulatorState::<CVE2Pipeline>::new(&program).clock_until_break(
+        &program,
+        &breakpoints,
+        1000,
+    );
+    assert!(state.has_terminated());
+    assert_eq!(
+        state.data_memory.text_writes(),
+        &BTreeSet::from([8, 9, 10, 11])
+    );
+}
+
+#[test]
+fn test_break_on_mnemonic() {
+    let program = crate::assembler::assemble(
+        "jal x1, func\naddi x2, x0, 2\nebreak\nfunc: addi x3, x0, 3\njalr x0, x1, 0",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+    let conditions = BreakConditions {
+        mnemonic: Some("JALR"),
+        ..Default::default()
+    };
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(
+        reason,
+        Some(BreakReason::Mnemonic {
+            pc: 16,
+            mnemonic: "JALR"
+        })
+    );
+    assert_eq!(state.x[3], 3);
+
+    // Continuing runs past the same jalr to the end of the program
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(reason, None);
+    assert!(state.has_terminated());
+}
+
+#[test]
+fn test_clock_until_return() {
+    let program = crate::assembler::assemble(
+        "addi x2, x0, 0x200\naddi x10, x0, 3\njal x1, count\naddi x5, x0, 1\nebreak\n\
+         count: addi x2, x2, -4\nsw x1, 0(x2)\naddi x10, x10, -1\nbeq x10, x0, done\n\
+         jal x1, count\ndone: lw x1, 0(x2)\naddi x2, x2, 4\njalr x0, x1, 0",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+    let entered = |state: &EmulatorState<CVE2Pipeline>| state.pipeline.id_pc() == Some(20);
+
+    // From the outermost call, every recursive call is stepped over
+    let outer = state.clock_until(&program, 100, entered);
+    let returned = outer.clock_until_return(&program, &breakpoints, 1000);
+    assert_eq!(returned.pipeline.id_pc(), Some(12));
+    assert_eq!(returned.x[10], 0);
+
+    // From the innermost call, stepping out lands back in its caller
+    let mut inner = outer.clone();
+    for _ in 0..2 {
+        inner = inner.clock_until_next_instruction(&program, 100);
+        inner = inner.clock_until(&program, 100, entered);
+    }
+    let returned = inner.clock_until_return(&program, &breakpoints, 1000);
+    assert_eq!(returned.pipeline.id_pc(), Some(40));
+    assert_eq!(returned.x[10], 0);
+
+    // Stepping out from the return itself goes straight to the caller
+    let at_return = inner.clock_until(&program, 100, |s| s.pipeline.id_pc() == Some(48));
+    let returned = at_return.clock_until_return(&program, &breakpoints, 1000);
+    assert_eq!(returned.pipeline.id_pc(), Some(40));
+
+    // Breakpoints inside the function still stop it early
+    let breakpoints = BTreeSet::from([program.source_map[&28]]);
+    let stopped = outer.clock_until_return(&program, &breakpoints, 1000);
+    assert_eq!(stopped.pipeline.id_pc(), Some(28));
+}
+
+#[test]
+fn test_wait_for_interrupt() {
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 1\nwfi\naddi x2, x0, 2\njal x0, 0",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(program.disassemble(4).as_deref(), Some("wfi"));
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    // Until Break stops on the halt without needing a breakpoint
+    let state = state.clock_until_break(&program, &BTreeSet::new(), 100);
+
+    let reason = state.pipeline.stop_reason();
+    assert_eq!(reason, Some(StopReason::WaitForInterrupt { pc: 4 }));
+    assert_eq!(
+        reason.unwrap().to_string(),
+        "Waiting for an interrupt at 0x00000004"
+    );
+    assert_eq!(state.x[1], 1);
+    assert_eq!(state.x[2], 0, "Instructions after wfi should not run");
+}
+
+#[test]
+fn test_timer_delay_loop() {
+    let program = crate::assembler::assemble(
+        "lw x1, -16(x0)\naddi x1, x1, 50\nwait: lw x2, -16(x0)\nbltu x2, x1, wait\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+    let state = state.clock_until_break(&program, &BTreeSet::new(), 1000);
+
+    assert!(state.x[2] >= state.x[1]);
+    assert!(state.x[1] >= 50);
+    assert!(state.data_memory.mtime() > state.x[2] as u64);
+
+    // Polling the timer makes progress, so it should not look like a stuck loop
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+    let (state, stuck_pc) = state.clock_until_break_or_stuck(&program, &BTreeSet::new(), 1000);
+    assert_eq!(stuck_pc, None);
+    assert!(state.x[2] >= state.x[1]);
+}
+
+#[test]
+fn test_run_program_diff() {
+    let run = |source: &str| {
+        let program = crate::assembler::assemble(source, AssembleOptions::default()).unwrap();
+        run_program(&program, EmulatorOption::CVE2, b"", 1000)
+    };
+
+    let before = run("addi x1, x0, 5\naddi x2, x0, 65\nsb x2, 0xF0(x0)\nsw x1, 0x10(x0)\nebreak");
+    let after = run("addi x1, x0, 6\naddi x2, x0, 65\nsb x2, 0xF0(x0)\nsw x1, 0x10(x0)\nebreak");
+    let diff = before.diff(&after);
+
+    assert_eq!(diff.registers, vec![(1, 5, 6)]);
+    assert_eq!(diff.memory, vec![(0x10, 5, 6)]);
+    assert_eq!(diff.uart_output, None);
+    assert_eq!(before.memory_io().get_serial_output(), b"A");
+    assert!(before.diff(&before).is_empty());
+
+    let quiet = run("addi x1, x0, 5\nsw x1, 0x10(x0)\nebreak");
+    assert_eq!(
+        before.diff(&quiet).uart_output,
+        Some(("A".to_string(), String::new()))
+    );
+}
+
+#[test]
+fn test_relocated_uart() {
+    let memory_map = MemoryMap {
+        uart: 0x200,
+        ..Default::default()
+    };
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 66\nsb x1, 0x200(x0)\nsb x1, 0xF0(x0)\n\
+         wait: lbu x2, 0x204(x0)\nandi x2, x2, 8\nbeq x2, x0, wait\nebreak",
+        AssembleOptions::with_memory_map(memory_map),
+    )
+    .unwrap();
+    let state = run_program(&program, EmulatorOption::CVE2, b"", 1000);
+
+    assert!(state.has_terminated());
+    assert_eq!(state.memory_io().get_serial_output(), b"B");
+    assert_eq!(
+        state.memory_io().preview(0xF0),
+        66,
+        "0xF0 should be plain memory"
+    );
+}
+
+#[test]
+fn test_zero_branches() {
+    let taken = |branch: &str, value: i32| {
+        let source = format!(
+            "addi x5, x0, {value}\n{branch} x5, taken\naddi x6, x0, 0\nebreak\ntaken: addi x6, x0, 1\nebreak"
+        );
+        run_source(&source, EmulatorOption::CVE2, 1000).0[6] == 1
+    };
+
+    // Signed comparisons, so -5 is below zero rather than a huge unsigned value
+    for (branch, expected) in [
+        ("beqz", [false, true, false]),
+        ("bnez", [true, false, true]),
+        ("blez", [true, true, false]),
+        ("bgez", [false, true, true]),
+        ("bltz", [true, false, false]),
+        ("bgtz", [false, false, true]),
+    ] {
+        let results = [-5, 0, 5].map(|value| taken(branch, value));
+        assert_eq!(results, expected, "{branch}");
+    }
+}
+
+#[test]
+fn test_unary_pseudo_instructions() {
+    let result = |unary: &str, value: i32| {
+        let source =
+            format!("lui x5, %hi({value})\naddi x5, x5, %lo({value})\n{unary} x6, x5\nebreak");
+        run_source(&source, EmulatorOption::CVE2, 1000).0[6]
+    };
+
+    let values = [0, 1, -1, i32::MIN];
+    assert_eq!(values.map(|value| result("seqz", value)), [1, 0, 0, 0]);
+    assert_eq!(values.map(|value| result("snez", value)), [0, 1, 1, 1]);
+    assert_eq!(
+        values.map(|value| result("not", value)),
+        [u32::MAX, !1, 0, i32::MAX as u32]
+    );
+    // INT_MIN has no positive counterpart, so negating it wraps back to itself
+    assert_eq!(
+        values.map(|value| result("neg", value)),
+        [0, -1i32 as u32, 1, i32::MIN as u32]
+    );
+}
+
+#[test]
+fn test_evaluate_expression() {
+    let program = crate::assembler::assemble(
+        ".equ OFFSET, 4\naddi x10, x0, 12\naddi x2, x0, 0x20\naddi x5, x0, -3\nsw x10, 0x20(x0)\nsw x2, 0x24(x0)\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = run_program(&program, EmulatorOption::CVE2, b"", 1000);
+
+    assert_eq!(state.evaluate("a0 + 4", &program), Ok(16));
+    assert_eq!(state.evaluate("x10 * (OFFSET + 1)", &program), Ok(60));
+    assert_eq!(state.evaluate("t0", &program), Ok(-3i32 as u32));
+    assert_eq!(state.evaluate("mem[sp]", &program), Ok(12));
+    assert_eq!(state.evaluate("mem[sp + OFFSET] + 1", &program), Ok(0x21));
+    assert_eq!(state.evaluate("mem[mem[sp + 4] + 4]", &program), Ok(0x20));
+    assert_eq!(state.evaluate("a0 - 16", &program), Ok(0xFFFF_FFFC));
+
+    assert_eq!(
+        state.evaluate("missing + 1", &program),
+        Err("Symbol missing not defined.".to_string())
+    );
+    assert!(state.evaluate("mem[sp", &program).is_err());
+    assert!(state.evaluate("a0]", &program).is_err());
+    assert!(state.evaluate("", &program).is_err());
+    assert!(state.evaluate("a0 a1", &program).is_err());
+    assert!(state.evaluate("1 << 40", &program).is_err());
+}
+
+#[test]
+fn test_break_on_uart_output() {
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 72\nsb x1, 0xF0(x0)\naddi x1, x0, 105\nsb x1, 0xF0(x0)\nwait: lbu x2, 0xF4(x0)\nandi x2, x2, 8\nbeq x2, x0, wait\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let conditions = BreakConditions {
+        uart_output: true,
+        ..Default::default()
+    };
+    let breakpoints = BTreeSet::new();
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(reason, Some(BreakReason::UartOutput { byte: b'H' }));
+    assert_eq!(state.data_memory.get_serial_output(), b"H");
+
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(reason, Some(BreakReason::UartOutput { byte: b'i' }));
+
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(reason, None, "The ebreak should stop without a reason");
+    assert_eq!(state.data_memory.get_serial_output(), b"Hi");
+}
+
+#[test]
+fn test_call_stack() {
+    let program = crate::assembler::assemble(
+        "addi x2, x0, 0x200\naddi x10, x0, 3\njal x1, count\naddi x5, x0, 1\nebreak\n\
+         count: addi x2, x2, -4\nsw x1, 0(x2)\naddi x10, x10, -1\nbeq x10, x0, done\n\
+         jal x1, count\ndone: lw x1, 0(x2)\naddi x2, x2, 4\njalr x0, x1, 0",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<CVE2Pipeline>::new(&program);
+    let retired = |pc: u32| {
+        let program = &program;
+        move |state: &EmulatorState<CVE2Pipeline>| {
+            state
+                .retiring(program)
+                .is_some_and(|(retiring, _)| retiring == pc)
+        }
+    };
+    let return_addresses = |state: &EmulatorState<CVE2Pipeline>| {
+        state
+            .call_stack
+            .frames()
+            .iter()
+            .map(|frame| frame.return_address)
+            .collect::<Vec<_>>()
+    };
+
+    // The outer call and both recursive calls are waiting at the base case
+    let innermost = state.clock_until(&program, 1000, retired(40));
+    assert_eq!(return_addresses(&innermost), vec![12, 40, 40]);
+    assert_eq!(innermost.call_stack.frames()[0].call_pc, 8);
+
+    // Each return pops its frame, leaving none once back in the caller
+    let returned = innermost.clock_until(&program, 1000, retired(12));
+    assert_eq!(return_addresses(&returned), Vec::<u32>::new());
+}
+
+#[test]
+fn test_does_terminate() {
+    let terminates = |source: &str| {
+        let program = crate::assembler::assemble(source, AssembleOptions::default()).unwrap();
+        does_terminate(&program, EmulatorOption::CVE2, 1000)
+    };
+
+    assert!(terminates("addi x1, x0, 1\nebreak"));
+    assert!(terminates("addi x1, x0, 1\nwfi"));
+    assert!(!terminates("loop: jal x0, loop"));
+    // Running off the end of the program is a fault, not a clean exit
+    assert!(!terminates("addi x1, x0, 1"));
+}
+
+#[test]
+fn test_run_source() {
+    let (registers, memory, output) = run_source(
+        "addi x1, x0, 5\nsw x1, 0x10(x0)\naddi x2, x0, 72\nsb x2, 0xF0(x0)\n\
+         wait: lbu x3, 0xF4(x0)\nandi x3, x3, 8\nbeq x3, x0, wait\nebreak",
+        EmulatorOption::CVE2,
+        1000,
+    );
+    assert_eq!(registers[1], 5);
+    assert_eq!(memory.preview(0x10), 5);
+    assert_eq!(output, b"H");
+}
+
+#[test]
+#[should_panic(expected = "Failed to assemble")]
+fn test_run_source_errors() {
+    run_source("addi x1, x0", EmulatorOption::CVE2, 100);
+}
+
+#[test]
+fn test_load_latency() {
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 7\nsw x1, 0x10(x0)\nlw x2, 0x10(x0)\nadd x3, x2, x2\nlw x4, 0x10(x0)\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+    let run = |latencies: latency::LatencyModel| {
+        EmulatorState::<CVE2Pipeline>::new(&program)
+            .with_latencies(latencies)
+            .clock_until_break(&program, &breakpoints, 1000)
+    };
+
+    let single = run(latency::LatencyModel::default());
+    let slow = run(latency::LatencyModel {
+        load: 3,
+        arithmetic: 1,
+        ..Default::default()
+    });
+
+    // Each load holds the pipeline for 3 more cycles and the add for 1
+    assert_eq!(slow.counters.cycle, single.counters.cycle + 2 * 3 + 1);
+    assert_eq!(slow.counters.instret, single.counters.instret);
+    assert_eq!(slow.x, single.x);
+    assert_eq!(slow.x[3], 14);
+    assert_eq!(slow.x[4], 7);
+}
+
+#[test]
+fn test_data_before_text() {
+    let source = ".data\nvalues: .word 42\n.byte 7\n.text\nmain: la x2, values\nlw x1, 0(x2)\nlbu x3, 4(x2)\nebreak";
+    let layouts = [
+        SectionBases::default(),
+        SectionBases::CLASSIC,
+        SectionBases {
+            text: 0x400,
+            data: 0x200,
+        },
+    ];
+
+    for bases in layouts {
+        let program = crate::assembler::assemble(
+            source,
+            AssembleOptions::with_memory_map(MemoryMap::with_sections(bases)),
+        )
+        .unwrap();
+        assert_eq!(program.get_section_start(Section::Text), bases.text);
+        assert_eq!(program.get_section_start(Section::Data), bases.data);
+
+        // Execution starts at the first instruction rather than the data
+        let state = EmulatorState::<CVE2Pipeline>::new(&program);
+        assert_eq!(state.pipeline.IF_pc, bases.text);
+
+        let state = run_program(&program, EmulatorOption::CVE2, b"", 1000);
+        assert!(state.has_terminated(), "{bases:?}");
+        assert_eq!(state.registers()[1], 42, "{bases:?}");
+        assert_eq!(state.registers()[3], 7, "{bases:?}");
+        assert_eq!(state.memory_io().preview(bases.data), 42, "{bases:?}");
+    }
+}
+
+#[test]
+fn test_written_registers() {
+    let registers = run_source(
+        "addi x5, x0, 1\nsw x5, 0(x0)\nbeq x5, x0, skip\naddi x0, x5, 1\nlw x6, 0(x0)\nskip: ebreak",
+        EmulatorOption::CVE2,
+        1000,
+    )
+    .0;
+    // Stores, branches and writes to x0 leave the mask alone
+    assert_eq!(registers.written, (1 << 5) | (1 << 6));
+}
@@ -0,0 +1,65 @@
+use super::AnyEmulatorState;
+use super::register_file::register_index;
+use crate::assembler::{AssembledProgram, evaluate_expression};
+
+/// Start of a memory operand, which reads the word at the address inside the brackets
+const MEMORY_OPERAND: &str = "mem[";
+
+impl AnyEmulatorState {
+    /// Evaluates an expression like the assembler's, where symbols can also be
+    /// registers by number or ABI name and `mem[address]` reads a word of data
+    /// memory. Negative results wrap around to their 32-bit two's complement.
+    pub fn evaluate(&self, source: &str, program: &AssembledProgram) -> Result<u32, String> {
+        let mut source = source.to_string();
+
+        // Replace memory operands with the words they read, innermost first
+        while let Some(start) = find_memory_operand(&source) {
+            let inner_start = start + MEMORY_OPERAND.len();
+            let inner_end = source[inner_start..]
+                .find(']')
+                .map(|end| inner_start + end)
+                .ok_or(format!("Missing ']' after '{}'.", MEMORY_OPERAND))?;
+            let address = self.evaluate_plain(&source[inner_start..inner_end], program)?;
+            let word = u32::from_le_bytes(
+                [0, 1, 2, 3].map(|i| self.memory_io().preview(address.wrapping_add(i))),
+            );
+            source.replace_range(start..=inner_end, &word.to_string());
+        }
+
+        if source.contains(['[', ']']) {
+            return Err("Brackets can only be used as 'mem[address]'.".into());
+        }
+        self.evaluate_plain(&source, program)
+    }
+
+    /// Evaluates an expression without memory operands
+    fn evaluate_plain(&self, source: &str, program: &AssembledProgram) -> Result<u32, String> {
+        let value = evaluate_expression(source, |name| match register_index(name) {
+            Some(index) => Some(self.registers()[index].into()),
+            None => program
+                .symbol_table
+                .get(name)
+                .map(|address| address.1.clone()),
+        })
+        .map_err(|error| error.error_message)?;
+
+        u32::try_from(&value)
+            .or_else(|_| i32::try_from(&value).map(|value| value as u32))
+            .map_err(|_| format!("{} does not fit in 32 bits.", value))
+    }
+}
+
+/// Where the innermost memory operand starts, which is the last one since any
+/// operand nested in another's address comes after it
+fn find_memory_operand(source: &str) -> Option<usize> {
+    source
+        .rmatch_indices(MEMORY_OPERAND)
+        .map(|(start, _)| start)
+        .find(|&start| {
+            // `mem` must be a whole name rather than the end of a longer symbol
+            !source[..start]
+                .chars()
+                .next_back()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
@@ -141,6 +141,7 @@ impl FiveStageControl {
 }
 
 impl FiveStageControl {
+    #[allow(clippy::unusual_byte_groupings)]
     pub fn for_instr(instr: Instruction) -> Option<FiveStageControl> {
         match instr.opcode() {
             0b0110111 => Some(FiveStageControl::immediate(ALUOp::SELB)), // LUI
@@ -226,7 +227,15 @@ impl FiveStageControl {
                     debug_req: true,
                     ..Default::default()
                 }), // EBREAK
-                _ => Some(FiveStageControl::default()), // CSR (no-op),
+                0b0001_0000_0101_00000_000_00000_1110011 => Some(FiveStageControl::default()), // WFI (halts in WB)
+                // CSRs are read-only, so every CSR instruction just reads the
+                // CSR into rd, leaving it unchanged if the CSR is not implemented
+                _ if instr.funct3() != 0b000 => Some(FiveStageControl {
+                    wb_src: Some(DataDestSel::CSR),
+                    reg_write: true,
+                    ..Default::default()
+                }),
+                _ => None,
             },
             _ => None,
         }
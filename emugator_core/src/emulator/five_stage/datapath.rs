@@ -1,4 +1,5 @@
-use crate::emulator::controller_common::PCSel;
+use crate::emulator::StopReason;
+use crate::emulator::controller_common::{ALUFlags, PCSel};
 
 #[derive(Clone, Copy, Default, Debug, PartialEq)]
 pub struct IfLines {
@@ -24,6 +25,9 @@ pub struct IdLines {
     // register reads
     pub rs1_v: u32,
     pub rs2_v: u32,
+
+    // csr read, `None` for CSRs that are not implemented
+    pub csr_v: Option<u32>,
 }
 
 #[derive(Clone, Copy, Default, Debug, PartialEq)]
@@ -32,7 +36,12 @@ pub struct IdExBuffer {
     pub rs1_v: u32,
     pub rs2_v: u32,
     pub imm: Option<u32>,
+    pub csr_v: Option<u32>,
     pub rd: Option<u8>,
+    // fault raised once this instruction completes
+    pub fault: Option<StopReason>,
+    // a stall sent a no op here instead of an instruction
+    pub bubble: bool,
 }
 
 #[derive(Clone, Copy, Default, Debug, PartialEq)]
@@ -41,6 +50,7 @@ pub struct ExLines {
     pub op_a: Option<u32>,
     pub op_b: Option<u32>,
     pub alu_out: Option<u32>,
+    pub alu_flags: Option<ALUFlags>,
 
     // next pc
     pub jmp_base: Option<u32>,
@@ -53,7 +63,10 @@ pub struct ExMemBuffer {
     pub mem_pc: Option<u32>,
     pub alu_o: Option<u32>,
     pub rs2_v: u32,
+    pub csr_v: Option<u32>,
     pub rd: Option<u8>,
+    pub fault: Option<StopReason>,
+    pub bubble: bool,
 }
 
 #[derive(Clone, Copy, Default, Debug, PartialEq)]
@@ -77,7 +90,10 @@ pub struct MemWbBuffer {
     pub wb_pc: Option<u32>,
     pub alu: Option<u32>,
     pub lsu: Option<u32>,
+    pub csr: Option<u32>,
     pub rd: Option<u8>,
+    pub fault: Option<StopReason>,
+    pub bubble: bool,
 }
 
 #[derive(Clone, Copy, Default, Debug, PartialEq)]
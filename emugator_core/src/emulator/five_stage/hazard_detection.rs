@@ -5,9 +5,15 @@ pub struct HazardDetector {
     // Which stages are blocked if a hazard is detected
     pub hazard_detected: Hazard,
 
+    /// The data hazard stalling the instruction in ID, if any
+    pub data_hazard: Option<DataHazard>,
+
     /// Tracks the number of cycles for each destination register that is a hazard.
     hazard_reg_track: [u8; 32],
 
+    /// Address of the instruction that last wrote each register in `hazard_reg_track`
+    hazard_reg_pc: [u32; 32],
+
     /// Tracks the number of cycles for branch and jump instructions that are hazards.
     /// This is 1 cycles of stopping, then 1 cycle of freeing IF, and 1 cycle of freeing ID.
     branch_jump_track: u8,
@@ -20,9 +26,16 @@ pub struct HazardDetector {
 impl HazardDetector {
     /// Process the current instruction in the ID stage and return if there
     /// are any current hazards with running that instruction.
-    pub fn detect_hazards(&mut self, id_inst: &Option<u32>, jump_not_taken: bool) {
+    pub fn detect_hazards(
+        &mut self,
+        id_pc: Option<u32>,
+        id_inst: &Option<u32>,
+        jump_not_taken: bool,
+    ) {
+        self.data_hazard = None;
         let Some(id_inst) = id_inst else {
-            // no id stage yet
+            // no id stage yet, or nothing was fetched, so nothing reads a register
+            self.hazard_detected = Hazard::all_go();
             return;
         };
         let instruction = Instruction::from_raw(*id_inst);
@@ -43,8 +56,13 @@ impl HazardDetector {
         }
 
         // check that neither register being read is a hazard.
-        let instr_def = InstructionDefinition::from_instr(instruction).unwrap();
+        let Some(instr_def) = InstructionDefinition::from_instr(instruction) else {
+            // illegal instructions halt the pipeline without reading registers
+            self.hazard_detected = Hazard::all_go();
+            return;
+        };
         let instr_frmt = instr_def.format;
+        let id_pc = id_pc.unwrap_or_default();
 
         if self.mem_access_track != 0 {
             match self.mem_access_track {
@@ -53,24 +71,34 @@ impl HazardDetector {
                 _ => self.hazard_detected = Hazard::all_go(),
             }
             self.mem_access_track -= 1;
+        // while a jump is in flight, ID still holds the jump itself, so it must not
+        // stall on its own destination (e.g. `jalr x1, x1, 0` after `auipc x1`)
+        } else if self.branch_jump_track != 0 {
+            match self.branch_jump_track {
+                1 => self.hazard_detected = Hazard::allow_up_to_id(),
+                2 => self.hazard_detected = Hazard::allow_if(),
+                3 => self.hazard_detected = Hazard::allow_ex(),
+                _ => self.hazard_detected = Hazard::stop_up_to_ex(),
+            }
         } else if instr_frmt != InstructionFormat::U
             && instr_frmt != InstructionFormat::J
             && self.hazard_reg_track[instruction.rs1() as usize] != 0
         {
             self.hazard_detected = Hazard::stop_up_to_ex();
+            self.data_hazard = Some(DataHazard {
+                producer_pc: self.hazard_reg_pc[instruction.rs1() as usize],
+                consumer_pc: id_pc,
+            });
         } else if (instr_frmt == InstructionFormat::R
             || instr_frmt == InstructionFormat::S
             || instr_frmt == InstructionFormat::B)
             && self.hazard_reg_track[instruction.rs2() as usize] != 0
         {
             self.hazard_detected = Hazard::stop_up_to_ex();
-        } else if self.branch_jump_track != 0 {
-            match self.branch_jump_track {
-                1 => self.hazard_detected = Hazard::allow_up_to_id(),
-                2 => self.hazard_detected = Hazard::allow_if(),
-                3 => self.hazard_detected = Hazard::allow_ex(),
-                _ => self.hazard_detected = Hazard::stop_up_to_ex(),
-            }
+            self.data_hazard = Some(DataHazard {
+                producer_pc: self.hazard_reg_pc[instruction.rs2() as usize],
+                consumer_pc: id_pc,
+            });
 
             // if instruction is a memory operation, lsu will take two stages, so freeze part of the pipeline so controller information isn't overwritten.
         } else if instr_def.opcode == 0b0000011 || instr_frmt == InstructionFormat::S {
@@ -79,6 +107,7 @@ impl HazardDetector {
 
             if instr_def.opcode == 0b0000011 {
                 self.hazard_reg_track[instruction.rd() as usize] = 5;
+                self.hazard_reg_pc[instruction.rd() as usize] = id_pc;
             }
 
             // if JAL, branch instr, or JALR
@@ -93,11 +122,13 @@ impl HazardDetector {
             if instr_frmt != InstructionFormat::B {
                 // must be 4 because register track is decremented at the beginning, and it is only at the start of the fourth cycle the hazard is gone.
                 self.hazard_reg_track[instruction.rd() as usize] = 4;
+                self.hazard_reg_pc[instruction.rd() as usize] = id_pc;
             }
         } else {
             if instr_frmt != InstructionFormat::S {
                 // must be 4 because register track is decremented at the beginning, and it is only at the start of the fourth cycle the hazard is gone.
                 self.hazard_reg_track[instruction.rd() as usize] = 4;
+                self.hazard_reg_pc[instruction.rd() as usize] = id_pc;
             }
             self.hazard_detected = Hazard::all_go();
         }
@@ -110,6 +141,15 @@ impl HazardDetector {
     }
 }
 
+/// A read-after-write hazard between two instructions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DataHazard {
+    /// Address of the instruction writing the register
+    pub producer_pc: u32,
+    /// Address of the instruction in ID waiting to read it
+    pub consumer_pc: u32,
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Hazard {
     pub stop_if: bool,
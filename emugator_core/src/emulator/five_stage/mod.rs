@@ -3,4 +3,5 @@ mod datapath;
 mod hazard_detection;
 mod pipeline;
 
+pub use hazard_detection::DataHazard;
 pub use pipeline::FiveStagePipeline;
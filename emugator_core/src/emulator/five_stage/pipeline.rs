@@ -1,6 +1,7 @@
 use crate::assembler::AssembledProgram;
 use crate::emulator::controller_common::{DataDestSel, OpASel, OpBSel, PCSel};
-use crate::emulator::{PcPos, read_instruction};
+use crate::emulator::counters::Counters;
+use crate::emulator::{PcPos, PendingStore, StageSnapshot, StopReason, decode_fault};
 use crate::emulator::{Pipeline, memory_module::MemoryModule, register_file::RegisterFile};
 use crate::isa::Instruction;
 use crate::{bitmask, bits};
@@ -31,6 +32,8 @@ pub struct FiveStagePipeline {
     pub wb_control: FiveStageControl,
 
     pub hazard_detector: HazardDetector,
+
+    pub stop_reason: Option<StopReason>,
 }
 
 impl Pipeline for FiveStagePipeline {
@@ -39,11 +42,28 @@ impl Pipeline for FiveStagePipeline {
         program: &AssembledProgram,
         registers: &mut RegisterFile,
         data_memory: &mut MemoryModule,
+        counters: &mut Counters,
     ) {
+        // An empty program halts before fetching anything
+        if program.instruction_memory.is_empty() {
+            self.stop_reason = Some(StopReason::NoInstructions);
+            return;
+        }
+
         // Run the registers that had stuff to write in the last cycle
         // (this is done first to represent it taking a clock edge to write)
         // Pass the control buffers along last so these all run like they were in the previous cycle
         self.run_write_register(registers);
+        if let Some(fault) = self.mem_wb.fault {
+            // Everything before the faulting instruction has been written back
+            self.stop_reason = Some(fault);
+            return;
+        }
+        // Loads and stores spend two cycles in write back, so only count the
+        // instruction once it leaves
+        if self.mem_wb.wb_pc.is_some() && self.mem_wb.wb_pc != self.ex_mem.mem_pc {
+            counters.instret += 1;
+        }
         self.run_pipeline_buffers();
         self.run_pc_reg();
         self.run_data_memory(data_memory);
@@ -51,13 +71,14 @@ impl Pipeline for FiveStagePipeline {
 
         // Run the pipeline stages
         self.run_if(program);
-        self.run_id(registers);
+        self.run_id(registers, counters);
         self.run_ex();
         self.run_mem();
         self.run_wb();
 
         // run hazard detection
         self.hazard_detector.detect_hazards(
+            self.if_id.id_pc,
             &self.if_id.id_inst,
             self.ex_lines.alu_out.is_some_and(|x| x == 0) && self.ex_control.jump_cond,
         );
@@ -101,12 +122,64 @@ impl Pipeline for FiveStagePipeline {
 
         pcs
     }
+
+    fn stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason
+    }
+
+    fn pending_store(&self) -> Option<PendingStore> {
+        let mem_lines = &self.mem_lines;
+        if !(mem_lines.data_req_o && mem_lines.data_we_o) {
+            return None;
+        }
+        // The lines were driven by the instruction in the memory stage
+        Some(PendingStore {
+            pc: self.ex_mem.mem_pc?,
+            address: mem_lines.data_addr_o,
+            byte_enable: mem_lines.data_be_o,
+        })
+    }
+
+    fn pipeline_snapshot(&self) -> Vec<StageSnapshot> {
+        let data_address = self
+            .mem_lines
+            .data_req_o
+            .then_some(self.mem_lines.data_addr_o);
+        vec![
+            StageSnapshot::new("IF", Some(self.if_pc), self.if_lines.instr)
+                .signal("next_pc", self.if_lines.next_pc),
+            StageSnapshot::new("ID", self.if_id.id_pc, self.if_id.id_inst)
+                .signal("rs1", Some(self.id_lines.rs1.into()))
+                .signal("rs2", Some(self.id_lines.rs2.into()))
+                .signal("rd", Some(self.id_lines.rd.into()))
+                .signal("imm", self.id_lines.imm)
+                .signal("rs1_v", Some(self.id_lines.rs1_v))
+                .signal("rs2_v", Some(self.id_lines.rs2_v)),
+            StageSnapshot::new("EX", self.id_ex.ex_pc, None)
+                .with_bubble(self.id_ex.bubble)
+                .signal("rd", self.id_ex.rd.map(u32::from))
+                .signal("op_a", self.ex_lines.op_a)
+                .signal("op_b", self.ex_lines.op_b)
+                .signal("alu_out", self.ex_lines.alu_out)
+                .signal("jmp_dst", self.ex_lines.jmp_dst),
+            StageSnapshot::new("MEM", self.ex_mem.mem_pc, None)
+                .with_bubble(self.ex_mem.bubble)
+                .signal("rd", self.ex_mem.rd.map(u32::from))
+                .signal("alu_out", self.ex_mem.alu_o)
+                .signal("mem_addr", data_address)
+                .signal("mem_data", self.mem_lines.mem_data),
+            StageSnapshot::new("WB", self.mem_wb.wb_pc, None)
+                .with_bubble(self.mem_wb.bubble)
+                .signal("rd", self.mem_wb.rd.map(u32::from))
+                .signal("wb_data", self.wb_lines.wb_data),
+        ]
+    }
 }
 
 impl FiveStagePipeline {
     /* ---------------------------- Instruction Fetch --------------------------- */
     fn run_if(&mut self, program: &AssembledProgram) {
-        self.if_lines.instr = read_instruction(&program.instruction_memory, self.if_pc);
+        self.if_lines.instr = program.instruction_at(self.if_pc).map(|instr| instr.raw());
         self.run_pc_mux();
     }
 
@@ -124,9 +197,10 @@ impl FiveStagePipeline {
 
     /* --------------------------- Instruction Decode --------------------------- */
 
-    fn run_id(&mut self, registers: &RegisterFile) {
+    fn run_id(&mut self, registers: &RegisterFile, counters: &Counters) {
         let Some(id_inst) = self.if_id.id_inst else {
-            // no id stage yet
+            // no id stage yet, or nothing was fetched, so send a no op
+            self.id_control = FiveStageControl::default();
             return;
         };
         let instr = Instruction::from_raw(id_inst);
@@ -143,6 +217,9 @@ impl FiveStagePipeline {
         // read from register file
         self.id_lines.rs1_v = registers[self.id_lines.rs1 as usize];
         self.id_lines.rs2_v = registers[self.id_lines.rs2 as usize];
+
+        // read from the CSRs
+        self.id_lines.csr_v = counters.read_csr(instr.csr());
     }
 
     /* --------------------------------- Execute -------------------------------- */
@@ -169,13 +246,13 @@ impl FiveStagePipeline {
     }
 
     fn run_alu(&mut self) {
-        self.ex_lines.alu_out = match (
+        (self.ex_lines.alu_out, self.ex_lines.alu_flags) = match (
             self.ex_control.alu_op,
             self.ex_lines.op_a,
             self.ex_lines.op_b,
         ) {
-            (Some(op), Some(a), Some(b)) => Some(op.apply(a, b)),
-            _ => None,
+            (Some(op), Some(a), Some(b)) => (Some(op.apply(a, b)), op.flags(a, b)),
+            _ => (None, None),
         };
     }
 
@@ -242,6 +319,7 @@ impl FiveStagePipeline {
         self.wb_lines.wb_data = self.wb_control.wb_src.and_then(|s| match s {
             DataDestSel::ALU => self.mem_wb.alu,
             DataDestSel::LSU => self.mem_wb.lsu,
+            DataDestSel::CSR => self.mem_wb.csr,
         });
     }
 
@@ -252,14 +330,20 @@ impl FiveStagePipeline {
             wb_pc: self.ex_mem.mem_pc,
             alu: self.ex_mem.alu_o,
             lsu: self.mem_lines.mem_data,
+            csr: self.ex_mem.csr_v,
             rd: self.ex_mem.rd,
+            fault: self.ex_mem.fault,
+            bubble: self.ex_mem.bubble,
         };
 
         self.ex_mem = ExMemBuffer {
             mem_pc: self.id_ex.ex_pc,
             alu_o: self.ex_lines.alu_out,
             rs2_v: self.id_ex.rs2_v,
+            csr_v: self.id_ex.csr_v,
             rd: self.id_ex.rd,
+            fault: self.id_ex.fault,
+            bubble: self.id_ex.bubble,
         };
 
         if !self.hazard_detector.hazard_detected.stop_ex {
@@ -268,11 +352,20 @@ impl FiveStagePipeline {
                 rs1_v: self.id_lines.rs1_v,
                 rs2_v: self.id_lines.rs2_v,
                 imm: self.id_lines.imm,
+                csr_v: self.id_lines.csr_v,
                 rd: Some(self.id_lines.rd),
+                fault: self
+                    .if_id
+                    .id_pc
+                    .and_then(|pc| decode_fault(pc, self.if_id.id_inst)),
+                bubble: false,
             };
         } else {
             // to stall, clear the ID-EX buffer to send a no op
-            self.id_ex = IdExBuffer::default();
+            self.id_ex = IdExBuffer {
+                bubble: true,
+                ..Default::default()
+            };
         }
 
         if !self.hazard_detector.hazard_detected.stop_id {
@@ -1,9 +1,10 @@
 #![allow(non_snake_case)]
 
-use bimap::BiBTreeMap;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use super::*;
+use crate::assembler::{AssembleOptions, MemoryMap};
+use crate::emulator::test_utils::run_source;
 use crate::isa::{ISA, Instruction, Operands};
 
 // normally used to write to memory map for data during testing
@@ -31,8 +32,12 @@ fn populate_with_offset(instructions: &[Instruction], offset: u32) -> AssembledP
     AssembledProgram {
         instruction_memory,
         initial_data_memory: BTreeMap::new(),
-        source_map: BiBTreeMap::new(),
+        source_map: BTreeMap::new(),
         symbol_table: HashMap::new(),
+        memory_map: MemoryMap::default(),
+        labels: BTreeMap::new(),
+        referenced_symbols: HashSet::new(),
+        warnings: Vec::new(),
     }
 }
 
@@ -2437,3 +2442,685 @@ fn test_AND() {
 
     assert_eq!(state.x[3], 0b1000); // x3 = 8 (0b1100 & 0b1010)
 }
+
+#[test]
+fn test_call_return() {
+    let program = crate::assembler::assemble(
+        "main: addi x10, x0, 3\ncall double\naddi x11, x10, 1\nebreak\n.text 0x2000\ndouble: add x10, x10, x10\njalr x0, x1, 0",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<FiveStagePipeline>::new(&program);
+    let state = state.clock_until_break(&program, &BTreeSet::new(), 1000);
+    // Let the instructions ahead of the ebreak write back
+    let state = (0..3).fold(state, |state, _| state.clock(&program));
+
+    // The return lands on the instruction after the two-instruction call
+    assert_eq!(state.x[1], 12);
+    assert_eq!(state.x[10], 6);
+    assert_eq!(state.x[11], 7);
+}
+
+#[test]
+fn test_performance_counters() {
+    let program = crate::assembler::assemble(
+        "addi x14, x0, 7\nlw x2, 0(x0)\ncsrrs x10, cycle, x0\ncsrrs x11, instret, x0\ncsrrs x12, time, x0\ncsrrs x13, cycleh, x0\ncsrrs x14, 0x123, x0\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<FiveStagePipeline>::new(&program);
+    let state = state.clock_until_break(&program, &BTreeSet::new(), 1000);
+    // Let the instructions ahead of the ebreak write back
+    let state = (0..3).fold(state, |state, _| state.clock(&program));
+
+    // Each CSR is read in decode, when only the first addi has written back
+    assert_eq!(state.x[10], 4);
+    assert_eq!(state.x[11], 1);
+    assert_eq!(state.x[12], 6);
+    assert_eq!(state.x[13], 0);
+    // Reading a CSR that isn't implemented leaves rd unchanged
+    assert_eq!(state.x[14], 7);
+    assert_eq!(state.counters.cycle, 13);
+    assert_eq!(state.counters.time, 13);
+    // The load spends two cycles in write back but retires once
+    assert_eq!(state.counters.instret, 7);
+}
+
+#[test]
+fn test_pipeline_snapshot() {
+    // ADDI x1, x0, 5 followed by ADDI x2, x0, 7
+    let program = populate(&[
+        ISA::ADDI.build(Operands {
+            rd: 1,
+            rs1: 0,
+            imm: 5,
+            ..Default::default()
+        }),
+        ISA::ADDI.build(Operands {
+            rd: 2,
+            rs1: 0,
+            imm: 7,
+            ..Default::default()
+        }),
+    ]);
+
+    let mut state = EmulatorState::<FiveStagePipeline>::new(&program);
+    state = state.clock(&program);
+    state = state.clock(&program);
+
+    let snapshot = state.pipeline.pipeline_snapshot();
+    let names: Vec<_> = snapshot.iter().map(|stage| stage.name).collect();
+    assert_eq!(names, vec!["IF", "ID", "EX", "MEM", "WB"]);
+
+    assert_eq!(snapshot[1].pc, Some(4));
+    assert_eq!(
+        snapshot[1].instruction,
+        program.instruction_at(4).map(|instr| instr.raw())
+    );
+    assert_eq!(snapshot[1].get("rd"), Some(2));
+    assert_eq!(snapshot[2].pc, Some(0));
+    assert_eq!(snapshot[2].get("alu_out"), Some(5));
+    assert_eq!(snapshot[3].pc, None);
+    assert_eq!(
+        AnyEmulatorState::FiveStage(state).pipeline_snapshot(),
+        snapshot
+    );
+}
+
+#[test]
+fn test_pipeline_snapshot_bubbles() {
+    // The explicit no op is a real instruction, while x2 reading x1 stalls
+    let program = crate::assembler::assemble(
+        "addi x0, x0, 0\naddi x1, x0, 5\naddi x2, x1, 1\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let mut state = EmulatorState::<FiveStagePipeline>::new(&program);
+
+    let mut snapshots = vec![state.pipeline.pipeline_snapshot()];
+    for _ in 0..20 {
+        state = state.clock(&program);
+        snapshots.push(state.pipeline.pipeline_snapshot());
+    }
+    assert_eq!(state.x[2], 6);
+
+    // Stalls are bubbles, while an empty pipeline at reset is not
+    assert!(snapshots[0].iter().all(|stage| !stage.bubble));
+    let ex_bubbles: Vec<_> = (0..snapshots.len())
+        .filter(|&cycle| snapshots[cycle][2].bubble)
+        .collect();
+    assert!(!ex_bubbles.is_empty(), "The hazard should stall");
+    for &cycle in &ex_bubbles {
+        assert_eq!(snapshots[cycle][2].pc, None);
+        if cycle + 2 < snapshots.len() {
+            assert!(snapshots[cycle + 1][3].bubble, "Bubbles move into MEM");
+            assert!(snapshots[cycle + 2][4].bubble, "Bubbles move into WB");
+        }
+    }
+
+    // The no op at address 0 flows through as an instruction
+    for stage in 2..5 {
+        let nop = snapshots.iter().find(|s| s[stage].pc == Some(0)).unwrap();
+        assert!(!nop[stage].bubble);
+    }
+    assert!(
+        snapshots[ex_bubbles[0]][2]
+            .to_string()
+            .contains("pc=bubble----")
+    );
+}
+
+#[test]
+fn test_instruction_access_fault() {
+    let program =
+        crate::assembler::assemble("addi x1, x0, 1\naddi x2, x0, 2", AssembleOptions::default())
+            .unwrap();
+    let mut state = EmulatorState::<FiveStagePipeline>::new(&program);
+
+    for _ in 0..20 {
+        state = state.clock(&program);
+    }
+
+    assert_eq!(
+        state.pipeline.stop_reason(),
+        Some(StopReason::InstructionAccessFault { pc: 8 })
+    );
+    assert_eq!(state.x[1], 1);
+    assert_eq!(state.x[2], 2, "Earlier instructions should finish first");
+    assert_eq!(
+        state.clock(&program),
+        state,
+        "Halted state should not change"
+    );
+
+    // Instructions after a jump to unmapped memory are never executed
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 1\njal x0, 0x100\naddi x3, x0, 3",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let mut state = EmulatorState::<FiveStagePipeline>::new(&program);
+    for _ in 0..20 {
+        state = state.clock(&program);
+    }
+    assert_eq!(
+        state.pipeline.stop_reason(),
+        Some(StopReason::InstructionAccessFault { pc: 0x100 })
+    );
+    assert_eq!(state.x[3], 0);
+
+    // Fetching past a loop at the end of the program is not a fault
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 1\nloop: addi x1, x1, 1\njal x0, loop",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let mut state = EmulatorState::<FiveStagePipeline>::new(&program);
+    for _ in 0..50 {
+        state = state.clock(&program);
+    }
+    assert_eq!(state.pipeline.stop_reason(), None);
+    assert!(state.x[1] > 5);
+}
+
+#[test]
+fn test_illegal_instruction() {
+    let mut program = crate::assembler::assemble(
+        "addi x1, x0, 1\naddi x2, x0, 2\naddi x3, x0, 3",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    write(
+        &mut program.instruction_memory,
+        4,
+        &0xFFFF_FFFFu32.to_le_bytes(),
+    );
+    let mut state = EmulatorState::<FiveStagePipeline>::new(&program);
+
+    for _ in 0..20 {
+        state = state.clock(&program);
+    }
+
+    let reason = state.pipeline.stop_reason();
+    assert_eq!(
+        reason,
+        Some(StopReason::IllegalInstruction {
+            pc: 4,
+            word: 0xFFFF_FFFF
+        })
+    );
+    assert_eq!(
+        reason.unwrap().to_string(),
+        "Illegal instruction 0xFFFFFFFF at 0x00000004"
+    );
+    assert_eq!(state.x[1], 1);
+    assert_eq!(
+        state.x[3], 0,
+        "Instructions after the illegal one should not run"
+    );
+}
+
+#[test]
+fn test_stack_overflow() {
+    let program = crate::assembler::assemble(
+        "addi x2, x0, 0x40\nloop: addi x2, x2, -4\nsw x1, 0(x2)\njal x0, loop",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+    let conditions = BreakConditions {
+        stack_overflow: true,
+        ..Default::default()
+    };
+    let state = EmulatorState::<FiveStagePipeline>::new(&program);
+
+    // The stack grows down until it overwrites the instructions at 0x0-0xF
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(
+        reason,
+        Some(BreakReason::StackOverflow {
+            pc: 8,
+            address: 0xC
+        })
+    );
+    assert_eq!(state.data_memory.preview(0xC), 0);
+
+    // Stores through other registers, or to the rest of memory, are not checked
+    let program = crate::assembler::assemble(
+        "addi x3, x0, 4\nsw x1, 0(x3)\nsb x1, 0x40(x2)\naddi x3, x0, 0\naddi x3, x0, 0\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<FiveStagePipeline>::new(&program);
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(reason, None);
+    assert!(state.data_memory.ram().contains_key(&0x40));
+}
+
+#[test]
+fn test_text_writes() {
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 0x13\nsw x1, 8(x0)\naddi x2, x0, 1\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+    let conditions = BreakConditions {
+        text_writes: true,
+        ..Default::default()
+    };
+    let state = EmulatorState::<FiveStagePipeline>::new(&program);
+
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(reason, Some(BreakReason::TextWrite { pc: 4, address: 8 }));
+
+    // The store lands in data memory and the program still runs to its end
+    let state = state.clock_until_break(&program, &breakpoints, 1000);
+    assert_eq!(state.data_memory.preview(8), 0x13);
+    assert!(state.has_terminated());
+
+    // Without the break condition, the store is still reported
+    let state = EmulatorState::<FiveStagePipeline>::new(&program).clock_until_break(
+        &program,
+        &breakpoints,
+        1000,
+    );
+    assert!(state.has_terminated());
+    assert_eq!(
+        state.data_memory.text_writes(),
+        &BTreeSet::from([8, 9, 10, 11])
+    );
+}
+
+#[test]
+fn test_break_on_mnemonic() {
+    let program = crate::assembler::assemble(
+        "jal x1, func\naddi x2, x0, 2\nebreak\nfunc: addi x3, x0, 3\njalr x0, x1, 0",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+    let conditions = BreakConditions {
+        mnemonic: Some("JALR"),
+        ..Default::default()
+    };
+    let state = EmulatorState::<FiveStagePipeline>::new(&program);
+
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(
+        reason,
+        Some(BreakReason::Mnemonic {
+            pc: 16,
+            mnemonic: "JALR"
+        })
+    );
+    assert_eq!(state.x[3], 3);
+
+    // Continuing runs past the same jalr to the end of the program
+    let (state, reason) = state.clock_until_break_on(&program, &breakpoints, conditions, 1000);
+    assert_eq!(reason, None);
+    assert!(state.has_terminated());
+}
+
+#[test]
+fn test_clock_until_return() {
+    let program = crate::assembler::assemble(
+        "addi x2, x0, 0x200\naddi x10, x0, 3\njal x1, count\naddi x5, x0, 1\nebreak\n\
+         count: addi x2, x2, -4\nsw x1, 0(x2)\naddi x10, x10, -1\nbeq x10, x0, done\n\
+         jal x1, count\ndone: lw x1, 0(x2)\naddi x2, x2, 4\njalr x0, x1, 0",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+    let state = EmulatorState::<FiveStagePipeline>::new(&program);
+    let entered = |state: &EmulatorState<FiveStagePipeline>| state.pipeline.id_pc() == Some(20);
+
+    // From the outermost call, every recursive call is stepped over
+    let outer = state.clock_until(&program, 100, entered);
+    let returned = outer.clock_until_return(&program, &breakpoints, 1000);
+    assert_eq!(returned.pipeline.id_pc(), Some(12));
+    assert_eq!(returned.x[10], 0);
+
+    // From the innermost call, stepping out lands back in its caller
+    let mut inner = outer.clone();
+    for _ in 0..2 {
+        inner = inner.clock_until_next_instruction(&program, 100);
+        inner = inner.clock_until(&program, 100, entered);
+    }
+    let returned = inner.clock_until_return(&program, &breakpoints, 1000);
+    assert_eq!(returned.pipeline.id_pc(), Some(40));
+    assert_eq!(returned.x[10], 0);
+
+    // Stepping out from the return itself goes straight to the caller
+    let at_return = inner.clock_until(&program, 100, |s| s.pipeline.id_pc() == Some(48));
+    let returned = at_return.clock_until_return(&program, &breakpoints, 1000);
+    assert_eq!(returned.pipeline.id_pc(), Some(40));
+
+    // Breakpoints inside the function still stop it early
+    let breakpoints = BTreeSet::from([program.source_map[&28]]);
+    let stopped = outer.clock_until_return(&program, &breakpoints, 1000);
+    assert_eq!(stopped.pipeline.id_pc(), Some(28));
+}
+
+#[test]
+fn test_clock_until_return_past_flushed_call() {
+    // The jal after the taken beq is fetched but flushed, so it must not count
+    // as a call
+    let program = crate::assembler::assemble(
+        "jal x1, func\naddi x5, x0, 1\nebreak\n\
+         func: beq x0, x0, skip\njal x1, other\nskip: addi x6, x0, 1\njalr x0, x1, 0\n\
+         other: jalr x0, x1, 0",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+    let state = EmulatorState::<FiveStagePipeline>::new(&program);
+    let entered = state.clock_until(&program, 100, |s| s.pipeline.id_pc() == Some(12));
+
+    let returned = entered.clock_until_return(&program, &breakpoints, 1000);
+    assert_eq!(returned.pipeline.id_pc(), Some(4));
+    assert_eq!(returned.x[6], 1);
+    assert!(returned.call_stack.frames().is_empty());
+}
+
+#[test]
+fn test_wait_for_interrupt() {
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 1\nwfi\naddi x2, x0, 2\njal x0, 0",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(program.disassemble(4).as_deref(), Some("wfi"));
+    let state = EmulatorState::<FiveStagePipeline>::new(&program);
+
+    // Until Break stops on the halt without needing a breakpoint
+    let state = state.clock_until_break(&program, &BTreeSet::new(), 100);
+
+    let reason = state.pipeline.stop_reason();
+    assert_eq!(reason, Some(StopReason::WaitForInterrupt { pc: 4 }));
+    assert_eq!(
+        reason.unwrap().to_string(),
+        "Waiting for an interrupt at 0x00000004"
+    );
+    assert_eq!(state.x[1], 1);
+    assert_eq!(state.x[2], 0, "Instructions after wfi should not run");
+}
+
+#[test]
+fn test_data_hazard_pcs() {
+    let mut program = crate::assembler::assemble(
+        "addi x1, x0, 4\nlw x2, 0(x1)\nadd x3, x2, x2\naddi x4, x0, 1\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let mut state = AnyEmulatorState::FiveStage(EmulatorState::<FiveStagePipeline>::new(&program));
+
+    let mut hazards = Vec::new();
+    for _ in 0..20 {
+        state = state.clock(&mut program);
+        if let Some(hazard) = state.data_hazard() {
+            hazards.push((hazard.producer_pc, hazard.consumer_pc));
+        }
+    }
+
+    // The load stalls on the addi writing its base, then the add on the load
+    assert!(hazards.contains(&(0, 4)));
+    assert!(hazards.contains(&(4, 8)));
+    assert!(
+        hazards
+            .iter()
+            .all(|&hazard| hazard == (0, 4) || hazard == (4, 8))
+    );
+}
+
+#[test]
+fn test_load_into_load() {
+    let source = "addi x2, x0, 4\nlw x1, 0(x2)\nlw x3, 0(x1)\nebreak\n\
+                  .data\n.word 0\n.word 12\n.word 0\n.word 0x1234";
+    let mut program = crate::assembler::assemble(source, AssembleOptions::default()).unwrap();
+    let mut state = AnyEmulatorState::FiveStage(EmulatorState::<FiveStagePipeline>::new(&program));
+
+    let mut hazards = Vec::new();
+    for _ in 0..20 {
+        state = state.clock(&mut program);
+        if let Some(hazard) = state.data_hazard() {
+            hazards.push((hazard.producer_pc, hazard.consumer_pc));
+        }
+    }
+
+    // The second load stalls until the first has loaded its base
+    assert!(hazards.contains(&(4, 8)));
+    assert_eq!(state.registers().x[1], 12);
+    assert_eq!(state.registers().x[3], 0x1234);
+
+    // The two-stage pipeline ends with the same registers
+    let registers = run_source(source, EmulatorOption::CVE2, 100).0;
+    assert_eq!(registers.x[1], 12);
+    assert_eq!(registers.x[3], 0x1234);
+}
+
+#[test]
+fn test_call_stack() {
+    let program = crate::assembler::assemble(
+        "addi x2, x0, 0x200\naddi x10, x0, 3\njal x1, count\naddi x5, x0, 1\nebreak\n\
+         count: addi x2, x2, -4\nsw x1, 0(x2)\naddi x10, x10, -1\nbeq x10, x0, done\n\
+         jal x1, count\ndone: lw x1, 0(x2)\naddi x2, x2, 4\njalr x0, x1, 0",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let state = EmulatorState::<FiveStagePipeline>::new(&program);
+    let retired = |pc: u32| {
+        let program = &program;
+        move |state: &EmulatorState<FiveStagePipeline>| {
+            state
+                .retiring(program)
+                .is_some_and(|(retiring, _)| retiring == pc)
+        }
+    };
+    let return_addresses = |state: &EmulatorState<FiveStagePipeline>| {
+        state
+            .call_stack
+            .frames()
+            .iter()
+            .map(|frame| frame.return_address)
+            .collect::<Vec<_>>()
+    };
+
+    // The outer call and both recursive calls are waiting at the base case
+    let innermost = state.clock_until(&program, 1000, retired(40));
+    assert_eq!(return_addresses(&innermost), vec![12, 40, 40]);
+    assert_eq!(innermost.call_stack.frames()[0].call_pc, 8);
+
+    // Each return pops its frame, leaving none once back in the caller
+    let returned = innermost.clock_until(&program, 1000, retired(12));
+    assert_eq!(return_addresses(&returned), Vec::<u32>::new());
+}
+
+#[test]
+fn test_does_terminate() {
+    let terminates = |source: &str| {
+        let program = crate::assembler::assemble(source, AssembleOptions::default()).unwrap();
+        does_terminate(&program, EmulatorOption::FiveStage, 1000)
+    };
+
+    assert!(terminates("addi x1, x0, 1\nebreak"));
+    assert!(terminates("addi x1, x0, 1\nwfi"));
+    assert!(!terminates("loop: jal x0, loop"));
+    // Running off the end of the program is a fault, not a clean exit
+    assert!(!terminates("addi x1, x0, 1"));
+}
+
+#[test]
+fn test_load_latency() {
+    let program = crate::assembler::assemble(
+        "addi x1, x0, 7\nsw x1, 0x10(x0)\nlw x2, 0x10(x0)\nadd x3, x2, x2\nebreak",
+        AssembleOptions::default(),
+    )
+    .unwrap();
+    let breakpoints = BTreeSet::new();
+    let run = |latencies: latency::LatencyModel| {
+        EmulatorState::<FiveStagePipeline>::new(&program)
+            .with_latencies(latencies)
+            .clock_until_break(&program, &breakpoints, 1000)
+    };
+
+    let single = run(latency::LatencyModel::default());
+    let slow = run(latency::LatencyModel {
+        load: 2,
+        store: 1,
+        ..Default::default()
+    });
+
+    // The load holds the pipeline for 2 more cycles once it reaches write
+    // back and the store for 1, with the results unchanged
+    assert_eq!(slow.counters.cycle, single.counters.cycle + 2 + 1);
+    assert_eq!(slow.x, single.x);
+    assert_eq!(slow.x[2], 7);
+    assert_eq!(slow.data_memory.preview(0x10), 7);
+}
+
+#[test]
+fn test_data_before_text() {
+    let (registers, memory, _) = run_source(
+        ".data 0x200\nvalues: .word 42\n.byte 7\n.text 0x400\n\
+         main: la x2, values\nlw x1, 0(x2)\nlbu x3, 4(x2)\naddi x0, x0, 0\naddi x0, x0, 0\naddi x0, x0, 0\nebreak",
+        EmulatorOption::FiveStage,
+        1000,
+    );
+    assert_eq!(registers[2], 0x200);
+    assert_eq!(registers[1], 42);
+    assert_eq!(registers[3], 7);
+    assert_eq!(memory.preview(0x200), 42);
+}
+
+/// How an ALU operation's result shows up in the registers
+#[derive(Clone, Copy)]
+enum ALUResult {
+    /// `op x3, x1, x2` writes the result to x3
+    Register,
+    /// `op x1, x2, taken` branches when the result is 1
+    Branch,
+    /// `op x3, b` writes the result of the upper immediate b to x3
+    Upper,
+}
+
+#[test]
+fn test_alu_op_coverage() {
+    use super::controller_common::ALUOp;
+    use super::five_stage::controller::FiveStageControl;
+
+    /// An instruction using an ALU operation, where the result goes and what
+    /// it should compute
+    type ALUCase = (&'static str, ALUOp, ALUResult, fn(u32, u32) -> u32);
+
+    // Each ALU operation with an instruction using it and what it should compute
+    let table: [ALUCase; 15] = [
+        ("add", ALUOp::ADD, ALUResult::Register, |a, b| {
+            a.wrapping_add(b)
+        }),
+        ("sub", ALUOp::SUB, ALUResult::Register, |a, b| {
+            a.wrapping_sub(b)
+        }),
+        ("xor", ALUOp::XOR, ALUResult::Register, |a, b| a ^ b),
+        ("or", ALUOp::OR, ALUResult::Register, |a, b| a | b),
+        ("and", ALUOp::AND, ALUResult::Register, |a, b| a & b),
+        ("sll", ALUOp::SLL, ALUResult::Register, |a, b| {
+            a.wrapping_shl(b)
+        }),
+        ("srl", ALUOp::SRL, ALUResult::Register, |a, b| {
+            a.wrapping_shr(b)
+        }),
+        ("sra", ALUOp::SRA, ALUResult::Register, |a, b| {
+            (a as i32).wrapping_shr(b) as u32
+        }),
+        ("beq", ALUOp::EQ, ALUResult::Branch, |a, b| (a == b) as u32),
+        ("bne", ALUOp::NEQ, ALUResult::Branch, |a, b| (a != b) as u32),
+        ("blt", ALUOp::LT, ALUResult::Branch, |a, b| {
+            ((a as i32) < (b as i32)) as u32
+        }),
+        ("bge", ALUOp::GE, ALUResult::Branch, |a, b| {
+            ((a as i32) >= (b as i32)) as u32
+        }),
+        ("bltu", ALUOp::LTU, ALUResult::Branch, |a, b| (a < b) as u32),
+        ("bgeu", ALUOp::GEU, ALUResult::Branch, |a, b| {
+            (a >= b) as u32
+        }),
+        ("lui", ALUOp::SELB, ALUResult::Upper, |_, b| b << 12),
+    ];
+
+    // Fails to compile when an operation is added, so the table gets a row for it
+    for (_, op, _, _) in &table {
+        match op {
+            ALUOp::ADD
+            | ALUOp::SUB
+            | ALUOp::XOR
+            | ALUOp::OR
+            | ALUOp::AND
+            | ALUOp::SLL
+            | ALUOp::SRL
+            | ALUOp::SRA
+            | ALUOp::EQ
+            | ALUOp::NEQ
+            | ALUOp::LT
+            | ALUOp::GE
+            | ALUOp::LTU
+            | ALUOp::GEU
+            | ALUOp::SELB => {}
+        }
+    }
+
+    let inputs: [(u32, u32); 8] = [
+        (0, 0),
+        (5, 3),
+        (3, 5),
+        (0xFFFFFFFF, 1),
+        (0x80000000, 0x7FFFFFFF),
+        (0x80000000, 31),
+        (0x12345678, 0x87654321),
+        (0xF0F0F0F0, 36), // Shifts only use the low 5 bits
+    ];
+
+    for (mnemonic, op, result, reference) in table {
+        for (a, b) in inputs {
+            let (source, expected) = match result {
+                ALUResult::Register => (
+                    format!("{} x3, x1, x2\naddi x0, x0, 0", mnemonic),
+                    reference(a, b),
+                ),
+                ALUResult::Branch => (
+                    format!(
+                        "{} x1, x2, taken\naddi x3, x0, 0\nebreak\ntaken: addi x3, x0, 1",
+                        mnemonic
+                    ),
+                    reference(a, b),
+                ),
+                ALUResult::Upper => (
+                    format!("{} x3, {}\naddi x0, x0, 0", mnemonic, b & 0xFFFFF),
+                    reference(a, b & 0xFFFFF),
+                ),
+            };
+            let source = format!(
+                ".data 0x200\noperands: .word {}, {}\n.text\n\
+                 la x4, operands\nlw x1, 0(x4)\nlw x2, 4(x4)\n{}\n\
+                 addi x0, x0, 0\naddi x0, x0, 0\naddi x0, x0, 0\nebreak",
+                a, b, source
+            );
+
+            // The instruction after the operand loads is decoded to the operation
+            let program = crate::assembler::assemble(&source, AssembleOptions::default()).unwrap();
+            let address = program.source_map.keys().nth(4).unwrap();
+            let raw = u32::from_le_bytes(std::array::from_fn(|i| {
+                program.instruction_memory[&(address + i as u32)]
+            }));
+            let control = FiveStageControl::for_instr(Instruction::from_raw(raw)).unwrap();
+            assert_eq!(control.alu_op, Some(op), "{}", mnemonic);
+
+            let (registers, _, _) = run_source(&source, EmulatorOption::FiveStage, 100);
+            assert_eq!(
+                registers[3], expected,
+                "{} with 0x{:08x} and 0x{:08x}",
+                mnemonic, a, b
+            );
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use std::collections::BTreeSet;
 
 use crate::{
-    assembler::assemble,
-    emulator::{EmulatorState, Pipeline, cve2::CVE2Pipeline, five_stage::FiveStagePipeline},
+    assembler::{AssembleOptions, assemble},
+    emulator::{
+        EmulatorState, Pipeline, coverage::OpcodeCoverage, cve2::CVE2Pipeline,
+        five_stage::FiveStagePipeline,
+    },
 };
 
 // Random instructions :)
@@ -84,6 +87,7 @@ fn test_fuzz() {
     let mut rng = StdRng::from_seed(seed);
 
     let mut errors_panic = "".to_string();
+    let mut coverage = OpcodeCoverage::default();
 
     for i in 0..10 {
         let mut source = String::from(".text\n");
@@ -99,7 +103,7 @@ fn test_fuzz() {
         )
         .unwrap();
 
-        let Ok(program) = assemble(&source) else {
+        let Ok(program) = assemble(&source, AssembleOptions::default()) else {
             continue;
         };
 
@@ -109,7 +113,7 @@ fn test_fuzz() {
         let breakpoints = BTreeSet::new();
         let cve2_state = cve2_state.clock_until_break(&program, &breakpoints, 1_000_000);
         let five_stage_state =
-            five_stage_state.clock_until_break(&program, &breakpoints, 1_000_000);
+            coverage.clock_until_break(&five_stage_state, &program, &breakpoints, 1_000_000);
 
         // If neither made it to the ebreak then skip this case its probably a bad source
         if !cve2_state.pipeline.requesting_debug() && !five_stage_state.pipeline.requesting_debug()
@@ -180,6 +184,8 @@ fn test_fuzz() {
         }
     }
 
+    println!("{}", coverage.report());
+
     if !errors_panic.is_empty() {
         panic!("Fuzz test failed:\n{}", errors_panic);
     }
@@ -0,0 +1,29 @@
+use crate::isa::ISA;
+
+/// Extra cycles each kind of instruction takes on top of the pipeline's own
+/// timing, such as a load waiting on slow memory. Once an instruction with
+/// extra cycles reaches the pipeline's last stage, the whole pipeline holds
+/// still for that many cycles while the counters and peripherals keep running.
+/// All zero, the default, is the pipelines' usual single-cycle model.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LatencyModel {
+    /// Extra cycles for loads, which also lengthens load-use stalls
+    pub load: u32,
+    /// Extra cycles for stores
+    pub store: u32,
+    /// Extra cycles for register-register arithmetic, where multiplies and
+    /// divides would be if the M extension were emulated
+    pub arithmetic: u32,
+}
+
+impl LatencyModel {
+    /// Extra cycles `isa` holds the pipeline for
+    pub fn extra_cycles(&self, isa: ISA) -> u32 {
+        match isa.definition().opcode {
+            0b0000011 => self.load,
+            0b0100011 => self.store,
+            0b0110011 => self.arithmetic,
+            _ => 0,
+        }
+    }
+}
@@ -1,41 +1,102 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::RangeInclusive;
 
+use super::timer::{TIMER_REGISTER_SIZE, Timer};
 use super::uart::Uart;
+use crate::assembler::MemoryMap;
+
+/// How data memory the program does not define starts out at reset
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MemoryInit {
+    /// Only bytes defined by the program exist. Reading any other byte before
+    /// it is written returns 0 and is recorded as an uninitialized read.
+    #[default]
+    Sparse,
+    /// The memory map's `zeroed_size` bytes from the start of the program's
+    /// data (or from 0 without data) are zeroed, so reading them is always defined.
+    Zeroed,
+}
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct MemoryModule {
     ram: BTreeMap<u32, u8>,
     uart_address: u32,
     uart: Uart,
+    timer_address: u32,
+    timer: Timer,
+    uninitialized_reads: BTreeSet<u32>,
+    access_counts: BTreeMap<u32, u32>,
+    /// Addresses of the program's instructions, which are read-only
+    text: Option<RangeInclusive<u32>>,
+    text_writes: BTreeSet<u32>,
 }
 
 impl MemoryModule {
-    pub fn new(initial: &BTreeMap<u32, u8>, uart_address: u32) -> Self {
-        let ram = initial.clone();
+    pub fn new(initial: &BTreeMap<u32, u8>, memory_map: &MemoryMap, init: MemoryInit) -> Self {
+        let mut ram = initial.clone();
         let uart = Uart::default();
 
+        if init == MemoryInit::Zeroed {
+            let start = initial.keys().next().copied().unwrap_or(0);
+            for address in start..start.saturating_add(memory_map.zeroed_size) {
+                ram.entry(address).or_insert(0);
+            }
+        }
+
         // insert uart addresses
         MemoryModule {
             ram,
-            uart_address,
+            uart_address: memory_map.uart,
             uart,
+            timer_address: memory_map.timer,
+            timer: Timer::default(),
+            uninitialized_reads: BTreeSet::new(),
+            access_counts: BTreeMap::new(),
+            text: None,
+            text_writes: BTreeSet::new(),
         }
     }
 
+    /// The same memory, warning about stores to the span of
+    /// `instruction_memory`. Instructions are fetched from the program rather
+    /// than data memory, so such a store only changes data memory and the
+    /// instructions stay read-only.
+    pub fn with_read_only_text(self, instruction_memory: &BTreeMap<u32, u8>) -> Self {
+        let text = instruction_memory
+            .first_key_value()
+            .zip(instruction_memory.last_key_value())
+            .map(|((&first, _), (&last, _))| first..=last);
+        Self { text, ..self }
+    }
+
     pub fn get(&mut self, address: u32) -> u8 {
         if address == self.uart_address {
             self.uart.rx_read()
         } else if address == self.uart_address + 4 {
             self.uart.lsr()
+        } else if let Some(offset) = self.timer_offset(address) {
+            self.timer.read(offset)
+        } else if let Some(&value) = self.ram.get(&address) {
+            value
         } else {
-            *self.ram.get(&address).unwrap_or(&0)
+            self.uninitialized_reads.insert(address);
+            0
         }
     }
 
     pub fn set(&mut self, address: u32, value: u8) {
         if address == self.uart_address {
             self.uart.tx_write(value);
+        } else if let Some(offset) = self.timer_offset(address) {
+            self.timer.write(offset, value);
         } else {
+            if self
+                .text
+                .as_ref()
+                .is_some_and(|text| text.contains(&address))
+            {
+                self.text_writes.insert(address);
+            }
             self.ram.insert(address, value);
         }
     }
@@ -46,11 +107,19 @@ impl MemoryModule {
             self.uart.rx_peek()
         } else if address == self.uart_address + 4 {
             self.uart.lsr()
+        } else if let Some(offset) = self.timer_offset(address) {
+            self.timer.read(offset)
         } else {
             *self.ram.get(&address).unwrap_or(&0)
         }
     }
 
+    /// Offset into the timer's registers if `address` falls on one of them
+    fn timer_offset(&self, address: u32) -> Option<u32> {
+        let offset = address.wrapping_sub(self.timer_address);
+        (offset < 2 * TIMER_REGISTER_SIZE).then_some(offset)
+    }
+
     pub fn len(&self) -> usize {
         self.ram.len()
     }
@@ -63,6 +132,7 @@ impl MemoryModule {
         let bytes = value.to_le_bytes();
         for i in 0..4 {
             if byte_enable[i] {
+                self.record_access(address + i as u32);
                 self.set(address + i as u32, bytes[i]);
             }
         }
@@ -72,16 +142,25 @@ impl MemoryModule {
         let mut bytes = [0; 4];
         for i in 0..4 {
             if byte_enable[i] {
+                self.record_access(address + i as u32);
                 bytes[i] = self.get(address + i as u32);
             }
         }
         u32::from_le_bytes(bytes)
     }
 
+    fn record_access(&mut self, address: u32) {
+        *self.access_counts.entry(address).or_insert(0) += 1;
+    }
+
     pub fn set_serial_input(&mut self, data: &[u8]) {
         self.uart.set_input(data);
     }
 
+    pub fn push_serial_input(&mut self, data: &[u8]) {
+        self.uart.push_input(data);
+    }
+
     pub fn get_serial_input(&self) -> &[u8] {
         self.uart.get_input()
     }
@@ -94,15 +173,55 @@ impl MemoryModule {
         self.uart.get_output()
     }
 
+    /// Sets the number of cycles the UART takes to transmit or receive a byte
+    pub fn set_serial_delay(&mut self, cycles: u32) {
+        self.uart.uart_cycle_count = cycles;
+    }
+
+    pub fn get_serial_delay(&self) -> u32 {
+        self.uart.uart_cycle_count
+    }
+
     pub fn clock(&mut self) {
         self.uart = self.uart.clock();
+        self.timer = self.timer.clock();
+    }
+
+    /// The number of cycles counted by the timer's `mtime` register
+    pub fn mtime(&self) -> u64 {
+        self.timer.mtime
+    }
+
+    pub(crate) fn clear_mtime(&mut self) {
+        self.timer.mtime = 0;
+    }
+
+    /// Forgets which bytes were accessed, keeping their values
+    pub(crate) fn clear_access_history(&mut self) {
+        self.uninitialized_reads.clear();
+        self.access_counts.clear();
     }
 
-    #[cfg(test)]
     pub fn ram(&self) -> &BTreeMap<u32, u8> {
         &self.ram
     }
 
+    /// Addresses read before the program or a store defined them
+    pub fn uninitialized_reads(&self) -> &BTreeSet<u32> {
+        &self.uninitialized_reads
+    }
+
+    /// Addresses of the program's instructions that a store wrote to, which
+    /// changed data memory but not the instructions
+    pub fn text_writes(&self) -> &BTreeSet<u32> {
+        &self.text_writes
+    }
+
+    /// Number of times each byte was read or written by a load or store
+    pub fn access_counts(&self) -> &BTreeMap<u32, u32> {
+        &self.access_counts
+    }
+
     #[cfg(test)]
     pub fn uart(&self) -> &Uart {
         &self.uart
@@ -115,9 +234,18 @@ mod tests {
 
     use super::*;
 
+    /// A map with the UART and timer out of the way of the addresses tested
+    fn memory_map() -> MemoryMap {
+        MemoryMap {
+            uart: 0x1000,
+            timer: 0x1008,
+            ..MemoryMap::default()
+        }
+    }
+
     #[test]
     fn test_memory_mapped_io() {
-        let mut memory = MemoryModule::new(&BTreeMap::new(), 0x1000);
+        let mut memory = MemoryModule::new(&BTreeMap::new(), &memory_map(), MemoryInit::Sparse);
         memory.set_serial_input(b"Hello");
 
         // Simulate a write to the UART data register
@@ -151,4 +279,107 @@ mod tests {
         assert_eq!(memory.get_serial_input(), b"Hello");
         assert_eq!(memory.get_serial_cursor(), 3);
     }
+
+    #[test]
+    fn test_serial_delay() {
+        let mut memory = MemoryModule::new(&BTreeMap::new(), &memory_map(), MemoryInit::Sparse);
+        memory.set_serial_delay(5);
+        assert_eq!(memory.get_serial_delay(), 5);
+
+        // The first byte is shifted out immediately, the second waits for the delay
+        memory.set(0x1000, 1);
+        memory.clock();
+        memory.set(0x1000, 2);
+
+        let mut cycles = 0;
+        while memory.get(0x1004) & LSRBitmask::TransmitReady as u8 == 0 {
+            memory.clock();
+            cycles += 1;
+        }
+        assert_eq!(cycles, 6);
+        assert_eq!(memory.get_serial_output(), &[1, 2]);
+    }
+
+    #[test]
+    fn test_timer() {
+        let mut memory = MemoryModule::new(&BTreeMap::new(), &memory_map(), MemoryInit::Sparse);
+        for _ in 0..0x1234 {
+            memory.clock();
+        }
+        assert_eq!(memory.mtime(), 0x1234);
+        assert_eq!(memory.read_word(0x1008, [true; 4]), 0x1234);
+        assert_eq!(memory.read_word(0x100C, [true; 4]), 0);
+        assert_eq!(memory.read_word(0x1010, [true; 4]), u32::MAX);
+
+        // Both registers are writable, one byte at a time
+        memory.write_word(0x1010, 0x5678, [true, true, false, false]);
+        assert_eq!(memory.read_word(0x1010, [true; 4]), 0xFFFF_5678);
+        memory.write_word(0x100C, 1, [true; 4]);
+        memory.clock();
+        assert_eq!(memory.mtime(), 0x1_0000_1235);
+        assert_eq!(memory.preview(0x1008), 0x35);
+        assert!(memory.uninitialized_reads().is_empty());
+        assert!(memory.is_empty());
+
+        // The default timer leaves the bytes after the UART as plain memory
+        let mut memory =
+            MemoryModule::new(&BTreeMap::new(), &MemoryMap::default(), MemoryInit::Sparse);
+        memory.write_word(0xF8, 0x1234, [true; 4]);
+        assert_eq!(memory.read_word(0xF8, [true; 4]), 0x1234);
+        memory.clock();
+        assert_eq!(memory.read_word(0xFFFF_FFF0, [true; 4]), 1);
+    }
+
+    #[test]
+    fn test_uninitialized_reads() {
+        let initial = BTreeMap::from([(0x10, 1)]);
+
+        let mut memory = MemoryModule::new(&initial, &memory_map(), MemoryInit::Sparse);
+        assert_eq!(memory.get(0x10), 1);
+        assert_eq!(memory.get(0x11), 0);
+        memory.set(0x12, 5);
+        assert_eq!(memory.get(0x12), 5);
+        assert_eq!(memory.preview(0x13), 0);
+        assert_eq!(memory.uninitialized_reads(), &BTreeSet::from([0x11]));
+
+        let mut memory = MemoryModule::new(&initial, &memory_map(), MemoryInit::Zeroed);
+        let zeroed_size = memory_map().zeroed_size;
+        assert_eq!(memory.len(), zeroed_size as usize);
+        assert_eq!(memory.get(0x10), 1);
+        assert_eq!(memory.get(0x11), 0);
+        assert_eq!(memory.get(0x10 + zeroed_size), 0);
+        assert_eq!(
+            memory.uninitialized_reads(),
+            &BTreeSet::from([0x10 + zeroed_size])
+        );
+    }
+
+    #[test]
+    fn test_read_only_text() {
+        let instruction_memory = BTreeMap::from([(0x0, 0x13), (0x7, 0)]);
+        let mut memory = MemoryModule::new(&BTreeMap::new(), &memory_map(), MemoryInit::Sparse)
+            .with_read_only_text(&instruction_memory);
+
+        memory.write_word(0x4, 0x1234, [true; 4]);
+        memory.write_word(0x8, 0x5678, [true; 4]);
+        assert_eq!(memory.text_writes(), &BTreeSet::from([0x4, 0x5, 0x6, 0x7]));
+        assert_eq!(memory.read_word(0x4, [true; 4]), 0x1234);
+        assert_eq!(memory.read_word(0x8, [true; 4]), 0x5678);
+    }
+
+    #[test]
+    fn test_access_counts() {
+        let mut memory = MemoryModule::new(&BTreeMap::new(), &memory_map(), MemoryInit::Sparse);
+        memory.write_word(0x10, 0x1234, [true, true, false, false]);
+        memory.read_word(0x10, [true; 4]);
+        memory.read_word(0x12, [false, false, true, false]);
+        // Accesses from outside the LSU are not counted
+        memory.get(0x20);
+        memory.preview(0x10);
+
+        assert_eq!(
+            memory.access_counts(),
+            &BTreeMap::from([(0x10, 2), (0x11, 2), (0x12, 1), (0x13, 1), (0x14, 1)])
+        );
+    }
 }
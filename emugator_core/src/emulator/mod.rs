@@ -1,25 +1,40 @@
+pub mod call_stack;
 pub mod controller_common;
+pub mod counters;
 pub mod cve2;
 pub mod five_stage;
+pub mod latency;
 pub mod memory_module;
-mod register_file;
+pub mod register_file;
+pub mod state_diff;
+pub mod timer;
 pub mod uart;
 
+mod expression;
+
+#[cfg(test)]
+mod coverage;
 #[cfg(test)]
 mod cve2_tests;
 #[cfg(test)]
 mod five_stage_tests;
 #[cfg(test)]
 mod fuzz_test;
+#[cfg(test)]
+mod test_utils;
 
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeSet, VecDeque};
 
 use crate::assembler::{AssembledProgram, Section};
+use crate::isa::{ISA, Instruction, InstructionFormat};
 use five_stage::FiveStagePipeline;
-use memory_module::MemoryModule;
+use memory_module::{MemoryInit, MemoryModule};
 
+use call_stack::CallStack;
+use counters::Counters;
 use cve2::CVE2Pipeline;
-use register_file::RegisterFile;
+use latency::LatencyModel;
+use register_file::{RegisterFile, RegisterInit};
 
 #[derive(Clone, Copy, Debug)]
 pub enum EmulatorOption {
@@ -68,10 +83,35 @@ impl AnyEmulatorState {
         AnyEmulatorState::FiveStage(EmulatorState::new(program))
     }
 
-    pub fn new_of_type(program: &AssembledProgram, emulator_type: EmulatorOption) -> Self {
+    pub fn new_of_type(
+        program: &AssembledProgram,
+        emulator_type: EmulatorOption,
+        memory_init: MemoryInit,
+        register_init: RegisterInit,
+    ) -> Self {
         match emulator_type {
-            EmulatorOption::CVE2 => AnyEmulatorState::new_cve2(program),
-            EmulatorOption::FiveStage => AnyEmulatorState::new_five_stage(program),
+            EmulatorOption::CVE2 => AnyEmulatorState::CVE2(EmulatorState::with_init(
+                program,
+                memory_init,
+                register_init,
+            )),
+            EmulatorOption::FiveStage => AnyEmulatorState::FiveStage(EmulatorState::with_init(
+                program,
+                memory_init,
+                register_init,
+            )),
+        }
+    }
+
+    /// The same state, with instructions taking the extra cycles in `latencies`
+    pub fn with_latencies(self, latencies: LatencyModel) -> Self {
+        match self {
+            AnyEmulatorState::CVE2(state) => {
+                AnyEmulatorState::CVE2(state.with_latencies(latencies))
+            }
+            AnyEmulatorState::FiveStage(state) => {
+                AnyEmulatorState::FiveStage(state.with_latencies(latencies))
+            }
         }
     }
 
@@ -106,6 +146,77 @@ impl AnyEmulatorState {
         }
     }
 
+    pub fn clock_until_return(
+        &self,
+        program: &AssembledProgram,
+        breakpoints: &BTreeSet<usize>,
+        max_clocks: usize,
+    ) -> Self {
+        match self {
+            AnyEmulatorState::CVE2(state) => {
+                AnyEmulatorState::CVE2(state.clock_until_return(program, breakpoints, max_clocks))
+            }
+            AnyEmulatorState::FiveStage(state) => AnyEmulatorState::FiveStage(
+                state.clock_until_return(program, breakpoints, max_clocks),
+            ),
+        }
+    }
+
+    pub fn clock_until_break_or_stuck(
+        &self,
+        program: &mut AssembledProgram,
+        breakpoints: &BTreeSet<usize>,
+        max_clocks: usize,
+    ) -> (Self, Option<u32>) {
+        match self {
+            AnyEmulatorState::CVE2(state) => {
+                let (state, stuck_pc) =
+                    state.clock_until_break_or_stuck(program, breakpoints, max_clocks);
+                (AnyEmulatorState::CVE2(state), stuck_pc)
+            }
+            AnyEmulatorState::FiveStage(state) => {
+                let (state, stuck_pc) =
+                    state.clock_until_break_or_stuck(program, breakpoints, max_clocks);
+                (AnyEmulatorState::FiveStage(state), stuck_pc)
+            }
+        }
+    }
+
+    pub fn clock_until_break_on(
+        &self,
+        program: &AssembledProgram,
+        breakpoints: &BTreeSet<usize>,
+        conditions: BreakConditions,
+        max_clocks: usize,
+    ) -> (Self, Option<BreakReason>) {
+        match self {
+            AnyEmulatorState::CVE2(state) => {
+                let (state, reason) =
+                    state.clock_until_break_on(program, breakpoints, conditions, max_clocks);
+                (AnyEmulatorState::CVE2(state), reason)
+            }
+            AnyEmulatorState::FiveStage(state) => {
+                let (state, reason) =
+                    state.clock_until_break_on(program, breakpoints, conditions, max_clocks);
+                (AnyEmulatorState::FiveStage(state), reason)
+            }
+        }
+    }
+
+    pub fn at_breakpoint(&self, program: &AssembledProgram, breakpoints: &BTreeSet<usize>) -> bool {
+        match self {
+            AnyEmulatorState::CVE2(state) => state.at_breakpoint(program, breakpoints),
+            AnyEmulatorState::FiveStage(state) => state.at_breakpoint(program, breakpoints),
+        }
+    }
+
+    pub fn has_terminated(&self) -> bool {
+        match self {
+            AnyEmulatorState::CVE2(state) => state.has_terminated(),
+            AnyEmulatorState::FiveStage(state) => state.has_terminated(),
+        }
+    }
+
     pub fn clock(&self, program: &mut AssembledProgram) -> Self {
         match self {
             AnyEmulatorState::CVE2(state) => AnyEmulatorState::CVE2(state.clock(program)),
@@ -120,6 +231,29 @@ impl AnyEmulatorState {
         }
     }
 
+    pub fn counters(&self) -> &Counters {
+        match self {
+            AnyEmulatorState::CVE2(state) => &state.counters,
+            AnyEmulatorState::FiveStage(state) => &state.counters,
+        }
+    }
+
+    pub fn call_stack(&self) -> &CallStack {
+        match self {
+            AnyEmulatorState::CVE2(state) => &state.call_stack,
+            AnyEmulatorState::FiveStage(state) => &state.call_stack,
+        }
+    }
+
+    /// Zeroes the cycle and retired instruction counters, leaving the
+    /// architectural state as it was
+    pub fn reset_counters(&mut self) {
+        match self {
+            AnyEmulatorState::CVE2(state) => state.counters.reset(),
+            AnyEmulatorState::FiveStage(state) => state.counters.reset(),
+        }
+    }
+
     pub fn memory_io(&self) -> &MemoryModule {
         match self {
             AnyEmulatorState::CVE2(state) => &state.data_memory,
@@ -141,12 +275,73 @@ impl AnyEmulatorState {
         }
     }
 
+    /// The data hazard stalling the five-stage pipeline, if any. The CVE2
+    /// pipeline has no hazard unit, so it never reports one.
+    pub fn data_hazard(&self) -> Option<five_stage::DataHazard> {
+        match self {
+            AnyEmulatorState::CVE2(_) => None,
+            AnyEmulatorState::FiveStage(state) => state.pipeline.hazard_detector.data_hazard,
+        }
+    }
+
     pub fn id_pc(&self) -> Option<u32> {
         match self {
             AnyEmulatorState::CVE2(state) => state.pipeline.id_pc(),
             AnyEmulatorState::FiveStage(state) => state.pipeline.id_pc(),
         }
     }
+
+    pub fn pipeline_snapshot(&self) -> Vec<StageSnapshot> {
+        match self {
+            AnyEmulatorState::CVE2(state) => state.pipeline.pipeline_snapshot(),
+            AnyEmulatorState::FiveStage(state) => state.pipeline.pipeline_snapshot(),
+        }
+    }
+
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        match self {
+            AnyEmulatorState::CVE2(state) => state.pipeline.stop_reason(),
+            AnyEmulatorState::FiveStage(state) => state.pipeline.stop_reason(),
+        }
+    }
+}
+
+/// Number of recent cycles compared against when detecting stuck loops
+const LOOP_DETECTION_WINDOW: usize = 8;
+
+/// Optional conditions that stop running until a breakpoint early
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BreakConditions {
+    /// Stop once the program is stuck in a loop that can never make progress.
+    /// Programs that deliberately spin, such as polling loops, count as stuck.
+    pub stuck_loops: bool,
+    /// Stop as soon as a byte is transmitted over the UART
+    pub uart_output: bool,
+    /// Stop when a store through `sp` writes into the program's instructions
+    /// or defined data, as happens when the stack grows too large
+    pub stack_overflow: bool,
+    /// Stop when any store writes to the address of one of the program's
+    /// instructions. Instruction and data memory are separate, so the store
+    /// only changes data memory and is recorded in its `text_writes` either way.
+    pub text_writes: bool,
+    /// Stop when an instruction with this mnemonic, as named by its
+    /// `InstructionDefinition`, reaches the pipeline's last stage
+    pub mnemonic: Option<&'static str>,
+}
+
+/// Which of the `BreakConditions` stopped the emulator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakReason {
+    /// Stuck in a loop, with the PC in decode when it was detected
+    StuckLoop { pc: Option<u32> },
+    /// Transmitted `byte` over the UART
+    UartOutput { byte: u8 },
+    /// The store at `pc` wrote through `sp` into the program at `address`
+    StackOverflow { pc: u32, address: u32 },
+    /// The store at `pc` wrote to `address`, which holds an instruction
+    TextWrite { pc: u32, address: u32 },
+    /// The instruction at `pc` with the chosen mnemonic is retiring
+    Mnemonic { pc: u32, mnemonic: &'static str },
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -154,29 +349,68 @@ pub struct EmulatorState<P: Pipeline> {
     pub x: RegisterFile,
     pub data_memory: MemoryModule,
     pub pipeline: P,
+    pub counters: Counters,
+    pub call_stack: CallStack,
+    pub latencies: LatencyModel,
+    /// Cycles left holding the pipeline for the extra latency of the
+    /// instruction in its last stage
+    pub stall_cycles: u32,
 }
 
-impl<P: Pipeline + Clone + Default> EmulatorState<P> {
+impl<P: Pipeline + Clone + Default + PartialEq> EmulatorState<P> {
     pub fn new(program: &AssembledProgram) -> Self {
+        Self::with_memory_init(program, MemoryInit::default())
+    }
+
+    /// Creates the state at reset, filling data memory according to `memory_init`
+    pub fn with_memory_init(program: &AssembledProgram, memory_init: MemoryInit) -> Self {
+        Self::with_init(program, memory_init, RegisterInit::default())
+    }
+
+    /// Creates the state at reset, filling data memory according to
+    /// `memory_init` and the registers according to `register_init`
+    pub fn with_init(
+        program: &AssembledProgram,
+        memory_init: MemoryInit,
+        register_init: RegisterInit,
+    ) -> Self {
         let mut pipeline = P::default();
-        let data_memory = MemoryModule::new(&program.initial_data_memory, 0xF0);
+        let data_memory = MemoryModule::new(
+            &program.initial_data_memory,
+            &program.memory_map,
+            memory_init,
+        )
+        .with_read_only_text(&program.instruction_memory);
 
         // set starting address to start
         let start_addr = program.get_section_start(Section::Text);
         pipeline.set_if_pc(start_addr, program);
 
         EmulatorState {
-            x: RegisterFile::default(),
+            x: RegisterFile::new(register_init),
             data_memory,
             pipeline,
+            counters: Counters::default(),
+            call_stack: CallStack::default(),
+            latencies: LatencyModel::default(),
+            stall_cycles: 0,
         }
     }
 
+    /// The same state, with instructions taking the extra cycles in `latencies`
+    pub fn with_latencies(self, latencies: LatencyModel) -> Self {
+        Self { latencies, ..self }
+    }
+
     pub fn into_five_stage(self) -> EmulatorState<FiveStagePipeline> {
         EmulatorState {
             x: self.x,
             data_memory: self.data_memory,
             pipeline: FiveStagePipeline::default(),
+            counters: self.counters,
+            call_stack: self.call_stack,
+            latencies: self.latencies,
+            stall_cycles: 0,
         }
     }
 
@@ -189,7 +423,7 @@ impl<P: Pipeline + Clone + Default> EmulatorState<P> {
         let mut state = self.clone();
         let mut num_cycles = 0;
         let old_id_pc = state.pipeline.id_pc();
-        while state.pipeline.id_pc() == old_id_pc {
+        while state.pipeline.id_pc() == old_id_pc && state.pipeline.stop_reason().is_none() {
             state = state.clock(program);
 
             num_cycles += 1;
@@ -200,32 +434,171 @@ impl<P: Pipeline + Clone + Default> EmulatorState<P> {
         state
     }
 
+    /// Clocks until `stop` holds for the new state, the pipeline halts, or
+    /// `max_clocks` cycles pass
+    pub fn clock_until(
+        &self,
+        program: &AssembledProgram,
+        max_clocks: usize,
+        mut stop: impl FnMut(&Self) -> bool,
+    ) -> Self {
+        let mut state = self.clone();
+        for _ in 0..max_clocks {
+            state = state.clock(program);
+            if stop(&state) || state.pipeline.stop_reason().is_some() {
+                break;
+            }
+        }
+        state
+    }
+
+    /// Steps out of the current function, running until it returns to its
+    /// caller. The function is the innermost frame of the call stack once
+    /// every instruction older than the one in decode has retired. Calls and
+    /// returns only count as they retire, so ones flushed from the pipeline by
+    /// a taken branch are ignored, and nested and recursive calls are stepped
+    /// over. Stops early at breakpoints.
+    pub fn clock_until_return(
+        &self,
+        program: &AssembledProgram,
+        breakpoints: &BTreeSet<usize>,
+        max_clocks: usize,
+    ) -> Self {
+        // Instructions between decode and the last stage, oldest first, have
+        // yet to update the call stack
+        let snapshot = self.pipeline.pipeline_snapshot();
+        let mut call_stack = self.call_stack.clone();
+        for stage in snapshot
+            .iter()
+            .rev()
+            .skip(1)
+            .take(snapshot.len().saturating_sub(3))
+        {
+            if let Some(pc) = stage.pc.filter(|_| !stage.bubble)
+                && let Some(instruction) = program.instruction_at(pc)
+            {
+                call_stack.retire(pc, instruction);
+            }
+        }
+        let depth = call_stack.frames().len();
+        let return_address = call_stack.frames().last().map(|frame| frame.return_address);
+
+        let mut id_pc = self.pipeline.id_pc();
+        self.clock_until(program, max_clocks, |state| {
+            if state.pipeline.id_pc().is_none() || state.pipeline.id_pc() == id_pc {
+                return false;
+            }
+            id_pc = state.pipeline.id_pc();
+
+            // Back in the caller once the frame's return has retired
+            let returned = state.call_stack.frames().len() < depth && id_pc == return_address;
+            returned || state.at_breakpoint(program, breakpoints)
+        })
+    }
+
     pub fn clock_until_break(
         &self,
         program: &AssembledProgram,
         breakpoints: &BTreeSet<usize>,
         max_clocks: usize,
     ) -> Self {
+        self.clock_until_break_on(program, breakpoints, BreakConditions::default(), max_clocks)
+            .0
+    }
+
+    /// Same as `clock_until_break`, but also stops once the program is stuck
+    /// in a loop that can never make progress (such as `j .`), returning the
+    /// PC in decode when it was detected.
+    ///
+    /// This is opt-in since programs that deliberately spin, such as polling
+    /// loops, are reported as stuck.
+    pub fn clock_until_break_or_stuck(
+        &self,
+        program: &AssembledProgram,
+        breakpoints: &BTreeSet<usize>,
+        max_clocks: usize,
+    ) -> (Self, Option<u32>) {
+        let conditions = BreakConditions {
+            stuck_loops: true,
+            ..Default::default()
+        };
+        let (state, reason) =
+            self.clock_until_break_on(program, breakpoints, conditions, max_clocks);
+        let stuck_pc = match reason {
+            Some(BreakReason::StuckLoop { pc }) => pc,
+            _ => None,
+        };
+        (state, stuck_pc)
+    }
+
+    /// Same as `clock_until_break`, but also stops on any of the enabled
+    /// `conditions`, returning which one stopped it.
+    pub fn clock_until_break_on(
+        &self,
+        program: &AssembledProgram,
+        breakpoints: &BTreeSet<usize>,
+        conditions: BreakConditions,
+        max_clocks: usize,
+    ) -> (Self, Option<BreakReason>) {
         let mut state = self.clone();
         let mut num_cycles = 0;
+        let mut recent_states = VecDeque::new();
+        let mut output_len = state.data_memory.get_serial_output().len();
 
         loop {
+            let store = state.pipeline.pending_store();
+            let retiring_pc = state.retiring(program).map(|(pc, _)| pc);
             state = state.clock(program);
 
-            let hit_breakpoint = if let Some(id_pc) = state.pipeline.id_pc() {
-                if let Some(line_num) = program.source_map.get_by_left(&id_pc) {
-                    breakpoints.contains(line_num)
-                } else {
-                    false
-                }
-            } else {
-                false
-            };
+            if state.at_breakpoint(program, breakpoints) || state.pipeline.stop_reason().is_some() {
+                break;
+            }
 
-            let hit_ebreak = state.pipeline.requesting_debug();
+            if let Some((pc, address)) = store
+                .filter(|_| conditions.stack_overflow)
+                .and_then(|store| stack_overflow(program, store))
+            {
+                return (state, Some(BreakReason::StackOverflow { pc, address }));
+            }
 
-            if hit_ebreak || hit_breakpoint {
-                break;
+            if let Some((pc, address)) = store
+                .filter(|_| conditions.text_writes)
+                .and_then(|store| text_write(program, store))
+            {
+                return (state, Some(BreakReason::TextWrite { pc, address }));
+            }
+
+            // Only instructions newly reaching the last stage count, so
+            // continuing from a match doesn't stop on it again
+            if let Some(mnemonic) = conditions.mnemonic
+                && let Some((pc, isa)) = state.retiring(program)
+                && Some(pc) != retiring_pc
+                && isa.definition()._name == mnemonic
+            {
+                return (state, Some(BreakReason::Mnemonic { pc, mnemonic }));
+            }
+
+            if conditions.uart_output {
+                let output = state.data_memory.get_serial_output();
+                if output.len() > output_len {
+                    let byte = output[output_len];
+                    return (state, Some(BreakReason::UartOutput { byte }));
+                }
+                output_len = output.len();
+            }
+
+            // The emulator is deterministic, so returning to an earlier state
+            // means the same cycles will repeat forever
+            if conditions.stuck_loops {
+                let untimed = state.without_time();
+                if recent_states.contains(&untimed) {
+                    let pc = state.pipeline.id_pc();
+                    return (state, Some(BreakReason::StuckLoop { pc }));
+                }
+                recent_states.push_back(untimed);
+                if recent_states.len() > LOOP_DETECTION_WINDOW {
+                    recent_states.pop_front();
+                }
             }
 
             // max cycles until we can move this to a web worker
@@ -234,27 +607,182 @@ impl<P: Pipeline + Clone + Default> EmulatorState<P> {
                 break;
             }
         }
+        (state, None)
+    }
+
+    /// Copy of the state with the timer's free-running `mtime`, the
+    /// performance counters and the memory access history cleared. They change
+    /// every cycle or every access, so comparing them would hide loops that
+    /// never read them, while loops polling them still change the registers
+    /// they load.
+    fn without_time(&self) -> Self {
+        let mut state = self.clone();
+        state.data_memory.clear_mtime();
+        state.data_memory.clear_access_history();
+        state.counters = Counters::default();
         state
     }
 
+    /// PC and decoded instruction in the pipeline's last stage, where it
+    /// retires. Bubbles and words that don't decode are skipped.
+    pub fn retiring(&self, program: &AssembledProgram) -> Option<(u32, ISA)> {
+        let snapshot = self.pipeline.pipeline_snapshot();
+        let pc = snapshot
+            .last()
+            .filter(|stage| !stage.bubble)
+            .and_then(|stage| stage.pc)?;
+        let isa = program.instruction_at(pc).and_then(ISA::instr_to_isa)?;
+        Some((pc, isa))
+    }
+
+    /// Whether the instruction in decode is on a breakpoint line or an ebreak
+    /// is requesting the debugger
+    pub fn at_breakpoint(&self, program: &AssembledProgram, breakpoints: &BTreeSet<usize>) -> bool {
+        let hit_breakpoint = self
+            .pipeline
+            .id_pc()
+            .and_then(|id_pc| program.source_line_at(id_pc))
+            .is_some_and(|line_num| breakpoints.contains(&line_num));
+
+        hit_breakpoint || self.pipeline.requesting_debug()
+    }
+
+    /// Whether the program stopped by itself, with an `ebreak` in decode or by
+    /// waiting for an interrupt. Faults don't count as terminating.
+    pub fn has_terminated(&self) -> bool {
+        self.pipeline.requesting_debug()
+            || matches!(
+                self.pipeline.stop_reason(),
+                Some(StopReason::WaitForInterrupt { .. })
+            )
+    }
+
     pub fn clock(&self, program: &AssembledProgram) -> Self {
+        if self.pipeline.stop_reason().is_some() {
+            return self.clone();
+        }
+
+        let retiring_pc = self.retiring(program).map(|(pc, _)| pc);
         let mut next_state = self.clone();
-        next_state
-            .pipeline
-            .clock(program, &mut next_state.x, &mut next_state.data_memory);
+        // The pipeline holds still while the retiring instruction's extra
+        // latency runs out
+        if next_state.stall_cycles > 0 {
+            next_state.stall_cycles -= 1;
+        } else {
+            next_state.pipeline.clock(
+                program,
+                &mut next_state.x,
+                &mut next_state.data_memory,
+                &mut next_state.counters,
+            );
+        }
+        next_state.counters.cycle += 1;
         // Clock the memory module
         next_state.data_memory.clock();
+        next_state.counters.time = next_state.data_memory.mtime();
+
+        // Only follow instructions newly reaching the last stage, since some
+        // stay there for several cycles
+        if let Some((pc, isa)) = next_state.retiring(program)
+            && Some(pc) != retiring_pc
+            && let Some(instruction) = program.instruction_at(pc)
+        {
+            next_state.call_stack.retire(pc, instruction);
+            next_state.stall_cycles = next_state.latencies.extra_cycles(isa);
+        }
         next_state
     }
 }
 
+/// The PC and first overwritten address if `store` goes through `sp` and
+/// writes over the program's instructions or defined data
+fn stack_overflow(program: &AssembledProgram, store: PendingStore) -> Option<(u32, u32)> {
+    let instruction = program.instruction_at(store.pc)?;
+    if ISA::instr_to_isa(instruction)?.definition().format != InstructionFormat::S
+        || instruction.rs1() != 2
+    {
+        return None;
+    }
+
+    store
+        .addresses()
+        .find(|address| {
+            program.instruction_memory.contains_key(address)
+                || program.initial_data_memory.contains_key(address)
+        })
+        .map(|address| (store.pc, address))
+}
+
+/// The PC and first address if `store` writes where one of the program's
+/// instructions is, as self-modifying code would
+fn text_write(program: &AssembledProgram, store: PendingStore) -> Option<(u32, u32)> {
+    store
+        .addresses()
+        .find(|address| program.instruction_memory.contains_key(address))
+        .map(|address| (store.pc, address))
+}
+
+/// Runs a program from the start without recording intermediate states, until
+/// it requests the debugger with an `ebreak`, halts, or `max_clocks` cycles pass.
+pub fn run_program(
+    program: &AssembledProgram,
+    emulator_type: EmulatorOption,
+    serial_input: &[u8],
+    max_clocks: usize,
+) -> AnyEmulatorState {
+    let mut state = AnyEmulatorState::new_of_type(
+        program,
+        emulator_type,
+        MemoryInit::default(),
+        RegisterInit::default(),
+    );
+    state.memory_io_mut().set_serial_input(serial_input);
+
+    let breakpoints = BTreeSet::new();
+    match state {
+        AnyEmulatorState::CVE2(state) => {
+            AnyEmulatorState::CVE2(state.clock_until_break(program, &breakpoints, max_clocks))
+        }
+        AnyEmulatorState::FiveStage(state) => {
+            AnyEmulatorState::FiveStage(state.clock_until_break(program, &breakpoints, max_clocks))
+        }
+    }
+}
+
+/// Whether a program run like [`run_program`] reaches an `ebreak` or `wfi`
+/// within `max_clocks` cycles, regardless of what it computes
+pub fn does_terminate(
+    program: &AssembledProgram,
+    emulator_type: EmulatorOption,
+    max_clocks: usize,
+) -> bool {
+    run_program(program, emulator_type, &[], max_clocks).has_terminated()
+}
+
+/// Walks the recorded states backward from just before `position` to the most
+/// recent one stopped at a breakpoint, without re-running the program.
+/// Returns the first state if no breakpoint is hit on the way.
+pub fn reverse_until_break(
+    states: &[AnyEmulatorState],
+    position: usize,
+    program: &AssembledProgram,
+    breakpoints: &BTreeSet<usize>,
+) -> usize {
+    states[..position.min(states.len())]
+        .iter()
+        .rposition(|state| state.at_breakpoint(program, breakpoints))
+        .unwrap_or(0)
+}
+
 pub trait Pipeline: Clone {
-    /// Clock all components in the pipeline by one
+    /// Clock all components in the pipeline by one, counting the instructions
+    /// it retires in `counters`
     fn clock(
         &mut self,
         program: &AssembledProgram,
         registers: &mut RegisterFile,
         data_memory: &mut MemoryModule,
+        counters: &mut Counters,
     );
 
     /// Set the initial address of the instruction fetch stage
@@ -271,6 +799,137 @@ pub trait Pipeline: Clone {
     /// Returns all current PCs in the pipeline
     /// This is used for editor line highlighting
     fn all_pcs(&self) -> Vec<PcPos>;
+
+    /// Describes the contents of each stage, in pipeline order
+    fn pipeline_snapshot(&self) -> Vec<StageSnapshot>;
+
+    /// Why the pipeline has halted, if it has
+    fn stop_reason(&self) -> Option<StopReason>;
+
+    /// The store the next clock writes to data memory, if any
+    fn pending_store(&self) -> Option<PendingStore>;
+}
+
+/// A store requested by the LSU, which reaches data memory on the next clock
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PendingStore {
+    /// Address of the store instruction
+    pub pc: u32,
+    /// Word address sent to data memory
+    pub address: u32,
+    pub byte_enable: [bool; 4],
+}
+
+impl PendingStore {
+    /// Addresses of the bytes the store writes
+    pub fn addresses(&self) -> impl Iterator<Item = u32> {
+        let (address, byte_enable) = (self.address, self.byte_enable);
+        (0..4)
+            .filter(move |&i| byte_enable[i])
+            .map(move |i| address + i as u32)
+    }
+}
+
+/// Reason the emulator stopped executing. Once stopped, clocking the emulator
+/// leaves its state unchanged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    /// Execution reached an address with no instruction in instruction memory
+    InstructionAccessFault { pc: u32 },
+    /// The word at `pc` does not decode to any instruction in the ISA
+    IllegalInstruction { pc: u32, word: u32 },
+    /// Reached a `wfi` at `pc`. Interrupts are not emulated, so nothing could
+    /// ever wake the processor and waiting is treated as a clean halt.
+    WaitForInterrupt { pc: u32 },
+    /// The program has no instructions at all, so there was nothing to fetch
+    NoInstructions,
+}
+
+impl std::fmt::Display for StopReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::InstructionAccessFault { pc } => {
+                write!(
+                    f,
+                    "Instruction access fault: no instruction at 0x{:08X}",
+                    pc
+                )
+            }
+            StopReason::IllegalInstruction { pc, word } => {
+                write!(f, "Illegal instruction 0x{:08X} at 0x{:08X}", word, pc)
+            }
+            StopReason::WaitForInterrupt { pc } => {
+                write!(f, "Waiting for an interrupt at 0x{:08X}", pc)
+            }
+            StopReason::NoInstructions => write!(f, "No instructions to run"),
+        }
+    }
+}
+
+/// Contents of a single pipeline stage, independent of the pipeline it is in
+#[derive(Clone, Debug, PartialEq)]
+pub struct StageSnapshot {
+    pub name: &'static str,
+    pub pc: Option<u32>,
+    /// Encoded instruction, if the stage still holds it
+    pub instruction: Option<u32>,
+    /// Named lines in the stage, `None` while a line is not driven
+    pub signals: Vec<(&'static str, Option<u32>)>,
+    /// Whether the stage holds a no op inserted by a stall rather than an
+    /// instruction from the program
+    pub bubble: bool,
+}
+
+impl StageSnapshot {
+    pub fn new(name: &'static str, pc: Option<u32>, instruction: Option<u32>) -> Self {
+        StageSnapshot {
+            name,
+            pc,
+            instruction,
+            signals: Vec::new(),
+            bubble: false,
+        }
+    }
+
+    pub fn with_bubble(mut self, bubble: bool) -> Self {
+        self.bubble = bubble;
+        self
+    }
+
+    pub fn signal(mut self, name: &'static str, value: Option<u32>) -> Self {
+        self.signals.push((name, value));
+        self
+    }
+
+    /// Value of the named signal, `None` if it is missing or not driven
+    pub fn get(&self, name: &str) -> Option<u32> {
+        self.signals
+            .iter()
+            .find(|(signal, _)| *signal == name)
+            .and_then(|(_, value)| *value)
+    }
+}
+
+impl std::fmt::Display for StageSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:<4}", self.name)?;
+        match self.pc {
+            Some(pc) => write!(f, " pc=0x{:08X}", pc)?,
+            None if self.bubble => write!(f, " pc=bubble----")?,
+            None => write!(f, " pc=----------")?,
+        }
+        match self.instruction {
+            Some(instruction) => write!(f, " inst=0x{:08X}", instruction)?,
+            None => write!(f, " inst=----------")?,
+        }
+        for (name, value) in &self.signals {
+            match value {
+                Some(value) => write!(f, " {}=0x{:X}", name, value)?,
+                None => write!(f, " {}=-", name)?,
+            }
+        }
+        Ok(())
+    }
 }
 
 pub struct PcPos {
@@ -284,21 +943,22 @@ impl PcPos {
     }
 }
 
-fn read_instruction(memory: &BTreeMap<u32, u8>, address: u32) -> Option<u32> {
-    let mut rdata_bytes: [u8; 4] = [0; 4];
-    let success = (0usize..4usize).all(|i| {
-        let addr = address + i as u32;
-        if let Some(byte) = memory.get(&addr).copied() {
-            rdata_bytes[i] = byte;
-            true
-        } else {
-            false
+/// Fault raised when the instruction fetched from `pc` reaches decode, if it
+/// was never fetched or is not a valid instruction. A `wfi` halts the same way.
+fn decode_fault(pc: u32, instruction: Option<u32>) -> Option<StopReason> {
+    match instruction {
+        None => Some(StopReason::InstructionAccessFault { pc }),
+        Some(word)
+            if matches!(
+                ISA::instr_to_isa(Instruction::from_raw(word)),
+                Some(ISA::WFI)
+            ) =>
+        {
+            Some(StopReason::WaitForInterrupt { pc })
         }
-    });
-
-    if success {
-        Some(u32::from_le_bytes(rdata_bytes))
-    } else {
-        None
+        Some(word) if ISA::instr_to_isa(Instruction::from_raw(word)).is_none() => {
+            Some(StopReason::IllegalInstruction { pc, word })
+        }
+        Some(_) => None,
     }
 }
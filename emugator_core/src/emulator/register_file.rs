@@ -1,8 +1,76 @@
 use std::ops::{Index, IndexMut};
 
+/// Value of every register at reset with `RegisterInit::Poison`
+pub const POISON: u32 = 0xDEADBEEF;
+
+/// ABI names of the registers, with both names of `s0/fp`
+pub const ABI_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0/fp", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+/// Index of the register called `name`, either as `x0`-`x31` or by its ABI
+/// name. Only the canonical spelling of each number is accepted, so `x01` and
+/// `x+1` are not registers.
+pub fn register_index(name: &str) -> Option<usize> {
+    if let Some(index) = name.strip_prefix('x').and_then(|number| {
+        number
+            .parse::<usize>()
+            .ok()
+            .filter(|index| index.to_string() == number)
+    }) {
+        return (index < 32).then_some(index);
+    }
+    ABI_NAMES
+        .iter()
+        .position(|names| names.split('/').any(|abi_name| abi_name == name))
+}
+
+/// How registers other than x0 start out at reset
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RegisterInit {
+    #[default]
+    Zeroed,
+    /// Every register holds `POISON`, so reading one before writing it stands out
+    Poison,
+    /// Pseudo-random values, which are the same every time for the same seed
+    Random(u64),
+}
+
 #[derive(Copy, Clone, Default, Debug, PartialEq)]
 pub struct RegisterFile {
     pub x: [u32; 32],
+    /// Bit `i` is set once register `i` has been written since reset. x0 is
+    /// never marked, since writes to it are discarded.
+    pub written: u32,
+}
+
+impl RegisterFile {
+    pub fn new(init: RegisterInit) -> Self {
+        let mut x = [0; 32];
+        match init {
+            RegisterInit::Zeroed => {}
+            RegisterInit::Poison => x[1..].fill(POISON),
+            RegisterInit::Random(seed) => {
+                // SplitMix64, so the values don't depend on an external generator
+                let mut state = seed;
+                for register in &mut x[1..] {
+                    state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+                    let mut z = state;
+                    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                    *register = ((z ^ (z >> 31)) >> 32) as u32;
+                }
+            }
+        }
+        Self { x, written: 0 }
+    }
+
+    /// Whether register `index` has been written since reset
+    pub fn was_written(&self, index: usize) -> bool {
+        self.written & (1 << index) != 0
+    }
 }
 
 impl Index<usize> for RegisterFile {
@@ -16,6 +84,61 @@ impl Index<usize> for RegisterFile {
 impl IndexMut<usize> for RegisterFile {
     fn index_mut(&mut self, index: usize) -> &mut Self::Output {
         self.x[0] = 0;
+        if index != 0 {
+            self.written |= 1 << index;
+        }
         &mut self.x[index]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_init() {
+        assert_eq!(
+            RegisterFile::new(RegisterInit::Zeroed),
+            RegisterFile::default()
+        );
+
+        let poisoned = RegisterFile::new(RegisterInit::Poison);
+        assert_eq!(poisoned[0], 0);
+        assert!((1..32).all(|i| poisoned[i] == POISON));
+
+        let random = RegisterFile::new(RegisterInit::Random(42));
+        assert_eq!(random, RegisterFile::new(RegisterInit::Random(42)));
+        assert_ne!(random, RegisterFile::new(RegisterInit::Random(43)));
+        assert_eq!(random[0], 0);
+        assert_eq!(random.x[0], 0);
+    }
+
+    #[test]
+    fn test_written_registers() {
+        let mut registers = RegisterFile::new(RegisterInit::Poison);
+        assert!((0..32).all(|i| !registers.was_written(i)));
+
+        registers[0] = 5;
+        registers[10] = 0;
+        let _ = registers[11];
+        assert!(!registers.was_written(0));
+        assert!(registers.was_written(10));
+        assert!(!registers.was_written(11));
+        assert_eq!(registers.written, 1 << 10);
+    }
+
+    #[test]
+    fn test_register_index() {
+        assert_eq!(register_index("x0"), Some(0));
+        assert_eq!(register_index("x31"), Some(31));
+        assert_eq!(register_index("x32"), None);
+        assert_eq!(register_index("x01"), None);
+        assert_eq!(register_index("x+1"), None);
+        assert_eq!(register_index("sp"), Some(2));
+        assert_eq!(register_index("s0"), Some(8));
+        assert_eq!(register_index("fp"), Some(8));
+        assert_eq!(register_index("t6"), Some(31));
+        assert_eq!(register_index("s0/fp"), None);
+        assert_eq!(register_index("loop"), None);
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeSet;
+
+use super::AnyEmulatorState;
+
+/// Differences between two emulator states, such as the final states of two
+/// runs of a program. Only values that changed are listed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StateDiff {
+    /// Register number with its value in the first and second state
+    pub registers: Vec<(usize, u32, u32)>,
+    /// Data memory address with its byte in the first and second state
+    pub memory: Vec<(u32, u8, u8)>,
+    /// UART output of the first and second state, if it differs
+    pub uart_output: Option<(String, String)>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.registers.is_empty() && self.memory.is_empty() && self.uart_output.is_none()
+    }
+}
+
+impl AnyEmulatorState {
+    /// Compares the registers, data memory and UART output of two states.
+    /// Memory that was never written reads as zero.
+    pub fn diff(&self, other: &AnyEmulatorState) -> StateDiff {
+        let registers = (0..32)
+            .map(|i| (i, self.registers()[i], other.registers()[i]))
+            .filter(|(_, before, after)| before != after)
+            .collect();
+
+        let (ram, other_ram) = (self.memory_io().ram(), other.memory_io().ram());
+        let addresses: BTreeSet<u32> = ram.keys().chain(other_ram.keys()).copied().collect();
+        let memory = addresses
+            .into_iter()
+            .map(|address| {
+                let before = ram.get(&address).copied().unwrap_or(0);
+                let after = other_ram.get(&address).copied().unwrap_or(0);
+                (address, before, after)
+            })
+            .filter(|(_, before, after)| before != after)
+            .collect();
+
+        let output = self.memory_io().get_serial_output();
+        let other_output = other.memory_io().get_serial_output();
+        let uart_output = (output != other_output).then(|| {
+            (
+                String::from_utf8_lossy(output).into_owned(),
+                String::from_utf8_lossy(other_output).into_owned(),
+            )
+        });
+
+        StateDiff {
+            registers,
+            memory,
+            uart_output,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use super::memory_module::MemoryModule;
+use super::register_file::RegisterFile;
+use super::{EmulatorOption, run_program};
+
+/// Assembles `source` and runs it like [`run_program`] with no serial input,
+/// returning the final registers, data memory and UART output. Panics with the
+/// assembler errors if the source does not assemble.
+pub(super) fn run_source(
+    source: &str,
+    emulator_type: EmulatorOption,
+    max_clocks: usize,
+) -> (RegisterFile, MemoryModule, Vec<u8>) {
+    let program = crate::assembler::assemble(source, crate::assembler::AssembleOptions::default())
+        .unwrap_or_else(|errors| {
+            let messages: Vec<_> = errors
+                .iter()
+                .map(|error| format!("line {}: {}", error.line_number, error.error_message))
+                .collect();
+            panic!("Failed to assemble:\n{}", messages.join("\n"))
+        });
+    let state = run_program(&program, emulator_type, &[], max_clocks);
+    let memory = state.memory_io().clone();
+    let output = memory.get_serial_output().to_vec();
+    (*state.registers(), memory, output)
+}
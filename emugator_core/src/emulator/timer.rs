@@ -0,0 +1,59 @@
+// Machine Timer Module for EmuGator
+
+/// Bytes taken by each of the timer's 64-bit registers
+pub const TIMER_REGISTER_SIZE: u32 = 8;
+
+/// A memory-mapped `mtime`/`mtimecmp` timer. `mtime` counts every clock cycle
+/// and `mtimecmp` holds a deadline. Interrupts are not emulated, so programs
+/// poll `mtime` (or compare it to `mtimecmp`) to wait for time to pass.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timer {
+    pub mtime: u64,
+    pub mtimecmp: u64,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self {
+            mtime: 0,
+            // Like real hardware, the deadline starts out as far away as possible
+            mtimecmp: u64::MAX,
+        }
+    }
+}
+
+impl Timer {
+    /// Reads a byte of the timer, with `mtime` at offsets 0-7 and `mtimecmp`
+    /// at offsets 8-15, both little endian
+    pub fn read(&self, offset: u32) -> u8 {
+        let (register, byte) = self.register(offset);
+        (register >> (byte * 8)) as u8
+    }
+
+    /// Writes a byte of the timer, laid out as in `read`
+    pub fn write(&mut self, offset: u32, value: u8) {
+        let byte = offset % TIMER_REGISTER_SIZE;
+        let register = if offset < TIMER_REGISTER_SIZE {
+            &mut self.mtime
+        } else {
+            &mut self.mtimecmp
+        };
+        *register = (*register & !(0xFF << (byte * 8))) | ((value as u64) << (byte * 8));
+    }
+
+    pub fn clock(&self) -> Self {
+        Self {
+            mtime: self.mtime.wrapping_add(1),
+            mtimecmp: self.mtimecmp,
+        }
+    }
+
+    fn register(&self, offset: u32) -> (u64, u32) {
+        let byte = offset % TIMER_REGISTER_SIZE;
+        if offset < TIMER_REGISTER_SIZE {
+            (self.mtime, byte)
+        } else {
+            (self.mtimecmp, byte)
+        }
+    }
+}
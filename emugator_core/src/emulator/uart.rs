@@ -70,6 +70,12 @@ impl Uart {
         self.input_buffer.extend(data);
     }
 
+    /// Queues more bytes after the input that has not been received yet, so
+    /// input can arrive while the program is running
+    pub fn push_input(&mut self, data: &[u8]) {
+        self.input_buffer.extend(data);
+    }
+
     pub fn get_cursor(&self) -> usize {
         self.rx_cursor
     }
@@ -0,0 +1,38 @@
+/// Cycle counter
+pub const CYCLE: u32 = 0xC00;
+/// Real time counter, shadowing the timer's `mtime`
+pub const TIME: u32 = 0xC01;
+/// Instructions-retired counter
+pub const INSTRET: u32 = 0xC02;
+/// Upper 32 bits of `cycle`
+pub const CYCLEH: u32 = 0xC80;
+/// Upper 32 bits of `time`
+pub const TIMEH: u32 = 0xC81;
+/// Upper 32 bits of `instret`
+pub const INSTRETH: u32 = 0xC82;
+
+/// Names of the CSRs the emulator implements, with their addresses
+pub const CSR_NAMES: [(&str, u32); 6] = [
+    ("cycle", CYCLE),
+    ("time", TIME),
+    ("instret", INSTRET),
+    ("cycleh", CYCLEH),
+    ("timeh", TIMEH),
+    ("instreth", INSTRETH),
+];
+
+/// Address of the CSR with the given name, ignoring case
+pub fn csr_address(name: &str) -> Option<u32> {
+    CSR_NAMES
+        .iter()
+        .find(|(csr, _)| csr.eq_ignore_ascii_case(name))
+        .map(|(_, address)| *address)
+}
+
+/// Name of the CSR at an address, if the emulator implements it
+pub fn csr_name(address: u32) -> Option<&'static str> {
+    CSR_NAMES
+        .iter()
+        .find(|(_, csr)| *csr == address)
+        .map(|(name, _)| *name)
+}
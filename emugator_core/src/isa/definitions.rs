@@ -1,4 +1,4 @@
-use strum::EnumString;
+use strum::{EnumIter, EnumString, IntoEnumIterator};
 
 use super::Instruction;
 
@@ -17,12 +17,36 @@ pub struct InstructionDefinition {
     pub funct3: Option<u8>,
     pub funct7: Option<u8>,
     pub format: InstructionFormat,
+    /// One-line summary of what the instruction does
+    pub description: &'static str,
 }
 
 impl InstructionDefinition {
     pub fn from_instr(instr: Instruction) -> Option<InstructionDefinition> {
         ISA::instr_to_isa(instr).map(|isa| isa.definition())
     }
+
+    /// The operands the assembler expects after the mnemonic, such as
+    /// `rd, imm(rs1)` for loads
+    pub fn operands(&self) -> &'static str {
+        match (self.format, self.opcode) {
+            (InstructionFormat::R, _) => "rd, rs1, rs2",
+            // ECALL, EBREAK, WFI and FENCE take no operands
+            (InstructionFormat::I, 0b1110011) if self.funct3 == Some(0x0) => "",
+            // CSRRWI, CSRRSI and CSRRCI put a 5-bit immediate in rs1
+            (InstructionFormat::I, 0b1110011) if self.funct3.is_some_and(|f| f & 0x4 != 0) => {
+                "rd, csr, uimm"
+            }
+            (InstructionFormat::I, 0b1110011) => "rd, csr, rs1",
+            (InstructionFormat::I, 0b0001111) => "",
+            (InstructionFormat::I, 0b0000011) => "rd, imm(rs1)",
+            (InstructionFormat::I, _) => "rd, rs1, imm",
+            (InstructionFormat::S, _) => "rs2, imm(rs1)",
+            (InstructionFormat::B, _) => "rs1, rs2, address",
+            (InstructionFormat::U, _) => "rd, imm",
+            (InstructionFormat::J, _) => "rd, address",
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -36,7 +60,7 @@ pub enum InstructionFormat {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(EnumString, Debug)]
+#[derive(EnumString, EnumIter, Debug)]
 pub enum ISA {
     ADD,
     SUB,
@@ -74,6 +98,7 @@ pub enum ISA {
     PAUSE,
     ECALL,
     EBREAK,
+    WFI,
     SW,
     SH,
     SB,
@@ -89,11 +114,17 @@ pub enum ISA {
 }
 
 impl ISA {
+    /// Every instruction in the ISA, in the order they are declared
+    pub fn all() -> impl Iterator<Item = ISA> {
+        ISA::iter()
+    }
+
     pub fn definition(&self) -> InstructionDefinition {
         use ISA::*;
         match self {
             ADD => InstructionDefinition {
                 _name: "ADD",
+                description: "Adds rs1 and rs2",
                 format: InstructionFormat::R,
                 opcode: 0b0110011,
                 funct3: Some(0x0),
@@ -101,6 +132,7 @@ impl ISA {
             },
             SUB => InstructionDefinition {
                 _name: "SUB",
+                description: "Subtracts rs2 from rs1",
                 format: InstructionFormat::R,
                 opcode: 0b0110011,
                 funct3: Some(0x0),
@@ -108,6 +140,7 @@ impl ISA {
             },
             SLT => InstructionDefinition {
                 _name: "SLT",
+                description: "Sets rd to 1 if rs1 is less than rs2 as signed numbers, otherwise 0",
                 format: InstructionFormat::R,
                 opcode: 0b0110011,
                 funct3: Some(0x2),
@@ -115,6 +148,7 @@ impl ISA {
             },
             SLTU => InstructionDefinition {
                 _name: "SLTU",
+                description: "Sets rd to 1 if rs1 is less than rs2 as unsigned numbers, otherwise 0",
                 format: InstructionFormat::R,
                 opcode: 0b0110011,
                 funct3: Some(0x3),
@@ -122,6 +156,7 @@ impl ISA {
             },
             AND => InstructionDefinition {
                 _name: "AND",
+                description: "Bitwise AND of rs1 and rs2",
                 format: InstructionFormat::R,
                 opcode: 0b0110011,
                 funct3: Some(0x7),
@@ -129,6 +164,7 @@ impl ISA {
             },
             OR => InstructionDefinition {
                 _name: "OR",
+                description: "Bitwise OR of rs1 and rs2",
                 format: InstructionFormat::R,
                 opcode: 0b0110011,
                 funct3: Some(0x6),
@@ -136,6 +172,7 @@ impl ISA {
             },
             XOR => InstructionDefinition {
                 _name: "XOR",
+                description: "Bitwise XOR of rs1 and rs2",
                 format: InstructionFormat::R,
                 opcode: 0b0110011,
                 funct3: Some(0x4),
@@ -143,6 +180,7 @@ impl ISA {
             },
             SLL => InstructionDefinition {
                 _name: "SLL",
+                description: "Shifts rs1 left by the low 5 bits of rs2",
                 format: InstructionFormat::R,
                 opcode: 0b0110011,
                 funct3: Some(0x1),
@@ -150,6 +188,7 @@ impl ISA {
             },
             SRL => InstructionDefinition {
                 _name: "SRL",
+                description: "Shifts rs1 right by the low 5 bits of rs2, filling with zeros",
                 format: InstructionFormat::R,
                 opcode: 0b0110011,
                 funct3: Some(0x5),
@@ -157,6 +196,7 @@ impl ISA {
             },
             SRA => InstructionDefinition {
                 _name: "SRA",
+                description: "Shifts rs1 right by the low 5 bits of rs2, copying the sign bit",
                 format: InstructionFormat::R,
                 opcode: 0b0110011,
                 funct3: Some(0x5),
@@ -166,6 +206,7 @@ impl ISA {
             // I-type instructions
             ADDI => InstructionDefinition {
                 _name: "ADDI",
+                description: "Adds a sign-extended immediate to rs1",
                 format: InstructionFormat::I,
                 opcode: 0b0010011,
                 funct3: Some(0x0),
@@ -173,6 +214,7 @@ impl ISA {
             },
             SLTI => InstructionDefinition {
                 _name: "SLTI",
+                description: "Sets rd to 1 if rs1 is less than the immediate as signed numbers, otherwise 0",
                 format: InstructionFormat::I,
                 opcode: 0b0010011,
                 funct3: Some(0x2),
@@ -180,6 +222,7 @@ impl ISA {
             },
             SLTIU => InstructionDefinition {
                 _name: "SLTIU",
+                description: "Sets rd to 1 if rs1 is less than the immediate as unsigned numbers, otherwise 0",
                 format: InstructionFormat::I,
                 opcode: 0b0010011,
                 funct3: Some(0x3),
@@ -187,6 +230,7 @@ impl ISA {
             },
             ANDI => InstructionDefinition {
                 _name: "ANDI",
+                description: "Bitwise AND of rs1 and a sign-extended immediate",
                 format: InstructionFormat::I,
                 opcode: 0b0010011,
                 funct3: Some(0x7),
@@ -194,6 +238,7 @@ impl ISA {
             },
             ORI => InstructionDefinition {
                 _name: "ORI",
+                description: "Bitwise OR of rs1 and a sign-extended immediate",
                 format: InstructionFormat::I,
                 opcode: 0b0010011,
                 funct3: Some(0x6),
@@ -201,6 +246,7 @@ impl ISA {
             },
             XORI => InstructionDefinition {
                 _name: "XORI",
+                description: "Bitwise XOR of rs1 and a sign-extended immediate",
                 format: InstructionFormat::I,
                 opcode: 0b0010011,
                 funct3: Some(0x4),
@@ -208,6 +254,7 @@ impl ISA {
             },
             SLLI => InstructionDefinition {
                 _name: "SLLI",
+                description: "Shifts rs1 left by a constant amount",
                 format: InstructionFormat::I,
                 opcode: 0b0010011,
                 funct3: Some(0x1),
@@ -215,6 +262,7 @@ impl ISA {
             },
             SRLI => InstructionDefinition {
                 _name: "SRLI",
+                description: "Shifts rs1 right by a constant amount, filling with zeros",
                 format: InstructionFormat::I,
                 opcode: 0b0010011,
                 funct3: Some(0x5),
@@ -222,6 +270,7 @@ impl ISA {
             },
             SRAI => InstructionDefinition {
                 _name: "SRAI",
+                description: "Shifts rs1 right by a constant amount, copying the sign bit",
                 format: InstructionFormat::I,
                 opcode: 0b0010011,
                 funct3: Some(0x5),
@@ -229,6 +278,7 @@ impl ISA {
             },
             JALR => InstructionDefinition {
                 _name: "JALR",
+                description: "Jumps to rs1 plus the immediate, saving the return address in rd",
                 format: InstructionFormat::I,
                 opcode: 0b1100111,
                 funct3: Some(0x0),
@@ -236,6 +286,7 @@ impl ISA {
             },
             CSRRW => InstructionDefinition {
                 _name: "CSRRW",
+                description: "Swaps a CSR with rs1, saving its old value in rd",
                 format: InstructionFormat::I,
                 opcode: 0b1110011,
                 funct3: Some(0x1),
@@ -243,6 +294,7 @@ impl ISA {
             },
             CSRRS => InstructionDefinition {
                 _name: "CSRRS",
+                description: "Sets the bits of a CSR that are set in rs1, saving its old value in rd",
                 format: InstructionFormat::I,
                 opcode: 0b1110011,
                 funct3: Some(0x2),
@@ -250,6 +302,7 @@ impl ISA {
             },
             CSRRC => InstructionDefinition {
                 _name: "CSRRC",
+                description: "Clears the bits of a CSR that are set in rs1, saving its old value in rd",
                 format: InstructionFormat::I,
                 opcode: 0b1110011,
                 funct3: Some(0x3),
@@ -257,6 +310,7 @@ impl ISA {
             },
             CSRRWI => InstructionDefinition {
                 _name: "CSRRWI",
+                description: "Writes a 5-bit immediate to a CSR, saving its old value in rd",
                 format: InstructionFormat::I,
                 opcode: 0b1110011,
                 funct3: Some(0x5),
@@ -264,6 +318,7 @@ impl ISA {
             },
             CSRRSI => InstructionDefinition {
                 _name: "CSRRSI",
+                description: "Sets the bits of a CSR that are set in a 5-bit immediate, saving its old value in rd",
                 format: InstructionFormat::I,
                 opcode: 0b1110011,
                 funct3: Some(0x6),
@@ -271,6 +326,7 @@ impl ISA {
             },
             CSRRCI => InstructionDefinition {
                 _name: "CSRRCI",
+                description: "Clears the bits of a CSR that are set in a 5-bit immediate, saving its old value in rd",
                 format: InstructionFormat::I,
                 opcode: 0b1110011,
                 funct3: Some(0x7),
@@ -280,6 +336,7 @@ impl ISA {
             // Load instructions (I-type)
             LB => InstructionDefinition {
                 _name: "LB",
+                description: "Loads a sign-extended byte from memory at rs1 plus the offset",
                 format: InstructionFormat::I,
                 opcode: 0b0000011,
                 funct3: Some(0x0),
@@ -287,6 +344,7 @@ impl ISA {
             },
             LH => InstructionDefinition {
                 _name: "LH",
+                description: "Loads a sign-extended halfword from memory at rs1 plus the offset",
                 format: InstructionFormat::I,
                 opcode: 0b0000011,
                 funct3: Some(0x1),
@@ -294,6 +352,7 @@ impl ISA {
             },
             LW => InstructionDefinition {
                 _name: "LW",
+                description: "Loads a word from memory at rs1 plus the offset",
                 format: InstructionFormat::I,
                 opcode: 0b0000011,
                 funct3: Some(0x2),
@@ -301,6 +360,7 @@ impl ISA {
             },
             LBU => InstructionDefinition {
                 _name: "LBU",
+                description: "Loads a zero-extended byte from memory at rs1 plus the offset",
                 format: InstructionFormat::I,
                 opcode: 0b0000011,
                 funct3: Some(0x4),
@@ -308,6 +368,7 @@ impl ISA {
             },
             LHU => InstructionDefinition {
                 _name: "LHU",
+                description: "Loads a zero-extended halfword from memory at rs1 plus the offset",
                 format: InstructionFormat::I,
                 opcode: 0b0000011,
                 funct3: Some(0x5),
@@ -317,6 +378,7 @@ impl ISA {
             // Special I-type instructions
             FENCE => InstructionDefinition {
                 _name: "FENCE",
+                description: "Orders memory accesses before and after it",
                 format: InstructionFormat::I,
                 opcode: 0b0001111,
                 funct3: Some(0x0),
@@ -324,6 +386,7 @@ impl ISA {
             },
             FENCE_TSO => InstructionDefinition {
                 _name: "FENCE_TSO",
+                description: "Orders memory accesses using total store ordering",
                 format: InstructionFormat::I,
                 opcode: 0b0001111,
                 funct3: Some(0x0),
@@ -331,6 +394,7 @@ impl ISA {
             },
             PAUSE => InstructionDefinition {
                 _name: "PAUSE",
+                description: "Hints that the program is spinning in a wait loop",
                 format: InstructionFormat::I,
                 opcode: 0b0001111,
                 funct3: Some(0x0),
@@ -338,6 +402,7 @@ impl ISA {
             },
             ECALL => InstructionDefinition {
                 _name: "ECALL",
+                description: "Requests a service from the execution environment",
                 format: InstructionFormat::I,
                 opcode: 0b1110011,
                 funct3: Some(0x0),
@@ -345,6 +410,15 @@ impl ISA {
             },
             EBREAK => InstructionDefinition {
                 _name: "EBREAK",
+                description: "Returns control to the debugger",
+                format: InstructionFormat::I,
+                opcode: 0b1110011,
+                funct3: Some(0x0),
+                funct7: None,
+            },
+            WFI => InstructionDefinition {
+                _name: "WFI",
+                description: "Waits for an interrupt, which halts the emulator",
                 format: InstructionFormat::I,
                 opcode: 0b1110011,
                 funct3: Some(0x0),
@@ -354,6 +428,7 @@ impl ISA {
             // S-type instructions
             SW => InstructionDefinition {
                 _name: "SW",
+                description: "Stores rs2 as a word to memory at rs1 plus the offset",
                 format: InstructionFormat::S,
                 opcode: 0b0100011,
                 funct3: Some(0x2),
@@ -361,6 +436,7 @@ impl ISA {
             },
             SH => InstructionDefinition {
                 _name: "SH",
+                description: "Stores the low halfword of rs2 to memory at rs1 plus the offset",
                 format: InstructionFormat::S,
                 opcode: 0b0100011,
                 funct3: Some(0x1),
@@ -368,6 +444,7 @@ impl ISA {
             },
             SB => InstructionDefinition {
                 _name: "SB",
+                description: "Stores the low byte of rs2 to memory at rs1 plus the offset",
                 format: InstructionFormat::S,
                 opcode: 0b0100011,
                 funct3: Some(0x0),
@@ -377,6 +454,7 @@ impl ISA {
             // B-type instructions
             BEQ => InstructionDefinition {
                 _name: "BEQ",
+                description: "Branches to the address if rs1 equals rs2",
                 format: InstructionFormat::B,
                 opcode: 0b1100011,
                 funct3: Some(0x0),
@@ -384,6 +462,7 @@ impl ISA {
             },
             BNE => InstructionDefinition {
                 _name: "BNE",
+                description: "Branches to the address if rs1 does not equal rs2",
                 format: InstructionFormat::B,
                 opcode: 0b1100011,
                 funct3: Some(0x1),
@@ -391,6 +470,7 @@ impl ISA {
             },
             BLT => InstructionDefinition {
                 _name: "BLT",
+                description: "Branches to the address if rs1 is less than rs2 as signed numbers",
                 format: InstructionFormat::B,
                 opcode: 0b1100011,
                 funct3: Some(0x4),
@@ -398,6 +478,7 @@ impl ISA {
             },
             BLTU => InstructionDefinition {
                 _name: "BLTU",
+                description: "Branches to the address if rs1 is less than rs2 as unsigned numbers",
                 format: InstructionFormat::B,
                 opcode: 0b1100011,
                 funct3: Some(0x6),
@@ -405,6 +486,7 @@ impl ISA {
             },
             BGE => InstructionDefinition {
                 _name: "BGE",
+                description: "Branches to the address if rs1 is at least rs2 as signed numbers",
                 format: InstructionFormat::B,
                 opcode: 0b1100011,
                 funct3: Some(0x5),
@@ -412,6 +494,7 @@ impl ISA {
             },
             BGEU => InstructionDefinition {
                 _name: "BGEU",
+                description: "Branches to the address if rs1 is at least rs2 as unsigned numbers",
                 format: InstructionFormat::B,
                 opcode: 0b1100011,
                 funct3: Some(0x7),
@@ -421,6 +504,7 @@ impl ISA {
             // U-type instructions
             LUI => InstructionDefinition {
                 _name: "LUI",
+                description: "Loads a 20-bit immediate into the upper bits of rd",
                 format: InstructionFormat::U,
                 opcode: 0b0110111,
                 funct3: None,
@@ -428,6 +512,7 @@ impl ISA {
             },
             AUIPC => InstructionDefinition {
                 _name: "AUIPC",
+                description: "Adds a 20-bit upper immediate to the PC",
                 format: InstructionFormat::U,
                 opcode: 0b0010111,
                 funct3: None,
@@ -437,6 +522,7 @@ impl ISA {
             // J-type instructions
             JAL => InstructionDefinition {
                 _name: "JAL",
+                description: "Jumps to the address, saving the return address in rd",
                 format: InstructionFormat::J,
                 opcode: 0b1101111,
                 funct3: None,
@@ -496,6 +582,10 @@ impl ISA {
                 0b0000_0000_0001_00000_000_00000_1110011 => Some(EBREAK),
                 _ => None,
             },
+            (0b1110011, 0b000, 0b0001000) => match instr.raw() {
+                0b0001_0000_0101_00000_000_00000_1110011 => Some(WFI),
+                _ => None,
+            },
             (0b1110011, 0b001, _) => Some(CSRRW),
             (0b1110011, 0b010, _) => Some(CSRRS),
             (0b1110011, 0b011, _) => Some(CSRRC),
@@ -1,4 +1,4 @@
-use super::{InstructionDefinition, InstructionFormat, Operands};
+use super::{ISA, InstructionDefinition, InstructionFormat, Operands, csr::csr_name};
 use crate::{bitmask, bits};
 
 #[derive(Clone, Copy, Debug)]
@@ -394,6 +394,16 @@ impl Instruction {
         }
     }
 
+    /// The immediate as it was most likely written: signed decimal for the
+    /// I, S, B and J formats, and the upper 20 bits in hex for the U format
+    pub fn immediate_asm(&self) -> Option<String> {
+        let imm = self.immediate()?;
+        Some(match InstructionDefinition::from_instr(*self)?.format {
+            InstructionFormat::U => format!("0x{:x}", (imm as u32) >> 12),
+            _ => imm.to_string(),
+        })
+    }
+
     pub fn rd(&self) -> u8 {
         bits!(self.instr, 7, 5) as u8
     }
@@ -414,8 +424,59 @@ impl Instruction {
         bits!(self.instr, 25, 7) as u8
     }
 
+    /// Address of the CSR a CSR instruction accesses
+    pub fn csr(&self) -> u32 {
+        bits!(self.instr, 31;20)
+    }
+
     #[allow(dead_code)]
     pub fn is_valid(&self) -> bool {
         InstructionDefinition::from_instr(*self).is_some()
     }
+
+    /// Formats the instruction as assembly the assembler accepts, such as
+    /// `addi x1, x2, 5`. Branch and jump targets are absolute addresses, named
+    /// by `name_target` when it knows a symbol for them. Returns `None` for
+    /// words that are not valid instructions.
+    pub fn to_asm(&self, pc: u32, name_target: impl Fn(u32) -> Option<String>) -> Option<String> {
+        let isa = ISA::instr_to_isa(*self)?;
+        let def = isa.definition();
+        let name = def._name.to_lowercase();
+        let imm = self.immediate().unwrap_or(0);
+        let imm_asm = self.immediate_asm().unwrap_or_default();
+        let (rd, rs1, rs2) = (self.rd(), self.rs1(), self.rs2());
+        let target = || {
+            let address = pc.wrapping_add(imm as u32);
+            name_target(address).unwrap_or_else(|| format!("0x{:x}", address))
+        };
+        let csr = || {
+            csr_name(self.csr())
+                .map(String::from)
+                .unwrap_or_else(|| format!("0x{:x}", self.csr()))
+        };
+
+        Some(match (isa, def.format) {
+            (ISA::ECALL | ISA::EBREAK | ISA::WFI | ISA::FENCE | ISA::FENCE_TSO | ISA::PAUSE, _) => {
+                name
+            }
+            (ISA::SLLI | ISA::SRLI | ISA::SRAI, _) => {
+                format!("{} x{}, x{}, {}", name, rd, rs1, imm & 0x1F)
+            }
+            (ISA::CSRRW | ISA::CSRRS | ISA::CSRRC, _) => {
+                format!("{} x{}, {}, x{}", name, rd, csr(), rs1)
+            }
+            (ISA::CSRRWI | ISA::CSRRSI | ISA::CSRRCI, _) => {
+                format!("{} x{}, {}, {}", name, rd, csr(), rs1)
+            }
+            (ISA::LB | ISA::LH | ISA::LW | ISA::LBU | ISA::LHU, _) => {
+                format!("{} x{}, {}(x{})", name, rd, imm_asm, rs1)
+            }
+            (_, InstructionFormat::R) => format!("{} x{}, x{}, x{}", name, rd, rs1, rs2),
+            (_, InstructionFormat::I) => format!("{} x{}, x{}, {}", name, rd, rs1, imm_asm),
+            (_, InstructionFormat::S) => format!("{} x{}, {}(x{})", name, rs2, imm_asm, rs1),
+            (_, InstructionFormat::B) => format!("{} x{}, x{}, {}", name, rs1, rs2, target()),
+            (_, InstructionFormat::U) => format!("{} x{}, {}", name, rd, imm_asm),
+            (_, InstructionFormat::J) => format!("{} x{}, {}", name, rd, target()),
+        })
+    }
 }
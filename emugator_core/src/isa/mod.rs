@@ -1,3 +1,4 @@
+pub mod csr;
 mod definitions;
 mod instruction;
 
@@ -57,6 +57,28 @@ fn test_bitmask() {
     assert_eq!(bitmask!(5), 0b11111);
 }
 
+/// Parses an address typed as hex (0x...) or decimal
+pub fn parse_address(input: &str) -> Option<u32> {
+    let input = input.trim();
+    match input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => input.parse().ok(),
+    }
+}
+
+#[test]
+fn test_parse_address() {
+    assert_eq!(parse_address("0x1F"), Some(0x1F));
+    assert_eq!(parse_address(" 0XFFFFFFFF "), Some(u32::MAX));
+    assert_eq!(parse_address("42"), Some(42));
+    assert_eq!(parse_address("0x100000000"), None);
+    assert_eq!(parse_address("-1"), None);
+    assert_eq!(parse_address("main"), None);
+}
+
 pub struct IBigLittleEndianIterator<'a> {
     value: &'a IBig,
     index: usize,
@@ -3,12 +3,10 @@ export const makeTokensProvider = () => {
     defaultToken: "",
     ignoreCase: true,
     tokenPostfix: ".riscv",
+    // Token types map onto colors of the built-in vs-dark theme: instructions
+    // as keywords, pseudo-instructions as flow keywords, directives as types,
+    // registers as predefined variables and relocation functions as attributes
     keywords: [
-      ".data",
-      ".text",
-      ".section",
-      ".align",
-      ".equ",
       "ADD",
       "SUB",
       "SLT",
@@ -37,6 +35,7 @@ export const makeTokensProvider = () => {
       "FENCE",
       "ECALL",
       "EBREAK",
+      "WFI",
       "SW",
       "SH",
       "SB",
@@ -50,17 +49,107 @@ export const makeTokensProvider = () => {
       "AUIPC",
       "JAL"
     ],
+    pseudoInstructions: [
+      "LA",
+      "CALL",
+      "BEQZ",
+      "BNEZ",
+      "BLEZ",
+      "BGEZ",
+      "BLTZ",
+      "BGTZ",
+      "NOT",
+      "NEG",
+      "SEQZ",
+      "SNEZ"
+    ],
+    directives: [
+      ".data",
+      ".text",
+      ".section",
+      ".align",
+      ".equ",
+      ".zero",
+      ".byte",
+      ".2byte",
+      ".4byte",
+      ".8byte",
+      ".half",
+      ".word",
+      ".dword",
+      ".ascii",
+      ".asciz",
+      ".string",
+      ".stringtable",
+      ".rept",
+      ".endr",
+      ".if",
+      ".else",
+      ".endif",
+      ".include",
+      ".incbin",
+      ".macro",
+      ".endm"
+    ],
+    registers: [
+      "pc",
+      "zero",
+      "ra",
+      "sp",
+      "gp",
+      "tp",
+      "fp",
+      "t0",
+      "t1",
+      "t2",
+      "t3",
+      "t4",
+      "t5",
+      "t6",
+      "s0",
+      "s1",
+      "s2",
+      "s3",
+      "s4",
+      "s5",
+      "s6",
+      "s7",
+      "s8",
+      "s9",
+      "s10",
+      "s11",
+      "a0",
+      "a1",
+      "a2",
+      "a3",
+      "a4",
+      "a5",
+      "a6",
+      "a7"
+    ],
+    relocations: ["%hi", "%lo", "%pcrel_hi", "%pcrel_lo"],
     symbols: /[\.,\:]+/,
     escapes: /\\(?:[abfnrtv\\"'$]|x[0-9A-Fa-f]{1,4}|u[0-9A-Fa-f]{4}|U[0-9A-Fa-f]{8})/,
     tokenizer: {
       root: [
-        [/x(?:[1-2][0-9]|3[0-1]|[0-9])|pc|zero/, "variable.predefined"],
+        [/x(?:[1-2][0-9]|3[0-1]|[0-9])\b/, "variable.predefined"],
+        [
+          /%\w+/,
+          {
+            cases: {
+              "@relocations": "attribute.name.relocation",
+              "@default": ""
+            }
+          }
+        ],
         [
           /[.a-zA-Z_]\w*/,
           {
             cases: {
-              this: "variable.predefined",
+              "@registers": "variable.predefined",
               "@keywords": { token: "keyword.$0" },
+              "@pseudoInstructions": { token: "keyword.flow.$0" },
+              "@directives": { token: "type.directive" },
               "@default": ""
             }
           }
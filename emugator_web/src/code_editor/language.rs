@@ -1,12 +1,20 @@
-use std::{borrow::Cow, collections::HashMap};
+use std::{borrow::Cow, collections::HashMap, str::FromStr};
 
 use dioxus::signals::Readable;
 use dioxus_logger::tracing::info;
+use emugator_core::{
+    emulator::register_file::register_index,
+    isa::{ISA, InstructionDefinition},
+};
 use js_sys::{Array, Object};
 use monaco::sys::{
     CancellationToken, IMarkdownString, IPosition,
     editor::ITextModel,
-    languages::{self, Hover, HoverProvider, ILanguageExtensionPoint, LanguageConfiguration},
+    languages::{
+        self, CompletionItem, CompletionItemKind, CompletionItemProvider, CompletionList,
+        DocumentHighlight, DocumentHighlightKind, DocumentHighlightProvider, Hover, HoverProvider,
+        ILanguageExtensionPoint, LanguageConfiguration,
+    },
 };
 use serde::Deserialize;
 use wasm_bindgen::prelude::*;
@@ -46,6 +54,8 @@ pub fn register_riscv_language() {
     languages::set_language_configuration(language_id, &cfg);
     languages::set_monarch_tokens_provider(language_id, &tokens_provider);
     languages::register_hover_provider(language_id, &make_hover_provider());
+    languages::register_completion_item_provider(language_id, &make_completion_provider());
+    languages::register_document_highlight_provider(language_id, &make_highlight_provider());
 }
 
 #[derive(Deserialize)]
@@ -71,6 +81,98 @@ fn get_word_at_position(line: &str, col: usize) -> &str {
     &line[start..end]
 }
 
+/// Words of a line outside its comment, with their 0-based starting column
+fn words(line: &str) -> impl Iterator<Item = (usize, &str)> {
+    let code = &line[..line.find('#').unwrap_or(line.len())];
+    code.char_indices()
+        .filter(|&(i, c)| {
+            is_word_char(c) && !code[..i].chars().next_back().is_some_and(is_word_char)
+        })
+        .map(move |(i, _)| {
+            let end = code[i..]
+                .find(|c| !is_word_char(c))
+                .map(|j| i + j)
+                .unwrap_or(code.len());
+            (i, &code[i..end])
+        })
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+/// A Monaco range on one line, with 1-based columns
+fn new_range(line_number: f64, start_column: f64, end_column: f64) -> JsValue {
+    let range = new_object();
+    for (key, value) in [
+        ("startLineNumber", line_number),
+        ("endLineNumber", line_number),
+        ("startColumn", start_column),
+        ("endColumn", end_column),
+    ] {
+        js_sys::Reflect::set(&range, &JsValue::from_str(key), &JsValue::from_f64(value)).unwrap();
+    }
+    range
+}
+
+/// Highlights every occurrence of the word under the cursor, treating a
+/// register's numeric and ABI names as the same word
+fn make_highlight_provider() -> DocumentHighlightProvider {
+    let provide_highlights_fn = Closure::wrap(Box::new(
+        move |model: ITextModel, position: IPosition, _token: CancellationToken| -> JsValue {
+            let highlights = Array::new();
+
+            let line = model.get_line_content(position.line_number());
+            let col = position.column() as usize;
+            let comment_start = line.find('#').unwrap_or(line.len());
+            let word = get_word_at_position(&line, col.min(line.len()));
+            if col > comment_start || word.is_empty() {
+                return highlights.into();
+            }
+
+            let register = register_index(word);
+            let matches = |other: &str| match register {
+                Some(index) => register_index(other) == Some(index),
+                None => other == word,
+            };
+
+            for line_number in 1..=model.get_line_count() as u32 {
+                let line = model.get_line_content(line_number as f64);
+                for (start, other) in words(&line).filter(|(_, other)| matches(other)) {
+                    let highlight: DocumentHighlight = new_object().into();
+                    highlight.set_range(
+                        new_range(
+                            line_number as f64,
+                            start as f64 + 1.0,
+                            (start + other.len()) as f64 + 1.0,
+                        )
+                        .unchecked_ref(),
+                    );
+                    highlight.set_kind(Some(DocumentHighlightKind::Text));
+                    highlights.push(&highlight);
+                }
+            }
+
+            highlights.into()
+        },
+    )
+        as Box<dyn Fn(ITextModel, IPosition, CancellationToken) -> JsValue>);
+
+    // Set the provideDocumentHighlights method on the object
+    let provider: DocumentHighlightProvider = new_object().into();
+    let _ = js_sys::Reflect::set(
+        &provider,
+        &JsValue::from_str("provideDocumentHighlights"),
+        provide_highlights_fn.as_ref().unchecked_ref(),
+    )
+    .unwrap();
+
+    // Keep the callback in memory forever
+    provide_highlights_fn.forget();
+
+    provider
+}
+
 fn make_hover_provider() -> HoverProvider {
     let docs: HashMap<&'static str, DocEntry<'static>> =
         serde_json::from_str(DOCS).expect("failed to parse docs.json");
@@ -92,12 +194,26 @@ fn make_hover_provider() -> HoverProvider {
                 info!("getting hover docs for '{}'", word);
 
                 if !word.is_empty() {
-                    // get static docs
-                    if let Some(doc) = docs.get(word) {
-                        content.push(&new_md_string(&format!(
-                            "**{}**\n\n{}\n\n_Example:_\n```riscv\n{}\n```\n",
-                            doc.format, doc.desc, doc.example
-                        )));
+                    // get static docs, preferring the ISA's own definition for instructions
+                    let definition = ISA::from_str(&word.to_uppercase()).ok();
+                    match (definition, docs.get(word)) {
+                        (Some(isa), doc) => {
+                            let mut markdown = definition_markdown(&isa.definition());
+                            if let Some(doc) = doc {
+                                markdown.push_str(&format!(
+                                    "\n\n_Example:_\n```riscv\n{}\n```\n",
+                                    doc.example
+                                ));
+                            }
+                            content.push(&new_md_string(&markdown));
+                        }
+                        (None, Some(doc)) => {
+                            content.push(&new_md_string(&format!(
+                                "**{}**\n\n{}\n\n_Example:_\n```riscv\n{}\n```\n",
+                                doc.format, doc.desc, doc.example
+                            )));
+                        }
+                        (None, None) => {}
                     }
 
                     // get dynamic info based on the current program
@@ -132,6 +248,64 @@ fn make_hover_provider() -> HoverProvider {
     provider
 }
 
+/// Format, operand pattern and description of an instruction
+fn definition_markdown(def: &InstructionDefinition) -> String {
+    format!(
+        "**{} {}** ({:?}-type)\n\n{}",
+        def._name,
+        def.operands(),
+        def.format,
+        def.description
+    )
+}
+
+/// Suggests every instruction mnemonic, with its operands and description
+fn make_completion_provider() -> CompletionItemProvider {
+    let provide_completion_items_fn = Closure::wrap(Box::new(
+        move |model: ITextModel, position: IPosition| -> JsValue {
+            // replace the partial word before the cursor
+            let word = model.get_word_until_position(&position);
+            let range = new_range(
+                position.line_number(),
+                word.start_column(),
+                word.end_column(),
+            );
+
+            let suggestions = Array::new();
+            for isa in ISA::all() {
+                let def = isa.definition();
+                let item: CompletionItem = new_object().into();
+                item.set_label(&JsValue::from_str(def._name));
+                item.set_kind(CompletionItemKind::Keyword);
+                item.set_detail(Some(&format!("{} {}", def._name, def.operands())));
+                item.set_documentation(&new_md_string(&definition_markdown(&def)));
+                item.set_insert_text(def._name);
+                item.set_range(&range);
+                suggestions.push(&item);
+            }
+
+            let list: CompletionList = new_object().into();
+            list.set_suggestions(&suggestions);
+            list.into()
+        },
+    )
+        as Box<dyn Fn(ITextModel, IPosition) -> JsValue>);
+
+    // Set the provideCompletionItems method on the object
+    let provider: CompletionItemProvider = new_object().into();
+    let _ = js_sys::Reflect::set(
+        &provider,
+        &JsValue::from_str("provideCompletionItems"),
+        provide_completion_items_fn.as_ref().unchecked_ref(),
+    )
+    .unwrap();
+
+    // Keep the callback in memory forever
+    provide_completion_items_fn.forget();
+
+    provider
+}
+
 fn new_md_string(value: &str) -> IMarkdownString {
     let md_string: IMarkdownString = new_object().into();
     js_sys::Reflect::set(
@@ -13,7 +13,7 @@ use monaco::sys::{
 use monaco_editor::MonacoEditor;
 use wasm_bindgen::JsValue;
 
-pub use monaco_editor::LineHighlight;
+pub use monaco_editor::{LineEncoding, LineHighlight, LineStages};
 
 /// A wrapper around the Monaco editor with our expected functionality
 #[component]
@@ -21,8 +21,12 @@ pub use monaco_editor::LineHighlight;
 pub fn CodeEditor(
     mut source: Signal<String>,
     line_highlights: ReadOnlySignal<Vec<LineHighlight>>,
+    line_encodings: ReadOnlySignal<Vec<LineEncoding>>,
+    line_stages: ReadOnlySignal<Vec<LineStages>>,
     breakpoints: Signal<BTreeSet<usize>>,
     assembler_errors: ReadOnlySignal<Vec<AssemblerError>>,
+    assembler_warnings: ReadOnlySignal<Vec<AssemblerError>>,
+    focus_position: Signal<Option<(usize, usize)>>,
 ) -> Element {
     // basic model
     // TODO: support external changes to source being reflected in the model
@@ -56,21 +60,53 @@ pub fn CodeEditor(
         options
     });
 
-    // set the error markers on the model
+    // set the error and warning markers on the model
     use_effect(move || {
+        let model = model.write();
         let markers_arr = js_sys::Array::new();
-        for err in assembler_errors.read().iter() {
+        let errors = assembler_errors.read();
+        let warnings = assembler_warnings.read();
+        let markers = errors
+            .iter()
+            .map(|err| (err, MarkerSeverity::Error))
+            .chain(warnings.iter().map(|warn| (warn, MarkerSeverity::Warning)));
+
+        for (err, severity) in markers {
+            let line_number = err.line_number as f64;
+            // spans ending on a later line run up to their end position,
+            // and zero-width spans cover the whole line
+            let (start_column, end_line_number, end_column) =
+                if let Some((end_line, end_column)) = err.end {
+                    (err.column as f64, end_line as f64, end_column as f64)
+                } else if err.width == 0 {
+                    (
+                        model
+                            .as_ref()
+                            .get_line_first_non_whitespace_column(line_number),
+                        line_number,
+                        model
+                            .as_ref()
+                            .get_line_last_non_whitespace_column(line_number),
+                    )
+                } else {
+                    (
+                        err.column as f64,
+                        line_number,
+                        (err.column + err.width) as f64,
+                    )
+                };
+
             let marker: IMarkerData = new_object().into();
             marker.set_message(&err.error_message);
-            marker.set_start_line_number(err.line_number as f64);
-            marker.set_end_line_number(err.line_number as f64);
-            marker.set_start_column(err.column as f64);
-            marker.set_end_column((err.column + err.width) as f64);
-            marker.set_severity(MarkerSeverity::Error);
+            marker.set_start_line_number(line_number);
+            marker.set_end_line_number(end_line_number);
+            marker.set_start_column(start_column);
+            marker.set_end_column(end_column);
+            marker.set_severity(severity);
             markers_arr.push(&marker);
         }
 
-        editor::set_model_markers(model.write().as_ref(), "assembler", &markers_arr);
+        editor::set_model_markers(model.as_ref(), "assembler", &markers_arr);
     });
 
     rsx! {
@@ -78,7 +114,10 @@ pub fn CodeEditor(
             model: model(),
             options: options(),
             line_highlights,
+            line_encodings,
+            line_stages,
             breakpoints,
+            focus_position,
         }
     }
 }
@@ -5,9 +5,9 @@ use dioxus::prelude::*;
 use monaco::{
     api::{CodeEditor as MonacoController, DisposableClosure, TextModel},
     sys::{
-        IRange, Range,
+        IRange, Position, Range,
         editor::{
-            IEditorMouseEvent, IModelDecorationOptions, IModelDeltaDecoration,
+            IEditor, IEditorMouseEvent, IModelDecorationOptions, IModelDeltaDecoration,
             IStandaloneEditorConstructionOptions, MouseTargetType,
         },
     },
@@ -22,6 +22,20 @@ pub struct LineHighlight {
     pub css_class: &'static str,
 }
 
+/// The encoded words of the instructions assembled from a line
+#[derive(Clone, PartialEq, Debug)]
+pub struct LineEncoding {
+    pub line: usize,
+    pub words: Vec<u32>,
+}
+
+/// The pipeline stages holding instructions assembled from a line
+#[derive(Clone, PartialEq, Debug)]
+pub struct LineStages {
+    pub line: usize,
+    pub stages: Vec<&'static str>,
+}
+
 /// The monaco editor directly wrapped
 #[component]
 #[allow(non_snake_case)]
@@ -29,7 +43,10 @@ pub fn MonacoEditor(
     options: ReadOnlySignal<Option<IStandaloneEditorConstructionOptions>>,
     model: ReadOnlySignal<Option<TextModel>>,
     line_highlights: ReadOnlySignal<Vec<LineHighlight>>,
+    line_encodings: ReadOnlySignal<Vec<LineEncoding>>,
+    line_stages: ReadOnlySignal<Vec<LineStages>>,
     breakpoints: Signal<BTreeSet<usize>>,
+    mut focus_position: Signal<Option<(usize, usize)>>,
 ) -> Element {
     let mut editor = use_signal::<Option<MonacoController>>(|| None);
     let element_id = "monaco-editor";
@@ -69,6 +86,21 @@ pub fn MonacoEditor(
         }
     });
 
+    // move the cursor to a requested line and column, then forget the request
+    use_effect(move || {
+        let Some((line, column)) = *focus_position.read() else {
+            return;
+        };
+        if let Some(editor_instance) = editor.read().as_ref() {
+            let editor_instance: &IEditor = editor_instance.as_ref().unchecked_ref();
+            let position = Position::new(line as f64, column.max(1) as f64);
+            editor_instance.set_position(position.unchecked_ref());
+            editor_instance.reveal_line_in_center(line as f64, None);
+            editor_instance.focus();
+            focus_position.set(None);
+        }
+    });
+
     // handle decorators
     use_effect(move || {
         if let Some(editor_instance) = editor.write().as_mut() {
@@ -83,6 +115,40 @@ pub fn MonacoEditor(
                     ));
                 }
 
+                // show encodings after the end of their lines
+                for line_encoding in line_encodings.read().iter() {
+                    let line = line_encoding.line as f64;
+                    if line > model.as_ref().get_line_count() {
+                        continue;
+                    }
+                    let text = line_encoding
+                        .words
+                        .iter()
+                        .map(|word| format!("{:08x}", word))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    new_decor.push(&line_end_decoration(
+                        line_encoding.line,
+                        model.as_ref().get_line_max_column(line) as usize,
+                        &format!("    {}", text),
+                        "monaco-encoding",
+                    ));
+                }
+
+                // label lines with the stages their instructions are in
+                for line_stages in line_stages.read().iter() {
+                    let line = line_stages.line as f64;
+                    if line > model.as_ref().get_line_count() {
+                        continue;
+                    }
+                    new_decor.push(&line_end_decoration(
+                        line_stages.line,
+                        model.as_ref().get_line_max_column(line) as usize,
+                        &line_stages.stages.join(" "),
+                        "monaco-stage",
+                    ));
+                }
+
                 // add breakpoint hover dot
                 if let Some(line) = *breakpoint_hover_line.read() {
                     if !breakpoints.read().contains(&line) {
@@ -153,6 +219,35 @@ fn line_highlight_decoration(line_number: usize, class: &str) -> IModelDeltaDeco
     decoration
 }
 
+/// Text shown after the end of a line, styled by `class`
+fn line_end_decoration(
+    line_number: usize,
+    column: usize,
+    text: &str,
+    class: &str,
+) -> IModelDeltaDecoration {
+    let decoration: IModelDeltaDecoration = new_object().into();
+    let range = Range::new(
+        line_number as f64,
+        column as f64,
+        line_number as f64,
+        column as f64,
+    );
+    decoration.set_range(&IRange::from(range.dyn_into::<JsValue>().unwrap()));
+
+    // the bindings predate injected text, so build the `after` options by hand
+    let after = new_object();
+    let _ = js_sys::Reflect::set(&after, &"content".into(), &text.into());
+    let _ = js_sys::Reflect::set(&after, &"inlineClassName".into(), &class.into());
+
+    let options: IModelDecorationOptions = new_object().into();
+    let _ = js_sys::Reflect::set(&options, &"after".into(), &after);
+
+    decoration.set_options(&options);
+
+    decoration
+}
+
 fn breakpoint_decoration(line_number: usize, class: &'static str) -> IModelDeltaDecoration {
     let decoration: IModelDeltaDecoration = new_object().into();
     let range = Range::new(line_number as f64, 1.0, line_number as f64, 1.0);
@@ -0,0 +1,51 @@
+use dioxus::prelude::*;
+use emugator_core::emulator::AnyEmulatorState;
+
+use super::{ASSEMBLED_PROGRAM, file_tabs::SourceFile, navbar::source_location};
+
+/// The calls the program is nested in, innermost first, as the source lines
+/// each one returns to. Calls are followed through `ra`, so this is only a
+/// guess for code that links or returns some other way.
+#[component]
+#[allow(non_snake_case)]
+pub fn CallStackView(
+    emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>,
+    files: ReadOnlySignal<Vec<SourceFile>>,
+) -> Element {
+    let Some(state) = emulator_state.read().clone() else {
+        return rsx! {};
+    };
+    let program = ASSEMBLED_PROGRAM.read();
+    let files = files.read();
+    let frames: Vec<(String, String)> = state
+        .call_stack()
+        .frames()
+        .iter()
+        .rev()
+        .map(|frame| {
+            let location = source_location(frame.return_address, &program, &files);
+            let title = format!(
+                "Called from 0x{:08X}, returns to 0x{:08X}",
+                frame.call_pc, frame.return_address
+            );
+            (location, title)
+        })
+        .collect();
+
+    rsx! {
+        div {
+            class: "flex items-center gap-x-2 mb-2 text-xs font-mono overflow-x-auto whitespace-nowrap",
+            title: "Calls that have not returned yet, found by following jal and jalr through ra",
+            span { class: "text-gray-400", "Call Stack" }
+            if frames.is_empty() {
+                span { class: "text-gray-500", "(top level)" }
+            }
+            for (depth , (location , title)) in frames.into_iter().enumerate() {
+                if depth > 0 {
+                    span { class: "text-gray-500", "<" }
+                }
+                span { class: "text-gray-200", title: "{title}", "{location}" }
+            }
+        }
+    }
+}
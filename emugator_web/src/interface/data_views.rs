@@ -1,15 +1,65 @@
+use std::ops::RangeInclusive;
+
 use dioxus::prelude::*;
 use emugator_core::{
     assembler::{AssembledProgram, Section},
-    emulator::AnyEmulatorState,
+    emulator::{AnyEmulatorState, memory_module::MemoryModule},
+    utils,
 };
 
+/// Bytes selected in the data view. The cursor follows the arrow keys while
+/// the anchor stays where the selection started.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ByteSelection {
+    pub anchor: u32,
+    pub cursor: u32,
+}
+
+impl ByteSelection {
+    pub fn at(address: u32) -> Self {
+        Self {
+            anchor: address,
+            cursor: address,
+        }
+    }
+
+    /// Moves the cursor, keeping the anchor if the selection is extended
+    pub fn move_to(self, address: u32, extend: bool) -> Self {
+        if extend {
+            Self {
+                cursor: address,
+                ..self
+            }
+        } else {
+            Self::at(address)
+        }
+    }
+
+    pub fn range(&self) -> RangeInclusive<u32> {
+        self.anchor.min(self.cursor)..=self.anchor.max(self.cursor)
+    }
+
+    pub fn len(&self) -> u32 {
+        self.anchor.abs_diff(self.cursor) + 1
+    }
+}
+
+/// Id of the element showing a byte, used to scroll the cursor into view
+fn byte_id(address: u32) -> String {
+    format!("data-byte-{:x}", address)
+}
+
 #[component]
 #[allow(non_snake_case)]
 pub fn DataView(
     assembled_program: ReadOnlySignal<Option<AssembledProgram>>,
     emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>,
+    previous_state: ReadOnlySignal<Option<AnyEmulatorState>>,
 ) -> Element {
+    let mut selection: Signal<Option<ByteSelection>> = use_signal(|| None);
+    let mut show_heatmap = use_signal(|| false);
+    let mut show_disassembly = use_signal(|| false);
+
     // Early return if no program is assembled
     let assembled_program = assembled_program.read();
     let emulator_state = emulator_state.read();
@@ -22,14 +72,99 @@ pub fn DataView(
     };
 
     let data_memory = state.memory_io();
+    let uninitialized_reads = data_memory.uninitialized_reads();
+    let text_writes = data_memory.text_writes();
+    let access_counts = data_memory.access_counts();
+    let max_accesses = access_counts.values().copied().max().unwrap_or(0);
+    let previous_state = previous_state.read();
+    let previous_memory = previous_state.as_ref().map(|e| e.memory_io());
     let data_start = program.get_section_start(Section::Data) as usize;
 
     // changed this to fix a bug where partial words did not show in data view
     let total_double_words = (data_memory.len() + 15) / 8;
+    let last_address = (data_start + total_double_words * 8 - 1) as u32;
+    let selected = *selection.read();
+
+    // Keys are only handled while the grid has focus, so they never reach the editor
+    let onkeydown = move |event: KeyboardEvent| {
+        let step: i64 = match event.key() {
+            Key::ArrowLeft => -1,
+            Key::ArrowRight => 1,
+            Key::ArrowUp => -8,
+            Key::ArrowDown => 8,
+            Key::Escape => {
+                selection.set(None);
+                return;
+            }
+            _ => return,
+        };
+        event.prevent_default();
+
+        let current = *selection.peek();
+        let next = match current {
+            Some(current) => {
+                let address =
+                    (current.cursor as i64 + step).clamp(data_start as i64, last_address as i64);
+                current.move_to(address as u32, event.modifiers().shift())
+            }
+            None => ByteSelection::at(data_start as u32),
+        };
+        selection.set(Some(next));
+
+        if let Some(element) = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id(&byte_id(next.cursor)))
+        {
+            element.scroll_into_view_with_bool(false);
+        }
+    };
 
     rsx! {
-        div { class: "h-full overflow-hidden",
-            div { class: "h-full overflow-auto pr-2",
+        div { class: "h-full flex flex-col overflow-hidden",
+            div { class: "flex-shrink-0 mb-2 font-mono text-sm text-gray-300",
+                match selected {
+                    Some(selected) => rsx! {
+                        "Selected 0x{selected.range().start():04x}-0x{selected.range().end():04x} ({selected.len()} bytes)"
+                    },
+                    None => rsx! { "Click a byte or focus the grid and use the arrow keys. Hold Shift to select a range." },
+                }
+                if let Some(first) = uninitialized_reads.first() {
+                    div { class: "text-red-400",
+                        "Read {uninitialized_reads.len()} uninitialized bytes, first at 0x{first:04x}"
+                    }
+                }
+                if let Some(first) = text_writes.first() {
+                    div { class: "text-red-400",
+                        "Stored to {text_writes.len()} bytes of read-only instructions, first at 0x{first:04x}"
+                    }
+                }
+                label {
+                    class: "flex items-center gap-x-1 text-xs cursor-pointer",
+                    title: "Shade each byte by how many times loads and stores have accessed it",
+                    input {
+                        r#type: "checkbox",
+                        class: "accent-indigo-500 cursor-pointer",
+                        checked: *show_heatmap.read(),
+                        onchange: move |event| show_heatmap.set(event.checked()),
+                    }
+                    "Access Heatmap"
+                }
+                label {
+                    class: "flex items-center gap-x-1 text-xs cursor-pointer",
+                    title: "Show aligned words at instruction addresses as the instructions assembled there",
+                    input {
+                        r#type: "checkbox",
+                        class: "accent-indigo-500 cursor-pointer",
+                        checked: *show_disassembly.read(),
+                        onchange: move |event| show_disassembly.set(event.checked()),
+                    }
+                    "Disassemble Text"
+                }
+            }
+            div {
+                class: "flex-grow overflow-auto pr-2 outline-none focus:ring-2 focus:ring-blue-400 rounded",
+                tabindex: 0,
+                onkeydown,
                 div { class: "bg-white rounded shadow-sm p-2",
                     table { class: "w-full font-mono text-gray-800 font-bold",
                         tbody {
@@ -41,31 +176,92 @@ pub fn DataView(
                                         for (j, b) in dw_bytes.iter_mut().enumerate() {
                                             *b = data_memory.preview((base_addr + j) as u32);
                                         }
-                                        let hex_string1 = format!(
-                                            "{:02x} {:02x} {:02x} {:02x}",
-                                            dw_bytes[0],
-                                            dw_bytes[1],
-                                            dw_bytes[2],
-                                            dw_bytes[3],
-                                        );
-                                        let hex_string2 = format!(
-                                            "{:02x} {:02x} {:02x} {:02x}",
-                                            dw_bytes[4],
-                                            dw_bytes[5],
-                                            dw_bytes[6],
-                                            dw_bytes[7],
-                                        );
+                                        // Highlight bytes written since the previous step
+                                        let changed: [bool; 8] = std::array::from_fn(|j| {
+                                            previous_memory.is_some_and(|previous| {
+                                                previous.preview((base_addr + j) as u32) != dw_bytes[j]
+                                            })
+                                        });
+                                        let hex_bytes = dw_bytes;
                                         for b in &mut dw_bytes {
                                             if *b < 0x21 || *b > 0x7e {
                                                 *b = b'.';
                                             }
                                         }
                                         let char_string = String::from_utf8_lossy(&dw_bytes[0..8]).to_string();
+                                        // Words of the text section are shown as their instruction, anything else as bytes
+                                        let instructions: [Option<(u32, String)>; 2] = std::array::from_fn(|half| {
+                                            let address = (base_addr + 4 * half) as u32;
+                                            if !*show_disassembly.read() || !address.is_multiple_of(4) {
+                                                return None;
+                                            }
+                                            let instruction = program.instruction_at(address)?;
+                                            Some((instruction.raw(), program.disassemble(address)?))
+                                        });
                                         rsx! {
                                             tr { padding: "20px",
                                                 td { class: "flex-1 text-gray-500 text-xs", "0x{base_addr:04x}:" }
-                                                td { class: "flex-1", "{hex_string1}" }
-                                                td { class: "flex-1", "{hex_string2}" }
+                                                for half in 0..2 {
+                                                    if let Some((raw, asm)) = &instructions[half] {
+                                                        td { class: "flex-1 text-indigo-700", title: "0x{raw:08x}", "{asm}" }
+                                                    } else {
+                                                        td { class: "flex-1",
+                                                            for j in (4 * half)..(4 * (half + 1)) {
+                                                                if j % 4 != 0 {
+                                                                    " "
+                                                                }
+                                                                {
+                                                                    let address = (base_addr + j) as u32;
+                                                                    let is_selected = selected.is_some_and(|selected| selected.range().contains(&address));
+                                                                    let is_cursor = selected.is_some_and(|selected| selected.cursor == address);
+                                                                    let is_uninitialized = uninitialized_reads.contains(&address);
+                                                                    let accesses = access_counts.get(&address).copied().unwrap_or(0);
+                                                                    // Hotter bytes are shaded darker, selection and changes take precedence
+                                                                    let heat = if *show_heatmap.read() && accesses > 0 && !is_selected && !changed[j] {
+                                                                        let alpha = 0.15 + 0.65 * accesses as f32 / max_accesses as f32;
+                                                                        format!("background-color: rgba(239, 68, 68, {alpha:.2})")
+                                                                    } else {
+                                                                        String::new()
+                                                                    };
+                                                                    let title = match (is_uninitialized, *show_heatmap.read()) {
+                                                                        (true, true) => format!("Read before it was initialized, accessed {accesses} times"),
+                                                                        (true, false) => "Read before it was initialized".to_string(),
+                                                                        (false, true) => format!("Accessed {accesses} times"),
+                                                                        (false, false) => String::new(),
+                                                                    };
+                                                                    rsx! {
+                                                                        span {
+                                                                            id: byte_id(address),
+                                                                            title,
+                                                                            style: heat,
+                                                                            class: format!(
+                                                                                "cursor-pointer rounded {} {} {}",
+                                                                                if is_selected {
+                                                                                    "bg-blue-200"
+                                                                                } else if changed[j] {
+                                                                                    "bg-yellow-200"
+                                                                                } else {
+                                                                                    ""
+                                                                                },
+                                                                                if is_cursor { "outline outline-1 outline-blue-600" } else { "" },
+                                                                                if is_uninitialized { "text-red-600" } else { "" },
+                                                                            ),
+                                                                            onclick: move |event| {
+                                                                                let current = *selection.peek();
+                                                                                let extend = event.modifiers().shift() && current.is_some();
+                                                                                let next = current
+                                                                                    .unwrap_or(ByteSelection::at(address))
+                                                                                    .move_to(address, extend);
+                                                                                selection.set(Some(next));
+                                                                            },
+                                                                            "{hex_bytes[j]:02x}"
+                                                                        }
+                                                                    }
+                                                                }
+                                                            }
+                                                        }
+                                                    }
+                                                }
                                                 td { class: "flex-1", "{char_string}" }
                                             }
                                         }
@@ -79,3 +275,184 @@ pub fn DataView(
         }
     }
 }
+
+/// Number of consecutive values shown by the typed data view
+const TYPED_ROWS: usize = 16;
+
+/// Longest string read by the c-string type before giving up on a NUL
+const MAX_CSTRING_LEN: usize = 256;
+
+/// How a typed data view interprets the bytes at an address
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DataType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    Char,
+    CString,
+}
+
+impl DataType {
+    pub const ALL: [DataType; 9] = [
+        DataType::I8,
+        DataType::U8,
+        DataType::I16,
+        DataType::U16,
+        DataType::I32,
+        DataType::U32,
+        DataType::F32,
+        DataType::Char,
+        DataType::CString,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataType::I8 => "i8",
+            DataType::U8 => "u8",
+            DataType::I16 => "i16",
+            DataType::U16 => "u16",
+            DataType::I32 => "i32",
+            DataType::U32 => "u32",
+            DataType::F32 => "f32",
+            DataType::Char => "char",
+            DataType::CString => "c-string",
+        }
+    }
+
+    /// Size of one value in bytes, `None` for variable length types
+    pub fn size(&self) -> Option<usize> {
+        match self {
+            DataType::I8 | DataType::U8 | DataType::Char => Some(1),
+            DataType::I16 | DataType::U16 => Some(2),
+            DataType::I32 | DataType::U32 | DataType::F32 => Some(4),
+            DataType::CString => None,
+        }
+    }
+
+    /// Formats the value stored little-endian at the address
+    pub fn format(&self, memory: &MemoryModule, address: u32) -> String {
+        let bytes = |n: u32| -> Vec<u8> {
+            (0..n)
+                .map(|i| memory.preview(address.wrapping_add(i)))
+                .collect()
+        };
+        let word = || u32::from_le_bytes(bytes(4).try_into().unwrap());
+        let half = || u16::from_le_bytes(bytes(2).try_into().unwrap());
+
+        match self {
+            DataType::I8 => format!("{}", memory.preview(address) as i8),
+            DataType::U8 => format!("{}", memory.preview(address)),
+            DataType::I16 => format!("{}", half() as i16),
+            DataType::U16 => format!("{}", half()),
+            DataType::I32 => format!("{}", word() as i32),
+            DataType::U32 => format!("{}", word()),
+            DataType::F32 => format!("{}", f32::from_bits(word())),
+            DataType::Char => format!("{:?}", memory.preview(address) as char),
+            DataType::CString => {
+                let string: Vec<u8> = (0..MAX_CSTRING_LEN as u32)
+                    .map(|i| memory.preview(address.wrapping_add(i)))
+                    .take_while(|&b| b != 0)
+                    .collect();
+                format!("{:?}", String::from_utf8_lossy(&string))
+            }
+        }
+    }
+}
+
+/// Parses an address typed as hex (0x...), decimal, or a label name
+fn parse_address(input: &str, program: &AssembledProgram) -> Option<u32> {
+    utils::parse_address(input).or_else(|| {
+        program
+            .symbol_table
+            .get(input.trim())
+            .and_then(|address| address.1.clone().try_into().ok())
+    })
+}
+
+/// Shows data memory starting at an address interpreted as a chosen type
+#[component]
+#[allow(non_snake_case)]
+pub fn TypedDataView(
+    assembled_program: ReadOnlySignal<Option<AssembledProgram>>,
+    emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>,
+) -> Element {
+    let mut address_input = use_signal(|| String::from("0x0"));
+    let mut data_type = use_signal(|| DataType::I32);
+
+    let assembled_program = assembled_program.read();
+    let emulator_state = emulator_state.read();
+    let (Some(program), Some(state)) = (assembled_program.as_ref(), emulator_state.as_ref()) else {
+        return rsx! {
+            div { class: "flex justify-center items-center h-full",
+                span { class: "text-gray-500 font-mono", "No program running" }
+            }
+        };
+    };
+
+    let data_memory = state.memory_io();
+    let address = parse_address(&address_input.read(), program);
+    let selected = *data_type.read();
+
+    rsx! {
+        div { class: "h-full flex flex-col overflow-hidden",
+            div { class: "flex items-center gap-2 mb-2 font-mono text-sm",
+                input {
+                    class: format!(
+                        "w-40 bg-gray-700 text-white rounded px-2 py-1 outline-none {}",
+                        if address.is_none() { "ring-1 ring-red-500" } else { "" },
+                    ),
+                    r#type: "text",
+                    placeholder: "Address or label",
+                    value: "{address_input}",
+                    oninput: move |event| address_input.set(event.value()),
+                }
+                select {
+                    class: "bg-gray-700 text-white rounded px-2 py-1 cursor-pointer",
+                    onchange: move |event| {
+                        if let Some(selected) = DataType::ALL
+                            .into_iter()
+                            .find(|data_type| data_type.name() == event.value())
+                        {
+                            data_type.set(selected);
+                        }
+                    },
+                    for option_type in DataType::ALL {
+                        option {
+                            value: option_type.name(),
+                            selected: option_type == selected,
+                            "{option_type.name()}"
+                        }
+                    }
+                }
+            }
+            div { class: "flex-grow overflow-auto pr-2",
+                div { class: "bg-white rounded shadow-sm p-2",
+                    if let Some(address) = address {
+                        table { class: "w-full font-mono text-gray-800 font-bold",
+                            tbody {
+                                for row in 0..selected.size().map_or(1, |_| TYPED_ROWS) {
+                                    {
+                                        let row_address = address
+                                            .wrapping_add((row * selected.size().unwrap_or(0)) as u32);
+                                        rsx! {
+                                            tr {
+                                                td { class: "text-gray-500 text-xs w-24", "0x{row_address:04x}:" }
+                                                td { "{selected.format(data_memory, row_address)}" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    } else {
+                        span { class: "text-gray-500 font-mono", "Enter an address or label" }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use dioxus::prelude::*;
+use emugator_core::emulator::AnyEmulatorState;
+
+use super::ASSEMBLED_PROGRAM;
+
+/// Calculator evaluating an expression of registers, `mem[address]` words and
+/// symbols against the displayed state, showing the result in hex and decimal
+#[component]
+#[allow(non_snake_case)]
+pub fn ExpressionView(emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>) -> Element {
+    let mut expression = use_signal(String::new);
+
+    let result = use_memo(move || {
+        let expression = expression.read();
+        if expression.trim().is_empty() {
+            return None;
+        }
+        let emulator_state = emulator_state.read();
+        let program = ASSEMBLED_PROGRAM.read();
+        Some(match (emulator_state.as_ref(), program.as_ref()) {
+            (Some(state), Some(program)) => state.evaluate(&expression, program),
+            _ => Err("No program running".to_string()),
+        })
+    });
+
+    rsx! {
+        div { class: "flex items-center gap-x-2 ml-auto font-mono text-xs min-w-0",
+            input {
+                class: "w-40 bg-gray-700 text-white rounded px-1 py-1 outline-none",
+                placeholder: "a0 + mem[sp]",
+                title: "Evaluate an expression of registers, mem[address] words and symbols",
+                value: "{expression}",
+                oninput: move |event| expression.set(event.value()),
+            }
+            match result() {
+                Some(Ok(value)) => rsx! {
+                    span { class: "text-gray-300 whitespace-nowrap",
+                        "= 0x{value:08X} = {value}"
+                        if (value as i32) < 0 {
+                            " ({value as i32})"
+                        }
+                    }
+                },
+                Some(Err(message)) => rsx! {
+                    span { class: "text-red-400 truncate", title: "{message}", "{message}" }
+                },
+                None => rsx! {},
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::BTreeSet;
+
+use dioxus::prelude::*;
+use dioxus_free_icons::Icon;
+use dioxus_free_icons::icons::ld_icons::{LdBinary, LdFileUp, LdPlus, LdX};
+use emugator_core::assembler::{
+    self, AssembleOptions, AssembledProgram, AssemblerError, MemoryMap,
+};
+
+use super::settings::Settings;
+
+/// A source buffer shown as a tab above the editor
+#[derive(Clone, PartialEq, Debug)]
+pub struct SourceFile {
+    pub name: String,
+    pub source: String,
+    pub breakpoints: BTreeSet<usize>,
+}
+
+impl SourceFile {
+    pub fn new(name: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            source: source.into(),
+            breakpoints: BTreeSet::new(),
+        }
+    }
+}
+
+/// An uploaded file that `.incbin` directives can include by name
+#[derive(Clone, PartialEq, Debug)]
+pub struct BinaryFile {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// Files uploaded for `.incbin`, shared by every assembly of the tabs
+pub static BINARY_FILES: GlobalSignal<Vec<BinaryFile>> = GlobalSignal::new(Vec::new);
+
+/// Whether unknown directives fail assembly rather than being skipped with a warning
+pub static STRICT_DIRECTIVES: GlobalSignal<bool> =
+    GlobalSignal::new(|| Settings::load().strict_directives);
+
+/// Assembles every tab as one program, in tab order, including uploaded files
+/// where `.incbin` names them and skipping unknown directives unless strict
+pub fn assemble_files(
+    files: &[SourceFile],
+    memory_map: MemoryMap,
+) -> Result<AssembledProgram, Vec<AssemblerError>> {
+    let files: Vec<_> = files
+        .iter()
+        .map(|file| (file.name.as_str(), file.source.as_str()))
+        .collect();
+    let binaries = BINARY_FILES.peek();
+    let options = AssembleOptions {
+        memory_map,
+        resolve_include: Box::new(|_| None),
+        resolve_binary: Box::new(|name| {
+            binaries
+                .iter()
+                .find(|binary| binary.name == name)
+                .map(|binary| binary.data.clone())
+        }),
+        strict: *STRICT_DIRECTIVES.peek(),
+    };
+    assembler::assemble_files(&files, options)
+}
+
+/// Number of lines before each tab in the joined program
+pub fn line_offsets(files: &[SourceFile]) -> Vec<usize> {
+    assembler::file_line_offsets(files.iter().map(|file| file.source.as_str()))
+}
+
+/// Makes the tab at `index` active, saving the editor's contents to the
+/// previously active tab and loading the new tab's into `source` and `breakpoints`
+pub fn select_file(
+    mut files: Signal<Vec<SourceFile>>,
+    mut active_file: Signal<usize>,
+    mut source: Signal<String>,
+    mut breakpoints: Signal<BTreeSet<usize>>,
+    index: usize,
+) {
+    {
+        let mut files = files.write();
+        let active = *active_file.peek();
+        if let Some(file) = files.get_mut(active) {
+            file.source = source.peek().clone();
+            file.breakpoints = breakpoints.peek().clone();
+        }
+    }
+    if let Some(file) = files.peek().get(index) {
+        source.set(file.source.clone());
+        breakpoints.set(file.breakpoints.clone());
+    }
+    active_file.set(index);
+}
+
+/// Tab bar for switching between, adding, renaming and closing source files.
+/// The editor always shows the active file through `source` and `breakpoints`,
+/// which are swapped out here when the active tab changes.
+#[component]
+#[allow(non_snake_case)]
+pub fn FileTabs(
+    files: Signal<Vec<SourceFile>>,
+    active_file: Signal<usize>,
+    source: Signal<String>,
+    breakpoints: Signal<BTreeSet<usize>>,
+    assembler_errors: ReadOnlySignal<Vec<AssemblerError>>,
+) -> Element {
+    let mut renaming: Signal<Option<usize>> = use_signal(|| None);
+
+    let select = move |index: usize| select_file(files, active_file, source, breakpoints, index);
+
+    let mut rename = move |index: usize, name: String| {
+        let name = name.trim();
+        let taken = files
+            .peek()
+            .iter()
+            .enumerate()
+            .any(|(i, file)| i != index && file.name == name);
+        if !name.is_empty()
+            && !taken
+            && let Some(file) = files.write().get_mut(index)
+        {
+            file.name = name.to_string();
+        }
+        renaming.set(None);
+    };
+
+    let upload = move |event: FormEvent| async move {
+        let Some(engine) = event.files() else {
+            return;
+        };
+        for name in engine.files() {
+            if let Some(data) = engine.read_file(&name).await {
+                let mut binaries = BINARY_FILES.write();
+                binaries.retain(|binary| binary.name != name);
+                binaries.push(BinaryFile { name, data });
+            }
+        }
+    };
+
+    let names: Vec<String> = files.read().iter().map(|file| file.name.clone()).collect();
+    let binaries: Vec<(String, usize)> = BINARY_FILES
+        .read()
+        .iter()
+        .map(|binary| (binary.name.clone(), binary.data.len()))
+        .collect();
+    let active = *active_file.read();
+    let can_close = names.len() > 1;
+
+    rsx! {
+        div { class: "flex items-stretch bg-gray-900 text-sm text-gray-300 border-b-2 border-gray-950 overflow-x-auto",
+            for (index , name) in names.into_iter().enumerate() {
+                div {
+                    key: "{index}",
+                    class: format!(
+                        "flex items-center gap-2 px-3 py-1 border-r border-gray-950 cursor-pointer select-none {}",
+                        if index == active {
+                            "bg-[#1E1E1E] text-white"
+                        } else {
+                            "hover:bg-gray-800"
+                        },
+                    ),
+                    onclick: move |_| {
+                        if index != *active_file.peek() {
+                            select(index);
+                        }
+                    },
+                    ondoubleclick: move |_| renaming.set(Some(index)),
+                    if *renaming.read() == Some(index) {
+                        input {
+                            class: "w-32 bg-gray-800 text-white px-1 rounded outline-none",
+                            r#type: "text",
+                            value: "{name}",
+                            onmounted: move |event| async move {
+                                let _ = event.set_focus(true).await;
+                            },
+                            onclick: move |event| event.stop_propagation(),
+                            onkeydown: move |event| {
+                                if event.key() == Key::Escape {
+                                    renaming.set(None);
+                                }
+                            },
+                            onchange: move |event| rename(index, event.value()),
+                            onblur: move |_| renaming.set(None),
+                        }
+                    } else {
+                        span { "{name}" }
+                    }
+                    {
+                        let error_count = assembler_errors
+                            .read()
+                            .iter()
+                            .filter(|error| error.file.as_deref() == Some(name.as_str()))
+                            .count();
+                        rsx! {
+                            if error_count > 0 {
+                                span { class: "text-xs text-red-400", "{error_count}" }
+                            }
+                        }
+                    }
+                    if can_close {
+                        button {
+                            class: "text-gray-500 hover:text-white cursor-pointer",
+                            title: "Close file",
+                            onclick: move |event| {
+                                event.stop_propagation();
+                                let active = *active_file.peek();
+                                if index == active {
+                                    files.write().remove(index);
+                                    let next = index.min(files.peek().len() - 1);
+                                    if let Some(file) = files.peek().get(next) {
+                                        source.set(file.source.clone());
+                                        breakpoints.set(file.breakpoints.clone());
+                                    }
+                                    active_file.set(next);
+                                } else {
+                                    files.write().remove(index);
+                                    if index < active {
+                                        active_file.set(active - 1);
+                                    }
+                                }
+                                renaming.set(None);
+                            },
+                            Icon { width: 12, icon: LdX }
+                        }
+                    }
+                }
+            }
+            button {
+                class: "px-2 text-gray-400 hover:text-white hover:bg-gray-800 cursor-pointer",
+                title: "New file",
+                onclick: move |_| {
+                    let name = (1..)
+                        .map(|n| format!("file{n}.s"))
+                        .find(|name| files.peek().iter().all(|file| &file.name != name))
+                        .unwrap_or_default();
+                    files.write().push(SourceFile::new(name, ""));
+                    let index = files.peek().len() - 1;
+                    select(index);
+                    renaming.set(Some(index));
+                },
+                Icon { width: 14, icon: LdPlus }
+            }
+            for (name , size) in binaries.into_iter() {
+                div {
+                    key: "{name}",
+                    class: "flex items-center gap-2 px-3 py-1 border-l border-gray-950 text-gray-400 select-none",
+                    title: "{size} bytes, included with .incbin \"{name}\"",
+                    Icon { width: 12, icon: LdBinary }
+                    span { "{name}" }
+                    button {
+                        class: "text-gray-500 hover:text-white cursor-pointer",
+                        title: "Remove file",
+                        onclick: move |_| BINARY_FILES.write().retain(|binary| binary.name != name),
+                        Icon { width: 12, icon: LdX }
+                    }
+                }
+            }
+            label {
+                class: "flex items-center px-2 text-gray-400 hover:text-white hover:bg-gray-800 cursor-pointer",
+                title: "Upload a data file for .incbin",
+                input {
+                    class: "hidden",
+                    r#type: "file",
+                    onchange: upload,
+                }
+                Icon { width: 14, icon: LdFileUp }
+            }
+        }
+    }
+}
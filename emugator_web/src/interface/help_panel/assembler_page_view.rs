@@ -2,15 +2,13 @@ use std::collections::HashMap;
 
 use dioxus::prelude::*;
 
-use crate::{code_editor::language::{DocEntry, DOCS}, interface::help_panel::{H3_STYLE, H4_STYLE, P_STYLE}};
+use crate::{
+    code_editor::language::{DOCS, DocEntry},
+    interface::help_panel::{H3_STYLE, H4_STYLE, P_STYLE},
+};
 
 #[component]
-pub fn InstructionCard(
-    name: String,
-    format: String,
-    desc: String,
-    example: String,
-) -> Element {
+pub fn InstructionCard(name: String, format: String, desc: String, example: String) -> Element {
     rsx! {
         div {
             class: "max-w-md mx-auto bg-white shadow-lg rounded-xl p-6 border border-gray-200 mb-2",
@@ -76,4 +74,3 @@ pub fn AssemblerPageView() -> Element {
         }
     )
 }
-
@@ -0,0 +1,157 @@
+use dioxus::prelude::*;
+use emugator_core::{
+    assembler,
+    isa::{ISA, Instruction, InstructionDefinition, InstructionFormat},
+};
+
+use crate::interface::help_panel::{H3_STYLE, H4_STYLE, P_STYLE};
+
+/// Parses either a hex word (`0x00100093` or 8 hex digits) or a single line of assembly
+fn parse_input(input: &str) -> Result<u32, String> {
+    let input = input.trim();
+    let hex = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .or((input.len() == 8 && input.chars().all(|c| c.is_ascii_hexdigit())).then_some(input));
+
+    if let Some(hex) = hex {
+        return u32::from_str_radix(hex, 16)
+            .map_err(|_| format!("'{}' is not a valid hex word.", input));
+    }
+
+    let program =
+        assembler::assemble(input, assembler::AssembleOptions::default()).map_err(|errors| {
+            errors
+                .first()
+                .map(|e| e.error_message.clone())
+                .unwrap_or_default()
+        })?;
+    let bytes: Vec<u8> = program
+        .instruction_memory
+        .values()
+        .take(4)
+        .copied()
+        .collect();
+
+    bytes
+        .try_into()
+        .map(u32::from_le_bytes)
+        .map_err(|_| "No instruction was assembled.".to_string())
+}
+
+/// Fields of an instruction format from the most to least significant bit
+fn fields(format: Option<InstructionFormat>) -> Vec<(&'static str, u32, u32)> {
+    match format {
+        Some(InstructionFormat::R) => vec![
+            ("funct7", 31, 25),
+            ("rs2", 24, 20),
+            ("rs1", 19, 15),
+            ("funct3", 14, 12),
+            ("rd", 11, 7),
+            ("opcode", 6, 0),
+        ],
+        Some(InstructionFormat::I) => vec![
+            ("imm[11:0]", 31, 20),
+            ("rs1", 19, 15),
+            ("funct3", 14, 12),
+            ("rd", 11, 7),
+            ("opcode", 6, 0),
+        ],
+        Some(InstructionFormat::S) => vec![
+            ("imm[11:5]", 31, 25),
+            ("rs2", 24, 20),
+            ("rs1", 19, 15),
+            ("funct3", 14, 12),
+            ("imm[4:0]", 11, 7),
+            ("opcode", 6, 0),
+        ],
+        Some(InstructionFormat::B) => vec![
+            ("imm[12|10:5]", 31, 25),
+            ("rs2", 24, 20),
+            ("rs1", 19, 15),
+            ("funct3", 14, 12),
+            ("imm[4:1|11]", 11, 7),
+            ("opcode", 6, 0),
+        ],
+        Some(InstructionFormat::U) => {
+            vec![("imm[31:12]", 31, 12), ("rd", 11, 7), ("opcode", 6, 0)]
+        }
+        Some(InstructionFormat::J) => vec![
+            ("imm[20|10:1|11|19:12]", 31, 12),
+            ("rd", 11, 7),
+            ("opcode", 6, 0),
+        ],
+        None => vec![("unknown", 31, 7), ("opcode", 6, 0)],
+    }
+}
+
+#[component]
+#[allow(non_snake_case)]
+pub fn DecoderView() -> Element {
+    let mut input = use_signal(|| "0x00A28293".to_string());
+    let decoded = use_memo(move || parse_input(&input.read()));
+
+    rsx!(
+        h3 { class: H3_STYLE, "Instruction Decoder" }
+
+        p { class: P_STYLE,
+            "Enter a 32-bit instruction word in hex (e.g. 0x00A28293) or a single line of assembly (e.g. addi x5, x5, 10) to see how it is encoded. The breakdown updates as you type."
+        }
+
+        input {
+            class: "w-full font-mono text-sm bg-white border border-gray-300 rounded px-2 py-1 mb-4",
+            value: "{input}",
+            oninput: move |event| input.set(event.value()),
+        }
+
+        match &*decoded.read() {
+            Ok(raw) => {
+                let instruction = Instruction::from_raw(*raw);
+                let format = InstructionDefinition::from_instr(instruction).map(|d| d.format);
+                let name = ISA::instr_to_isa(instruction)
+                    .map(|isa| format!("{:?}", isa))
+                    .unwrap_or("Unknown instruction".to_string());
+                rsx! {
+                    h4 { class: H4_STYLE, "{name}" }
+                    p { class: P_STYLE,
+                        "Encoding: "
+                        code { class: "bg-gray-200 rounded px-1", "0x{raw:08X}" }
+                        if let Some(format) = format {
+                            " ({format:?}-type)"
+                        }
+                    }
+                    table { class: "w-full font-mono text-sm mb-2",
+                        thead {
+                            tr { class: "text-left text-gray-500",
+                                th { "Field" }
+                                th { "Bits" }
+                                th { "Value" }
+                            }
+                        }
+                        tbody {
+                            for (field , hi , lo) in fields(format) {
+                                {
+                                    let width = (hi - lo + 1) as usize;
+                                    let value = (raw >> lo) & (u32::MAX >> (32 - width));
+                                    rsx! {
+                                        tr { class: "border-t border-gray-200",
+                                            td { "{field}" }
+                                            td { "[{hi}:{lo}]" }
+                                            td { "{value:0width$b}" }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    if let (Some(immediate), Some(asm)) = (instruction.immediate(), instruction.immediate_asm()) {
+                        p { class: P_STYLE, "Immediate: {asm} (0x{immediate:08X})" }
+                    }
+                }
+            }
+            Err(error) => rsx! {
+                p { class: "text-red-600 text-sm", "{error}" }
+            },
+        }
+    )
+}
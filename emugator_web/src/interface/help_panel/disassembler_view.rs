@@ -0,0 +1,80 @@
+use dioxus::prelude::*;
+use emugator_core::isa::Instruction;
+
+use crate::interface::help_panel::{H3_STYLE, P_STYLE};
+
+/// Disassembly of one pasted word, or why it could not be disassembled
+type Line = (String, Result<String, String>);
+
+/// Parses whitespace or comma separated hex words, with or without `0x`, and
+/// disassembles each as if the first were at address 0
+fn disassemble(input: &str) -> Vec<Line> {
+    input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty())
+        .enumerate()
+        .map(|(i, word)| {
+            let hex = word
+                .strip_prefix("0x")
+                .or_else(|| word.strip_prefix("0X"))
+                .unwrap_or(word);
+            let raw = (!hex.is_empty() && hex.len() <= 8)
+                .then(|| u32::from_str_radix(hex, 16).ok())
+                .flatten();
+            let result = match raw {
+                Some(raw) => Instruction::from_raw(raw)
+                    .to_asm(4 * i as u32, |_| None)
+                    .ok_or_else(|| "Not a valid instruction".to_string()),
+                None => Err("Not a 32-bit hex word".to_string()),
+            };
+            (word.to_string(), result)
+        })
+        .collect()
+}
+
+#[component]
+#[allow(non_snake_case)]
+pub fn DisassemblerView() -> Element {
+    let mut input = use_signal(|| "0x00208033 0x00A28293\n0x00000463 0x00100073".to_string());
+    let lines = use_memo(move || disassemble(&input.read()));
+
+    rsx!(
+        h3 { class: H3_STYLE, "Disassembler" }
+
+        p { class: P_STYLE,
+            "Paste 32-bit instruction words in hex, separated by spaces, commas or new lines, to see the assembly each encodes. The words are read as consecutive instructions starting at address 0, so branch and jump targets are shown as addresses from there."
+        }
+
+        textarea {
+            class: "w-full h-32 font-mono text-sm bg-white border border-gray-300 rounded px-2 py-1 mb-4",
+            value: "{input}",
+            oninput: move |event| input.set(event.value()),
+        }
+
+        table { class: "w-full font-mono text-sm mb-2",
+            thead {
+                tr { class: "text-left text-gray-500",
+                    th { "Address" }
+                    th { "Word" }
+                    th { "Assembly" }
+                }
+            }
+            tbody {
+                for (i , (word , result)) in lines.read().iter().enumerate() {
+                    tr { class: "border-t border-gray-200",
+                        td { "0x{4 * i:08X}" }
+                        td { "{word}" }
+                        match result {
+                            Ok(asm) => rsx! {
+                                td { "{asm}" }
+                            },
+                            Err(error) => rsx! {
+                                td { class: "text-red-600", "{error}" }
+                            },
+                        }
+                    }
+                }
+            }
+        }
+    )
+}
@@ -47,6 +47,19 @@ pub fn FiveStageView() -> Element {
         p { class: P_STYLE,
             "Each instruction is visually tracked as it progresses through these stages, helping students develop a deeper understanding of pipeline behavior and instruction-level parallelism."
         }
+
+        p { class: P_STYLE,
+            "Above the diagram, each stage is labeled with the instruction it holds. When a hazard stalls the pipeline, the hazard unit sends a no op down in place of an instruction, which is shown as a grayed "
+            code { class: "bg-gray-200 rounded px-1", "bubble" },
+            " so stall cycles can be counted as they move through EX, MEM and WB. An explicit "
+            code { class: "bg-gray-200 rounded px-1", "addi x0, x0, 0" },
+            " in the program is a real instruction and is labeled "
+            code { class: "bg-gray-200 rounded px-1", "nop" },
+            " instead."
+        }
+
+        p { class: P_STYLE,
+            "Below each pipeline buffer is the mnemonic of the instruction it is passing to the next stage, so the buffers can be followed without hovering. Buffers feeding an empty stage or a bubble are left blank."
+        }
     )
 }
-
@@ -20,11 +20,37 @@ pub fn InterfacePageView() -> Element {
             li { strong { "Start/Reload" }, ": Assembles, initializes, and reloads the program." }
             li { strong { "Tick Clock" }, ": Advances the emulator by one clock cycle." }
             li { strong { "Next Instruction" }, ": Executes a single instruction through the pipeline." }
+            li { strong { "Step Out" }, ": Runs until the current function returns to its caller, stepping over any calls it makes along the way." }
             li { strong { "Until Break" }, ": Continues execution until a breakpoint is reached." }
+            li { strong { "Detect Stuck Loops" }, ": Makes Until Break stop early when the program repeats the exact same state, such as a jump to itself." }
+            li { strong { "Break on UART Output" }, ": Makes Until Break stop as soon as the program transmits a byte over the UART, showing the byte that was sent." }
+            li { strong { "Detect Stack Overflow" }, ": Makes Until Break stop when a store through ", code { "sp" }, " writes over the program's instructions or data, which usually means the stack grew too large, such as from recursion that never ends." }
+            li { strong { "Break on Text Writes" }, ": Makes Until Break stop when any store writes to the address of one of the program's instructions. Instruction and data memory are separate, so the store only changes data memory and self-modifying code has no effect on what runs." }
+            li { strong { "Break on" }, ": Makes Until Break stop the next time an instruction with the chosen mnemonic retires, wherever it is, such as ", code { "jalr" }, " to stop on the next return or ", code { "lw" }, " to stop on the next load." }
+            li { strong { "Show Encodings" }, ": Shows the machine code each line assembled to, as hexadecimal words, at the end of the line in the editor. It updates whenever the program is reassembled." }
+            li { strong { "Share" }, ": Copies a link that opens EmuGator with the current file's source in the editor, so examples can be shared without a server. The source is compressed into the link itself, and very large files are too big to share this way. A link that can't be read opens the demo program instead." }
+            li { strong { "Show Stages" }, ": Labels each highlighted line in the editor with the pipeline stages its instructions are in, such as ", code { "EX" }, " or ", code { "MEM WB" }, " when a loop puts the same line in two stages, so the editor doubles as a pipeline diagram." }
+            li { strong { "Back to Break" }, ": Moves back through the recorded states to the last one stopped at a breakpoint." }
+            li { strong { "Memory Map" }, ": Sets where the ", code { ".text" }, " and ", code { ".data" }, " sections start when a section directive gives no address, the address of the UART, and how many bytes of data memory are zeroed at reset. The ", strong { "Zero" }, " preset starts both sections at 0 and the ", strong { "Classic" }, " preset uses the classic RISC-V addresses ", code { "0x00400000" }, " and ", code { "0x10010000" }, ". Addresses can be typed in hex or decimal, and changing them stops the running program." }
+            li { strong { "Directives" }, ": Chooses whether directives EmuGator doesn't know, like ", code { ".globl" }, " or ", code { ".file" }, ", stop assembly or are skipped with a warning, which helps when assembling GCC output. Debugging directives such as ", code { ".size" }, ", ", code { ".type" }, ", ", code { ".ident" }, " and ", code { ".cfi_*" }, " are always skipped." }
+            li { strong { "Memory" }, ": Chooses whether data memory the program does not define is zeroed when the program starts, or left undefined. With undefined memory, the Data Memory tab points out bytes the program read before storing to them." }
+            li { strong { "Latency" }, ": Sets how many extra cycles loads, stores and register-register arithmetic take. Once such an instruction reaches the pipeline's last stage, the whole pipeline holds still for its extra cycles while the cycle counter, timer and UART keep running, so the ", code { "cycle" }, " counter shows the cost of slow instructions. All zero is the usual single-cycle timing. Applies on the next start." }
             li { strong { "Pipeline Toggle" }, ": Toggles between the Two and Five-stage pipelines." }
         }
         p { class: P_STYLE,
-            "You'll also find a ", strong { "status indicator" }, " that shows whether the program is running, ready, or has errors."
+            "The pipeline, memory map, directive handling, latencies, UART delay and the Highlight Hazards, Show Encodings and Show Stages toggles are remembered in the browser, so they are the same the next time EmuGator is opened."
+        }
+        p { class: P_STYLE,
+            "Once a program is running, the ", strong { "timeline" }, " below the control bar lets you drag back through previously recorded states. Stepping from an earlier state discards the states that came after it."
+        }
+        p { class: P_STYLE,
+            "You'll also find a ", strong { "status indicator" }, " that shows whether the source is still assembling, how many instructions it assembled to, or how many errors it has. Click the error count to jump to the first error."
+        }
+        p { class: P_STYLE,
+            "While a program runs, the control bar also previews the ", strong { "next instruction" }, " to be decoded, with its address, its disassembly, and the file and line it came from."
+        }
+        p { class: P_STYLE,
+            "If execution reaches an address with no instruction, such as by running past the end of the program, or a word that is not a valid instruction, such as after jumping into data, the emulator halts and the control bar shows the faulting address. A ", code { "wfi" }, " instruction halts the program the same way. EmuGator does not emulate interrupts, so nothing could ever wake the processor and ", code { "wfi" }, " is treated as the end of the program."
         }
 
         h4 { class: H4_STYLE, "Editor" }
@@ -40,6 +66,28 @@ pub fn InterfacePageView() -> Element {
             code { class: "px-1 py-0.5 bg-gray-200 rounded text-sm", "F1" },
             " while focused on the editor to bring up a menu that lists helpful shortcuts."
         }
+        p { class: P_STYLE,
+            "As you type, the editor suggests instruction mnemonics along with the operands each one expects. Hover over a mnemonic to see its format, operand order, and a short description of what it does."
+        }
+        p { class: P_STYLE,
+            "Placing the cursor on a register highlights every use of that register in the file, whether it is written by number or by ABI name, so "
+            code { class: "px-1 py-0.5 bg-gray-200 rounded text-sm", "x10" },
+            " and "
+            code { class: "px-1 py-0.5 bg-gray-200 rounded text-sm", "a0" },
+            " are highlighted together."
+        }
+        p { class: P_STYLE,
+            "Labels that nothing refers to are underlined as warnings, since they often mean a branch or jump misspelled the label it meant, such as ", code { "lop" }, " instead of ", code { "loop" }, ". Entry points named ", code { "main" }, " or ", code { "_start" }, " are never flagged."
+        }
+        p { class: P_STYLE,
+            "Branches and jumps whose target is not an instruction are also underlined as warnings, since landing in data or past the end of the program is almost always a mistake."
+        }
+        p { class: P_STYLE,
+            "Instructions that compute a value only to write it to ", code { "x0" }, ", such as ", code { "add x0, x1, x2" }, ", are flagged too, since the result is thrown away. The usual no-op ", code { "addi x0, x0, 0" }, " and jumps that don't link are left alone."
+        }
+        p { class: P_STYLE,
+            "For programs that work on large inputs, upload a data file with the button at the end of the file tabs and place its bytes in memory with ", code { ".incbin \"name\"" }, ", using the uploaded file's name. Uploaded files stay available until removed and are not editable."
+        }
 
         h5 { class: H5_STYLE, "Editor Breakpoints" }
         p { class: P_STYLE,
@@ -61,6 +109,12 @@ pub fn InterfacePageView() -> Element {
         p { class: P_STYLE,
             "Switching between ", strong { "Two-Stage" }, " and ", strong { "Five-Stage" }, " views allows for flexible learning depending on your course or curiosity."
         }
+        p { class: P_STYLE,
+            "The ", strong { "Legend" }, " button beside the control signal toggle explains what the diagram's colors mean, such as which parts the current instruction is using and what each control signal color stands for."
+        }
+        p { class: P_STYLE,
+            "The ", strong { "Download SVG" }, " button in the bottom left corner saves the diagram as it looks now, colors included, as a standalone ", code { ".svg" }, " file for slides or bug reports. The button beside it exports a range of recorded cycles as an animation."
+        }
 
         h4 { class: H4_STYLE, "Register View" }
         p { class: P_STYLE,
@@ -71,10 +125,43 @@ pub fn InterfacePageView() -> Element {
             code { class: "bg-gray-200 rounded px-1", "a0–a7" },
             " are labeled for quick identification."
         }
+        p { class: P_STYLE,
+            "Click a register to pin a small chart of its value over the last few recorded states. Up to two registers can be pinned at once."
+        }
+        p { class: P_STYLE,
+            "Check ", strong { "Show as f32" }, " to also see the float each register's bits encode, which helps when debugging software floating-point routines that keep IEEE-754 values in integer registers."
+        }
+        p { class: P_STYLE,
+            "Check ", strong { "Hide Unwritten" }, " to show only the registers the program has written since it started, hiding ", code { "zero" }, " and any register the program never touches. The count beside it is how many are hidden, and unchecking it shows all 32 again."
+        }
+        p { class: P_STYLE,
+            "The ", strong { "Copy as JSON" }, " button copies every register, under both its ", code { "x" }, " name and its ABI name, along with the PC in each pipeline stage, so you can paste the machine state into a bug report."
+        }
+        p { class: P_STYLE,
+            "The box beside the title evaluates an expression against the displayed state, such as ", code { "a0 + 4" }, " or ", code { "mem[sp + 8]" }, ". Registers can be named by number or ABI name, ", code { "mem[address]" }, " reads the word at an address, and labels and ", code { ".equ" }, " constants stand for their values. The result is shown in hex and decimal."
+        }
+        p { class: P_STYLE,
+            "Above the registers, the ", strong { "Call Stack" }, " lists the calls the program is inside, innermost first, by the line each one returns to. Calls are ", code { "jal" }, " or ", code { "jalr" }, " instructions that write ", code { "ra" }, ", and returns are ", code { "jalr" }, " through ", code { "ra" }, " that discard the link, so functions that link or return another way are not followed."
+        }
 
         h4 { class: H4_STYLE, "Memory View" }
         p { class: P_STYLE,
-            "At the bottom right, the ", strong { "Memory View" }, " shows instruction and data memory contents. You can observe how instructions are stored and watch memory values change as your program reads and writes data. Binary encodings and memory addresses are displayed side-by-side, helping reinforce how high-level assembly maps to actual machine code. Additionally, data memory is displayed in both raw bytes expressed as hex and as ASCII if the value at that location is a valid ASCII character."
+            "At the bottom right, the ", strong { "Memory View" }, " shows instruction and data memory contents. You can observe how instructions are stored and watch memory values change as your program reads and writes data. Binary encodings and memory addresses are displayed side-by-side, helping reinforce how high-level assembly maps to actual machine code. Additionally, data memory is displayed in both raw bytes expressed as hex and as ASCII if the value at that location is a valid ASCII character. Bytes that changed during the last step are highlighted, making the effect of each store easy to spot."
+        }
+        p { class: P_STYLE,
+            "Each instruction in instruction memory is also shown disassembled, with branch and jump targets named after the label they land on, or a nearby label and an offset such as ", code { "loop+8" }, "."
+        }
+        p { class: P_STYLE,
+            "Click a byte in data memory to place the cursor there, then use the arrow keys to move it. Holding ", strong { "Shift" }, " while clicking or moving selects a range of bytes, and ", strong { "Escape" }, " clears the selection. The keys only apply while the memory grid has focus, so typing in the editor is unaffected."
+        }
+        p { class: P_STYLE,
+            "Check ", strong { "Disassemble Text" }, " to show each aligned word at an instruction's address as the instruction assembled there instead of its bytes. Hover over it to see its encoding. Words outside the text section, or not aligned to four bytes, stay as bytes."
+        }
+        p { class: P_STYLE,
+            "The ", strong { "Typed Data" }, " tab reads data memory starting at an address or label as a chosen type, such as signed and unsigned integers, floats, characters, or null-terminated strings."
+        }
+        p { class: P_STYLE,
+            "The ", strong { "Compare Runs" }, " tab helps track down regressions. Snapshot a run of the program, change the source, then compare to see which registers, data bytes, and UART output ended up different."
         }
     )
 }
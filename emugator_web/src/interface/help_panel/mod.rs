@@ -1,21 +1,25 @@
 mod assembler_page_view;
+mod decoder_view;
+mod disassembler_view;
+mod five_stage_view;
 mod interface_page_view;
 mod intro_page_view;
 mod page_not_found_view;
 mod quick_start_view;
 mod two_stage_view;
-mod five_stage_view;
 mod uart_view;
 
 use dioxus::prelude::*;
 
 use assembler_page_view::AssemblerPageView;
+use decoder_view::DecoderView;
+use disassembler_view::DisassemblerView;
+use five_stage_view::FiveStageView;
 use interface_page_view::InterfacePageView;
 use intro_page_view::IntroPageView;
 use page_not_found_view::PageNotFoundView;
 use quick_start_view::QuickStartView;
 use two_stage_view::TwoStageView;
-use five_stage_view::FiveStageView;
 use uart_view::UartView;
 
 // Style class constants
@@ -35,6 +39,8 @@ pub fn HelpPanelView() -> Element {
         "Quick Start",
         "Interface",
         "Assembler and Instructions",
+        "Instruction Decoder",
+        "Disassembler",
         "Two stage",
         "Five stage",
         "UART",
@@ -61,6 +67,8 @@ pub fn HelpPanelView() -> Element {
                     "Quick Start" => rsx!(QuickStartView {}),
                     "Interface" => rsx!(InterfacePageView {}),
                     "Assembler and Instructions" => rsx!(AssemblerPageView {}),
+                    "Instruction Decoder" => rsx!(DecoderView {}),
+                    "Disassembler" => rsx!(DisassemblerView {}),
                     "Two stage" => rsx!(TwoStageView {}),
                     "Five stage" => rsx!(FiveStageView {}),
                     "UART" => rsx!(UartView {}),
@@ -1,10 +1,22 @@
 use dioxus::prelude::*;
+use emugator_core::assembler::MemoryMap;
 
 use crate::interface::help_panel::{H3_STYLE, H4_STYLE, P_STYLE};
 
 #[component]
 #[allow(non_snake_case)]
 pub fn UartView() -> Element {
+    let map = MemoryMap::default();
+    let data = format!("0x{:02X}", map.uart);
+    let status = format!("0x{:02X}", map.uart_status());
+    let mtime = format!("0x{:08X}", map.timer);
+    let mtime_high = format!("0x{:08X}", map.timer + 4);
+    let mtimecmp = format!("0x{:08X}", map.timer_compare());
+    let mtimecmp_high = format!("0x{:08X}", map.timer_compare() + 4);
+    // The timer sits at the top of memory, so programs reach it with a
+    // negative offset from x0
+    let mtime_offset = map.timer as i32;
+
     rsx!(
         h3 { class: H3_STYLE, "UART" }
 
@@ -18,7 +30,7 @@ pub fn UartView() -> Element {
             ul { class: "list-disc list-inside mb-2",
                 li {
                     strong { "DATA Register (TX/RX):" }
-                    code { class: "ml-1 bg-gray-100 rounded px-1", "0xF0" }
+                    code { class: "ml-1 bg-gray-100 rounded px-1", "{data}" }
                     ul {
                         li { class: "ml-4", "Write to this address to transmit a character." }
                         li { class: "ml-4", "Read from this address to receive a character." }
@@ -26,7 +38,7 @@ pub fn UartView() -> Element {
                 }
                 li {
                     strong { "Line Status Register (LSR):" }
-                    code { class: "ml-1 bg-gray-100 rounded px-1", "0xF4" }
+                    code { class: "ml-1 bg-gray-100 rounded px-1", "{status}" }
                     ul { class: "mt-1",
                         li {
                             code { class: "bg-gray-100 rounded px-1", "TX_READY = 0x04" }
@@ -41,6 +53,18 @@ pub fn UartView() -> Element {
             }
         }
 
+        p { class: P_STYLE,
+            "These are the default addresses. The UART can be moved in the navbar's Memory Map settings, and the line status register always follows 4 bytes after the data register."
+        }
+
+        h4 { class: H4_STYLE, "Transmission Delay" }
+
+        p { class: P_STYLE,
+            "Each byte takes a number of clock cycles to send or receive. The delay slider in the UART Console header sets this number, so larger delays keep "
+            code { class: "bg-gray-100 rounded px-1", "TX_READY" }
+            " clear for longer and make polling loops spin visibly while they wait."
+        }
+
         h4 { class: H4_STYLE, "UART Output Example (Print Function)" }
 
         p { class: P_STYLE,
@@ -52,8 +76,8 @@ pub fn UartView() -> Element {
         pre { class: "bg-gray-900 text-gray-100 text-xs rounded p-4 overflow-x-auto my-2",
             code {
                 r#".data
-.equ DATA_ADR, 0xF0
-.equ LSR_ADR, 0xF4
+.equ DATA_ADR, {data}
+.equ LSR_ADR, {status}
 .equ TX_READY, 1 << 2
 message: .string "Alas!\nPoor\tYorick\n"
 
@@ -97,8 +121,8 @@ print_end:
         pre { class: "bg-gray-900 text-gray-100 text-xs rounded p-4 overflow-x-auto my-4",
             code {
                 r#".data
-.equ DATA_ADR, 0xF0
-.equ LSR_ADR, 0xF4
+.equ DATA_ADR, {data}
+.equ LSR_ADR, {status}
 .equ RX_READY, 1 << 0
 
 input: .zero 50                # input buffer
@@ -132,6 +156,63 @@ wait_rx:
             }
         }
 
+        h4 { class: H4_STYLE, "Timer" }
+        p { class: P_STYLE,
+            "EmuGator also has a machine timer. Its 64-bit "
+            code { class: "bg-gray-100 rounded px-1", "mtime" }
+            " register counts every clock cycle since the program started, and the 64-bit "
+            code { class: "bg-gray-100 rounded px-1", "mtimecmp" }
+            " register holds a deadline you can store and read back. EmuGator does not emulate interrupts, so programs poll the timer instead of waiting for it."
+        }
+        div { class: "ml-6 text-sm",
+            ul { class: "list-disc list-inside mb-2",
+                li {
+                    strong { "mtime:" }
+                    code { class: "ml-1 bg-gray-100 rounded px-1", "{mtime}" }
+                    " (low word) and "
+                    code { class: "bg-gray-100 rounded px-1", "{mtime_high}" }
+                    " (high word)"
+                }
+                li {
+                    strong { "mtimecmp:" }
+                    code { class: "ml-1 bg-gray-100 rounded px-1", "{mtimecmp}" }
+                    " (low word) and "
+                    code { class: "bg-gray-100 rounded px-1", "{mtimecmp_high}" }
+                    " (high word)"
+                }
+            }
+        }
+        p { class: P_STYLE,
+            "This program waits 100 cycles by reading the low word of "
+            code { class: "bg-gray-100 rounded px-1", "mtime" }
+            " until it passes a deadline, no matter how many instructions the loop takes. The timer sits at the top of memory, so the program reaches it as a negative offset from "
+            code { class: "bg-gray-100 rounded px-1", "x0" }
+            ". It can be moved in the navbar's Memory Map settings."
+        }
+
+        pre { class: "bg-gray-900 text-gray-100 text-xs rounded p-4 overflow-x-auto my-2",
+            code {
+                r#".equ MTIME_ADR, {mtime_offset}
+
+.text
+main:
+    ADDI x10, x0, 100           # x10 (a0) = cycles to wait (argument register)
+    JAL x1, delay               # call delay function, return address in x1 (ra)
+
+loop_forever:
+    JAL x0, loop_forever        # infinite loop
+
+delay:
+    LW x5, MTIME_ADR(x0)        # x5 (t0) = current time
+    ADD x5, x5, x10             # x5 (t0) = deadline
+wait_time:
+    LW x6, MTIME_ADR(x0)        # x6 (t1) = current time
+    BLTU x6, x5, wait_time      # wait until the deadline passes
+    JALR x0, x1, 0              # return using x1 (ra)
+"#
+            }
+        }
+
         h4 { class: H4_STYLE, "Register Usage Notes" }
         p { class: P_STYLE, "These examples demonstrate proper RISC-V register conventions:" }
         div { class: "ml-6 text-sm",
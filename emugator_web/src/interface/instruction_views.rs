@@ -33,15 +33,8 @@ pub fn InstructionView(
                     for i in 0..total_instructions {
                         {
                             let base_addr = text_start + i * 4;
-                            let instruction = (instruction_memory.get(&(base_addr)).copied().unwrap_or(0)
-                                as u32)
-                                | ((instruction_memory.get(&(base_addr + 1)).copied().unwrap_or(0) as u32)
-                                    << 8)
-                                | ((instruction_memory.get(&(base_addr + 2)).copied().unwrap_or(0) as u32)
-                                    << 16)
-                                | ((instruction_memory.get(&(base_addr + 3)).copied().unwrap_or(0) as u32)
-                                    << 24);
-                            let instr = Instruction::from_raw(instruction);
+                            let instr = program.instruction_at(base_addr).unwrap_or(Instruction::from_raw(0));
+                            let instruction = instr.raw();
                             let instr_frmt = InstructionDefinition::from_instr(instr).unwrap().format;
                             rsx! {
                                 div {
@@ -105,7 +98,10 @@ pub fn InstructionView(
                                                     span { class: "font-mono font-bold text-gray-500 text-xs", "{instruction:032b}" }
                                                 }
                                             }
-                                            if let Some(line) = program.source_map.get_by_left(&base_addr) {
+                                            if let Some(asm) = program.disassemble(base_addr) {
+                                                span { class: "font-mono text-xs text-gray-700", "{asm}" }
+                                            }
+                                            if let Some(line) = program.source_line_at(base_addr) {
                                                 span { class: "text-xs text-gray-500", "Line {line}" }
                                             }
                                         }
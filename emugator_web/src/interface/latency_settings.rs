@@ -0,0 +1,98 @@
+use dioxus::prelude::*;
+use dioxus_free_icons::Icon;
+use dioxus_free_icons::icons::ld_icons::LdHourglass;
+use emugator_core::emulator::latency::LatencyModel;
+
+/// A kind of instruction with a configurable latency, as its label, tooltip,
+/// and how to read and change its extra cycles
+type LatencyKind = (
+    &'static str,
+    &'static str,
+    fn(&LatencyModel) -> u32,
+    fn(&mut LatencyModel, u32),
+);
+
+const KINDS: [LatencyKind; 3] = [
+    (
+        "Loads",
+        "Extra cycles for lb, lh, lw, lbu and lhu, which also lengthen load-use stalls",
+        |latencies| latencies.load,
+        |latencies, cycles| latencies.load = cycles,
+    ),
+    (
+        "Stores",
+        "Extra cycles for sb, sh and sw",
+        |latencies| latencies.store,
+        |latencies, cycles| latencies.store = cycles,
+    ),
+    (
+        "Arithmetic",
+        "Extra cycles for register-register arithmetic such as add and sll",
+        |latencies| latencies.arithmetic,
+        |latencies, cycles| latencies.arithmetic = cycles,
+    ),
+];
+
+/// Most extra cycles an instruction may take
+const MAX_EXTRA_CYCLES: u32 = 20;
+
+/// Collapsible table of the extra cycles each kind of instruction takes.
+/// Applies on the next start.
+#[component]
+#[allow(non_snake_case)]
+pub fn LatencySettings(latencies: Signal<LatencyModel>) -> Element {
+    let mut expanded = use_signal(|| false);
+    let is_single_cycle = *latencies.read() == LatencyModel::default();
+
+    rsx! {
+        div { class: "relative",
+            button {
+                class: format!(
+                    "flex items-center gap-x-1 text-sm py-1 px-2 rounded cursor-pointer {}",
+                    if *expanded.read() { "bg-blue-500 text-white" } else { "bg-gray-700 text-gray-300 hover:bg-gray-600" },
+                ),
+                title: "Extra cycles loads, stores and arithmetic take. Applies on the next start.",
+                onclick: move |_| {
+                    let shown = *expanded.read();
+                    expanded.set(!shown);
+                },
+                Icon { width: 16, height: 16, icon: LdHourglass }
+                if is_single_cycle {
+                    "Latency"
+                } else {
+                    "Latency*"
+                }
+            }
+            if *expanded.read() {
+                div { class: "absolute top-9 right-0 z-20 bg-gray-800 text-gray-200 text-sm rounded p-2 flex flex-col gap-2 w-56 shadow-lg",
+                    for (label , title , get , set) in KINDS {
+                        label { class: "flex items-center justify-between gap-x-2", title,
+                            "{label}"
+                            input {
+                                class: "bg-gray-700 text-white rounded py-0.5 px-1 w-16 font-mono",
+                                r#type: "number",
+                                min: 0,
+                                max: MAX_EXTRA_CYCLES,
+                                value: get(&latencies.read()),
+                                onchange: move |event| {
+                                    if let Ok(cycles) = event.value().parse::<u32>() {
+                                        let mut model = *latencies.peek();
+                                        set(&mut model, cycles.min(MAX_EXTRA_CYCLES));
+                                        latencies.set(model);
+                                    }
+                                },
+                            }
+                        }
+                    }
+                    button {
+                        class: "py-0.5 px-2 rounded cursor-pointer bg-gray-700 hover:bg-gray-600",
+                        disabled: is_single_cycle,
+                        onclick: move |_| latencies.set(LatencyModel::default()),
+                        "Single Cycle"
+                    }
+                    span { class: "text-gray-400 text-xs", "Extra cycles hold the whole pipeline once the instruction reaches its last stage. Applies on the next start." }
+                }
+            }
+        }
+    }
+}
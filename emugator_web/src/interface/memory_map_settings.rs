@@ -0,0 +1,130 @@
+use dioxus::prelude::*;
+use dioxus_free_icons::Icon;
+use dioxus_free_icons::icons::ld_icons::LdSettings;
+use emugator_core::{
+    assembler::{MemoryMap, SectionBases},
+    utils::parse_address,
+};
+
+/// An address of the memory map, as its label, tooltip, and how to read and
+/// change it
+type Field = (
+    &'static str,
+    &'static str,
+    fn(&MemoryMap) -> u32,
+    fn(&mut MemoryMap, u32),
+);
+
+const FIELDS: [Field; 5] = [
+    (
+        "Text Base",
+        "Where .text starts when a section directive gives no address",
+        |map| map.sections.text,
+        |map, value| map.sections.text = value,
+    ),
+    (
+        "Data Base",
+        "Where .data starts when a section directive gives no address",
+        |map| map.sections.data,
+        |map, value| map.sections.data = value,
+    ),
+    (
+        "UART",
+        "Address of the UART's data register. The line status register is 4 bytes after it.",
+        |map| map.uart,
+        |map, value| map.uart = value,
+    ),
+    (
+        "Timer",
+        "Address of the timer's mtime register. The mtimecmp register is 8 bytes after it.",
+        |map| map.timer,
+        |map, value| map.timer = value,
+    ),
+    (
+        "Zeroed Size",
+        "Bytes of data memory cleared at reset when undefined memory is zeroed",
+        |map| map.zeroed_size,
+        |map, value| map.zeroed_size = value,
+    ),
+];
+
+/// Collapsible panel for where the sections, the UART, the timer and zeroed
+/// memory live.
+/// `onchange` receives the new map whenever a field is committed or a preset
+/// is picked.
+#[component]
+#[allow(non_snake_case)]
+pub fn MemoryMapSettings(
+    memory_map: ReadOnlySignal<MemoryMap>,
+    onchange: EventHandler<MemoryMap>,
+) -> Element {
+    let mut expanded = use_signal(|| false);
+    let mut error: Signal<Option<String>> = use_signal(|| None);
+
+    let presets = [
+        ("Zero", MemoryMap::default()),
+        ("Classic", MemoryMap::with_sections(SectionBases::CLASSIC)),
+    ];
+
+    rsx! {
+        div { class: "relative",
+            button {
+                class: format!(
+                    "flex items-center gap-x-1 text-sm py-1 px-2 rounded cursor-pointer {}",
+                    if *expanded.read() { "bg-blue-500 text-white" } else { "bg-gray-700 text-gray-300 hover:bg-gray-600" },
+                ),
+                title: "Where the sections, the UART, the timer and zeroed memory live",
+                onclick: move |_| {
+                    let shown = *expanded.read();
+                    expanded.set(!shown);
+                },
+                Icon { width: 16, height: 16, icon: LdSettings }
+                "Memory Map"
+            }
+            if *expanded.read() {
+                div { class: "absolute top-9 right-0 z-20 bg-gray-800 text-gray-200 text-sm rounded p-2 flex flex-col gap-2 w-64 shadow-lg",
+                    div { class: "flex items-center gap-x-2",
+                        span { class: "text-gray-400", "Presets" }
+                        for (name , preset) in presets {
+                            button {
+                                class: format!(
+                                    "py-0.5 px-2 rounded cursor-pointer {}",
+                                    if *memory_map.read() == preset { "bg-blue-500 text-white" } else { "bg-gray-700 hover:bg-gray-600" },
+                                ),
+                                onclick: move |_| {
+                                    error.set(None);
+                                    onchange.call(preset);
+                                },
+                                "{name}"
+                            }
+                        }
+                    }
+                    for (label , title , get , set) in FIELDS {
+                        label { class: "flex items-center justify-between gap-x-2", title,
+                            "{label}"
+                            input {
+                                class: "bg-gray-700 text-white rounded py-0.5 px-1 w-28 font-mono",
+                                value: format!("0x{:08X}", get(&memory_map.read())),
+                                onchange: move |event| {
+                                    match parse_address(&event.value()) {
+                                        Some(value) => {
+                                            let mut map = *memory_map.peek();
+                                            set(&mut map, value);
+                                            error.set(None);
+                                            onchange.call(map);
+                                        }
+                                        None => error.set(Some(format!("'{}' is not an address", event.value()))),
+                                    }
+                                },
+                            }
+                        }
+                    }
+                    if let Some(message) = error.read().as_ref() {
+                        span { class: "text-red-400 text-xs", "{message}" }
+                    }
+                    span { class: "text-gray-400 text-xs", "Changing the map stops the running program." }
+                }
+            }
+        }
+    }
+}
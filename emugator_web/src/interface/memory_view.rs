@@ -1,13 +1,17 @@
-use super::data_views::DataView;
+use super::data_views::{DataView, TypedDataView};
+use super::file_tabs::SourceFile;
 use super::instruction_views::InstructionView;
+use super::run_comparison::RunComparison;
 use dioxus::prelude::*;
-use emugator_core::assembler::AssembledProgram;
-use emugator_core::emulator::AnyEmulatorState;
+use emugator_core::assembler::{AssembledProgram, MemoryMap};
+use emugator_core::emulator::{AnyEmulatorState, EmulatorOption};
 
 #[derive(PartialEq, Clone, Copy)]
 pub enum MemoryViewType {
     Instruction,
     Data,
+    Typed,
+    Compare,
 }
 
 #[component]
@@ -15,6 +19,11 @@ pub enum MemoryViewType {
 pub fn MemoryView(
     assembled_program: ReadOnlySignal<Option<AssembledProgram>>,
     emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>,
+    previous_state: ReadOnlySignal<Option<AnyEmulatorState>>,
+    files: ReadOnlySignal<Vec<SourceFile>>,
+    serial_input: ReadOnlySignal<String>,
+    selected_emulator: ReadOnlySignal<EmulatorOption>,
+    memory_map: ReadOnlySignal<MemoryMap>,
 ) -> Element {
     let mut view_type = use_signal(|| MemoryViewType::Instruction);
 
@@ -35,6 +44,20 @@ pub fn MemoryView(
                     onclick: move |_| view_type.set(MemoryViewType::Data),
                     "Data Memory"
                 }
+                span { class: "text-lg font-mono font-bold text-gray-200", "/" }
+                button {
+                    class: "text-lg font-mono font-bold text-gray-200 hover:text-gray-300 transition-colors cursor-pointer",
+                    style: if *view_type.read() == MemoryViewType::Typed { "text-decoration: underline" } else { "" },
+                    onclick: move |_| view_type.set(MemoryViewType::Typed),
+                    "Typed Data"
+                }
+                span { class: "text-lg font-mono font-bold text-gray-200", "/" }
+                button {
+                    class: "text-lg font-mono font-bold text-gray-200 hover:text-gray-300 transition-colors cursor-pointer",
+                    style: if *view_type.read() == MemoryViewType::Compare { "text-decoration: underline" } else { "" },
+                    onclick: move |_| view_type.set(MemoryViewType::Compare),
+                    "Compare Runs"
+                }
             }
 
             div { class: "flex-grow overflow-hidden",
@@ -43,7 +66,13 @@ pub fn MemoryView(
                         InstructionView { assembled_program, emulator_state }
                     },
                     MemoryViewType::Data => rsx! {
-                        DataView { assembled_program, emulator_state }
+                        DataView { assembled_program, emulator_state, previous_state }
+                    },
+                    MemoryViewType::Typed => rsx! {
+                        TypedDataView { assembled_program, emulator_state }
+                    },
+                    MemoryViewType::Compare => rsx! {
+                        RunComparison { files, serial_input, selected_emulator, memory_map }
                     },
                 }
             }
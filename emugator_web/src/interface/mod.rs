@@ -1,10 +1,19 @@
+mod call_stack_view;
 mod data_views;
+mod expression_view;
+mod file_tabs;
 mod help_panel;
 mod instruction_views;
+mod latency_settings;
+mod memory_map_settings;
 mod memory_view;
 mod navbar;
 mod pipeline_visualization;
-mod register_view;
+pub(crate) mod register_view;
+mod run_comparison;
+mod settings;
+mod share_link;
+mod timeline_view;
 mod uart_view;
 
 use std::{collections::BTreeSet, time::Duration};
@@ -14,14 +23,25 @@ use dioxus_logger::tracing::info;
 use dioxus_sdk::utils::timing::use_debounce;
 
 use self::{
-    help_panel::HelpPanelView, memory_view::MemoryView, navbar::Navbar,
-    pipeline_visualization::PipelineVisualization, register_view::RegisterView,
-    uart_view::UartView,
+    call_stack_view::CallStackView,
+    expression_view::ExpressionView,
+    file_tabs::{FileTabs, SourceFile},
+    help_panel::HelpPanelView,
+    memory_view::MemoryView,
+    navbar::Navbar,
+    pipeline_visualization::PipelineVisualization,
+    register_view::RegisterView,
+    settings::Settings,
+    timeline_view::TimelineView,
+    uart_view::{OutputFormat, UartView},
 };
-use crate::code_editor::{CodeEditor, LineHighlight};
+use crate::code_editor::{CodeEditor, LineEncoding, LineHighlight, LineStages};
 use emugator_core::{
-    assembler::{self, AssembledProgram, AssemblerError},
-    emulator::{AnyEmulatorState, EmulatorOption},
+    assembler::{self, AssembledProgram, AssemblerError, MemoryMap},
+    emulator::{
+        AnyEmulatorState, EmulatorOption, latency::LatencyModel, memory_module::MemoryInit,
+        register_file::RegisterInit,
+    },
     include_test_file,
 };
 
@@ -31,22 +51,132 @@ pub static ASSEMBLED_PROGRAM: GlobalSignal<Option<AssembledProgram>> = GlobalSig
 #[component]
 #[allow(non_snake_case)]
 pub fn App() -> Element {
+    // Preferences from last session, saved again whenever one changes
+    let settings = use_hook(Settings::load);
     let serial_input = use_signal(|| String::new());
-    let source = use_signal(|| include_test_file!("beta-demo.s").to_string());
+    let uart_delay = use_signal(|| settings.uart_delay);
+    let uart_format: Signal<OutputFormat> = use_signal(|| settings.uart_format());
+    // A shared link opens its program, falling back to the demo when it doesn't decode
+    let mut files: Signal<Vec<SourceFile>> = use_signal(|| {
+        let source = share_link::source_from_url()
+            .unwrap_or_else(|| include_test_file!("beta-demo.s").to_string());
+        vec![SourceFile::new("main.s", source)]
+    });
+    let active_file: Signal<usize> = use_signal(|| 0);
+    // The contents of the active file, as edited in the code editor
+    let source = use_signal(|| files.peek()[0].source.clone());
     let mut assembler_errors: Signal<Vec<AssemblerError>> = use_signal(Vec::new);
-    let selected_emulator: Signal<EmulatorOption> = use_signal(|| EmulatorOption::CVE2);
+    let selected_emulator: Signal<EmulatorOption> = use_signal(|| settings.pipeline());
+    let memory_map: Signal<MemoryMap> = use_signal(|| settings.memory_map());
+    let latencies: Signal<LatencyModel> = use_signal(|| settings.latencies());
+    let memory_init: Signal<MemoryInit> = use_signal(|| settings.memory_init());
+    let register_init: Signal<RegisterInit> = use_signal(|| settings.register_init());
     let emulator_states: Signal<Vec<AnyEmulatorState>> = use_signal(|| vec![]);
-    let emulator_state_memo = use_memo(move || emulator_states.read().last().map(|e| e.to_owned()));
+    let history_position: Signal<Option<usize>> = use_signal(|| None);
+    let emulator_state_memo = use_memo(move || {
+        let states = emulator_states.read();
+        match *history_position.read() {
+            Some(position) => states.get(position).or(states.last()).map(|e| e.to_owned()),
+            None => states.last().map(|e| e.to_owned()),
+        }
+    });
     let emulator_state: ReadOnlySignal<_> = emulator_state_memo.into();
+    let previous_state_memo = use_memo(move || {
+        // The state shown before the last step, used to highlight changes
+        let states = emulator_states.read();
+        let current = history_position
+            .read()
+            .filter(|&position| position < states.len())
+            .unwrap_or(states.len().saturating_sub(1));
+        current
+            .checked_sub(1)
+            .and_then(|previous| states.get(previous))
+            .map(|e| e.to_owned())
+    });
+    let previous_state: ReadOnlySignal<_> = previous_state_memo.into();
     let breakpoints: Signal<BTreeSet<usize>> = use_signal(BTreeSet::new);
 
+    // keep the active file up to date with the editor
+    use_effect(move || {
+        let source = source.read().clone();
+        let breakpoints = breakpoints.read().clone();
+        if let Some(file) = files.write().get_mut(*active_file.peek()) {
+            file.source = source;
+            file.breakpoints = breakpoints;
+        }
+    });
+
+    let line_offsets_memo = use_memo(move || file_tabs::line_offsets(&files.read()));
+
+    // breakpoints of every file as lines of the joined program
+    let all_breakpoints_memo = use_memo(move || {
+        let files = files.read();
+        files
+            .iter()
+            .zip(line_offsets_memo.read().iter())
+            .flat_map(|(file, offset)| file.breakpoints.iter().map(move |line| line + offset))
+            .collect::<BTreeSet<usize>>()
+    });
+    let all_breakpoints: ReadOnlySignal<_> = all_breakpoints_memo.into();
+
+    let active_errors_memo = use_memo(move || {
+        let files = files.read();
+        let active_name = files
+            .get(*active_file.read())
+            .map(|file| file.name.as_str());
+        assembler_errors
+            .read()
+            .iter()
+            .filter(|error| error.file.is_none() || error.file.as_deref() == active_name)
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+    let active_errors: ReadOnlySignal<_> = active_errors_memo.into();
+
+    let assembler_warnings_memo = use_memo(move || {
+        let active = *active_file.read();
+        let offsets = line_offsets_memo.read();
+        ASSEMBLED_PROGRAM
+            .read()
+            .as_ref()
+            .map(|program| {
+                let mut warnings = assembler::validate_branch_targets(program);
+                warnings.extend(assembler::find_unused_labels(program));
+                warnings.extend(assembler::lint_operands(program));
+                warnings.extend(program.warnings.iter().cloned());
+                warnings
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(
+                |warning| match assembler::locate_line(&offsets, warning.line_number) {
+                    Some((index, line_number)) if index == active => {
+                        Some(warning.with_line_number(line_number))
+                    }
+                    _ => None,
+                },
+            )
+            .collect::<Vec<_>>()
+    });
+    let assembler_warnings: ReadOnlySignal<_> = assembler_warnings_memo.into();
+
     let minimize_console: Signal<bool> = use_signal(|| true);
     let help_panel_displayed: Signal<bool> = use_signal(|| false);
+    let highlight_hazards: Signal<bool> = use_signal(|| settings.highlight_hazards);
+    let show_encodings: Signal<bool> = use_signal(|| settings.show_encodings);
+    let show_stages: Signal<bool> = use_signal(|| settings.show_stages);
+    let show_float_registers: Signal<bool> = use_signal(|| settings.show_float_registers);
+    let hide_unwritten_registers: Signal<bool> = use_signal(|| settings.hide_unwritten_registers);
+    // whether an edit is waiting on the debounced assembly
+    let mut assembling: Signal<bool> = use_signal(|| true);
+    // an error to show in the editor, switching to its file if needed
+    let mut error_focus: Signal<Option<AssemblerError>> = use_signal(|| None);
+    let mut focus_position: Signal<Option<(usize, usize)>> = use_signal(|| None);
 
     // assemble as typing to get live errors
     let mut assemble_debounce = use_debounce(Duration::from_secs(1), move |_| {
         info!("Assembling...");
-        match assembler::assemble(&source.peek()) {
+        match file_tabs::assemble_files(&files.peek(), *memory_map.peek()) {
             Ok(assembled) => {
                 info!("Assembly succeeded.");
                 *ASSEMBLED_PROGRAM.write() = Some(assembled);
@@ -58,14 +188,54 @@ pub fn App() -> Element {
                 assembler_errors.set(errors);
             }
         }
+        assembling.set(false);
     });
 
     use_effect(move || {
         info!("Source changed");
-        let _ = source.read();
+        let _ = files.read();
+        let _ = file_tabs::BINARY_FILES.read();
+        let _ = file_tabs::STRICT_DIRECTIVES.read();
+        let _ = memory_map.read();
+        assembling.set(true);
         assemble_debounce.action(());
     });
 
+    use_effect(move || {
+        let mut settings = Settings {
+            strict_directives: *file_tabs::STRICT_DIRECTIVES.read(),
+            uart_delay: *uart_delay.read(),
+            highlight_hazards: *highlight_hazards.read(),
+            show_encodings: *show_encodings.read(),
+            show_stages: *show_stages.read(),
+            show_float_registers: *show_float_registers.read(),
+            hide_unwritten_registers: *hide_unwritten_registers.read(),
+            ..Settings::default()
+        };
+        settings.set_pipeline(*selected_emulator.read());
+        settings.set_memory_map(*memory_map.read());
+        settings.set_latencies(*latencies.read());
+        settings.set_uart_format(*uart_format.read());
+        settings.set_memory_init(*memory_init.read());
+        settings.set_register_init(*register_init.read());
+        settings.save();
+    });
+
+    use_effect(move || {
+        let Some(error) = error_focus.read().clone() else {
+            return;
+        };
+        let index = error
+            .file
+            .as_ref()
+            .and_then(|name| files.peek().iter().position(|file| &file.name == name));
+        if let Some(index) = index.filter(|index| *index != *active_file.peek()) {
+            file_tabs::select_file(files, active_file, source, breakpoints, index);
+        }
+        focus_position.set(Some((error.line_number, error.column)));
+        error_focus.set(None);
+    });
+
     let mut line_highlights = use_signal(Vec::<LineHighlight>::new);
     use_effect(move || {
         line_highlights.write().clear();
@@ -73,27 +243,111 @@ pub fn App() -> Element {
         fn get_pc_line(pc: u32, assembled_program: &Option<AssembledProgram>) -> Option<usize> {
             assembled_program
                 .as_ref()
-                .and_then(|p| p.source_map.get_by_left(&pc).copied())
+                .and_then(|p| p.source_line_at(pc))
         }
 
         if let Some(emulator_state) = &*emulator_state_memo.read() {
+            let active = *active_file.read();
+            let offsets = line_offsets_memo.read();
+            // the producer and consumer of the data hazard stalling the pipeline
+            let hazard_pcs = emulator_state
+                .data_hazard()
+                .filter(|_| *highlight_hazards.read())
+                .into_iter()
+                .flat_map(|hazard| [hazard.producer_pc, hazard.consumer_pc]);
             line_highlights.set(
                 emulator_state
                     .all_pcs()
                     .iter()
-                    .filter_map(|pc_pos| {
-                        get_pc_line(pc_pos.pc, &ASSEMBLED_PROGRAM.read()).map(|line| {
-                            LineHighlight {
-                                line,
-                                css_class: pc_pos.name,
-                            }
-                        })
+                    .map(|pc_pos| (pc_pos.pc, pc_pos.name))
+                    .chain(hazard_pcs.map(|pc| (pc, "hazard")))
+                    .filter_map(|(pc, css_class)| {
+                        get_pc_line(pc, &ASSEMBLED_PROGRAM.read())
+                            .and_then(|line| assembler::locate_line(&offsets, line))
+                            .filter(|(index, _)| *index == active)
+                            .map(|(_, line)| LineHighlight { line, css_class })
                     })
                     .collect(),
             );
         }
     });
 
+    // the machine code each line of the active file assembled to
+    let line_encodings_memo = use_memo(move || {
+        let mut encodings = Vec::<LineEncoding>::new();
+        if !*show_encodings.read() {
+            return encodings;
+        }
+
+        let active = *active_file.read();
+        let offsets = line_offsets_memo.read();
+        if let Some(program) = ASSEMBLED_PROGRAM.read().as_ref() {
+            for (&address, &line) in program.source_map.iter() {
+                let Some(word) = program.instruction_at(address) else {
+                    continue;
+                };
+                let Some((_, line)) =
+                    assembler::locate_line(&offsets, line).filter(|(index, _)| *index == active)
+                else {
+                    continue;
+                };
+                // pseudo-instructions can expand to several words on one line
+                match encodings.iter_mut().find(|encoding| encoding.line == line) {
+                    Some(encoding) => encoding.words.push(word.raw()),
+                    None => encodings.push(LineEncoding {
+                        line,
+                        words: vec![word.raw()],
+                    }),
+                }
+            }
+        }
+        encodings
+    });
+    let line_encodings: ReadOnlySignal<_> = line_encodings_memo.into();
+
+    // the pipeline stages holding each line of the active file's instructions
+    let line_stages_memo = use_memo(move || {
+        let mut line_stages = Vec::<LineStages>::new();
+        if !*show_stages.read() {
+            return line_stages;
+        }
+        let Some(emulator_state) = &*emulator_state_memo.read() else {
+            return line_stages;
+        };
+
+        let active = *active_file.read();
+        let offsets = line_offsets_memo.read();
+        let program = ASSEMBLED_PROGRAM.read();
+        for pc_pos in emulator_state.all_pcs() {
+            let Some((_, line)) = program
+                .as_ref()
+                .and_then(|program| program.source_line_at(pc_pos.pc))
+                .and_then(|line| assembler::locate_line(&offsets, line))
+                .filter(|(index, _)| *index == active)
+            else {
+                continue;
+            };
+            let stage = match pc_pos.name {
+                "if" => "IF",
+                "id" => "ID",
+                "ex" => "EX",
+                "mem" => "MEM",
+                "wb" => "WB",
+                other => other,
+            };
+            // loops and pseudo-instructions can put one line in several stages
+            match line_stages.iter_mut().find(|stages| stages.line == line) {
+                Some(stages) => stages.stages.push(stage),
+                None => line_stages.push(LineStages {
+                    line,
+                    stages: vec![stage],
+                }),
+            }
+        }
+        line_stages
+    });
+    let line_stages: ReadOnlySignal<_> = line_stages_memo.into();
+
     rsx! {
         document::Title { "EmuGator" }
         document::Stylesheet { href: asset!("/assets/tailwind.css") }
@@ -103,24 +357,51 @@ pub fn App() -> Element {
 
         div { class: "flex flex-col h-screen w-full bg-gray-800 m-0 p-0",
             Navbar {
-                source,
+                files,
+                active_file,
                 assembled_program: ASSEMBLED_PROGRAM.signal(),
                 assembler_errors,
+                assembling,
+                error_focus,
                 emulator_states,
+                emulator_state,
+                history_position,
                 serial_input,
+                uart_delay,
                 selected_emulator,
-                breakpoints,
+                memory_map,
+                latencies,
+                breakpoints: all_breakpoints,
                 minimize_console,
                 help_panel_displayed,
+                highlight_hazards,
+                show_encodings,
+                show_stages,
+                memory_init,
+                register_init,
             }
+            TimelineView { emulator_states, history_position }
             div { class: "flex flex-1 overflow-hidden",
                 div { class: "w-1/2 flex flex-col h-full bg-[#1E1E1E] overflow-hidden border-r-2 border-gray-900",
+                    FileTabs {
+                        files,
+                        active_file,
+                        source,
+                        breakpoints,
+                        assembler_errors,
+                    }
                     div { class: "flex-1 relative overflow-hidden",
+                        // remount the editor so it picks up the newly active file
                         CodeEditor {
+                            key: "{active_file}",
                             source,
                             line_highlights,
+                            line_encodings,
+                            line_stages,
                             breakpoints,
-                            assembler_errors,
+                            assembler_errors: active_errors,
+                            assembler_warnings,
+                            focus_position,
                         }
                     }
                     div {
@@ -132,6 +413,8 @@ pub fn App() -> Element {
                             emulator_states,
                             emulator_state,
                             serial_input,
+                            uart_delay,
+                            output_format: uart_format,
                             minimize_console,
                         }
                     }
@@ -149,6 +432,7 @@ pub fn App() -> Element {
                                 div { class: "h-[calc(100%-2rem)] overflow-auto",
                                     PipelineVisualization {
                                         emulator_state,
+                                        emulator_states,
                                         selected_emulator,
                                     }
                                 }
@@ -161,9 +445,17 @@ pub fn App() -> Element {
                                     span { class: "text-sm font-medium text-gray-300",
                                         "Register View"
                                     }
+                                    ExpressionView { emulator_state }
                                 }
                                 div { class: "h-[calc(100%-2rem)] overflow-auto",
-                                    RegisterView { emulator_state }
+                                    CallStackView { emulator_state, files }
+                                    RegisterView {
+                                        emulator_state,
+                                        emulator_states,
+                                        history_position,
+                                        show_float: show_float_registers,
+                                        hide_unwritten: hide_unwritten_registers,
+                                    }
                                 }
                             }
                         }
@@ -179,6 +471,11 @@ pub fn App() -> Element {
                                     MemoryView {
                                         assembled_program: ASSEMBLED_PROGRAM.signal(),
                                         emulator_state,
+                                        previous_state,
+                                        files,
+                                        serial_input,
+                                        selected_emulator,
+                                        memory_map,
                                     }
                                 }
                             }
@@ -1,5 +1,14 @@
-use emugator_core::assembler::{self, AssembledProgram, AssemblerError};
-use emugator_core::emulator::{AnyEmulatorState, EmulatorOption};
+use emugator_core::assembler::{self, AssembledProgram, AssemblerError, MemoryMap};
+use emugator_core::emulator::{
+    AnyEmulatorState, BreakConditions, BreakReason, EmulatorOption, latency::LatencyModel,
+    memory_module::MemoryInit, register_file::RegisterInit, reverse_until_break,
+};
+use emugator_core::isa::ISA;
+
+use super::file_tabs::{self, SourceFile};
+use super::latency_settings::LatencySettings;
+use super::memory_map_settings::MemoryMapSettings;
+use super::share_link;
 
 use dioxus::prelude::*;
 use dioxus_logger::tracing::info;
@@ -10,38 +19,136 @@ use wasm_bindgen::JsCast;
 
 use dioxus_free_icons::Icon;
 use dioxus_free_icons::icons::ld_icons::{
-    LdCircleArrowRight, LdCircleCheck, LdCircleX, LdDownload, LdInfo, LdPlay, LdRefreshCw, LdUndo,
+    LdCircleArrowLeft, LdCircleArrowOutUpLeft, LdCircleArrowRight, LdCircleCheck, LdCircleX,
+    LdDownload, LdInfo, LdLink, LdLoader, LdPlay, LdRefreshCw, LdUndo,
 };
 use dioxus_free_icons::icons::ld_icons::{LdClock3, LdClock6, LdClock9, LdClock12};
 
+/// Maximum number of snapshots kept in the emulator state history
+pub const MAX_HISTORY_LEN: usize = 5_000;
+
+/// Push a new snapshot onto the history.
+/// Snapshots after the one selected in the timeline are discarded,
+/// as are the oldest snapshots once the history is full.
+fn push_state(
+    mut emulator_states: Signal<Vec<AnyEmulatorState>>,
+    mut history_position: Signal<Option<usize>>,
+    new_state: AnyEmulatorState,
+) {
+    let position = *history_position.peek();
+    let mut states = emulator_states.write();
+    if let Some(position) = position {
+        states.truncate(position + 1);
+    }
+    states.push(new_state);
+    if states.len() > MAX_HISTORY_LEN {
+        let excess = states.len() - MAX_HISTORY_LEN;
+        states.drain(..excess);
+    }
+    history_position.set(None);
+}
+
+/// Describes where the instruction at `pc` came from as `file:line` when possible
+pub fn source_location(
+    pc: u32,
+    program: &Option<AssembledProgram>,
+    files: &[SourceFile],
+) -> String {
+    program
+        .as_ref()
+        .and_then(|program| program.source_line_at(pc))
+        .and_then(|line| assembler::locate_line(&file_tabs::line_offsets(files), line))
+        .and_then(|(index, line)| {
+            files
+                .get(index)
+                .map(|file| format!("{}:{}", file.name, line))
+        })
+        .unwrap_or_else(|| format!("0x{:08X}", pc))
+}
+
+/// Shows a byte as hex, along with the character it encodes if printable
+fn describe_byte(byte: u8) -> String {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        format!("0x{:02X} ('{}')", byte, byte as char)
+    } else {
+        format!("0x{:02X}", byte)
+    }
+}
+
 #[component]
 #[allow(non_snake_case)]
 pub fn Navbar(
-    source: ReadOnlySignal<String>,
+    files: ReadOnlySignal<Vec<SourceFile>>,
+    active_file: ReadOnlySignal<usize>,
     assembled_program: Signal<Option<AssembledProgram>>,
     assembler_errors: Signal<Vec<AssemblerError>>,
+    assembling: ReadOnlySignal<bool>,
+    error_focus: Signal<Option<AssemblerError>>,
     emulator_states: Signal<Vec<AnyEmulatorState>>,
+    emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>,
+    history_position: Signal<Option<usize>>,
     serial_input: Signal<String>,
+    uart_delay: ReadOnlySignal<u32>,
     selected_emulator: Signal<EmulatorOption>,
+    memory_map: Signal<MemoryMap>,
+    latencies: Signal<LatencyModel>,
     breakpoints: ReadOnlySignal<BTreeSet<usize>>,
     minimize_console: Signal<bool>,
     help_panel_displayed: Signal<bool>,
+    highlight_hazards: Signal<bool>,
+    show_encodings: Signal<bool>,
+    show_stages: Signal<bool>,
+    memory_init: Signal<MemoryInit>,
+    register_init: Signal<RegisterInit>,
 ) -> Element {
     let is_started = !emulator_states.read().is_empty();
     let is_assembled = assembled_program.read().is_some();
     let error_count = assembler_errors.read().len();
+    let instruction_count = assembled_program
+        .read()
+        .as_ref()
+        .map_or(0, |program| program.instruction_memory.len() / 4);
+
+    // The instruction about to be decoded, as its address, assembly and source line
+    let next_instruction = emulator_state
+        .read()
+        .as_ref()
+        .filter(|state| state.stop_reason().is_none())
+        .and_then(|state| state.id_pc())
+        .map(|pc| {
+            let program = assembled_program.read();
+            let assembly = program
+                .as_ref()
+                .and_then(|program| program.disassemble(pc))
+                .unwrap_or_else(|| "???".to_string());
+            let location = program
+                .as_ref()
+                .is_some_and(|program| program.source_map.contains_key(&pc))
+                .then(|| source_location(pc, &program, &files.read()));
+            (pc, assembly, location)
+        });
 
     let mut tick = use_signal(|| 1);
+    let mut detect_loops = use_signal(|| false);
+    let mut break_on_uart = use_signal(|| false);
+    let mut detect_stack_overflow = use_signal(|| false);
+    let mut break_on_text_write = use_signal(|| false);
+    let mut break_mnemonic: Signal<Option<&'static str>> = use_signal(|| None);
+    let mut break_message: Signal<Option<String>> = use_signal(|| None);
+    // What happened to the last share link, shown on the button until the mouse leaves
+    let mut share_message: Signal<Option<&'static str>> = use_signal(|| None);
 
     // Function to handle file download
     let download_file = move |_| {
-        let content = source.read().clone();
+        let Some(file) = files.read().get(*active_file.read()).cloned() else {
+            return;
+        };
         let window = web_sys::window().unwrap();
         let document = window.document().unwrap();
 
         // Create a blob with the content
         let array = js_sys::Array::new();
-        array.push(&wasm_bindgen::JsValue::from_str(&content));
+        array.push(&wasm_bindgen::JsValue::from_str(&file.source));
         let blob = web_sys::Blob::new_with_str_sequence(&array).unwrap();
 
         // Create download URL
@@ -51,7 +158,7 @@ pub fn Navbar(
         let anchor = document.create_element("a").unwrap();
         let anchor = anchor.dyn_into::<web_sys::HtmlAnchorElement>().unwrap();
         anchor.set_href(&url);
-        anchor.set_download("code.txt");
+        anchor.set_download(&file.name);
         anchor.style().set_property("display", "none").unwrap();
 
         // Append to body, click, and remove
@@ -77,17 +184,23 @@ pub fn Navbar(
                         class: "bg-green-600 gap-x-1 hover:bg-green-700 text-white font-medium py-1 px-2 rounded transition duration-150 ease-in-out flex items-center cursor-pointer",
                         onclick: move |_| {
                             info!("Start clicked");
-                            match assembler::assemble(&source.read()) {
+                            match file_tabs::assemble_files(&files.read(), *memory_map.read()) {
                                 Ok(assembled) => {
                                     info!("Final assembly succeeded.");
                                     let mut new_state = AnyEmulatorState::new_of_type(
                                         &assembled,
                                         *selected_emulator.read(),
-                                    );
+                                        *memory_init.read(),
+                                        *register_init.read(),
+                                    )
+                                    .with_latencies(*latencies.read());
                                     new_state
                                         .memory_io_mut()
                                         .set_serial_input(serial_input.read().as_bytes());
+                                    new_state.memory_io_mut().set_serial_delay(*uart_delay.read());
                                     emulator_states.set(vec![new_state]);
+                                    history_position.set(None);
+                                    break_message.set(None);
                                     assembled_program.set(Some(assembled));
                                     assembler_errors.set(Vec::new());
                                     minimize_console.set(false);
@@ -122,13 +235,13 @@ pub fn Navbar(
                             (*tick.write()) += 1;
                             if let Some(new_state) = if let (Some(mut program), Some(emulator_state)) = (
                                 assembled_program.as_mut(),
-                                emulator_states.read().last(),
+                                emulator_state.read().as_ref(),
                             ) {
                                 Some(emulator_state.clock(&mut program))
                             } else {
                                 None
                             } {
-                                emulator_states.write().push(new_state);
+                                push_state(emulator_states, history_position, new_state);
                             }
                         },
                         match *tick.read() % 4 {
@@ -160,13 +273,13 @@ pub fn Navbar(
                         onclick: move |_| {
                             if let Some(new_state) = if let (Some(mut program), Some(emulator_state)) = (
                                 assembled_program.as_mut(),
-                                emulator_states.read().last(),
+                                emulator_state.read().as_ref(),
                             ) {
                                 Some(emulator_state.clock_until_next_instruction(&mut program, 1000))
                             } else {
                                 None
                             } {
-                                emulator_states.write().push(new_state);
+                                push_state(emulator_states, history_position, new_state);
                             }
                         },
                         Icon { width: 17, icon: LdCircleArrowRight }
@@ -182,24 +295,206 @@ pub fn Navbar(
                             },
                         ),
                         disabled: !is_started,
+                        title: "Run until the current function returns to its caller",
                         onclick: move |_| {
-                            if let Some(new_state) = if let (Some(mut program), Some(emulator_state)) = (
-                                assembled_program.as_mut(),
-                                emulator_states.read().last(),
+                            if let Some(new_state) = if let (Some(program), Some(emulator_state)) = (
+                                assembled_program.read().as_ref(),
+                                emulator_state.read().as_ref(),
+                            ) {
+                                Some(emulator_state.clock_until_return(program, breakpoints.read().deref(), 10_000))
+                            } else {
+                                None
+                            } {
+                                push_state(emulator_states, history_position, new_state);
+                            }
+                        },
+                        Icon { width: 17, icon: LdCircleArrowOutUpLeft }
+                        "Step Out"
+                    }
+                    button {
+                        class: format!(
+                            "{} text-white font-medium py-1 px-2 rounded transition duration-150 ease-in-out flex items-center gap-x-1",
+                            if is_started {
+                                "bg-indigo-600 hover:bg-indigo-700 text-white cursor-pointer"
+                            } else {
+                                "bg-gray-600 text-gray-300 cursor-not-allowed"
+                            },
+                        ),
+                        disabled: !is_started,
+                        onclick: move |_| {
+                            if let Some((new_state, reason)) = if let (Some(program), Some(emulator_state)) = (
+                                assembled_program.read().as_ref(),
+                                emulator_state.read().as_ref(),
                             ) {
+                                let conditions = BreakConditions {
+                                    stuck_loops: *detect_loops.read(),
+                                    uart_output: *break_on_uart.read(),
+                                    stack_overflow: *detect_stack_overflow.read(),
+                                    text_writes: *break_on_text_write.read(),
+                                    mnemonic: *break_mnemonic.read(),
+                                };
                                 Some(
                                     emulator_state
-                                        .clock_until_break(&mut program, breakpoints.read().deref(), 10_000),
+                                        .clock_until_break_on(program, breakpoints.read().deref(), conditions, 10_000),
                                 )
                             } else {
                                 None
                             } {
-                                emulator_states.write().push(new_state);
+                                let message = reason
+                                    .map(|reason| match reason {
+                                        BreakReason::StuckLoop { pc: Some(pc) } => {
+                                            let location = source_location(pc, &assembled_program.read(), &files.read());
+                                            format!("No-progress loop detected at {location}")
+                                        }
+                                        BreakReason::StuckLoop { pc: None } => "No-progress loop detected".to_string(),
+                                        BreakReason::UartOutput { byte } => {
+                                            format!("Transmitted UART byte {}", describe_byte(byte))
+                                        }
+                                        BreakReason::StackOverflow { pc, address } => {
+                                            let location = source_location(pc, &assembled_program.read(), &files.read());
+                                            format!("Stack overflow at {location}: wrote into the program at 0x{address:08X}")
+                                        }
+                                        BreakReason::TextWrite { pc, address } => {
+                                            let location = source_location(pc, &assembled_program.read(), &files.read());
+                                            format!("Store at {location} wrote to instruction address 0x{address:08X}. Only data memory changed, instructions are read-only.")
+                                        }
+                                        BreakReason::Mnemonic { pc, mnemonic } => {
+                                            let location = source_location(pc, &assembled_program.read(), &files.read());
+                                            format!("Reached {} at {location}", mnemonic.to_lowercase())
+                                        }
+                                    });
+                                break_message.set(message);
+                                push_state(emulator_states, history_position, new_state);
                             }
                         },
                         Icon { width: 17, icon: LdCircleArrowRight }
                         "Until Break"
                     }
+                    label {
+                        class: "flex items-center gap-x-1 text-sm text-gray-300 cursor-pointer",
+                        title: "Stop Until Break when the program is stuck in a loop that changes nothing. Polling loops are reported too.",
+                        input {
+                            r#type: "checkbox",
+                            class: "accent-indigo-500 cursor-pointer",
+                            checked: *detect_loops.read(),
+                            onchange: move |event| detect_loops.set(event.checked()),
+                        }
+                        "Detect Stuck Loops"
+                    }
+                    label {
+                        class: "flex items-center gap-x-1 text-sm text-gray-300 cursor-pointer",
+                        title: "Stop Until Break as soon as the program transmits a byte over the UART.",
+                        input {
+                            r#type: "checkbox",
+                            class: "accent-indigo-500 cursor-pointer",
+                            checked: *break_on_uart.read(),
+                            onchange: move |event| break_on_uart.set(event.checked()),
+                        }
+                        "Break on UART Output"
+                    }
+                    label {
+                        class: "flex items-center gap-x-1 text-sm text-gray-300 cursor-pointer",
+                        title: "Stop Until Break when a store through sp writes over the program's instructions or data.",
+                        input {
+                            r#type: "checkbox",
+                            class: "accent-indigo-500 cursor-pointer",
+                            checked: *detect_stack_overflow.read(),
+                            onchange: move |event| detect_stack_overflow.set(event.checked()),
+                        }
+                        "Detect Stack Overflow"
+                    }
+                    label {
+                        class: "flex items-center gap-x-1 text-sm text-gray-300 cursor-pointer",
+                        title: "Stop Until Break when a store writes to an instruction's address. Instructions are read-only, so the store only changes data memory.",
+                        input {
+                            r#type: "checkbox",
+                            class: "accent-indigo-500 cursor-pointer",
+                            checked: *break_on_text_write.read(),
+                            onchange: move |event| break_on_text_write.set(event.checked()),
+                        }
+                        "Break on Text Writes"
+                    }
+                    label {
+                        class: "flex items-center gap-x-1 text-sm text-gray-300",
+                        title: "Stop Until Break when an instruction with this mnemonic retires, wherever it is in the program.",
+                        "Break on"
+                        select {
+                            class: "bg-gray-700 text-white rounded py-1 px-1 cursor-pointer",
+                            value: break_mnemonic.read().unwrap_or(""),
+                            onchange: move |event| {
+                                let name = event.value();
+                                break_mnemonic.set(
+                                    ISA::all().map(|isa| isa.definition()._name).find(|&mnemonic| mnemonic == name),
+                                );
+                            },
+                            option { value: "", "Nothing" }
+                            for isa in ISA::all() {
+                                option { value: isa.definition()._name, "{isa.definition()._name.to_lowercase()}" }
+                            }
+                        }
+                    }
+                    label {
+                        class: "flex items-center gap-x-1 text-sm text-gray-300 cursor-pointer",
+                        title: "Highlight the two lines involved when the five-stage pipeline stalls on a data hazard.",
+                        input {
+                            r#type: "checkbox",
+                            class: "accent-indigo-500 cursor-pointer",
+                            checked: *highlight_hazards.read(),
+                            onchange: move |event| highlight_hazards.set(event.checked()),
+                        }
+                        "Highlight Hazards"
+                    }
+                    label {
+                        class: "flex items-center gap-x-1 text-sm text-gray-300 cursor-pointer",
+                        title: "Show the machine code each line assembles to at the end of the line.",
+                        input {
+                            r#type: "checkbox",
+                            class: "accent-indigo-500 cursor-pointer",
+                            checked: *show_encodings.read(),
+                            onchange: move |event| show_encodings.set(event.checked()),
+                        }
+                        "Show Encodings"
+                    }
+                    label {
+                        class: "flex items-center gap-x-1 text-sm text-gray-300 cursor-pointer",
+                        title: "Label each highlighted line with the pipeline stages its instructions are in.",
+                        input {
+                            r#type: "checkbox",
+                            class: "accent-indigo-500 cursor-pointer",
+                            checked: *show_stages.read(),
+                            onchange: move |event| show_stages.set(event.checked()),
+                        }
+                        "Show Stages"
+                    }
+                    button {
+                        class: format!(
+                            "{} text-white font-medium py-1 px-2 rounded transition duration-150 ease-in-out flex items-center gap-x-1",
+                            if is_started {
+                                "bg-indigo-600 hover:bg-indigo-700 text-white cursor-pointer"
+                            } else {
+                                "bg-gray-600 text-gray-300 cursor-not-allowed"
+                            },
+                        ),
+                        disabled: !is_started,
+                        onclick: move |_| {
+                            // Only moves through the recorded history, nothing is re-run
+                            if let Some(program) = assembled_program.read().as_ref() {
+                                let states = emulator_states.read();
+                                let position = history_position
+                                    .peek()
+                                    .unwrap_or(states.len().saturating_sub(1));
+                                let hit = reverse_until_break(
+                                    &states,
+                                    position,
+                                    program,
+                                    breakpoints.read().deref(),
+                                );
+                                history_position.set(Some(hit));
+                            }
+                        },
+                        Icon { width: 17, icon: LdCircleArrowLeft }
+                        "Back to Break"
+                    }
 
                     button {
                         class: format!(
@@ -212,6 +507,9 @@ pub fn Navbar(
                         ),
                         disabled: !is_started,
                         onclick: move |_| {
+                            if let Some(position) = history_position.take() {
+                                emulator_states.write().truncate(position + 1);
+                            }
                             emulator_states.write().pop();
                         },
                         Icon { width: 17, icon: LdUndo }
@@ -225,40 +523,176 @@ pub fn Navbar(
                         Icon { width: 17, icon: LdDownload }
                         "Save"
                     }
+
+                    // Share Button
+                    button {
+                        class: "bg-blue-600 hover:bg-blue-700 text-white font-medium py-1 px-2 rounded transition duration-150 ease-in-out flex items-center gap-x-1 cursor-pointer",
+                        title: "Copy a link that opens this file's source",
+                        onclick: move |_| {
+                            let Some(file) = files.read().get(*active_file.read()).cloned() else {
+                                return;
+                            };
+                            match share_link::share_url(&file.source) {
+                                Some(url) => {
+                                    if let Some(window) = web_sys::window() {
+                                        let _ = window.navigator().clipboard().write_text(&url);
+                                    }
+                                    share_message.set(Some("Link Copied"));
+                                }
+                                None => share_message.set(Some("Too Large to Share")),
+                            }
+                        },
+                        onmouseleave: move |_| share_message.set(None),
+                        Icon { width: 17, icon: LdLink }
+                        {share_message.read().unwrap_or("Share")}
+                    }
                 }
             }
             div { class: "flex items-stretch space-x-2 py-2",
-                span {
-                    class: format!(
-                        "flex items-center gap-2 text-sm font-medium mr-4 {}",
-                        if is_assembled {
-                            "text-green-300"
-                        } else if error_count > 0 {
-                            "text-red-400"
-                        } else {
-                            "text-green-100"
-                        },
-                    ),
-                    if is_assembled {
+                if let Some(message) = break_message.read().as_ref() {
+                    span { class: "flex items-center text-sm font-medium text-yellow-400",
+                        "{message}"
+                    }
+                }
+                if let Some((pc, assembly, location)) = next_instruction {
+                    span {
+                        class: "flex items-center gap-x-2 text-sm font-mono text-gray-300",
+                        title: "The next instruction to be decoded",
+                        span { class: "text-gray-400", "Next" }
+                        span { "0x{pc:08X}" }
+                        span { class: "text-white", "{assembly}" }
+                        if let Some(location) = location {
+                            span { class: "text-gray-400", "{location}" }
+                        }
+                    }
+                }
+                if let Some(reason) = emulator_state.read().as_ref().and_then(|state| state.stop_reason()) {
+                    span { class: "flex items-center text-sm font-medium text-red-400",
+                        "Halted: {reason}"
+                    }
+                }
+                if *assembling.read() {
+                    span { class: "flex items-center gap-2 text-sm font-medium mr-4 text-gray-300",
+                        Icon { width: 17, icon: LdLoader }
+                        "Assembling..."
+                    }
+                } else if is_assembled {
+                    span {
+                        class: "flex items-center gap-2 text-sm font-medium mr-4 text-green-300",
+                        title: "The current source assembles and can be run",
                         Icon { width: 17, icon: LdCircleCheck }
                         if is_started {
                             "Program Running"
+                        } else if instruction_count == 1 {
+                            "Assembled OK (1 instruction)"
                         } else {
-                            "Program Assembled"
+                            "Assembled OK ({instruction_count} instructions)"
                         }
-                    } else if error_count > 0 {
+                    }
+                } else if error_count > 0 {
+                    button {
+                        class: "flex items-center gap-2 text-sm font-medium mr-4 text-red-400 hover:text-red-300 cursor-pointer",
+                        title: "Show the first error in the editor",
+                        onclick: move |_| {
+                            let first = assembler_errors.read().first().cloned();
+                            error_focus.set(first);
+                        },
                         Icon { width: 17, icon: LdCircleX }
-                        "Errors: {error_count}"
-                    } else {
+                        if error_count == 1 {
+                            "1 error"
+                        } else {
+                            "{error_count} errors"
+                        }
+                    }
+                } else {
+                    span { class: "flex items-center gap-2 text-sm font-medium mr-4 text-green-100",
                         "Ready"
                     }
                 }
+                MemoryMapSettings {
+                    memory_map,
+                    onchange: move |map| {
+                        memory_map.set(map);
+                        emulator_states.set(vec![]);
+                        history_position.set(None);
+                    },
+                }
+                label {
+                    class: "flex items-center gap-x-1 text-sm text-gray-300",
+                    title: "Whether directives EmuGator doesn't know are errors, or skipped with a warning so programs written for other assemblers still assemble. Debugger directives like .size, .type and .cfi_* are always skipped.",
+                    "Directives"
+                    select {
+                        class: "bg-gray-700 text-white rounded py-1 px-1 cursor-pointer",
+                        value: if *file_tabs::STRICT_DIRECTIVES.read() { "strict" } else { "lenient" },
+                        onchange: move |event| {
+                            *file_tabs::STRICT_DIRECTIVES.write() = event.value() == "strict";
+                        },
+                        option { value: "strict", "Strict" }
+                        option { value: "lenient", "Lenient" }
+                    }
+                }
+                LatencySettings { latencies }
+                label {
+                    class: "flex items-center gap-x-1 text-sm text-gray-300",
+                    title: "Whether data memory the program does not define is zeroed at reset, or left undefined so uninitialized reads are reported. Applies on the next start.",
+                    "Memory"
+                    select {
+                        class: "bg-gray-700 text-white rounded py-1 px-1 cursor-pointer",
+                        value: if *memory_init.read() == MemoryInit::Zeroed { "zeroed" } else { "sparse" },
+                        onchange: move |event| {
+                            memory_init.set(match event.value().as_str() {
+                                "zeroed" => MemoryInit::Zeroed,
+                                _ => MemoryInit::Sparse,
+                            });
+                        },
+                        option { value: "sparse", "Undefined" }
+                        option { value: "zeroed", "Zeroed" }
+                    }
+                }
+                label {
+                    class: "flex items-center gap-x-1 text-sm text-gray-300",
+                    title: "What registers hold at reset. Poison and random values make reading a register before writing it easy to spot. Random values are the same for the same seed. Applies on the next start.",
+                    "Registers"
+                    select {
+                        class: "bg-gray-700 text-white rounded py-1 px-1 cursor-pointer",
+                        value: match *register_init.read() {
+                            RegisterInit::Zeroed => "zeroed",
+                            RegisterInit::Poison => "poison",
+                            RegisterInit::Random(_) => "random",
+                        },
+                        onchange: move |event| {
+                            register_init.set(match event.value().as_str() {
+                                "poison" => RegisterInit::Poison,
+                                "random" => RegisterInit::Random(0),
+                                _ => RegisterInit::Zeroed,
+                            });
+                        },
+                        option { value: "zeroed", "Zeroed" }
+                        option { value: "poison", "0xDEADBEEF" }
+                        option { value: "random", "Random" }
+                    }
+                    if let RegisterInit::Random(seed) = *register_init.read() {
+                        input {
+                            class: "w-16 bg-gray-700 text-white rounded px-1 py-1 outline-none",
+                            r#type: "number",
+                            min: "0",
+                            title: "Seed",
+                            value: "{seed}",
+                            oninput: move |event| {
+                                if let Ok(seed) = event.value().parse() {
+                                    register_init.set(RegisterInit::Random(seed));
+                                }
+                            },
+                        }
+                    }
+                }
                 button {
                     class: "bg-yellow-600 hover:bg-yellow-700 text-white font-medium py-1 px-2 rounded transition duration-150 ease-in-out flex gap-x-1 items-center cursor-pointer",
                     onclick: move |_| {
                         let new_selection = selected_emulator.read().other();
                         selected_emulator.set(new_selection);
                         emulator_states.set(vec![]);
+                        history_position.set(None);
                     },
                     img { width: 20, src: asset!("assets/pipeline.svg") }
                     "{selected_emulator.read().display_string()}"
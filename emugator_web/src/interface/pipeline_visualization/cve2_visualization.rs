@@ -52,7 +52,14 @@ enum CVE2Element {
 impl CVE2Element {
     fn tooltip_text(&self, pipeline: &CVE2Pipeline) -> String {
         match self {
-            CVE2Element::IfIdBuffer => "IF/ID Buffer".to_string(),
+            CVE2Element::IfIdBuffer => match pipeline.instruction_cycles() {
+                Some(cycles) if cycles > 1 => format!(
+                    "IF/ID Buffer: holding for cycle {} of {}",
+                    pipeline.instr_cycle + 1,
+                    cycles
+                ),
+                _ => "IF/ID Buffer".to_string(),
+            },
             CVE2Element::Decoder => "Decoder".to_string(),
             CVE2Element::RegisterFile => "Register File".to_string(),
             CVE2Element::DataMemory => "Data Memory".to_string(),
@@ -83,6 +90,7 @@ impl CVE2Element {
             CVE2Element::WriteMuxControlSignal => match pipeline.control.data_dest_sel {
                 Some(DataDestSel::ALU) => "Write Mux: ALU",
                 Some(DataDestSel::LSU) => "Write Mux: LSU",
+                Some(DataDestSel::CSR) => "Write Mux: CSR",
                 None => "Write Mux: DON'T CARE",
             }
             .to_string(),
@@ -177,7 +185,12 @@ impl CVE2Element {
                     Some(value) => format!("0x{:08X}", value),
                     None => "None".to_string(),
                 };
-                format!("ALU Output: {}", alu_value)
+                format!(
+                    "{}ALU Output: {}{}",
+                    super::format_alu_op(pipeline.control.alu_op, pipeline.datapath.alu_op_b),
+                    alu_value,
+                    super::format_alu_flags(pipeline.datapath.alu_flags)
+                )
             }
             CVE2Element::LsuAddr => {
                 format!("Memory Address: 0x{:08X}", pipeline.datapath.data_addr_o,)
@@ -344,6 +357,12 @@ fn find_active_elements(control: CVE2Control) -> BTreeSet<CVE2Element> {
     active_elements
 }
 
+/// Outline of the element under the mouse
+pub(super) const HOVER_STROKE: &str = "rgba(66, 133, 244, 1)";
+/// Outline of elements the instruction in the pipeline is using this cycle
+pub(super) const ACTIVE_STROKE: &str = "rgba(147, 112, 219, 1)";
+const HOVER_FILL: &str = "rgba(66, 133, 244, 0.1)";
+
 #[component]
 #[allow(non_snake_case)]
 pub fn CVE2Visualization(
@@ -351,10 +370,6 @@ pub fn CVE2Visualization(
     tooltip_text: Signal<Option<String>>,
     show_control_signals: Signal<bool>,
 ) -> Element {
-    const HOVER_STROKE: &str = "rgba(66, 133, 244, 1)";
-    const ACTIVE_STROKE: &str = "rgba(147, 112, 219, 1)";
-    const HOVER_FILL: &str = "rgba(66, 133, 244, 0.1)";
-
     let mut hovered_element = use_signal(|| Option::<CVE2Element>::None);
     let mut active_elements = use_signal(BTreeSet::<CVE2Element>::new);
 
@@ -2133,8 +2148,16 @@ pub fn CVE2Visualization(
                 y: "101",
                 width: "78",
                 height: "438",
-                stroke: match &*hovered_element.read() {
-                    Some(CVE2Element::IfIdBuffer) => HOVER_STROKE,
+                stroke: match &*emulator_state.read() {
+                    Some(AnyEmulatorState::CVE2(state)) => {
+                        if *hovered_element.read() == Some(CVE2Element::IfIdBuffer) {
+                            HOVER_STROKE
+                        } else if state.pipeline.in_multi_cycle() {
+                            ACTIVE_STROKE
+                        } else {
+                            "black"
+                        }
+                    }
                     _ => "black",
                 },
                 "stroke-width": "2",
@@ -2172,6 +2195,22 @@ pub fn CVE2Visualization(
                 },
                 "ID"
             }
+            // Phase of the instruction in ID, highlighted while it spans several cycles
+            if let Some(AnyEmulatorState::CVE2(state)) = &*emulator_state.read() {
+                if let Some(cycles) = state.pipeline.instruction_cycles() {
+                    text {
+                        id: "instr_cycle_label",
+                        x: "460",
+                        y: "560",
+                        "text-anchor": "middle",
+                        "dominant-baseline": "middle",
+                        "font-size": "16",
+                        "font-weight": "bold",
+                        fill: if cycles > 1 { ACTIVE_STROKE } else { "black" },
+                        "Cycle {state.pipeline.instr_cycle + 1}/{cycles}"
+                    }
+                }
+            }
         }
         g {
             id: "id_pc_group",
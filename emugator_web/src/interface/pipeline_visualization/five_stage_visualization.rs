@@ -2,12 +2,26 @@ use std::{collections::BTreeSet, ops::Deref};
 
 use dioxus::prelude::*;
 
-use emugator_core::emulator::{
-    AnyEmulatorState,
-    controller_common::{DataDestSel, LSUDataType, OpASel, OpBSel, PCSel},
-    five_stage::FiveStagePipeline,
+use emugator_core::{
+    assembler::AssembledProgram,
+    emulator::{
+        AnyEmulatorState, StageSnapshot,
+        controller_common::{DataDestSel, LSUDataType, OpASel, OpBSel, PCSel},
+        five_stage::FiveStagePipeline,
+    },
 };
 
+use crate::interface::ASSEMBLED_PROGRAM;
+
+/// Horizontal center of the IF, ID, EX, MEM and WB stages
+const STAGE_LABEL_X: [u32; 5] = [200, 621, 977, 1334, 1620];
+
+/// Horizontal center of the IF/ID, ID/EX, EX/MEM and MEM/WB buffers
+const BUFFER_LABEL_X: [u32; 4] = [443, 799, 1156, 1513];
+
+/// Encoding of `addi x0, x0, 0`
+const NOP: u32 = 0x0000_0013;
+
 macro_rules! format_opt {
     ($fmt:literal, $val:expr) => {
         match $val {
@@ -200,6 +214,7 @@ impl FiveStageElement {
             FiveStageElement::WBSrcControlSignal => match pipeline.wb_control.wb_src {
                 Some(DataDestSel::ALU) => "Write Back Source: ALU".to_string(),
                 Some(DataDestSel::LSU) => "Write Back Source: LSU".to_string(),
+                Some(DataDestSel::CSR) => "Write Back Source: CSR".to_string(),
                 None => "Write Back Source: DON'T CARE".to_string(),
             },
             FiveStageElement::RegWriteControlSignal => {
@@ -263,7 +278,12 @@ impl FiveStageElement {
             FiveStageElement::IDEXImm => format_opt!("EX IMM: 0x{:08X}", pipeline.id_ex.imm),
             FiveStageElement::ALUMuxA => format_opt!("ALU OP A: 0x{:08X}", pipeline.ex_lines.op_a),
             FiveStageElement::ALUMuxB => format_opt!("ALU OP B: 0x{:08X}", pipeline.ex_lines.op_b),
-            FiveStageElement::ALU => format_opt!("ALU Output: 0x{:08X}", pipeline.ex_lines.alu_out),
+            FiveStageElement::ALU => format!(
+                "{}{}{}",
+                super::format_alu_op(pipeline.ex_control.alu_op, pipeline.ex_lines.op_b),
+                format_opt!("ALU Output: 0x{:08X}", pipeline.ex_lines.alu_out),
+                super::format_alu_flags(pipeline.ex_lines.alu_flags)
+            ),
             // Branch Calculation
             FiveStageElement::JMPBaseAddress => {
                 format_opt!("JMP Base Address: 0x{:08X}", pipeline.ex_lines.jmp_base)
@@ -483,6 +503,48 @@ fn find_active_elements(pipeline: &FiveStagePipeline) -> BTreeSet<FiveStageEleme
     active_elements
 }
 
+/// Disassembles the instruction a stage holds, or `None` for an empty stage or
+/// a bubble a stall inserted
+fn stage_instruction(stage: &StageSnapshot, program: Option<&AssembledProgram>) -> Option<String> {
+    if stage.bubble {
+        return None;
+    }
+    let pc = stage.pc?;
+    Some(
+        match program.and_then(|program| program.instruction_at(pc)) {
+            Some(instruction) if instruction.raw() == NOP => "nop".to_string(),
+            Some(_) => program
+                .and_then(|program| program.disassemble(pc))
+                .unwrap_or_else(|| "???".to_string()),
+            None => "???".to_string(),
+        },
+    )
+}
+
+/// Names what a stage holds above the diagram, and whether it is a bubble a
+/// stall inserted rather than an instruction from the program
+fn stage_label(stage: &StageSnapshot, program: Option<&AssembledProgram>) -> (String, bool) {
+    match stage_instruction(stage, program) {
+        Some(instruction) => (format!("{}: {}", stage.name, instruction), false),
+        None if stage.bubble => (format!("{}: bubble", stage.name), true),
+        None => (stage.name.to_string(), true),
+    }
+}
+
+/// Mnemonic of the instruction a stage holds, shown under the buffer feeding
+/// the stage and left blank for empty stages and bubbles
+fn buffer_label(stage: &StageSnapshot, program: Option<&AssembledProgram>) -> String {
+    stage_instruction(stage, program)
+        .and_then(|instruction| instruction.split_whitespace().next().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Outline of the element under the mouse
+pub(super) const HOVER_STROKE: &str = "rgba(66, 133, 244, 1)";
+/// Outline of elements the instruction in the pipeline is using this cycle
+pub(super) const ACTIVE_STROKE: &str = "rgba(66, 133, 244, 0.7)";
+const HOVER_FILL: &str = "rgba(66, 133, 244, 0.1)";
+
 #[component]
 #[allow(non_snake_case)]
 pub fn FiveStageVisualization(
@@ -490,10 +552,6 @@ pub fn FiveStageVisualization(
     tooltip_text: Signal<Option<String>>,
     show_control_signals: Signal<bool>,
 ) -> Element {
-    const HOVER_STROKE: &str = "rgba(66, 133, 244, 1)";
-    const ACTIVE_STROKE: &str = "rgba(66, 133, 244, 0.7)";
-    const HOVER_FILL: &str = "rgba(66, 133, 244, 0.1)";
-
     let mut hovered_element = use_signal(|| Option::<FiveStageElement>::None);
     let mut active_elements = use_signal(BTreeSet::<FiveStageElement>::new);
 
@@ -538,7 +596,55 @@ pub fn FiveStageVisualization(
             }
         };
     }
+
+    let (stage_labels, buffer_labels) = match &*emulator_state.read() {
+        Some(state @ AnyEmulatorState::FiveStage(_)) => {
+            let program = ASSEMBLED_PROGRAM.read();
+            let snapshot = state.pipeline_snapshot();
+            let stage_labels: Vec<(u32, String, bool)> = snapshot
+                .iter()
+                .zip(STAGE_LABEL_X)
+                .map(|(stage, x)| {
+                    let (label, muted) = stage_label(stage, program.as_ref());
+                    (x, label, muted)
+                })
+                .collect();
+            // Each buffer holds the instruction in the stage after it
+            let buffer_labels: Vec<(u32, String)> = snapshot
+                .iter()
+                .skip(1)
+                .zip(BUFFER_LABEL_X)
+                .map(|(stage, x)| (x, buffer_label(stage, program.as_ref())))
+                .collect();
+            (stage_labels, buffer_labels)
+        }
+        _ => (Vec::new(), Vec::new()),
+    };
+
     rsx! {
+        for (x, label, muted) in stage_labels {
+            text {
+                x: "{x}",
+                y: "-20",
+                "font-family": "monospace",
+                "font-size": "16",
+                "font-style": if muted { "italic" } else { "normal" },
+                "text-anchor": "middle",
+                fill: if muted { "gray" } else { "black" },
+                "{label}"
+            }
+        }
+        for (x, label) in buffer_labels {
+            text {
+                x: "{x}",
+                y: "684",
+                "font-family": "monospace",
+                "font-size": "16",
+                "text-anchor": "middle",
+                fill: "black",
+                "{label}"
+            }
+        }
         if *show_control_signals.read() {
             g {
                 id: "ifpc_write_enable_group",
@@ -789,7 +895,7 @@ pub fn FiveStageVisualization(
                                 Some(DataDestSel::LSU) => {
                                     if is_hovered { "red" } else { "rgba(200, 0, 0, 0.4)" }
                                 }
-                                None => "gray",
+                                _ => "gray",
                             }
                         }
                         _ => "gray",
@@ -814,7 +920,7 @@ pub fn FiveStageVisualization(
                                 Some(DataDestSel::LSU) => {
                                     if is_hovered { "red" } else { "rgba(200, 0, 0, 0.4)" }
                                 }
-                                None => "gray",
+                                _ => "gray",
                             }
                         }
                         _ => "gray",
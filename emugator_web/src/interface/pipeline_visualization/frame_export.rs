@@ -0,0 +1,201 @@
+use super::{
+    CVE2_PIPELINE_RECT, CVE2Visualization, FIVE_STAGE_PIPELINE_RECT, FiveStageVisualization,
+};
+use dioxus::prelude::*;
+use dioxus_free_icons::Icon;
+use dioxus_free_icons::icons::ld_icons::LdFilm;
+use emugator_core::emulator::{AnyEmulatorState, EmulatorOption};
+use wasm_bindgen::JsCast;
+
+/// Most frames a single export captures
+const MAX_FRAMES: usize = 100;
+/// How long each frame is shown in the exported animation
+const FRAME_SECONDS: f64 = 1.0;
+/// Id of the off-screen SVG each frame is rendered into
+const FRAME_SVG_ID: &str = "frame_export_svg";
+
+/// Exports the visualization of the recorded states in a range of cycles as a
+/// single animated SVG that shows one state after another
+#[component]
+#[allow(non_snake_case)]
+pub fn FrameExport(
+    emulator_states: ReadOnlySignal<Vec<AnyEmulatorState>>,
+    selected_emulator: ReadOnlySignal<EmulatorOption>,
+) -> Element {
+    let mut show_options = use_signal(|| false);
+    let mut first_cycle = use_signal(|| 0u64);
+    let mut last_cycle = use_signal(|| u64::MAX);
+    let mut frame_state: Signal<Option<AnyEmulatorState>> = use_signal(|| None);
+    let mut exporting = use_signal(|| false);
+    let tooltip_text: Signal<Option<String>> = use_signal(|| None);
+    let show_control_signals = use_signal(|| true);
+
+    // Recorded states within the chosen cycles, bounded to MAX_FRAMES
+    let frames = use_memo(move || {
+        let (first, last) = (*first_cycle.read(), *last_cycle.read());
+        emulator_states
+            .read()
+            .iter()
+            .filter(|state| (first..=last).contains(&state.counters().cycle))
+            .take(MAX_FRAMES)
+            .cloned()
+            .collect::<Vec<_>>()
+    });
+
+    let view_box = match *selected_emulator.read() {
+        EmulatorOption::CVE2 => CVE2_PIPELINE_RECT,
+        EmulatorOption::FiveStage => FIVE_STAGE_PIPELINE_RECT,
+    };
+
+    let export = move |_| {
+        if *exporting.peek() {
+            return;
+        }
+        exporting.set(true);
+        spawn(async move {
+            let mut captured = Vec::new();
+            for state in frames.peek().iter() {
+                let cycle = state.counters().cycle;
+                frame_state.set(Some(state.clone()));
+                // Give the off-screen SVG a chance to render the new state
+                let _ = document::eval(
+                    "await new Promise((resolve) => requestAnimationFrame(() => setTimeout(resolve)));",
+                )
+                .await;
+                let contents = web_sys::window()
+                    .and_then(|window| window.document())
+                    .and_then(|document| document.get_element_by_id(FRAME_SVG_ID))
+                    .map(|element| element.inner_html())
+                    .unwrap_or_default();
+                captured.push((cycle, contents));
+            }
+            frame_state.set(None);
+            exporting.set(false);
+
+            if !captured.is_empty() {
+                download("pipeline.svg", &animated_svg(&captured, view_box));
+            }
+        });
+    };
+
+    rsx! {
+        button {
+            class: "bg-gray-200 hover:bg-gray-300 p-1 rounded cursor-pointer",
+            title: "Export Animation",
+            onclick: move |_| {
+                let shown = *show_options.read();
+                show_options.set(!shown);
+            },
+            Icon { width: 16, height: 16, icon: LdFilm }
+        }
+        if *show_options.read() {
+            div { class: "absolute bottom-8 left-0 bg-gray-800 text-gray-200 text-xs font-mono rounded p-2 flex flex-col gap-1 w-56",
+                label { class: "flex items-center justify-between gap-x-1",
+                    "From cycle"
+                    input {
+                        class: "w-20 bg-gray-700 text-white rounded px-1 py-1 outline-none",
+                        r#type: "number",
+                        min: "0",
+                        value: "{first_cycle}",
+                        oninput: move |event| {
+                            if let Ok(cycle) = event.value().parse() {
+                                first_cycle.set(cycle);
+                            }
+                        },
+                    }
+                }
+                label { class: "flex items-center justify-between gap-x-1",
+                    "To cycle"
+                    input {
+                        class: "w-20 bg-gray-700 text-white rounded px-1 py-1 outline-none",
+                        r#type: "number",
+                        min: "0",
+                        value: if *last_cycle.read() == u64::MAX { String::new() } else { last_cycle.to_string() },
+                        placeholder: "end",
+                        oninput: move |event| {
+                            last_cycle.set(event.value().parse().unwrap_or(u64::MAX));
+                        },
+                    }
+                }
+                span { class: "text-gray-400", "{frames.read().len()} recorded states (at most {MAX_FRAMES})" }
+                button {
+                    class: "bg-indigo-600 hover:bg-indigo-700 text-white rounded py-1 cursor-pointer disabled:bg-gray-600 disabled:cursor-not-allowed",
+                    disabled: *exporting.read() || frames.read().is_empty(),
+                    onclick: export,
+                    if *exporting.read() {
+                        "Exporting..."
+                    } else {
+                        "Export SVG"
+                    }
+                }
+            }
+        }
+        // Off-screen copy of the visualization that each frame is rendered into
+        if frame_state.read().is_some() {
+            div { style: "position: fixed; left: -10000px; top: 0; width: 1500px; height: 750px;",
+                svg { id: FRAME_SVG_ID, view_box: "{view_box.0} {view_box.1} {view_box.2} {view_box.3}",
+                    match *selected_emulator.read() {
+                        EmulatorOption::CVE2 => rsx! {
+                            CVE2Visualization { emulator_state: frame_state, tooltip_text, show_control_signals }
+                        },
+                        EmulatorOption::FiveStage => rsx! {
+                            FiveStageVisualization { emulator_state: frame_state, tooltip_text, show_control_signals }
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Combines the contents of each frame's SVG into one SVG that loops through
+/// them, labelling every frame with its cycle
+fn animated_svg(frames: &[(u64, String)], view_box: (f64, f64, f64, f64)) -> String {
+    let count = frames.len() as f64;
+    let (x, y, width, height) = view_box;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{x} {y} {width} {height}\" width=\"{width}\" height=\"{height}\">\n\
+         <rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" fill=\"white\"/>\n"
+    );
+    for (i, (cycle, contents)) in frames.iter().enumerate() {
+        let (start, end) = (i as f64 / count, (i + 1) as f64 / count);
+        svg.push_str(&format!(
+            "<g display=\"none\">\
+             <animate attributeName=\"display\" values=\"none;inline;none\" keyTimes=\"0;{start};{end}\" \
+             dur=\"{}s\" calcMode=\"discrete\" repeatCount=\"indefinite\"/>\
+             <text x=\"{}\" y=\"{}\" font-size=\"20\" font-family=\"monospace\">Cycle {cycle}</text>\
+             {contents}</g>\n",
+            count * FRAME_SECONDS,
+            x + 10.0,
+            y + 30.0,
+        ));
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Saves `contents` as a file called `name` through a temporary link
+pub(super) fn download(name: &str, contents: &str) {
+    let Some(document) = web_sys::window().and_then(|window| window.document()) else {
+        return;
+    };
+    let array = js_sys::Array::new();
+    array.push(&wasm_bindgen::JsValue::from_str(contents));
+    let Ok(blob) = web_sys::Blob::new_with_str_sequence(&array) else {
+        return;
+    };
+    let Ok(url) = web_sys::Url::create_object_url_with_blob(&blob) else {
+        return;
+    };
+
+    if let Some(anchor) = document
+        .create_element("a")
+        .ok()
+        .and_then(|anchor| anchor.dyn_into::<web_sys::HtmlAnchorElement>().ok())
+    {
+        anchor.set_href(&url);
+        anchor.set_download(name);
+        anchor.click();
+    }
+    let _ = web_sys::Url::revoke_object_url(&url);
+}
@@ -0,0 +1,92 @@
+use dioxus::prelude::*;
+use dioxus_free_icons::Icon;
+use dioxus_free_icons::icons::ld_icons::LdInfo;
+use emugator_core::emulator::EmulatorOption;
+
+use super::{cve2_visualization, five_stage_visualization};
+
+/// Faded and hovered colors of a control signal's value, as the diagrams draw them
+const SIGNAL_COLORS: [(&str, &str, &str); 3] = [
+    (
+        "rgba(0, 200, 0, 0.4)",
+        "green",
+        "Enabled, or the first input of a mux",
+    ),
+    (
+        "rgba(200, 0, 0, 0.4)",
+        "red",
+        "Disabled or stalling, or the second input of a mux",
+    ),
+    ("rgba(0, 0, 200, 0.4)", "blue", "The third input of a mux"),
+];
+
+/// Collapsible key to the colors of the pipeline diagram, listing only what
+/// the selected pipeline draws with the current settings
+#[component]
+#[allow(non_snake_case)]
+pub fn Legend(
+    selected_emulator: ReadOnlySignal<EmulatorOption>,
+    show_control_signals: ReadOnlySignal<bool>,
+) -> Element {
+    let mut expanded = use_signal(|| false);
+
+    let (hover_stroke, active_stroke) = match *selected_emulator.read() {
+        EmulatorOption::CVE2 => (
+            cve2_visualization::HOVER_STROKE,
+            cve2_visualization::ACTIVE_STROKE,
+        ),
+        EmulatorOption::FiveStage => (
+            five_stage_visualization::HOVER_STROKE,
+            five_stage_visualization::ACTIVE_STROKE,
+        ),
+    };
+
+    rsx! {
+        button {
+            class: format!(
+                "p-1 rounded cursor-pointer {}",
+                if *expanded.read() { "bg-blue-500 text-white" } else { "bg-gray-200 hover:bg-gray-300" },
+            ),
+            title: if *expanded.read() { "Hide Legend" } else { "Show Legend" },
+            onclick: move |_| {
+                let shown = *expanded.read();
+                expanded.set(!shown);
+            },
+            Icon { width: 16, height: 16, icon: LdInfo }
+        }
+        if *expanded.read() {
+            div { class: "absolute top-8 left-0 bg-gray-800 text-gray-200 text-xs rounded p-2 flex flex-col gap-1 w-72",
+                div { class: "flex items-center gap-x-2",
+                    span { class: "w-4 h-3 shrink-0 border-2 rounded-sm", style: "border-color: {active_stroke};" }
+                    "Used by the instruction this cycle"
+                }
+                div { class: "flex items-center gap-x-2",
+                    span { class: "w-4 h-3 shrink-0 border-2 rounded-sm", style: "border-color: {hover_stroke};" }
+                    "Under the mouse, described in the top right"
+                }
+                if *show_control_signals.read() {
+                    span { class: "text-gray-400 mt-1", "Control signals, brighter while hovered" }
+                    for (faded , hovered , meaning) in SIGNAL_COLORS {
+                        div { class: "flex items-center gap-x-2",
+                            span {
+                                class: "w-4 h-3 shrink-0",
+                                style: "background: linear-gradient(to right, {faded} 50%, {hovered} 50%);",
+                            }
+                            "{meaning}"
+                        }
+                    }
+                    div { class: "flex items-center gap-x-2",
+                        span { class: "w-4 h-3 shrink-0", style: "background-color: gray;" }
+                        "No value this cycle, or no program running"
+                    }
+                }
+                if matches!(*selected_emulator.read(), EmulatorOption::FiveStage) {
+                    div { class: "flex items-center gap-x-2",
+                        span { class: "w-4 shrink-0 italic text-gray-400 font-mono", "ID" }
+                        "Italic gray stage labels are bubbles inserted by a stall"
+                    }
+                }
+            }
+        }
+    }
+}
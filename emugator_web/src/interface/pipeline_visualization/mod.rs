@@ -10,12 +10,23 @@ use dioxus_elements::geometry::WheelDelta;
 use dioxus_elements::input_data::MouseButton;
 use dioxus_free_icons::icons::ld_icons::LdRotateCcw;
 use dioxus_free_icons::{Icon, icons::ld_icons::LdGamepad2};
-use emugator_core::emulator::{AnyEmulatorState, EmulatorOption};
+use emugator_core::emulator::{
+    AnyEmulatorState, EmulatorOption,
+    controller_common::{ALUFlags, ALUOp},
+};
 use five_stage_visualization::FiveStageVisualization;
+use frame_export::FrameExport;
+use legend::Legend;
+use occupancy_bar::OccupancyBar;
+use snapshot_export::{PIPELINE_SVG_ID, SnapshotExport};
 use std::rc::Rc;
 
 mod cve2_visualization;
 mod five_stage_visualization;
+mod frame_export;
+mod legend;
+mod occupancy_bar;
+mod snapshot_export;
 
 const SCROLL_MULTIPLIER: f64 = 1.1;
 const CVE2_PIPELINE_RECT: (f64, f64, f64, f64) = (-50.0, -50.0, 1500.0, 750.0);
@@ -41,10 +52,33 @@ fn svg_viewport(
     Rect::new(Point2D::new(x, y), Size2D::new(width, height))
 }
 
+/// Names the operation the ALU performs this cycle for the ALU tooltip, such
+/// as "SRA: arithmetic right shift by 3, "
+fn format_alu_op(op: Option<ALUOp>, op_b: Option<u32>) -> String {
+    match (op, op_b) {
+        (Some(op), Some(b)) if op.is_shift() => {
+            format!("{:?}: {} by {}, ", op, op.description(), b & 0x1F)
+        }
+        (Some(op), _) => format!("{:?}: {}, ", op, op.description()),
+        (None, _) => String::new(),
+    }
+}
+
+/// Describes the carry/overflow flags of an add or sub for the ALU tooltip
+fn format_alu_flags(flags: Option<ALUFlags>) -> String {
+    match flags {
+        Some(ALUFlags { carry, overflow }) => {
+            format!(" (Carry: {}, Overflow: {})", carry as u8, overflow as u8)
+        }
+        None => String::new(),
+    }
+}
+
 #[component]
 #[allow(non_snake_case)]
 pub fn PipelineVisualization(
     emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>,
+    emulator_states: ReadOnlySignal<Vec<AnyEmulatorState>>,
     selected_emulator: ReadOnlySignal<EmulatorOption>,
 ) -> Element {
     let initial_view = use_memo(move || match *selected_emulator.read() {
@@ -112,7 +146,20 @@ pub fn PipelineVisualization(
                 title: if *show_control_signals.read() { "Hide Control Signals" } else { "Show Control Signals" },
                 Icon { width: 16, height: 16, icon: LdGamepad2 }
             }
+            div { class: "absolute top-2 left-22 z-10",
+                Legend { selected_emulator, show_control_signals }
+            }
+            if matches!(*selected_emulator.read(), EmulatorOption::FiveStage) {
+                div { class: "absolute top-2 left-32 z-10",
+                    OccupancyBar { emulator_state }
+                }
+            }
+            div { class: "absolute bottom-2 left-2 z-10 flex gap-x-1",
+                SnapshotExport { emulator_state, selected_emulator }
+                FrameExport { emulator_states, selected_emulator }
+            }
             svg {
+                id: PIPELINE_SVG_ID,
                 width: "100%",
                 height: "100%",
                 view_box: format!(
@@ -0,0 +1,88 @@
+use dioxus::prelude::*;
+
+use emugator_core::{
+    assembler::AssembledProgram,
+    emulator::{AnyEmulatorState, StageSnapshot},
+};
+
+use crate::interface::ASSEMBLED_PROGRAM;
+
+/// What a pipeline stage holds this cycle
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Occupancy {
+    Instruction,
+    Bubble,
+    Empty,
+}
+
+impl Occupancy {
+    fn of(stage: &StageSnapshot, program: Option<&AssembledProgram>) -> Self {
+        if stage.bubble {
+            Occupancy::Bubble
+        } else if stage
+            .pc
+            .and_then(|pc| program?.instruction_at(pc))
+            .is_some()
+        {
+            Occupancy::Instruction
+        } else {
+            Occupancy::Empty
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            Occupancy::Instruction => "bg-indigo-500 text-white",
+            Occupancy::Bubble => "bg-amber-400 text-gray-900",
+            Occupancy::Empty => "bg-gray-200 text-gray-500",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            Occupancy::Instruction => "instruction",
+            Occupancy::Bubble => "bubble",
+            Occupancy::Empty => "empty",
+        }
+    }
+}
+
+/// One segment per stage showing at a glance how full the pipeline is
+#[component]
+#[allow(non_snake_case)]
+pub fn OccupancyBar(emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>) -> Element {
+    let stages: Vec<(&'static str, Occupancy)> = match &*emulator_state.read() {
+        Some(state) => {
+            let program = ASSEMBLED_PROGRAM.read();
+            state
+                .pipeline_snapshot()
+                .iter()
+                .map(|stage| (stage.name, Occupancy::of(stage, program.as_ref())))
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    let occupied = stages
+        .iter()
+        .filter(|(_, occupancy)| *occupancy == Occupancy::Instruction)
+        .count();
+    let summary = format!(
+        "{} of {} stages hold an instruction",
+        occupied,
+        stages.len()
+    );
+
+    rsx! {
+        div {
+            class: "flex rounded overflow-hidden text-xs font-mono",
+            title: summary,
+            for (name, occupancy) in stages {
+                div {
+                    class: format!("w-10 py-1 text-center {}", occupancy.color()),
+                    title: "{name}: {occupancy.description()}",
+                    "{name}"
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use super::frame_export::download;
+use super::{CVE2_PIPELINE_RECT, FIVE_STAGE_PIPELINE_RECT};
+use dioxus::prelude::*;
+use dioxus_free_icons::Icon;
+use dioxus_free_icons::icons::ld_icons::LdImageDown;
+use emugator_core::emulator::{AnyEmulatorState, EmulatorOption};
+use wasm_bindgen::JsCast;
+
+/// Id of the SVG the pipeline is drawn in
+pub const PIPELINE_SVG_ID: &str = "pipeline_svg";
+
+/// Style properties copied from the page onto every exported element, so the
+/// file looks the same without the page's stylesheets
+const STYLE_PROPERTIES: [&str; 16] = [
+    "display",
+    "visibility",
+    "opacity",
+    "fill",
+    "fill-opacity",
+    "stroke",
+    "stroke-opacity",
+    "stroke-width",
+    "stroke-dasharray",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "font-family",
+    "font-size",
+    "font-weight",
+    "text-anchor",
+    "dominant-baseline",
+];
+
+/// Downloads the pipeline as it is drawn now as a standalone SVG, framed like
+/// the recentered view
+#[component]
+#[allow(non_snake_case)]
+pub fn SnapshotExport(
+    emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>,
+    selected_emulator: ReadOnlySignal<EmulatorOption>,
+) -> Element {
+    let export = move |_| {
+        let view_box = match *selected_emulator.peek() {
+            EmulatorOption::CVE2 => CVE2_PIPELINE_RECT,
+            EmulatorOption::FiveStage => FIVE_STAGE_PIPELINE_RECT,
+        };
+        let name = match &*emulator_state.peek() {
+            Some(state) => format!("pipeline-cycle-{}.svg", state.counters().cycle),
+            None => "pipeline.svg".to_string(),
+        };
+        let svg = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id(PIPELINE_SVG_ID))
+            .and_then(|element| standalone_svg(&element, view_box));
+        if let Some(svg) = svg {
+            download(&name, &svg);
+        }
+    };
+
+    rsx! {
+        button {
+            class: "bg-gray-200 hover:bg-gray-300 p-1 rounded cursor-pointer",
+            title: "Download SVG",
+            onclick: export,
+            Icon { width: 16, height: 16, icon: LdImageDown }
+        }
+    }
+}
+
+/// Copies the contents of `svg` into a new SVG with the styles the page
+/// computed for each element written onto it
+fn standalone_svg(svg: &web_sys::Element, view_box: (f64, f64, f64, f64)) -> Option<String> {
+    let window = web_sys::window()?;
+    let copy: web_sys::Element = svg.clone_node_with_deep(true).ok()?.dyn_into().ok()?;
+
+    // Both lists are in document order, so the nth copy is of the nth original
+    let originals = svg.query_selector_all("*").ok()?;
+    let copies = copy.query_selector_all("*").ok()?;
+    for i in 0..originals.length() {
+        let (Some(original), Some(copy)) = (originals.item(i), copies.item(i)) else {
+            continue;
+        };
+        let (Ok(original), Ok(copy)) = (
+            original.dyn_into::<web_sys::Element>(),
+            copy.dyn_into::<web_sys::Element>(),
+        ) else {
+            continue;
+        };
+        let Ok(Some(computed)) = window.get_computed_style(&original) else {
+            continue;
+        };
+        let style: String = STYLE_PROPERTIES
+            .iter()
+            .filter_map(|property| {
+                let value = computed.get_property_value(property).ok()?;
+                (!value.is_empty()).then(|| format!("{property}: {value}; "))
+            })
+            .collect();
+        let _ = copy.set_attribute("style", style.trim_end());
+    }
+
+    let (x, y, width, height) = view_box;
+    Some(format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{x} {y} {width} {height}\" width=\"{width}\" height=\"{height}\">\n\
+         <rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"{height}\" fill=\"white\"/>\n\
+         {}\n</svg>\n",
+        copy.inner_html()
+    ))
+}
@@ -1,16 +1,62 @@
 use dioxus::prelude::*;
 use emugator_core::emulator::AnyEmulatorState;
 
+pub use emugator_core::emulator::register_file::ABI_NAMES;
+
+/// Number of recorded states shown in a register's sparkline
+const SPARKLINE_LEN: usize = 50;
+
+/// Maximum number of registers that can be pinned at once
+const MAX_PINNED: usize = 2;
+
+/// Serializes the register file as a JSON object with an `x0`..`x31` key and
+/// an ABI-name key for every register, followed by the PC in each pipeline
+/// stage under `pcs`
+pub fn registers_json(state: &AnyEmulatorState) -> String {
+    let registers = state.registers();
+    let mut fields: Vec<String> = (0..32)
+        .map(|i| format!("\"x{i}\":{}", registers[i]))
+        .collect();
+    for (i, names) in ABI_NAMES.iter().enumerate() {
+        // s0 and fp are two names for the same register
+        for name in names.split('/') {
+            fields.push(format!("\"{name}\":{}", registers[i]));
+        }
+    }
+    let pcs = state
+        .all_pcs()
+        .iter()
+        .map(|pos| format!("\"{}\":{}", pos.name, pos.pc))
+        .collect::<Vec<_>>()
+        .join(",");
+    fields.push(format!("\"pcs\":{{{pcs}}}"));
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Shows the float a register's bits encode, switching to scientific notation
+/// for magnitudes that would otherwise print a long run of zeros
+fn format_f32(bits: u32) -> String {
+    let value = f32::from_bits(bits);
+    if value == 0.0 || !value.is_finite() || (1e-4..1e7).contains(&value.abs()) {
+        format!("{value}")
+    } else {
+        format!("{value:e}")
+    }
+}
+
 #[component]
 #[allow(non_snake_case)]
-pub fn RegisterView(emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>) -> Element {
-    let abi_names = &[
-        "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0/fp", "s1", "a0", "a1", "a2", "a3",
-        "a4", "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3",
-        "t4", "t5", "t6",
-    ];
+pub fn RegisterView(
+    emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>,
+    emulator_states: ReadOnlySignal<Vec<AnyEmulatorState>>,
+    history_position: ReadOnlySignal<Option<usize>>,
+    show_float: Signal<bool>,
+    hide_unwritten: Signal<bool>,
+) -> Element {
+    let mut pinned: Signal<Vec<usize>> = use_signal(Vec::new);
+
     let emulator_state = emulator_state.read();
-    let Some(register_vals) = emulator_state.as_ref().map(|e| e.registers()) else {
+    let Some(state) = emulator_state.as_ref() else {
         return rsx! {
             div { class: "flex justify-center items-center h-full",
                 span { class: "text-gray-500 font-mono", "No program running" }
@@ -18,29 +64,97 @@ pub fn RegisterView(emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>) ->
         };
     };
 
+    let register_vals = state.registers();
+    let json = registers_json(state);
+    // Registers the program has not written since reset, such as x0, can be hidden
+    let is_shown = |i: usize| !*hide_unwritten.read() || register_vals.was_written(i);
+    let hidden_count = (0..32).filter(|&i| !is_shown(i)).count();
+
     rsx! {
         div { class: "flex flex-col h-full",
+            div { class: "flex justify-end items-center gap-3 mb-2",
+                label {
+                    class: "flex items-center gap-x-1 font-mono text-xs text-gray-300 cursor-pointer",
+                    title: "Also show each register's bits as an IEEE-754 single-precision float",
+                    input {
+                        r#type: "checkbox",
+                        class: "accent-indigo-500 cursor-pointer",
+                        checked: *show_float.read(),
+                        onchange: move |event| show_float.set(event.checked()),
+                    }
+                    "Show as f32"
+                }
+                label {
+                    class: "flex items-center gap-x-1 font-mono text-xs text-gray-300 cursor-pointer",
+                    title: "Only show the registers the program has written since reset. Uncheck to show all 32.",
+                    input {
+                        r#type: "checkbox",
+                        class: "accent-indigo-500 cursor-pointer",
+                        checked: *hide_unwritten.read(),
+                        onchange: move |event| hide_unwritten.set(event.checked()),
+                    }
+                    if hidden_count > 0 {
+                        "Hide Unwritten ({hidden_count})"
+                    } else {
+                        "Hide Unwritten"
+                    }
+                }
+                button {
+                    class: "bg-gray-600 hover:bg-gray-700 text-white font-mono text-xs py-1 px-2 rounded cursor-pointer",
+                    title: "Copy every register and the PC in each pipeline stage as JSON",
+                    onclick: move |_| {
+                        if let Some(window) = web_sys::window() {
+                            let _ = window.navigator().clipboard().write_text(&json);
+                        }
+                    },
+                    "Copy as JSON"
+                }
+            }
+            for register in pinned.read().iter().copied() {
+                RegisterSparkline {
+                    key: "{register}",
+                    register,
+                    name: ABI_NAMES[register],
+                    emulator_states,
+                    history_position,
+                }
+            }
 
             div { class: "grid grid-cols-1 md:grid-cols-2 lg:grid-cols-4 gap-2 overflow-auto max-h-[calc(100vh-12rem)] pr-2",
                 for c in 0..4 {
                     div { class: "bg-white rounded shadow-sm p-2",
                         div { class: "grid gap-1",
-                            for i in (8 * c)..(8 * (c + 1)) {
+                            for i in ((8 * c)..(8 * (c + 1))).filter(|&i| is_shown(i)) {
                                 div {
-                                    class: {
-                                        if i < 8 * (c + 1) - 1 {
-                                            "flex justify-between items-center border-b border-gray-100 py-1"
+                                    class: format!(
+                                        "flex justify-between items-center py-1 cursor-pointer {} {}",
+                                        if i < 8 * (c + 1) - 1 { "border-b border-gray-100" } else { "" },
+                                        if pinned.read().contains(&i) { "bg-blue-50" } else { "" },
+                                    ),
+                                    title: "Click to pin a chart of this register",
+                                    onclick: move |_| {
+                                        let mut pinned = pinned.write();
+                                        if let Some(index) = pinned.iter().position(|&r| r == i) {
+                                            pinned.remove(index);
                                         } else {
-                                            "flex justify-between items-center py-1"
+                                            if pinned.len() >= MAX_PINNED {
+                                                pinned.remove(0);
+                                            }
+                                            pinned.push(i);
                                         }
                                     },
                                     div { class: "flex-1",
                                         div { class: "font-mono text-gray-500 text-xs",
-                                            "x{i} ({abi_names[i]})"
+                                            "x{i} ({ABI_NAMES[i]})"
                                         }
                                         div { class: "font-mono font-bold text-black",
                                             "{register_vals[i]:#010x}"
                                         }
+                                        if *show_float.read() {
+                                            div { class: "font-mono text-xs text-gray-600",
+                                                "{format_f32(register_vals[i])}"
+                                            }
+                                        }
                                     }
                                 }
                             }
@@ -51,3 +165,60 @@ pub fn RegisterView(emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>) ->
         }
     }
 }
+
+/// Inline chart of a register's value over the most recent recorded states,
+/// up to the state currently shown
+#[component]
+#[allow(non_snake_case)]
+fn RegisterSparkline(
+    register: usize,
+    name: &'static str,
+    emulator_states: ReadOnlySignal<Vec<AnyEmulatorState>>,
+    history_position: ReadOnlySignal<Option<usize>>,
+) -> Element {
+    let states = emulator_states.read();
+    let end = history_position
+        .read()
+        .map_or(states.len(), |position| (position + 1).min(states.len()));
+    let values: Vec<u32> = states[end.saturating_sub(SPARKLINE_LEN)..end]
+        .iter()
+        .map(|state| state.registers()[register])
+        .collect();
+
+    let (width, height) = (200.0, 24.0);
+    let min = values.iter().copied().min().unwrap_or(0) as f64;
+    let max = values.iter().copied().max().unwrap_or(0) as f64;
+    let range = if max > min { max - min } else { 1.0 };
+    let step = width / (values.len().max(2) - 1) as f64;
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            let x = i as f64 * step;
+            let y = height - (value as f64 - min) / range * (height - 2.0) - 1.0;
+            format!("{x:.1},{y:.1}")
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let current = values.last().copied().unwrap_or(0);
+
+    rsx! {
+        div { class: "flex items-center gap-2 bg-white rounded shadow-sm px-2 py-1 mb-2 font-mono text-xs",
+            span { class: "w-20 text-gray-500", "x{register} ({name})" }
+            svg {
+                class: "flex-1",
+                view_box: "0 0 {width} {height}",
+                preserve_aspect_ratio: "none",
+                height: "{height}",
+                polyline {
+                    points,
+                    fill: "none",
+                    stroke: "#3B82F6",
+                    stroke_width: "1.5",
+                    vector_effect: "non-scaling-stroke",
+                }
+            }
+            span { class: "w-24 text-right font-bold text-black", "{current:#010x}" }
+        }
+    }
+}
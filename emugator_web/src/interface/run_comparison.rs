@@ -0,0 +1,159 @@
+use dioxus::prelude::*;
+use emugator_core::assembler::MemoryMap;
+use emugator_core::emulator::{self, AnyEmulatorState, EmulatorOption, state_diff::StateDiff};
+
+use super::file_tabs::{self, SourceFile};
+use super::register_view::ABI_NAMES;
+
+/// Cycles each headless run may take before it is cut off
+const MAX_CLOCKS: usize = 100_000;
+/// Changed bytes shown before the rest are summarized
+const MAX_MEMORY_ROWS: usize = 64;
+
+/// Assembles every tab and runs the program to completion without recording
+/// the intermediate states
+fn run_files(
+    files: &[SourceFile],
+    emulator_type: EmulatorOption,
+    serial_input: &str,
+    memory_map: MemoryMap,
+) -> Result<AnyEmulatorState, String> {
+    let program = file_tabs::assemble_files(files, memory_map)
+        .map_err(|errors| format!("Program has {} assembler errors", errors.len()))?;
+    Ok(emulator::run_program(
+        &program,
+        emulator_type,
+        serial_input.as_bytes(),
+        MAX_CLOCKS,
+    ))
+}
+
+/// Snapshots the final state of one run of the program and compares it to a
+/// later run, showing only the registers, bytes and UART output that changed.
+#[component]
+#[allow(non_snake_case)]
+pub fn RunComparison(
+    files: ReadOnlySignal<Vec<SourceFile>>,
+    serial_input: ReadOnlySignal<String>,
+    selected_emulator: ReadOnlySignal<EmulatorOption>,
+    memory_map: ReadOnlySignal<MemoryMap>,
+) -> Element {
+    let mut baseline: Signal<Option<AnyEmulatorState>> = use_signal(|| None);
+    let mut diff: Signal<Option<StateDiff>> = use_signal(|| None);
+    let mut error: Signal<Option<String>> = use_signal(|| None);
+
+    let run = move || {
+        run_files(
+            &files.read(),
+            *selected_emulator.read(),
+            &serial_input.read(),
+            *memory_map.read(),
+        )
+    };
+
+    let has_baseline = baseline.read().is_some();
+
+    rsx! {
+        div { class: "h-full flex flex-col overflow-hidden",
+            div { class: "flex items-center gap-2 mb-2 font-mono text-sm",
+                button {
+                    class: "bg-blue-600 hover:bg-blue-700 text-white py-1 px-2 rounded cursor-pointer",
+                    title: "Run the program and keep its final state to compare against",
+                    onclick: move |_| {
+                        match run() {
+                            Ok(state) => {
+                                baseline.set(Some(state));
+                                error.set(None);
+                            }
+                            Err(e) => error.set(Some(e)),
+                        }
+                        diff.set(None);
+                    },
+                    "Snapshot Run"
+                }
+                button {
+                    class: format!(
+                        "py-1 px-2 rounded {}",
+                        if has_baseline {
+                            "bg-green-600 hover:bg-green-700 text-white cursor-pointer"
+                        } else {
+                            "bg-gray-600 text-gray-300 cursor-not-allowed"
+                        },
+                    ),
+                    disabled: !has_baseline,
+                    title: "Run the current program and compare it to the snapshot",
+                    onclick: move |_| {
+                        match run() {
+                            Ok(state) => {
+                                diff.set(baseline.read().as_ref().map(|baseline| baseline.diff(&state)));
+                                error.set(None);
+                            }
+                            Err(e) => error.set(Some(e)),
+                        }
+                    },
+                    "Compare"
+                }
+                if let Some(error) = error.read().as_ref() {
+                    span { class: "text-red-400", "{error}" }
+                }
+            }
+            div { class: "flex-grow overflow-auto pr-2",
+                div { class: "bg-white rounded shadow-sm p-2 font-mono text-sm text-gray-800",
+                    match diff.read().as_ref() {
+                        None if has_baseline => rsx! {
+                            span { class: "text-gray-500", "Change the program, then compare" }
+                        },
+                        None => rsx! {
+                            span { class: "text-gray-500", "Snapshot a run to compare against" }
+                        },
+                        Some(diff) if diff.is_empty() => rsx! {
+                            span { class: "text-gray-500", "No differences" }
+                        },
+                        Some(diff) => rsx! {
+                            if !diff.registers.is_empty() {
+                                div { class: "font-bold mb-1", "Registers" }
+                                table { class: "mb-2",
+                                    tbody {
+                                        for (index , before , after) in diff.registers.iter().copied() {
+                                            tr {
+                                                td { class: "pr-4", "x{index} ({ABI_NAMES[index]})" }
+                                                td { class: "pr-2", "0x{before:08X}" }
+                                                td { class: "pr-2", "→" }
+                                                td { class: "text-blue-700", "0x{after:08X}" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            if !diff.memory.is_empty() {
+                                div { class: "font-bold mb-1", "Data Memory" }
+                                table { class: "mb-2",
+                                    tbody {
+                                        for (address , before , after) in diff.memory.iter().take(MAX_MEMORY_ROWS).copied() {
+                                            tr {
+                                                td { class: "pr-4", "0x{address:04x}:" }
+                                                td { class: "pr-2", "0x{before:02X}" }
+                                                td { class: "pr-2", "→" }
+                                                td { class: "text-blue-700", "0x{after:02X}" }
+                                            }
+                                        }
+                                    }
+                                }
+                                if diff.memory.len() > MAX_MEMORY_ROWS {
+                                    div { class: "text-gray-500 mb-2",
+                                        "... and {diff.memory.len() - MAX_MEMORY_ROWS} more bytes"
+                                    }
+                                }
+                            }
+                            if let Some((before, after)) = &diff.uart_output {
+                                div { class: "font-bold mb-1", "UART Output" }
+                                pre { class: "whitespace-pre-wrap", "{before:?}" }
+                                pre { class: "whitespace-pre-wrap text-blue-700", "{after:?}" }
+                            }
+                        },
+                    }
+                }
+            }
+        }
+    }
+}
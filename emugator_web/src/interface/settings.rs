@@ -0,0 +1,190 @@
+use emugator_core::{
+    assembler::{MemoryMap, SectionBases},
+    emulator::{
+        EmulatorOption, latency::LatencyModel, memory_module::MemoryInit,
+        register_file::RegisterInit, uart::Uart,
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use super::uart_view::OutputFormat;
+
+/// Preferences kept in the browser's local storage between sessions. Options
+/// are stored as plain values, and any that are missing or unreadable take
+/// their defaults, so new options can be added without losing saved ones.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Either "cve2" or "five_stage"
+    pub pipeline: String,
+    pub text_base: u32,
+    pub data_base: u32,
+    pub uart: u32,
+    pub timer: u32,
+    pub zeroed_size: u32,
+    pub strict_directives: bool,
+    pub uart_delay: u32,
+    /// Either "text" or "hex" followed by the bytes per row
+    pub uart_format: String,
+    pub load_latency: u32,
+    pub store_latency: u32,
+    pub arithmetic_latency: u32,
+    pub highlight_hazards: bool,
+    pub show_encodings: bool,
+    pub show_stages: bool,
+    pub show_float_registers: bool,
+    pub hide_unwritten_registers: bool,
+    /// Either "sparse" or "zeroed"
+    pub memory_init: String,
+    /// One of "zeroed", "poison" or "random"
+    pub register_init: String,
+    pub register_seed: u64,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        let mut settings = Self {
+            pipeline: String::new(),
+            text_base: 0,
+            data_base: 0,
+            uart: 0,
+            timer: 0,
+            zeroed_size: 0,
+            strict_directives: true,
+            uart_delay: Uart::default().uart_cycle_count,
+            uart_format: String::new(),
+            load_latency: 0,
+            store_latency: 0,
+            arithmetic_latency: 0,
+            highlight_hazards: false,
+            show_encodings: false,
+            show_stages: false,
+            show_float_registers: false,
+            hide_unwritten_registers: false,
+            memory_init: String::new(),
+            register_init: String::new(),
+            register_seed: 0,
+        };
+        settings.set_pipeline(EmulatorOption::CVE2);
+        settings.set_memory_map(MemoryMap::default());
+        settings.set_latencies(LatencyModel::default());
+        settings.set_uart_format(OutputFormat::Text);
+        settings.set_memory_init(MemoryInit::default());
+        settings.set_register_init(RegisterInit::default());
+        settings
+    }
+}
+
+impl Settings {
+    const STORAGE_KEY: &'static str = "emugator.settings";
+
+    /// The settings saved last session, or the defaults if there are none
+    pub fn load() -> Self {
+        web_sys::window()
+            .and_then(|window| window.local_storage().ok().flatten())
+            .and_then(|storage| storage.get_item(Self::STORAGE_KEY).ok().flatten())
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let (Some(storage), Ok(json)) = (
+            web_sys::window().and_then(|window| window.local_storage().ok().flatten()),
+            serde_json::to_string(self),
+        ) {
+            let _ = storage.set_item(Self::STORAGE_KEY, &json);
+        }
+    }
+
+    pub fn pipeline(&self) -> EmulatorOption {
+        match self.pipeline.as_str() {
+            "five_stage" => EmulatorOption::FiveStage,
+            _ => EmulatorOption::CVE2,
+        }
+    }
+
+    pub fn set_pipeline(&mut self, pipeline: EmulatorOption) {
+        self.pipeline = match pipeline {
+            EmulatorOption::CVE2 => "cve2",
+            EmulatorOption::FiveStage => "five_stage",
+        }
+        .to_string();
+    }
+
+    pub fn memory_map(&self) -> MemoryMap {
+        MemoryMap {
+            sections: SectionBases {
+                text: self.text_base,
+                data: self.data_base,
+            },
+            uart: self.uart,
+            timer: self.timer,
+            zeroed_size: self.zeroed_size,
+        }
+    }
+
+    pub fn set_memory_map(&mut self, memory_map: MemoryMap) {
+        self.text_base = memory_map.sections.text;
+        self.data_base = memory_map.sections.data;
+        self.uart = memory_map.uart;
+        self.timer = memory_map.timer;
+        self.zeroed_size = memory_map.zeroed_size;
+    }
+
+    pub fn latencies(&self) -> LatencyModel {
+        LatencyModel {
+            load: self.load_latency,
+            store: self.store_latency,
+            arithmetic: self.arithmetic_latency,
+        }
+    }
+
+    pub fn set_latencies(&mut self, latencies: LatencyModel) {
+        self.load_latency = latencies.load;
+        self.store_latency = latencies.store;
+        self.arithmetic_latency = latencies.arithmetic;
+    }
+
+    pub fn uart_format(&self) -> OutputFormat {
+        OutputFormat::from_key(&self.uart_format).unwrap_or(OutputFormat::Text)
+    }
+
+    pub fn set_uart_format(&mut self, format: OutputFormat) {
+        self.uart_format = format.key();
+    }
+
+    pub fn memory_init(&self) -> MemoryInit {
+        match self.memory_init.as_str() {
+            "zeroed" => MemoryInit::Zeroed,
+            _ => MemoryInit::Sparse,
+        }
+    }
+
+    pub fn set_memory_init(&mut self, memory_init: MemoryInit) {
+        self.memory_init = match memory_init {
+            MemoryInit::Sparse => "sparse",
+            MemoryInit::Zeroed => "zeroed",
+        }
+        .to_string();
+    }
+
+    pub fn register_init(&self) -> RegisterInit {
+        match self.register_init.as_str() {
+            "poison" => RegisterInit::Poison,
+            "random" => RegisterInit::Random(self.register_seed),
+            _ => RegisterInit::Zeroed,
+        }
+    }
+
+    pub fn set_register_init(&mut self, register_init: RegisterInit) {
+        self.register_init = match register_init {
+            RegisterInit::Zeroed => "zeroed",
+            RegisterInit::Poison => "poison",
+            RegisterInit::Random(seed) => {
+                self.register_seed = seed;
+                "random"
+            }
+        }
+        .to_string();
+    }
+}
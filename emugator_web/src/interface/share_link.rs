@@ -0,0 +1,49 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+
+/// Prefix of the URL fragment holding a shared program
+const FRAGMENT_PREFIX: &str = "#source=";
+/// Longest encoded program put in a link, so links stay small enough to paste
+const MAX_ENCODED_LEN: usize = 8_000;
+/// Largest source a link may decompress to
+const MAX_SOURCE_LEN: usize = 256 * 1024;
+
+/// Compresses `source` and encodes it as URL-safe base64, `None` if the
+/// result is longer than `MAX_ENCODED_LEN`
+fn encode_source(source: &str) -> Option<String> {
+    let compressed = miniz_oxide::deflate::compress_to_vec(source.as_bytes(), 9);
+    let encoded = URL_SAFE_NO_PAD.encode(compressed);
+    (encoded.len() <= MAX_ENCODED_LEN).then_some(encoded)
+}
+
+/// Reverses `encode_source`, `None` if the text is not a valid encoding
+fn decode_source(encoded: &str) -> Option<String> {
+    if encoded.len() > MAX_ENCODED_LEN {
+        return None;
+    }
+    let compressed = URL_SAFE_NO_PAD.decode(encoded).ok()?;
+    let bytes =
+        miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, MAX_SOURCE_LEN).ok()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Link to this page that opens with `source` in the editor, `None` if the
+/// source is too large to share
+pub fn share_url(source: &str) -> Option<String> {
+    let location = web_sys::window()?.location();
+    let origin = location.origin().ok()?;
+    let path = location.pathname().ok()?;
+    Some(format!(
+        "{}{}{}{}",
+        origin,
+        path,
+        FRAGMENT_PREFIX,
+        encode_source(source)?
+    ))
+}
+
+/// The program shared in the page's URL, `None` if there is none or it
+/// does not decode
+pub fn source_from_url() -> Option<String> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    decode_source(hash.strip_prefix(FRAGMENT_PREFIX)?)
+}
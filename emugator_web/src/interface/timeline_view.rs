@@ -0,0 +1,99 @@
+use dioxus::prelude::*;
+use emugator_core::emulator::AnyEmulatorState;
+
+use super::navbar::MAX_HISTORY_LEN;
+
+/// Longest gap between updates, in milliseconds, that still counts as one run
+/// when measuring how fast cycles go by
+const RATE_WINDOW_MS: f64 = 2000.0;
+
+/// Slider over the recorded emulator states.
+/// Dragging it displays an earlier snapshot without re-running the program.
+/// Beside it are the counters of the displayed state and, while the program is
+/// running or being stepped through quickly, how many cycles go by per second.
+#[component]
+#[allow(non_snake_case)]
+pub fn TimelineView(
+    emulator_states: Signal<Vec<AnyEmulatorState>>,
+    history_position: Signal<Option<usize>>,
+) -> Element {
+    // Time and cycle of the last update, with the rate since the one before
+    let mut last_update: Signal<Option<(f64, u64)>> = use_signal(|| None);
+    let mut cycle_rate: Signal<Option<f64>> = use_signal(|| None);
+
+    use_effect(move || {
+        let latest = emulator_states
+            .read()
+            .last()
+            .map(|state| state.counters().cycle);
+        let now = js_sys::Date::now();
+        let previous = *last_update.peek();
+        cycle_rate.set(match (previous, latest) {
+            (Some((then, previous_cycle)), Some(cycle))
+                if cycle > previous_cycle && now - then < RATE_WINDOW_MS =>
+            {
+                Some((cycle - previous_cycle) as f64 * 1000.0 / (now - then).max(1.0))
+            }
+            _ => None,
+        });
+        last_update.set(latest.map(|cycle| (now, cycle)));
+    });
+
+    let history_len = emulator_states.read().len();
+    if history_len == 0 {
+        return rsx! {};
+    }
+
+    let last = history_len - 1;
+    let position = history_position.read().unwrap_or(last).min(last);
+
+    rsx! {
+        div { class: "flex items-center gap-3 bg-gray-900 text-gray-300 text-xs font-mono px-4 py-1 border-b-2 border-gray-950",
+            span { class: "font-medium", "Timeline" }
+            input {
+                class: "flex-1 cursor-pointer accent-blue-500",
+                r#type: "range",
+                min: "0",
+                max: "{last}",
+                value: "{position}",
+                disabled: last == 0,
+                oninput: move |event| {
+                    if let Ok(selected) = event.value().parse::<usize>() {
+                        history_position.set(if selected >= last { None } else { Some(selected) });
+                    }
+                },
+            }
+            span { class: "w-28 text-right", "{position} / {last}" }
+            {
+                let counters = *emulator_states.read()[position].counters();
+                rsx! {
+                    span {
+                        class: "whitespace-nowrap",
+                        title: "Cycles and retired instructions since the program started or the counters were reset",
+                        "Cycles {counters.cycle} / Instret {counters.instret}"
+                    }
+                }
+            }
+            if let Some(rate) = *cycle_rate.read() {
+                span { class: "text-gray-400 whitespace-nowrap", "{rate:.0} cycles/s" }
+            }
+            button {
+                class: "bg-gray-700 hover:bg-gray-600 rounded px-2 cursor-pointer",
+                title: "Zero the cycle and instruction counters of the latest state without changing registers or memory",
+                onclick: move |_| {
+                    if let Some(state) = emulator_states.write().last_mut() {
+                        state.reset_counters();
+                    }
+                },
+                "Reset Counters"
+            }
+            if history_len >= MAX_HISTORY_LEN {
+                span {
+                    class: "text-yellow-500",
+                    title: "Only the most recent {MAX_HISTORY_LEN} states are kept",
+                    "(full)"
+                }
+            }
+        }
+    }
+}
@@ -5,12 +5,81 @@ use emugator_core::emulator::AnyEmulatorState;
 use dioxus_free_icons::Icon;
 use dioxus_free_icons::icons::ld_icons::{LdChevronDown, LdChevronUp};
 
+/// How the serial output is displayed
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum OutputFormat {
+    Text,
+    /// Hexdump with an offset column and this many bytes per row
+    Hex(usize),
+}
+
+impl OutputFormat {
+    pub fn key(&self) -> String {
+        match self {
+            OutputFormat::Text => "text".to_string(),
+            OutputFormat::Hex(bytes_per_row) => format!("hex{}", bytes_per_row),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "text" => Some(OutputFormat::Text),
+            "hex8" => Some(OutputFormat::Hex(8)),
+            "hex16" => Some(OutputFormat::Hex(16)),
+            "hex32" => Some(OutputFormat::Hex(32)),
+            _ => None,
+        }
+    }
+
+    fn format(&self, bytes: &[u8]) -> String {
+        match self {
+            OutputFormat::Text => String::from_utf8_lossy(bytes).to_string(),
+            OutputFormat::Hex(bytes_per_row) => hexdump(bytes, *bytes_per_row),
+        }
+    }
+}
+
+/// Formats bytes like `hexdump -C`: offset, hex bytes, then the printable characters
+fn hexdump(bytes: &[u8], bytes_per_row: usize) -> String {
+    bytes
+        .chunks(bytes_per_row)
+        .enumerate()
+        .map(|(row, chunk)| {
+            let hex = chunk
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii: String = chunk
+                .iter()
+                .map(|&byte| {
+                    if byte.is_ascii_graphic() || byte == b' ' {
+                        byte as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!(
+                "{:08x}  {:<width$}  |{}|",
+                row * bytes_per_row,
+                hex,
+                ascii,
+                width = bytes_per_row * 3 - 1
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[component]
 #[allow(non_snake_case)]
 pub fn UartView(
     emulator_states: Signal<Vec<AnyEmulatorState>>,
     emulator_state: ReadOnlySignal<Option<AnyEmulatorState>>,
     serial_input: Signal<String>,
+    uart_delay: Signal<u32>,
+    output_format: Signal<OutputFormat>,
     minimize_console: Signal<bool>,
 ) -> Element {
     let icon_width = 25;
@@ -19,6 +88,28 @@ pub fn UartView(
             div {
                 div { class: "flex w-full font-bold p-2 items-center align-center justify-between",
                     "UART Console"
+                    label { class: "flex flex-1 items-center justify-end gap-2 px-4 text-xs font-normal",
+                        "Delay: {uart_delay} cycles"
+                        input {
+                            class: "w-32 cursor-pointer accent-blue-500",
+                            r#type: "range",
+                            min: "0",
+                            max: "200",
+                            value: "{uart_delay}",
+                            oninput: move |event| {
+                                if let Ok(delay) = event.value().parse::<u32>() {
+                                    uart_delay.set(delay);
+                                    if let Some(memory_io_mut) = emulator_states
+                                        .write()
+                                        .last_mut()
+                                        .map(|e| e.memory_io_mut())
+                                    {
+                                        memory_io_mut.set_serial_delay(delay);
+                                    }
+                                }
+                            },
+                        }
+                    }
                     button {
                         class: "flex items-center justify-center text-center text-sm bg-inherit px-2 hover:outline outline-gray-400 rounded cursor-pointer",
                         onclick: move |_| {
@@ -67,12 +158,27 @@ pub fn UartView(
                     }
                 }
                 div { class: "flex flex-1 flex-col",
-                    div { class: "w-full p-2 font-semibold border-b border-l", "Serial Output" }
+                    div { class: "flex w-full p-2 font-semibold border-b border-l items-center justify-between",
+                        "Serial Output"
+                        select {
+                            class: "bg-gray-700 text-white text-xs font-normal rounded py-1 px-1 cursor-pointer",
+                            value: output_format.read().key(),
+                            onchange: move |event| {
+                                if let Some(format) = OutputFormat::from_key(&event.value()) {
+                                    output_format.set(format);
+                                }
+                            },
+                            option { value: "text", "Text" }
+                            option { value: "hex8", "Hex, 8 bytes per row" }
+                            option { value: "hex16", "Hex, 16 bytes per row" }
+                            option { value: "hex32", "Hex, 32 bytes per row" }
+                        }
+                    }
                     textarea {
                         class: "text-nowrap flex-1 leading-none p-3 border-l resize-none overflow-auto focus:outline-none",
                         placeholder: "> UART Output",
                         readonly: "true",
-                        value: if let Some(memory_io) = emulator_state.read().as_ref().map(|e| e.memory_io()) { String::from_utf8_lossy(memory_io.get_serial_output()).to_string() } else { "".to_string() },
+                        value: if let Some(memory_io) = emulator_state.read().as_ref().map(|e| e.memory_io()) { output_format.read().format(memory_io.get_serial_output()) } else { "".to_string() },
                     }
                 }
             }